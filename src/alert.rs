@@ -0,0 +1,324 @@
+//! Alerting on the graph updates
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::Sender;
+use std::time::{Duration, SystemTime};
+
+use tracing::{debug, instrument, warn};
+
+use super::{Dex, Vertex};
+use crate::config::{Config, ConfigError};
+use crate::provider::ProviderId;
+
+/// The condition to alert on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Rule {
+    /// The best rate of the pair moved more than the `threshold`, e.g.
+    /// `0.01` for 1%, since the last alert.
+    RateMove {
+        src: Vertex,
+        dst: Vertex,
+        threshold: f32,
+    },
+    /// The arbitrage cycle above `min_bps` net of the fees appeared.
+    Arbitrage { min_bps: f32 },
+    /// The provider has not quoted for more than `max_age`.
+    ProviderStale {
+        provider: ProviderId,
+        max_age: Duration,
+    },
+}
+
+/// The fired alert.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Alert {
+    RateMoved {
+        src: Vertex,
+        dst: Vertex,
+        from: f32,
+        to: f32,
+    },
+    Arbitrage {
+        cycle: Vec<Vertex>,
+        rate: f32,
+    },
+    ProviderStale {
+        provider: ProviderId,
+        name: String,
+    },
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RateMoved { src, dst, from, to } => {
+                write!(f, "{src} -> {dst} moved from {from} to {to}")
+            }
+            Self::Arbitrage { cycle, rate } => {
+                for v in cycle {
+                    write!(f, "{v} -> ")?;
+                }
+                match cycle.first() {
+                    Some(v) => write!(f, "{v}: {rate} arbitrage"),
+                    None => write!(f, "{rate} arbitrage"),
+                }
+            }
+            Self::ProviderStale { name, .. } => write!(f, "provider {name} is stale"),
+        }
+    }
+}
+
+/// The destination of the alerts.
+pub trait Sink: Send {
+    fn send(&mut self, alert: &Alert) -> io::Result<()>;
+}
+
+impl Sink for Sender<Alert> {
+    fn send(&mut self, alert: &Alert) -> io::Result<()> {
+        Sender::send(self, alert.clone()).map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e))
+    }
+}
+
+/// The HTTP webhook, posting the alert as the JSON `{"text": ...}`.
+///
+/// Only the plain `http://host[:port]/path` URL is supported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Webhook {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Webhook {
+    pub fn new(url: &str) -> io::Result<Self> {
+        let invalid =
+            || io::Error::new(io::ErrorKind::InvalidInput, format!("invalid url {url:?}"));
+        let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+impl Sink for Webhook {
+    fn send(&mut self, alert: &Alert) -> io::Result<()> {
+        self.post(&alert.to_string())
+    }
+}
+
+impl Webhook {
+    /// Posts the `text` as the JSON `{"text": ...}`.
+    pub fn post(&self, text: &str) -> io::Result<()> {
+        let text = text.replace('\\', "\\\\").replace('"', "\\\"");
+        let body = format!("{{\"text\": \"{text}\"}}");
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            self.path,
+            self.host,
+            body.len(),
+        )?;
+        let mut status = String::new();
+        BufReader::new(stream).read_line(&mut status)?;
+        match status.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("webhook failed: {}", status.trim()),
+            )),
+        }
+    }
+}
+
+/// The alert rules evaluated on each graph update, e.g. by the daemon.
+///
+/// Each condition fires once until it's cleared, e.g. the arbitrage
+/// cycle fires again only after it has gone once.
+#[derive(Default)]
+pub struct Alerter {
+    rules: Vec<Rule>,
+    sinks: Vec<Box<dyn Sink>>,
+    rates: BTreeMap<(Vertex, Vertex), f32>,
+    cycles: BTreeSet<Vec<Vertex>>,
+    stale: BTreeSet<ProviderId>,
+}
+
+impl fmt::Debug for Alerter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Alerter")
+            .field("rules", &self.rules)
+            .field("sinks", &self.sinks.len())
+            .finish()
+    }
+}
+
+impl Alerter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn sink<S: Sink + 'static>(mut self, sink: S) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Returns the alerters of the `graph` out of the `[alert.<NAME>]`
+    /// sections, one each.
+    ///
+    /// The alert has the one rule, the `pair` with the `threshold`, the
+    /// `min_bps`, or the `provider` of the `dex` with the `max_age_ms`,
+    /// posted to its `webhook`.  The alert without the `graph` setting
+    /// is on all the graphs.
+    pub fn from_config(config: &Config, graph: &str, dex: &Dex) -> Result<Vec<Self>, ConfigError> {
+        let mut alerters = Vec::new();
+        for name in config.names("alert") {
+            let setting = |key: &str| format!("alert.{name}.{key}");
+            let invalid = |msg: &str| ConfigError::new(format!("alert {name}: {msg}"));
+            match config.get(&setting("graph")) {
+                Some(alert_graph) if alert_graph != graph => continue,
+                _ => {}
+            }
+            let rule = if let Some(pair) = config.get(&setting("pair")) {
+                let (src, dst) = pair
+                    .split_once('/')
+                    .and_then(|(src, dst)| Some((src.parse().ok()?, dst.parse().ok()?)))
+                    .ok_or_else(|| invalid(&format!("invalid pair {pair:?}")))?;
+                let threshold = config.parse(&setting("threshold"))?.unwrap_or_default();
+                Rule::RateMove {
+                    src,
+                    dst,
+                    threshold,
+                }
+            } else if let Some(min_bps) = config.parse(&setting("min_bps"))? {
+                Rule::Arbitrage { min_bps }
+            } else if let Some(provider) = config.get(&setting("provider")) {
+                let id = dex
+                    .providers()
+                    .find(|(_, p)| p.name() == provider)
+                    .map(|(id, _)| id)
+                    .ok_or_else(|| invalid(&format!("unknown provider {provider:?}")))?;
+                let max_age = config.parse(&setting("max_age_ms"))?.unwrap_or_default();
+                Rule::ProviderStale {
+                    provider: id,
+                    max_age: Duration::from_millis(max_age),
+                }
+            } else {
+                return Err(invalid("no rule"));
+            };
+            let webhook = config
+                .get(&setting("webhook"))
+                .ok_or_else(|| invalid("missing webhook"))?;
+            let webhook = Webhook::new(webhook).map_err(|e| invalid(&e.to_string()))?;
+            alerters.push(Self::new().rule(rule).sink(webhook));
+        }
+        Ok(alerters)
+    }
+
+    /// Evaluates the rules against the `dex` at `now`, sends the fired
+    /// alerts to the sinks, and returns them.
+    ///
+    /// The failed sink is logged, and doesn't stop the other sinks.
+    #[instrument(level = "debug", skip_all)]
+    pub fn check(&mut self, dex: &Dex, now: SystemTime) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        for rule in &self.rules {
+            match *rule {
+                Rule::RateMove {
+                    src,
+                    dst,
+                    threshold,
+                } => {
+                    let rate = match dex.get_best_rate(&src, &dst) {
+                        Some(path) => path.rate(),
+                        None => continue,
+                    };
+                    match self.rates.get(&(src, dst)) {
+                        Some(from) if ((rate / from) - 1.0).abs() <= threshold => {}
+                        Some(from) => {
+                            alerts.push(Alert::RateMoved {
+                                src,
+                                dst,
+                                from: *from,
+                                to: rate,
+                            });
+                            self.rates.insert((src, dst), rate);
+                        }
+                        None => {
+                            self.rates.insert((src, dst), rate);
+                        }
+                    }
+                }
+                Rule::Arbitrage { min_bps } => {
+                    let mut current = BTreeSet::new();
+                    for cycle in dex.find_arbitrage(min_bps, None) {
+                        let vertices: Vec<_> = cycle.edges().iter().map(|(src, ..)| *src).collect();
+                        if !self.cycles.contains(&vertices) {
+                            alerts.push(Alert::Arbitrage {
+                                cycle: vertices.clone(),
+                                rate: cycle.net_rate(),
+                            });
+                        }
+                        current.insert(vertices);
+                    }
+                    self.cycles = current;
+                }
+                Rule::ProviderStale { provider, max_age } => {
+                    let (name, updated_at) = match dex.provider(provider) {
+                        Some(p) => (p.name(), p.updated_at()),
+                        None => continue,
+                    };
+                    let is_stale = match updated_at {
+                        Some(updated_at) => match now.duration_since(updated_at) {
+                            Ok(age) => age > max_age,
+                            Err(_) => false,
+                        },
+                        None => true,
+                    };
+                    if !is_stale {
+                        self.stale.remove(&provider);
+                    } else if self.stale.insert(provider) {
+                        alerts.push(Alert::ProviderStale {
+                            provider,
+                            name: name.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        for alert in &alerts {
+            debug!(%alert, "fired");
+            for sink in &mut self.sinks {
+                if let Err(e) = sink.send(alert) {
+                    warn!(%alert, %e, "sink failed");
+                }
+            }
+        }
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod test;
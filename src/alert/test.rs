@@ -0,0 +1,149 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use super::{Alert, Alerter, Rule, Webhook};
+use crate::config::Config;
+use crate::Dex;
+
+#[test]
+fn test_alerter() {
+    let mut dex = Dex::new();
+    let feed = dex.register_provider("feed", 1);
+    dex.add_provider_rate(feed, 'A', 'B', 2.0);
+    dex.add_provider_rate(feed, 'B', 'C', 2.0);
+    dex.add_provider_rate(feed, 'A', 'C', 4.0);
+
+    let (tx, rx) = mpsc::channel();
+    let mut alerter = Alerter::new()
+        .rule(Rule::RateMove {
+            src: 'A'.into(),
+            dst: 'C'.into(),
+            threshold: 0.01,
+        })
+        .rule(Rule::Arbitrage { min_bps: 10.0 })
+        .rule(Rule::ProviderStale {
+            provider: feed,
+            max_age: Duration::from_secs(60),
+        })
+        .sink(tx);
+    let now = SystemTime::now();
+    assert!(alerter.check(&dex, now).is_empty());
+
+    dex.add_provider_rate(feed, 'A', 'C', 5.0);
+    let alerts = alerter.check(&dex, now);
+    assert_eq!(alerts.len(), 2);
+    assert_eq!(
+        alerts[0],
+        Alert::RateMoved {
+            src: 'A'.into(),
+            dst: 'C'.into(),
+            from: 4.0,
+            to: 5.0,
+        }
+    );
+    assert_eq!(alerts[1].to_string(), "A -> C -> B -> A: 1.25 arbitrage");
+    assert_eq!(rx.try_iter().count(), 2);
+
+    // Fired once until cleared.
+    let later = now + Duration::from_secs(3600);
+    let alerts = alerter.check(&dex, later);
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].to_string(), "provider feed is stale");
+    assert!(alerter.check(&dex, later).is_empty());
+}
+
+#[test]
+fn test_webhook() {
+    assert!(Webhook::new("https://example.com/hook").is_err());
+    assert!(Webhook::new("http://:80/").is_err());
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request = String::new();
+        let mut len = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                len = value.trim().parse().unwrap();
+            }
+            request.push_str(&line);
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+            .unwrap();
+        (request, String::from_utf8(body).unwrap())
+    });
+
+    let mut webhook = Webhook::new(&format!("http://127.0.0.1:{port}/hook")).unwrap();
+    let alert = Alert::ProviderStale {
+        provider: Dex::new().register_provider("feed", 1),
+        name: "feed".into(),
+    };
+    super::Sink::send(&mut webhook, &alert).unwrap();
+    let (request, body) = server.join().unwrap();
+    assert!(request.starts_with("POST /hook HTTP/1.1\r\n"));
+    assert_eq!(body, "{\"text\": \"provider feed is stale\"}");
+}
+
+#[test]
+fn test_from_config() {
+    let mut dex = Dex::new();
+    dex.register_provider("feed", 1);
+    let path = Path::new("best-rate.conf");
+    let mut config = Config::new();
+    config
+        .load_str(
+            "[alert.jpy]\n\
+             pair = USD/JPY\n\
+             threshold = 0.01\n\
+             webhook = http://127.0.0.1:9000/hook\n\
+             [alert.arb]\n\
+             min_bps = 10\n\
+             graph = crypto\n\
+             webhook = http://127.0.0.1:9000/hook\n\
+             [alert.feed]\n\
+             provider = feed\n\
+             max_age_ms = 60000\n\
+             webhook = http://127.0.0.1:9000/hook\n",
+            path,
+        )
+        .unwrap();
+    let alerters = Alerter::from_config(&config, "crypto", &dex).unwrap();
+    let rules: Vec<_> = alerters.iter().map(|alerter| &alerter.rules[0]).collect();
+    assert_eq!(
+        rules,
+        [
+            &Rule::Arbitrage { min_bps: 10.0 },
+            &Rule::ProviderStale {
+                provider: dex.providers().next().unwrap().0,
+                max_age: Duration::from_secs(60),
+            },
+            &Rule::RateMove {
+                src: "USD".parse().unwrap(),
+                dst: "JPY".parse().unwrap(),
+                threshold: 0.01,
+            },
+        ]
+    );
+    assert_eq!(Alerter::from_config(&config, "fx", &dex).unwrap().len(), 2);
+
+    let error = Alerter::from_config(&config, "fx", &Dex::new()).unwrap_err();
+    assert_eq!(error.to_string(), "alert feed: unknown provider \"feed\"");
+    config.load_str("[alert.jpy]\npair = USDJPY", path).unwrap();
+    let error = Alerter::from_config(&config, "fx", &dex).unwrap_err();
+    assert_eq!(error.to_string(), "alert jpy: invalid pair \"USDJPY\"");
+}
@@ -0,0 +1,63 @@
+//! Vertex aliases and display labels
+
+use tracing::debug;
+
+use super::{Dex, ParseVertexError, Path, Vertex};
+
+impl Dex {
+    /// Registers the `alias` symbol of the canonical `v`, e.g. `XBT` of
+    /// `BTC`.
+    ///
+    /// The aliased vertices are resolved to the canonical one on the
+    /// insert as well as on [`Dex::resolve`] and the queries, so that
+    /// the feeds disagreeing on the symbols share the same vertex.  The
+    /// rates between the aliases of the same vertex are ignored, and
+    /// rejected by [`Dex::load_csv`].
+    pub fn add_alias<V: Into<Vertex>>(&mut self, alias: V, v: V) {
+        let alias = alias.into();
+        let v = self.canonical(v.into());
+        assert!(alias != v);
+        debug!(%alias, %v, "alias");
+        self.aliases.insert(alias, v);
+    }
+
+    /// Returns the canonical vertex of the symbol, resolving the alias.
+    pub fn resolve(&self, symbol: &str) -> Result<Vertex, ParseVertexError> {
+        symbol.parse().map(|v| self.canonical(v))
+    }
+
+    /// Sets the human-readable label of the vertex, e.g. `WETH` of the
+    /// token address.
+    pub fn set_label<V: Into<Vertex>>(&mut self, v: V, label: &str) {
+        let v = self.canonical(v.into());
+        self.labels.insert(v, label.to_string());
+    }
+
+    /// Returns the label of the vertex, if any.
+    pub fn label(&self, v: &Vertex) -> Option<&str> {
+        self.labels.get(v).map(String::as_str)
+    }
+
+    /// Returns the name to display the vertex with, the label or the
+    /// symbol.
+    pub fn display_name(&self, v: &Vertex) -> String {
+        match self.label(v) {
+            Some(label) => label.to_string(),
+            None => v.to_string(),
+        }
+    }
+
+    /// Formats the path as its [`Display`](std::fmt::Display), with
+    /// the vertex display names, e.g. `WETH -> USDC: 1800`.
+    pub fn display_path(&self, path: &Path) -> String {
+        let names: Vec<_> = path.path.iter().map(|v| self.display_name(v)).collect();
+        format!("{}: {}", names.join(" -> "), path.rate)
+    }
+
+    pub(crate) fn canonical(&self, v: Vertex) -> Vertex {
+        self.aliases.get(&v).copied().unwrap_or(v)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,70 @@
+use super::*;
+use crate::test::vertex;
+
+#[test]
+fn test_add_alias() {
+    let mut dex = Dex::new();
+    dex.add_alias(vertex("XBT"), vertex("BTC"));
+    dex.add_rate(vertex("XBT"), vertex("USD"), 30000.0);
+    dex.add_rate(vertex("BTC"), vertex("EUR"), 28000.0);
+    let vertices: Vec<_> = dex.vertices().map(Vertex::as_str).collect();
+    assert_eq!(vertices, ["BTC", "EUR", "USD"]);
+    let btc = dex.resolve("XBT").unwrap();
+    assert_eq!(btc, vertex("BTC"));
+    let path = dex.get_best_rate(&btc, &vertex("USD")).unwrap();
+    assert_eq!(path.rate(), 30000.0);
+
+    // The queries resolve the aliases as well.
+    let path = dex.get_best_rate(&vertex("XBT"), &vertex("EUR")).unwrap();
+    assert_eq!(path.to_string(), "BTC -> EUR: 28000");
+    let paths = dex.get_best_rates_from(&vertex("XBT"), &Default::default());
+    assert_eq!(paths.len(), 2);
+}
+
+#[test]
+fn test_resolve() {
+    let dex = Dex::new();
+    assert_eq!(dex.resolve("ETH"), Ok(vertex("ETH")));
+    assert!(dex.resolve("").is_err());
+}
+
+#[test]
+fn test_load_csv_alias() {
+    let mut dex = Dex::new();
+    dex.add_alias(vertex("XBT"), vertex("BTC"));
+    let count = dex
+        .load_csv("XBT,USD,30000\nBTC,EUR,28000\n".as_bytes())
+        .unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(dex.vertices().count(), 3);
+
+    // The rate between the aliases of the same vertex.
+    let err = dex.load_csv("XBT,BTC,1.0\n".as_bytes()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"line 1: rate between the aliases: "XBT,BTC,1.0""#
+    );
+    assert_eq!(dex.add_rate(vertex("XBT"), vertex("BTC"), 1.0), None);
+    let id = dex.register_provider("kraken", 0);
+    assert_eq!(
+        dex.add_provider_rate(id, vertex("BTC"), vertex("XBT"), 1.0),
+        None
+    );
+    assert_eq!(dex.vertices().count(), 3);
+}
+
+#[test]
+fn test_label() {
+    let mut dex = Dex::new();
+    dex.add_alias(vertex("XETH"), vertex("0xc02aaa39"));
+    dex.set_label(vertex("XETH"), "WETH");
+    dex.add_rate(vertex("0xc02aaa39"), vertex("USDC"), 1800.0);
+    let weth = vertex("0xc02aaa39");
+    let usdc = vertex("USDC");
+    assert_eq!(dex.label(&weth), Some("WETH"));
+    assert_eq!(dex.label(&usdc), None);
+    assert_eq!(dex.display_name(&weth), "WETH");
+    assert_eq!(dex.display_name(&usdc), "USDC");
+    let path = dex.get_best_rate(&weth, &usdc).unwrap();
+    assert_eq!(dex.display_path(&path), "WETH -> USDC: 1800");
+}
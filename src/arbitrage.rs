@@ -0,0 +1,285 @@
+//! Arbitrage execution planner
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex};
+use crate::cancel::{CancelToken, Timeout};
+use crate::cycle::{Cycle, MAX_CYCLE_LEN};
+use crate::progress::Progress;
+use crate::simulate::Hop;
+
+/// The sized arbitrage cycle, starting and ending in the same currency.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArbTrade {
+    pub hops: Vec<Hop>,
+}
+
+impl fmt::Display for ArbTrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for hop in &self.hops {
+            write!(f, "{} -> ", hop.src)?;
+        }
+        write!(
+            f,
+            "{}: {} -> {} ({} profit)",
+            self.start(),
+            self.amount_in(),
+            self.amount_out(),
+            self.profit(),
+        )
+    }
+}
+
+impl ArbTrade {
+    /// Returns the currency the trade starts and ends with.
+    pub fn start(&self) -> &Vertex {
+        &self.hops[0].src
+    }
+
+    pub fn amount_in(&self) -> f32 {
+        self.hops[0].amount_in
+    }
+
+    pub fn amount_out(&self) -> f32 {
+        self.hops[self.hops.len() - 1].amount_out
+    }
+
+    /// Returns the expected profit net of the fees, in the start
+    /// currency.
+    pub fn profit(&self) -> f32 {
+        self.amount_out() - self.amount_in()
+    }
+}
+
+/// The executable sequence of the arbitrage trades.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ArbPlan {
+    pub trades: Vec<ArbTrade>,
+}
+
+impl ArbPlan {
+    /// Returns the total expected profit by the currency.
+    pub fn profit(&self) -> BTreeMap<Vertex, f32> {
+        let mut profit = BTreeMap::new();
+        for trade in &self.trades {
+            *profit.entry(*trade.start()).or_default() += trade.profit();
+        }
+        profit
+    }
+}
+
+impl Dex {
+    /// Returns the arbitrage cycles with the profit above `min_bps` net
+    /// of the costs.
+    ///
+    /// The cycles are checked against the rates net of the fees, and
+    /// against the fixed fees of each hop, e.g. the gas, in case the
+    /// `amount` of the first currency is given.  The gross rate is still
+    /// available as [`Cycle::rate`].
+    #[instrument(level = "debug", skip(self))]
+    pub fn find_arbitrage(&self, min_bps: f32, amount: Option<f32>) -> Vec<Cycle> {
+        self.find_arbitrage_with_progress(min_bps, amount, |_| {})
+    }
+
+    /// Returns the arbitrage cycles as [`Dex::find_arbitrage`], and
+    /// calls `progress` as the scan goes through the start vertices.
+    pub fn find_arbitrage_with_progress<F>(
+        &self,
+        min_bps: f32,
+        amount: Option<f32>,
+        progress: F,
+    ) -> Vec<Cycle>
+    where
+        F: FnMut(Progress),
+    {
+        self.try_find_arbitrage(min_bps, amount, &CancelToken::new(), progress)
+            .unwrap_or_else(Timeout::into_partial)
+    }
+
+    /// Returns the arbitrage cycles as [`Dex::find_arbitrage`], or the
+    /// [`Timeout`] error with the ones found so far once the `cancel`
+    /// token is cancelled.
+    pub fn try_find_arbitrage<F>(
+        &self,
+        min_bps: f32,
+        amount: Option<f32>,
+        cancel: &CancelToken,
+        mut progress: F,
+    ) -> Result<Vec<Cycle>, Timeout<Vec<Cycle>>>
+    where
+        F: FnMut(Progress),
+    {
+        let filter = |cycles: Vec<Cycle>| -> Vec<Cycle> {
+            cycles
+                .into_iter()
+                .filter(|cycle| is_profitable(cycle, min_bps, amount))
+                .collect()
+        };
+        self.try_check_consistency(min_bps / 10_000.0, cancel, &mut progress)
+            .map(filter)
+            .map_err(|timeout| timeout.map(filter))
+    }
+
+    /// Calls `f` with each arbitrage cycle of [`Dex::find_arbitrage`] as
+    /// it's found, until the `cancel` token is cancelled.
+    ///
+    /// The cycles come in the order of the search rather than of the
+    /// vertices.  The cycles through the equivalent vertices are only
+    /// known to be the best of their class once all are found, so those
+    /// are called back at the end of the search instead.  Returns false
+    /// in case it's cancelled before the end.
+    #[instrument(level = "debug", skip(self, cancel, f))]
+    pub fn for_each_arbitrage<F>(
+        &self,
+        min_bps: f32,
+        amount: Option<f32>,
+        cancel: &CancelToken,
+        mut f: F,
+    ) -> bool
+    where
+        F: FnMut(Cycle),
+    {
+        if !self.equivalences.is_empty() {
+            return match self.try_find_arbitrage(min_bps, amount, cancel, |_| {}) {
+                Ok(cycles) => {
+                    cycles.into_iter().for_each(f);
+                    true
+                }
+                Err(timeout) => {
+                    timeout.into_partial().into_iter().for_each(f);
+                    false
+                }
+            };
+        }
+        let epsilon = min_bps / 10_000.0;
+        self.cycles(
+            MAX_CYCLE_LEN,
+            |cycle| {
+                if (cycle.rate() - 1.0).abs() > epsilon && is_profitable(&cycle, min_bps, amount) {
+                    f(cycle);
+                }
+            },
+            cancel,
+            &mut |_| {},
+        )
+    }
+
+    /// Plans the arbitrage of the cycles above `min_bps` with the
+    /// available `capital` of each currency.
+    ///
+    /// The cycles are taken in the order of the rate net of the fees,
+    /// each started from the currency with the most capital, and sized
+    /// to the capital and the edge liquidity left by the earlier
+    /// trades.  The profit of each trade adds to the capital of the
+    /// next ones, and the trades not profitable after the fixed fees
+    /// are dropped.
+    #[instrument(level = "debug", skip(self, capital))]
+    pub fn plan_arbitrage<V>(&self, capital: &[(V, f32)], min_bps: f32) -> ArbPlan
+    where
+        V: Into<Vertex> + Copy,
+    {
+        let mut capital: BTreeMap<Vertex, f32> = capital
+            .iter()
+            .map(|(v, amount)| ((*v).into(), *amount))
+            .collect();
+        let mut cycles = self.find_arbitrage(min_bps, None);
+        cycles.sort_by(|a, b| {
+            b.net_rate()
+                .partial_cmp(&a.net_rate())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mut residual = self.clone();
+        let mut plan = ArbPlan::default();
+        for cycle in cycles {
+            let start = cycle
+                .edges()
+                .iter()
+                .map(|(src, _, _)| src)
+                .filter(|src| capital.get(src).copied().unwrap_or_default() > 0.0)
+                .max_by(|a, b| {
+                    capital[a]
+                        .partial_cmp(&capital[b])
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+            let start = match start {
+                Some(start) => *start,
+                None => continue,
+            };
+            if let Some(trade) = residual.size_cycle(&cycle, &start, capital[&start]) {
+                debug!(%trade, "arbitrage");
+                *capital.get_mut(&start).unwrap() += trade.profit();
+                plan.trades.push(trade);
+            }
+        }
+        plan
+    }
+
+    // Sizes the cycle from `start` up to `capital`, and consumes the
+    // liquidity in case it's profitable.
+    fn size_cycle(&mut self, cycle: &Cycle, start: &Vertex, capital: f32) -> Option<ArbTrade> {
+        let i = cycle.edges().iter().position(|(src, _, _)| src == start)?;
+        let hops: Vec<_> = cycle.edges()[i..]
+            .iter()
+            .chain(&cycle.edges()[..i])
+            .map(|(src, dst, _)| (*src, *dst))
+            .collect();
+
+        // The bottleneck in the start currency.
+        let mut amount = capital;
+        let mut rate = 1.0;
+        for (src, dst) in &hops {
+            let edge = self.edges.get(src)?.get(dst)?;
+            if let Some(liquidity) = edge.liquidity {
+                amount = amount.min(liquidity / rate);
+            }
+            rate *= edge.effective_rate(None);
+        }
+        if amount <= 0.0 {
+            return None;
+        }
+
+        let mut edges = Vec::with_capacity(hops.len());
+        let mut trade = ArbTrade { hops: Vec::new() };
+        let mut amount_in = amount;
+        for (src, dst) in hops {
+            let mut edge = self.edges[&src][&dst].clone();
+            let hop = Hop {
+                src,
+                dst,
+                amount_in,
+                amount_out: edge.consume(amount_in),
+                fee: edge.fees(amount_in),
+                quoted_rate: self.edges[&src][&dst].effective_rate(None),
+                dust: 0.0,
+            };
+            amount_in = hop.amount_out;
+            trade.hops.push(hop);
+            edges.push((src, dst, edge));
+        }
+        if trade.profit() <= 0.0 {
+            return None;
+        }
+        for (src, dst, edge) in edges {
+            self.edges.get_mut(&src).unwrap().insert(dst, edge);
+            self.changes.push(src, dst);
+        }
+        Some(trade)
+    }
+}
+
+// Checks if the cycle makes more than `min_bps` net of the fees, and of
+// the fixed fees in case the `amount` is given.
+fn is_profitable(cycle: &Cycle, min_bps: f32, amount: Option<f32>) -> bool {
+    let min_rate = 1.0 + min_bps / 10_000.0;
+    match amount {
+        Some(amount) => cycle.amount_out(amount) > amount * min_rate,
+        None => cycle.net_rate() > min_rate,
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use crate::cancel::CancelToken;
+use crate::{Dex, Edge};
+
+#[test]
+fn test_plan_arbitrage() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_liquidity(50.0));
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 0.2);
+
+    let plan = dex.plan_arbitrage(&[('B', 1_000.0)], 10.0);
+    assert_eq!(plan.trades.len(), 1);
+    let trade = &plan.trades[0];
+    assert_eq!(trade.start(), &'B'.into());
+    assert_eq!(trade.hops.len(), 3);
+
+    // B -> C -> A -> B, limited by the A -> B liquidity.
+    assert!((trade.amount_out() - 100.0).abs() < 1e-3);
+    assert!((trade.amount_in() - 100.0 / 1.2).abs() < 1e-3);
+    assert!((plan.profit()[&'B'.into()] - (100.0 - 100.0 / 1.2)).abs() < 1e-3);
+}
+
+#[test]
+fn test_plan_arbitrage_net_of_fees() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_fee(0.2));
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 0.2);
+
+    // 1.2 gross, but 0.96 net of the fee.
+    assert!(dex.plan_arbitrage(&[('A', 100.0)], 10.0).trades.is_empty());
+}
+
+#[test]
+fn test_plan_arbitrage_without_capital() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 0.2);
+
+    let plan = dex.plan_arbitrage(&[('D', 100.0)], 10.0);
+    assert!(plan.trades.is_empty());
+
+    let plan = dex.plan_arbitrage(&[('A', 100.0)], 10.0);
+    assert_eq!(plan.trades.len(), 1);
+    assert_eq!(plan.trades[0].amount_in(), 100.0);
+    assert!((plan.trades[0].profit() - 20.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_find_arbitrage() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_fee(0.1));
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_edge('C', 'A', Edge::bridge(0.2, 1.0, 0.0, Duration::ZERO));
+
+    let cycles = dex.find_arbitrage(10.0, None);
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].to_string(), "A -> B -> C -> A: 1.2");
+    assert!((cycles[0].net_rate() - 1.08).abs() < 1e-6);
+
+    // 100 A -> 180 B -> 540 C -> 107 A, net of the 1 A bridge gas.
+    assert!((cycles[0].amount_out(100.0) - 107.0).abs() < 1e-3);
+    assert_eq!(dex.find_arbitrage(10.0, Some(100.0)).len(), 1);
+    assert!(dex.find_arbitrage(10.0, Some(10.0)).is_empty());
+    assert!(dex.find_arbitrage(1_000.0, None).is_empty());
+}
+
+#[test]
+fn test_for_each_arbitrage() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_fee(0.1));
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 0.2);
+    dex.add_rate('C', 'D', 1.0);
+    dex.add_rate('D', 'A', 0.25);
+
+    let mut cycles = Vec::new();
+    assert!(
+        dex.for_each_arbitrage(10.0, None, &CancelToken::new(), |cycle| {
+            cycles.push(cycle)
+        })
+    );
+    assert_eq!(cycles, dex.find_arbitrage(10.0, None));
+
+    // Cancelled before the first start vertex.
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let mut found = 0;
+    assert!(!dex.for_each_arbitrage(10.0, None, &cancel, |_| found += 1));
+    assert_eq!(found, 0);
+
+    // The equivalent cycles are deduplicated at the end of the search.
+    dex.add_equivalence('C', 'D', 0.0);
+    let mut cycles = Vec::new();
+    assert!(
+        dex.for_each_arbitrage(10.0, None, &CancelToken::new(), |cycle| {
+            cycles.push(cycle)
+        })
+    );
+    assert_eq!(cycles, dex.find_arbitrage(10.0, None));
+}
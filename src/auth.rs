@@ -0,0 +1,171 @@
+//! API-key authentication
+
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+use crate::config::{Config, ConfigError};
+use crate::fetch::{Bucket, RateLimit};
+use crate::server::Request;
+
+/// The API key, with the optional rate limit.
+#[derive(Clone, PartialEq)]
+pub struct ApiKey {
+    name: String,
+    key: String,
+    rate_limit: Option<RateLimit>,
+    write: bool,
+}
+
+/// Never prints the key itself.
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("name", &self.name)
+            .field("rate_limit", &self.rate_limit)
+            .field("write", &self.write)
+            .finish()
+    }
+}
+
+impl ApiKey {
+    /// Creates the `key` allowed to read and write.
+    pub fn new(name: &str, key: &str) -> Self {
+        assert!(!key.is_empty());
+        Self {
+            name: name.to_string(),
+            key: key.to_string(),
+            rate_limit: None,
+            write: true,
+        }
+    }
+
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Allows the mutation, e.g. `POST /rates`, or not.
+    pub fn with_write(mut self, write: bool) -> Self {
+        self.write = write;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit
+    }
+
+    pub fn write(&self) -> bool {
+        self.write
+    }
+}
+
+/// The request denied by the [`Auth`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Denied {
+    Missing,
+    Invalid,
+    ReadOnly,
+    RateLimited(Duration),
+}
+
+impl fmt::Display for Denied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "missing api key"),
+            Self::Invalid => write!(f, "invalid api key"),
+            Self::ReadOnly => write!(f, "read-only api key"),
+            Self::RateLimited(_) => write!(f, "rate limited"),
+        }
+    }
+}
+
+/// The API keys accepted by the server, by the `X-Api-Key` or the
+/// `Authorization: Bearer` header.
+#[derive(Debug, Default)]
+pub struct Auth {
+    keys: Vec<(ApiKey, Mutex<Option<Bucket>>)>,
+}
+
+impl Auth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn key(mut self, key: ApiKey) -> Self {
+        let bucket = key.rate_limit.map(Bucket::new);
+        self.keys.push((key, Mutex::new(bucket)));
+        self
+    }
+
+    /// Loads the keys from the `[api_key.<NAME>]` sections, or returns
+    /// `None` without any.
+    ///
+    /// The `rate_limit` is the requests per second, with the `burst` of
+    /// the same by default.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, ConfigError> {
+        let names = config.names("api_key");
+        if names.is_empty() {
+            return Ok(None);
+        }
+        let mut auth = Self::new();
+        for name in names {
+            let setting = |key: &str| format!("api_key.{name}.{key}");
+            let key = config
+                .get(&setting("key"))
+                .ok_or_else(|| ConfigError::new(format!("missing {}", setting("key"))))?;
+            let mut key = ApiKey::new(name, key);
+            if let Some(write) = config.parse(&setting("write"))? {
+                key = key.with_write(write);
+            }
+            if let Some(per_second) = config.parse::<f32>(&setting("rate_limit"))? {
+                let burst = config.parse(&setting("burst"))?;
+                if per_second.is_nan() || per_second <= 0.0 || burst == Some(0) {
+                    return Err(ConfigError::new(format!("invalid {name} rate limit")));
+                }
+                let burst = burst.unwrap_or_else(|| (per_second.ceil() as u32).max(1));
+                key = key.with_rate_limit(RateLimit::new(per_second, burst));
+            }
+            auth = auth.key(key);
+        }
+        Ok(Some(auth))
+    }
+
+    /// Authenticates the request at `now`, and returns the key name.
+    pub(crate) fn check(&self, request: &Request, now: Instant) -> Result<&str, Denied> {
+        let presented = match (request.header("X-Api-Key"), request.header("Authorization")) {
+            (Some(key), _) => key,
+            (None, Some(value)) => value.strip_prefix("Bearer ").ok_or(Denied::Missing)?,
+            (None, None) => return Err(Denied::Missing),
+        };
+        // Compares with all the keys to not leak the match by the time.
+        let mut found = None;
+        for (i, (key, _)) in self.keys.iter().enumerate() {
+            if constant_time_eq(key.key.as_bytes(), presented.trim().as_bytes()) {
+                found = Some(i);
+            }
+        }
+        let (key, bucket) = &self.keys[found.ok_or(Denied::Invalid)?];
+        if request.method != "GET" && !key.write {
+            return Err(Denied::ReadOnly);
+        }
+        if let Some(bucket) = bucket.lock().unwrap().as_mut() {
+            bucket.take(now).map_err(Denied::RateLimited)?;
+        }
+        debug!(key = %key.name, "authenticated");
+        Ok(&key.name)
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod test;
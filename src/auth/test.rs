@@ -0,0 +1,90 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::{ApiKey, Auth, Denied};
+use crate::config::Config;
+use crate::fetch::RateLimit;
+use crate::server::Request;
+
+fn request(method: &str, header: Option<(&str, &str)>) -> Request {
+    Request {
+        method: method.into(),
+        path: "/rates".into(),
+        headers: header
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .into_iter()
+            .collect(),
+        ..Request::default()
+    }
+}
+
+#[test]
+fn test_check() {
+    let auth = Auth::new().key(ApiKey::new("ops", "s3cret")).key(
+        ApiKey::new("dashboard", "read0nly")
+            .with_write(false)
+            .with_rate_limit(RateLimit::new(1.0, 1)),
+    );
+    let now = Instant::now();
+    assert_eq!(auth.check(&request("GET", None), now), Err(Denied::Missing));
+    assert_eq!(
+        auth.check(&request("GET", Some(("x-api-key", "guess"))), now),
+        Err(Denied::Invalid)
+    );
+    assert_eq!(
+        auth.check(&request("POST", Some(("X-Api-Key", "s3cret"))), now),
+        Ok("ops")
+    );
+    assert_eq!(
+        auth.check(
+            &request("POST", Some(("Authorization", "Bearer read0nly"))),
+            now
+        ),
+        Err(Denied::ReadOnly)
+    );
+    assert_eq!(
+        auth.check(
+            &request("GET", Some(("Authorization", "Bearer read0nly"))),
+            now
+        ),
+        Ok("dashboard")
+    );
+    assert_eq!(
+        auth.check(&request("GET", Some(("X-Api-Key", "read0nly"))), now),
+        Err(Denied::RateLimited(Duration::from_secs(1)))
+    );
+    assert!(format!("{auth:?}").find("s3cret").is_none());
+}
+
+#[test]
+fn test_from_config() {
+    let path = Path::new("best-rate.conf");
+    let mut config = Config::new();
+    assert!(Auth::from_config(&config).unwrap().is_none());
+    config
+        .load_str(
+            "[api_key.ops]\n\
+             key = \"s3cret\"\n\
+             rate_limit = 2.5\n\
+             [api_key.dashboard]\n\
+             key = \"read0nly\"\n\
+             write = false\n",
+            path,
+        )
+        .unwrap();
+    assert!(!config.to_string().contains("s3cret"));
+    let auth = Auth::from_config(&config).unwrap().unwrap();
+    let keys: Vec<_> = auth.keys.iter().map(|(key, _)| key).collect();
+    assert_eq!(keys[0].name(), "dashboard");
+    assert!(!keys[0].write());
+    assert_eq!(keys[1].rate_limit(), Some(RateLimit::new(2.5, 3)));
+
+    config
+        .load_str("[api_key.ci]\nwrite = true\n", path)
+        .unwrap();
+    assert!(Auth::from_config(&config).is_err());
+    config
+        .load_str("[api_key.ci]\nkey = k\nrate_limit = 0\n", path)
+        .unwrap();
+    assert!(Auth::from_config(&config).is_err());
+}
@@ -0,0 +1,112 @@
+//! Liquidity-weighted backbone extraction
+
+use std::collections::{BTreeMap, HashSet};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex};
+use crate::query::QueryOptions;
+
+impl Dex {
+    /// Extracts the backbone of the graph, the reduced graph to route
+    /// over.
+    ///
+    /// The backbone keeps the maximum liquidity spanning forest, for the
+    /// vertices to stay connected through the deepest markets, and the
+    /// edges within the `tolerance` of the best rate of their own pair,
+    /// e.g. `0.001` for 10 bps.  As the hops of the best paths are the
+    /// best paths of their pairs, the best paths are all kept, and the
+    /// edges dominated by the other routes beyond the tolerance are
+    /// dropped.
+    #[instrument(level = "debug", skip(self))]
+    pub fn backbone(&self, tolerance: f32) -> Dex {
+        assert!((0.0..1.0).contains(&tolerance));
+        let mut keep = self.spanning_forest();
+        let options = QueryOptions::new();
+        for (src, edges) in &self.edges {
+            let paths = self.get_best_rates_from(src, &options);
+            for (dst, edge) in edges {
+                let best = paths.get(dst).map_or(0.0, |path| path.rate);
+                if edge.effective_rate(None) >= best * (1.0 - tolerance) {
+                    keep.insert((*src, *dst));
+                }
+            }
+        }
+        let mut backbone = self.clone();
+        backbone.retain_edges(|src, dst, _| keep.contains(&(*src, *dst)));
+        debug!(
+            edges = self.edge_count(),
+            backbone = backbone.edge_count(),
+            "backbone"
+        );
+        backbone
+    }
+
+    // Returns both directions of the pairs of the maximum liquidity
+    // spanning forest, by Kruskal's algorithm.  The edge without the
+    // liquidity limit is the deepest.
+    fn spanning_forest(&self) -> HashSet<(Vertex, Vertex)> {
+        let liquidity = |src: &Vertex, dst: &Vertex| {
+            self.edges
+                .get(src)
+                .and_then(|edges| edges.get(dst))
+                .map(|edge| edge.liquidity().unwrap_or(f32::INFINITY))
+        };
+        let mut pairs = Vec::new();
+        for (src, edges) in &self.edges {
+            for dst in edges.keys() {
+                let reverse = liquidity(dst, src);
+                // The pair is taken once, from its smaller vertex unless
+                // it's one way.
+                if src < dst || reverse.is_none() {
+                    let depth = liquidity(src, dst).into_iter().chain(reverse);
+                    pairs.push((depth.fold(0.0, f32::max), *src, *dst));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then((a.1, a.2).cmp(&(b.1, b.2)))
+        });
+
+        let index: BTreeMap<_, _> = self
+            .edges
+            .keys()
+            .enumerate()
+            .map(|(i, v)| (*v, i))
+            .collect();
+        let mut parents: Vec<_> = (0..index.len()).collect();
+        let mut forest = HashSet::new();
+        for (_, src, dst) in pairs {
+            let (a, b) = (
+                root(&mut parents, index[&src]),
+                root(&mut parents, index[&dst]),
+            );
+            if a == b {
+                continue;
+            }
+            parents[a] = b;
+            forest.insert((src, dst));
+            forest.insert((dst, src));
+        }
+        forest
+    }
+
+    // Returns the number of the directed edges.
+    fn edge_count(&self) -> usize {
+        self.edges.values().map(|edges| edges.len()).sum()
+    }
+}
+
+// Returns the root of the union-find set, with the path halving.
+fn root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod test;
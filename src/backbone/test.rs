@@ -0,0 +1,41 @@
+use crate::{Dex, Edge};
+
+fn dex(ac_liquidity: f32, bc_liquidity: f32) -> Dex {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_liquidity(1_000.0));
+    dex.add_edge('B', 'C', Edge::new(3.0).with_liquidity(bc_liquidity));
+    dex.add_edge('A', 'C', Edge::new(5.0).with_liquidity(ac_liquidity));
+    dex
+}
+
+#[test]
+fn test_backbone() {
+    let dex = dex(10.0, 1_000.0);
+    let backbone = dex.backbone(0.01);
+    assert_eq!(backbone.edge_count(), 5);
+    assert!(!backbone.edges[&'A'.into()].contains_key(&'C'.into()));
+    assert!(backbone.edges[&'C'.into()].contains_key(&'A'.into()));
+    for src in dex.vertices() {
+        for dst in dex.vertices().filter(|dst| *dst != src) {
+            let rate = |dex: &Dex| dex.get_best_rate(src, dst).map(|path| path.rate());
+            assert_eq!(rate(&backbone), rate(&dex), "{src} -> {dst}");
+        }
+    }
+    assert_eq!(dex.backbone(0.2).edge_count(), 6);
+}
+
+#[test]
+fn test_backbone_spanning_forest() {
+    // The deep A-C pair is kept, even though A -> B -> C is better, and
+    // C -> B is dropped for C -> A -> B.
+    let dex = dex(5_000.0, 1.0);
+    let backbone = dex.backbone(0.0);
+    assert_eq!(backbone.edge_count(), 5);
+    assert!(backbone.edges[&'A'.into()].contains_key(&'C'.into()));
+    assert!(!backbone.edges[&'C'.into()].contains_key(&'B'.into()));
+
+    let forest = dex.spanning_forest();
+    assert_eq!(forest.len(), 4);
+    assert!(forest.contains(&('A'.into(), 'C'.into())));
+    assert!(!forest.contains(&('B'.into(), 'C'.into())));
+}
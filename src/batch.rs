@@ -0,0 +1,42 @@
+//! Batch quoting
+
+use std::collections::hash_map::{Entry, HashMap};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path, Vertex};
+use crate::query::QueryOptions;
+
+impl Dex {
+    /// Quotes the best rate paths for each `(src, dst, amount)` request.
+    ///
+    /// The requests with the same source and amount share the single
+    /// source search, which is the common case for the pricing pages.
+    /// The requests with the zero, negative, infinite, or NaN amount are
+    /// quoted as `None`.
+    #[instrument(level = "debug", skip_all, fields(requests = requests.len()))]
+    pub fn quote_many(&self, requests: &[(Vertex, Vertex, f32)]) -> Vec<Option<Path>> {
+        let mut searches = HashMap::new();
+        requests
+            .iter()
+            .map(|(src, dst, amount)| {
+                if !amount.is_finite() || *amount <= 0.0 {
+                    debug!(%src, %dst, %amount, "invalid amount");
+                    return None;
+                }
+                let paths = match searches.entry((*src, amount.to_bits())) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        debug!(%src, %amount, "search");
+                        let options = QueryOptions::new().with_amount(*amount);
+                        entry.insert(self.get_best_rates_from(src, &options))
+                    }
+                };
+                paths.get(dst).cloned()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test;
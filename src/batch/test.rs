@@ -0,0 +1,66 @@
+use crate::query::QueryOptions;
+use crate::{Dex, Edge};
+
+#[test]
+fn test_quote_many() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(1.4).with_liquidity(500.0));
+    dex.add_rate('A', 'C', 0.1);
+    dex.add_rate('B', 'C', 0.2);
+    dex.add_rate('C', 'D', 0.2);
+    dex.add_rate('C', 'B', 12.0);
+
+    let a = 'A'.into();
+    let b = 'B'.into();
+    let d = 'D'.into();
+    let e = 'E'.into();
+    let requests = [
+        (a, b, 100.0),
+        (a, d, 100.0),
+        (a, b, 1_000.0),
+        (b, a, 1.0),
+        (a, e, 1.0),
+    ];
+    let quotes = dex.quote_many(&requests);
+    assert_eq!(quotes.len(), requests.len());
+    for ((src, dst, amount), quote) in requests.iter().zip(&quotes) {
+        let options = QueryOptions::new().with_amount(*amount);
+        let path = dex.get_best_rate_with(src, dst, &options);
+        assert_eq!(
+            quote.as_ref().map(ToString::to_string),
+            path.as_ref().map(ToString::to_string)
+        );
+    }
+    assert_eq!(quotes[2].as_ref().unwrap().to_string(), "A -> C -> B: 1.2");
+    assert!(quotes[4].is_none());
+
+    // The invalid amounts don't fail the rest of the batch.
+    let requests = [
+        (a, b, 0.0),
+        (a, b, -1.0),
+        (a, b, f32::NAN),
+        (a, b, f32::INFINITY),
+        (a, b, 100.0),
+    ];
+    let quotes = dex.quote_many(&requests);
+    assert!(quotes[..4].iter().all(Option::is_none));
+    assert_eq!(quotes[4].as_ref().unwrap().to_string(), "A -> B: 1.4");
+}
+
+#[test]
+fn test_get_best_rates_from() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1.4);
+    dex.add_rate('A', 'C', 0.1);
+    dex.add_rate('B', 'C', 0.2);
+    dex.add_rate('C', 'D', 0.2);
+
+    let paths = dex.get_best_rates_from(&'A'.into(), &QueryOptions::new());
+    assert_eq!(paths.len(), 3);
+    for (dst, path) in &paths {
+        assert_eq!(
+            path.rate(),
+            dex.get_best_rate(&'A'.into(), dst).unwrap().rate()
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Fluent graph builder
+
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use super::{Dex, Edge, Vertex};
+
+/// The builder of [`Dex`], validating all the edges at once.
+///
+/// The edge settings, e.g. [`fee_bps`](Self::fee_bps), apply to the
+/// most recently added edge.
+#[derive(Debug, Default)]
+pub struct DexBuilder {
+    edges: Vec<(Vertex, Vertex, Edge, bool)>,
+    errors: Vec<BuildError>,
+    ops: usize,
+}
+
+/// The invalid builder operation, with its zero-based index.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    SelfLoop { op: usize, vertex: Vertex },
+    InvalidRate { op: usize, rate: f32 },
+    InvalidFee { op: usize, bps: u32 },
+    InvalidLiquidity { op: usize, liquidity: f32 },
+    InvalidRisk { op: usize, risk: f32 },
+    NoEdge { op: usize },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SelfLoop { op, vertex } => write!(f, "#{op}: {vertex} -> {vertex} self loop"),
+            Self::InvalidRate { op, rate } => write!(f, "#{op}: invalid rate {rate}"),
+            Self::InvalidFee { op, bps } => write!(f, "#{op}: invalid fee {bps} bps"),
+            Self::InvalidLiquidity { op, liquidity } => {
+                write!(f, "#{op}: invalid liquidity {liquidity}")
+            }
+            Self::InvalidRisk { op, risk } => write!(f, "#{op}: invalid risk {risk}"),
+            Self::NoEdge { op } => write!(f, "#{op}: no edge to apply the setting"),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+/// All the errors reported by [`DexBuilder::build`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BuildErrors(Vec<BuildError>);
+
+impl fmt::Display for BuildErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, e) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{e}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for BuildErrors {}
+
+impl BuildErrors {
+    pub fn errors(&self) -> &[BuildError] {
+        &self.0
+    }
+}
+
+impl Dex {
+    pub fn builder() -> DexBuilder {
+        DexBuilder::default()
+    }
+}
+
+impl DexBuilder {
+    /// Adds the `src -> dst` rate, as well as the reverse rate.
+    pub fn rate<V: Into<Vertex>>(self, src: V, dst: V, rate: f32) -> Self {
+        self.edge(src.into(), dst.into(), rate, false)
+    }
+
+    /// Adds the `src -> dst` rate only.
+    pub fn directed<V: Into<Vertex>>(self, src: V, dst: V, rate: f32) -> Self {
+        self.edge(src.into(), dst.into(), rate, true)
+    }
+
+    /// Sets the percentage fee in basis points.
+    pub fn fee_bps(mut self, bps: u32) -> Self {
+        let op = self.next_op();
+        if bps >= 10_000 {
+            self.errors.push(BuildError::InvalidFee { op, bps });
+        }
+        self.modify(op, |edge| edge.fee = bps as f32 / 10_000.0)
+    }
+
+    /// Sets the available liquidity in the source currency.
+    pub fn liquidity(mut self, liquidity: f32) -> Self {
+        let op = self.next_op();
+        if liquidity.is_nan() || liquidity < 0.0 {
+            self.errors
+                .push(BuildError::InvalidLiquidity { op, liquidity });
+        }
+        self.modify(op, |edge| edge.liquidity = Some(liquidity))
+    }
+
+    /// Sets the settlement delay.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        let op = self.next_op();
+        self.modify(op, |edge| edge.delay = delay)
+    }
+
+    /// Sets the risk weight.
+    pub fn risk(mut self, risk: f32) -> Self {
+        let op = self.next_op();
+        if risk.is_nan() || risk < 0.0 {
+            self.errors.push(BuildError::InvalidRisk { op, risk });
+        }
+        self.modify(op, |edge| edge.risk = risk)
+    }
+
+    /// Builds the graph, or returns all the errors found.
+    pub fn build(self) -> Result<Dex, BuildErrors> {
+        if !self.errors.is_empty() {
+            return Err(BuildErrors(self.errors));
+        }
+        let mut dex = Dex::new();
+        for (src, dst, edge, directed) in self.edges {
+            if directed {
+                dex.edges.entry(dst).or_default();
+                dex.edges.entry(src).or_default().insert(dst, edge);
+            } else {
+                dex.insert_edge(src, dst, edge);
+            }
+        }
+        Ok(dex)
+    }
+
+    fn edge(mut self, src: Vertex, dst: Vertex, rate: f32, directed: bool) -> Self {
+        let op = self.next_op();
+        if src == dst {
+            self.errors.push(BuildError::SelfLoop { op, vertex: src });
+        }
+        if !rate.is_finite() || rate <= 0.0 {
+            self.errors.push(BuildError::InvalidRate { op, rate });
+        }
+        self.edges.push((src, dst, Edge::new(rate), directed));
+        self
+    }
+
+    fn modify<F: FnOnce(&mut Edge)>(mut self, op: usize, f: F) -> Self {
+        match self.edges.last_mut() {
+            Some((_, _, edge, _)) => f(edge),
+            None => self.errors.push(BuildError::NoEdge { op }),
+        }
+        self
+    }
+
+    fn next_op(&mut self) -> usize {
+        self.ops += 1;
+        self.ops - 1
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,41 @@
+use super::BuildError;
+use crate::Dex;
+
+#[test]
+fn test_build() {
+    let dex = Dex::builder()
+        .rate('A', 'B', 1.4)
+        .directed('B', 'C', 0.2)
+        .fee_bps(30)
+        .rate('C', 'D', 0.2)
+        .liquidity(1_000.0)
+        .build()
+        .unwrap();
+
+    let path = dex.get_best_rate(&'A'.into(), &'D'.into()).unwrap();
+    assert!((path.rate() - 1.4 * 0.2 * 0.997 * 0.2).abs() < 1e-6);
+    assert!(dex.get_best_rate(&'C'.into(), &'B'.into()).is_none());
+}
+
+#[test]
+fn test_build_errors() {
+    let err = Dex::builder()
+        .fee_bps(30)
+        .rate('A', 'A', 1.4)
+        .directed('B', 'C', -0.2)
+        .fee_bps(10_000)
+        .liquidity(f32::NAN)
+        .build()
+        .unwrap_err();
+
+    assert_eq!(err.errors().len(), 5);
+    assert_eq!(err.errors()[0], BuildError::NoEdge { op: 0 });
+    assert_eq!(
+        err.to_string(),
+        "#0: no edge to apply the setting\n\
+         #1: A -> A self loop\n\
+         #2: invalid rate -0.2\n\
+         #3: invalid fee 10000 bps\n\
+         #4: invalid liquidity NaN"
+    );
+}
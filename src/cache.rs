@@ -0,0 +1,210 @@
+//! Amount-bucketed route cache
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path, Vertex};
+use crate::change::Version;
+use crate::edge::Edge;
+use crate::query::QueryOptions;
+
+/// The default number of the amount buckets per the power of ten.
+const BUCKETS_PER_DECADE: u32 = 4;
+
+/// The cache of the amount-aware routes, keyed by the pair and the
+/// amount bucket.
+///
+/// The optimal route is usually stable within the size band, so the
+/// cached route is re-priced for the exact amount instead of searched
+/// again.  The route is invalidated once any of its edges changes, and
+/// all the routes are once any other edge improves, in the rate or in
+/// the liquidity, by more than the tolerance, as the improvement may
+/// open the better route.  The changes are read from the change log of
+/// the graph, see [`Dex::changed_since`].
+#[derive(Debug, Default)]
+pub struct RouteCache {
+    options: QueryOptions,
+    buckets_per_decade: Option<u32>,
+    tolerance: f32,
+    // The edges as of the `version`, to tell the improvement.
+    version: Version,
+    edges: BTreeMap<(Vertex, Vertex), Edge>,
+    routes: HashMap<(Vertex, Vertex, i32), Option<Vec<Vertex>>>,
+    hits: u64,
+    misses: u64,
+}
+
+impl RouteCache {
+    pub fn new(options: QueryOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the number of the amount buckets per the power of ten, e.g.
+    /// `1` for the 1-10, the 10-100 and so on.
+    pub fn with_buckets_per_decade(mut self, buckets: u32) -> Self {
+        assert!(buckets > 0);
+        self.buckets_per_decade = Some(buckets);
+        self
+    }
+
+    /// Sets the relative improvement of the edge tolerated without
+    /// invalidating the routes not through it, e.g. `0.0001` for 1 bp.
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        assert!(tolerance >= 0.0);
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn buckets_per_decade(&self) -> u32 {
+        self.buckets_per_decade.unwrap_or(BUCKETS_PER_DECADE)
+    }
+
+    pub fn tolerance(&self) -> f32 {
+        self.tolerance
+    }
+
+    /// Returns the number of the routes cached.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Returns the number of the queries served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of the queries searched.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Returns the bucket of the `amount`.
+    pub fn bucket(&self, amount: f32) -> i32 {
+        (amount.log10() * self.buckets_per_decade() as f32).floor() as i32
+    }
+
+    /// Returns the best rate path for the `amount` of `src` on the
+    /// current `dex`.
+    pub fn get_best_rate(
+        &mut self,
+        dex: &Dex,
+        src: &Vertex,
+        dst: &Vertex,
+        amount: f32,
+    ) -> Option<Path> {
+        assert!(amount > 0.0);
+        self.refresh(dex);
+        let options = self.options.clone().with_amount(amount);
+        let key = (*src, *dst, self.bucket(amount));
+        match self.routes.get(&key) {
+            Some(None) => {
+                self.hits += 1;
+                return None;
+            }
+            Some(Some(route)) => match price(dex, route, &options) {
+                Some(path) => {
+                    self.hits += 1;
+                    return Some(path);
+                }
+                // The exact amount is out of the route within the bucket,
+                // e.g. over the liquidity, to be searched without replacing
+                // the route for the rest of the bucket.
+                None => {
+                    debug!(%src, %dst, %amount, "unroutable");
+                    self.misses += 1;
+                    return dex.get_best_rate_with(src, dst, &options);
+                }
+            },
+            None => {}
+        }
+        self.misses += 1;
+        let path = dex.get_best_rate_with(src, dst, &options);
+        self.routes
+            .insert(key, path.as_ref().map(|path| path.path.clone()));
+        path
+    }
+
+    // Invalidates the routes by the edge changes since the last query,
+    // read from the change log of the `dex`, or found by comparing all
+    // the edges in case it's another graph or too far behind the log.
+    #[instrument(level = "debug", skip_all)]
+    fn refresh(&mut self, dex: &Dex) {
+        let pairs: BTreeSet<_> = match dex.changed_since(self.version) {
+            Some(changes) => changes.copied().collect(),
+            None => dex
+                .edges
+                .iter()
+                .flat_map(|(src, edges)| edges.keys().map(move |dst| (*src, *dst)))
+                .chain(self.edges.keys().copied())
+                .collect(),
+        };
+        self.version = dex.version();
+        let mut changed = Vec::new();
+        let mut improved = false;
+        for pair in pairs {
+            let edge = dex.edges.get(&pair.0).and_then(|edges| edges.get(&pair.1));
+            match (self.edges.get(&pair), edge) {
+                (Some(old), Some(edge)) if old == edge => continue,
+                (Some(old), Some(edge)) => {
+                    let margin = 1.0 + self.tolerance;
+                    let liquidity = |edge: &Edge| edge.liquidity.unwrap_or(f32::INFINITY);
+                    improved |= edge.effective_rate(None) > old.effective_rate(None) * margin
+                        || liquidity(edge) > liquidity(old) * margin;
+                }
+                (None, Some(_)) => improved = true,
+                (Some(_), None) => {}
+                (None, None) => continue,
+            }
+            match edge {
+                Some(edge) => self.edges.insert(pair, edge.clone()),
+                None => self.edges.remove(&pair),
+            };
+            changed.push(pair);
+        }
+        if changed.is_empty() {
+            return;
+        }
+        if improved {
+            debug!(changed = changed.len(), "improved");
+            self.routes.clear();
+            return;
+        }
+        let before = self.routes.len();
+        self.routes.retain(|_, route| match route {
+            Some(route) => !route
+                .windows(2)
+                .any(|hop| changed.contains(&(hop[0], hop[1]))),
+            None => true,
+        });
+        debug!(
+            changed = changed.len(),
+            invalidated = before - self.routes.len(),
+            "invalidated"
+        );
+    }
+}
+
+// Prices the cached route for the query amount, or `None` in case the
+// route is not routable with it, e.g. over the liquidity or the deadline.
+fn price(dex: &Dex, route: &[Vertex], options: &QueryOptions) -> Option<Path> {
+    let mut path = Path::new(route[0]);
+    for hop in route.windows(2) {
+        let edge = &dex.edges[&hop[0]][&hop[1]];
+        if !options.is_routable(&path, edge) {
+            return None;
+        }
+        path.push(hop[1], edge, options);
+    }
+    Some(path)
+}
+
+#[cfg(test)]
+mod test;
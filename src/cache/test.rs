@@ -0,0 +1,124 @@
+use super::RouteCache;
+use crate::edge::Edge;
+use crate::query::QueryOptions;
+use crate::Dex;
+
+fn dex() -> Dex {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_liquidity(100.0));
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('A', 'C', 5.0);
+    dex.add_rate('C', 'D', 1.0);
+    dex
+}
+
+#[test]
+fn test_bucket() {
+    let cache = RouteCache::new(QueryOptions::new());
+    assert_eq!(cache.buckets_per_decade(), 4);
+    assert_eq!(cache.bucket(1.0), 0);
+    assert_eq!(cache.bucket(1.5), 0);
+    assert_eq!(cache.bucket(2.0), 1);
+    assert_eq!(cache.bucket(10.0), 4);
+    assert_eq!(cache.bucket(0.5), -2);
+    let cache = cache.with_buckets_per_decade(1);
+    assert_eq!(cache.bucket(99.0), 1);
+}
+
+#[test]
+fn test_get_best_rate() {
+    let dex = dex();
+    let (a, d) = ('A'.into(), 'D'.into());
+    let mut cache = RouteCache::new(QueryOptions::new());
+
+    let path = cache.get_best_rate(&dex, &a, &d, 10.0).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D: 6");
+    let path = cache.get_best_rate(&dex, &a, &d, 12.0).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D: 6");
+    assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+    // The other bucket, too large for the A -> B liquidity.
+    let path = cache.get_best_rate(&dex, &a, &d, 1000.0).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> D: 5");
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get_best_rate(&dex, &d, &'E'.into(), 1.0).is_none());
+    assert!(cache.get_best_rate(&dex, &d, &'E'.into(), 1.0).is_none());
+    assert_eq!((cache.hits(), cache.misses()), (2, 3));
+}
+
+#[test]
+fn test_get_best_rate_unroutable() {
+    let mut dex = dex();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_liquidity(50.0));
+    let (a, d) = ('A'.into(), 'D'.into());
+    let mut cache = RouteCache::new(QueryOptions::new()).with_buckets_per_decade(1);
+
+    let path = cache.get_best_rate(&dex, &a, &d, 10.0).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D: 6");
+
+    // Over the A -> B liquidity within the same bucket.
+    let path = cache.get_best_rate(&dex, &a, &d, 90.0).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> D: 5");
+    assert_eq!((cache.hits(), cache.misses()), (0, 2));
+
+    // The cached route still serves the rest of the bucket.
+    let path = cache.get_best_rate(&dex, &a, &d, 20.0).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D: 6");
+    assert_eq!((cache.hits(), cache.misses()), (1, 2));
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn test_invalidate() {
+    let mut dex = dex();
+    let (a, d) = ('A'.into(), 'D'.into());
+    let mut cache = RouteCache::new(QueryOptions::new()).with_tolerance(0.5);
+    cache.get_best_rate(&dex, &a, &d, 10.0).unwrap();
+    cache.get_best_rate(&dex, &a, &d, 1000.0).unwrap();
+    assert_eq!(cache.len(), 2);
+
+    // The worse rate invalidates the routes through it only.
+    dex.add_edge('A', 'B', Edge::new(1.5).with_liquidity(100.0));
+    let path = cache.get_best_rate(&dex, &a, &d, 10.0).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> D: 5");
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.misses(), 3);
+
+    // The route through the changed edge is searched again.
+    dex.add_rate('C', 'D', 1.2);
+    let path = cache.get_best_rate(&dex, &a, &d, 1000.0).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> D: 6");
+    assert_eq!(cache.misses(), 4);
+
+    // The improvement within the tolerance keeps the other routes.
+    dex.add_edge('A', 'B', Edge::new(1.6).with_liquidity(100.0));
+    let path = cache.get_best_rate(&dex, &a, &d, 1000.0).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> D: 6");
+    assert_eq!(cache.misses(), 4);
+
+    // The improvement beyond the tolerance invalidates all.
+    dex.add_rate('B', 'C', 6.0);
+    let path = cache.get_best_rate(&dex, &a, &d, 10.0).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D: 11.52");
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn test_invalidate_removed() {
+    let mut dex = dex();
+    let (a, d) = ('A'.into(), 'D'.into());
+    let mut cache = RouteCache::new(QueryOptions::new());
+    cache.get_best_rate(&dex, &a, &d, 10.0).unwrap();
+
+    let (b, c) = ('B'.into(), 'C'.into());
+    dex.retain_edges(|src, dst, _| (*src, *dst) != (b, c));
+    let path = cache.get_best_rate(&dex, &a, &d, 10.0).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> D: 5");
+    assert_eq!(cache.misses(), 2);
+
+    // The other graph is compared edge by edge.
+    let other = self::dex();
+    let path = cache.get_best_rate(&other, &a, &d, 10.0).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D: 6");
+    assert_eq!(cache.misses(), 3);
+}
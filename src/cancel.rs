@@ -0,0 +1,79 @@
+//! Query timeout and cancellation
+
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::shutdown::Shutdown;
+
+/// The token to cancel the queries from another thread.
+///
+/// The clones share the same state, and cancelling one cancels the
+/// queries with any of them.  The token of the [`Shutdown`] is
+/// cancelled as well once the shutdown is requested, e.g. on Ctrl-C,
+/// see [`Shutdown::cancel_token`].
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    shutdown: Option<Shutdown>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn with_shutdown(shutdown: Shutdown) -> Self {
+        Self {
+            shutdown: Some(shutdown),
+            ..Self::default()
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || matches!(&self.shutdown, Some(shutdown) if shutdown.is_requested())
+    }
+}
+
+/// The query timed out or cancelled, with the best result found so
+/// far.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timeout<T> {
+    partial: T,
+}
+
+impl<T> Timeout<T> {
+    pub(crate) fn new(partial: T) -> Self {
+        Self { partial }
+    }
+
+    /// Returns the best result found before the query stopped.
+    pub fn partial(&self) -> &T {
+        &self.partial
+    }
+
+    pub fn into_partial(self) -> T {
+        self.partial
+    }
+
+    pub(crate) fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Timeout<U> {
+        Timeout::new(f(self.partial))
+    }
+}
+
+impl<T> fmt::Display for Timeout<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "query timed out")
+    }
+}
+
+impl<T: fmt::Debug> Error for Timeout<T> {}
+
+#[cfg(test)]
+mod test;
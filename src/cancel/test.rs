@@ -0,0 +1,59 @@
+use super::{CancelToken, Timeout};
+use crate::shutdown::Shutdown;
+use crate::Dex;
+
+#[test]
+fn test_cancel_token() {
+    let token = CancelToken::new();
+    let clone = token.clone();
+    assert!(!token.is_cancelled());
+    clone.cancel();
+    assert!(token.is_cancelled());
+}
+
+#[test]
+fn test_timeout() {
+    let timeout = Timeout::new(Some(1.4));
+    assert_eq!(timeout.to_string(), "query timed out");
+    assert_eq!(timeout.partial(), &Some(1.4));
+    assert!(timeout.map(|rate| rate.is_some()).into_partial());
+}
+
+#[test]
+fn test_shutdown_cancel_token() {
+    let shutdown = Shutdown::new();
+    let token = shutdown.cancel_token();
+    assert!(!token.is_cancelled());
+    shutdown.request();
+    assert!(token.is_cancelled());
+}
+
+#[test]
+fn test_cancelled_computations() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 2.0);
+    dex.add_rate('C', 'A', 0.3);
+    let token = CancelToken::new();
+    assert_eq!(
+        dex.try_find_arbitrage(0.0, None, &token, |_| {}),
+        Ok(dex.find_arbitrage(0.0, None))
+    );
+    let report = dex.try_report(1e-4, &token, |_| {}).unwrap();
+    assert!(!report.is_partial());
+
+    token.cancel();
+    let cycles = dex.try_find_arbitrage(0.0, None, &token, |_| {});
+    assert_eq!(cycles.unwrap_err().into_partial(), []);
+    let report = dex.try_report(1e-4, &token, |_| {}).unwrap_err();
+    assert!(report.partial().is_partial());
+    assert!(report.partial().paths().is_empty());
+    let mut json = Vec::new();
+    report.partial().write_json(&mut json).unwrap();
+    assert!(String::from_utf8(json)
+        .unwrap()
+        .contains("\"partial\": true"));
+    let matrix = dex.try_matrix(None, &token, |_| {}).unwrap_err();
+    assert_eq!(matrix.partial().vertices().len(), 3);
+    assert_eq!(matrix.partial().rate(&'A'.into(), &'A'.into()), None);
+}
@@ -0,0 +1,174 @@
+//! Currency centrality over the best paths
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex};
+use crate::query::QueryOptions;
+
+/// The maximum number of the power iterations of the eigenvector
+/// centrality.
+const MAX_ITERATIONS: usize = 100;
+
+/// The convergence threshold of the power iteration.
+const TOLERANCE: f32 = 1e-6;
+
+/// The centrality of the vertex in the best paths.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VertexCentrality {
+    pub vertex: Vertex,
+    /// The number of the best paths through the vertex as the
+    /// intermediary.
+    pub routes: usize,
+    /// The fraction of the best paths through the vertex, the
+    /// betweenness centrality over the best paths.
+    pub betweenness: f32,
+    /// The eigenvector centrality of the graph of the hops taken by the
+    /// best paths, scaled to 1.0 for the most central vertex.
+    pub eigenvector: f32,
+}
+
+/// The centrality of all the vertices, the routing hubs first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Centrality {
+    routes: usize,
+    vertices: Vec<VertexCentrality>,
+}
+
+impl fmt::Display for Centrality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .vertices
+            .iter()
+            .map(|v| v.vertex.to_string().len())
+            .max()
+            .unwrap_or_default();
+        for (i, v) in self.vertices.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{:width$} {:>6} routes {:>6.2}% eigenvector {:.4}",
+                v.vertex.to_string(),
+                v.routes,
+                v.betweenness * 100.0,
+                v.eigenvector,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Centrality {
+    /// Returns the number of the best paths of all the pairs.
+    pub fn routes(&self) -> usize {
+        self.routes
+    }
+
+    /// Returns the centrality of the vertices, by the betweenness and
+    /// then the eigenvector centrality.
+    pub fn vertices(&self) -> &[VertexCentrality] {
+        &self.vertices
+    }
+
+    pub fn get(&self, v: &Vertex) -> Option<&VertexCentrality> {
+        self.vertices.iter().find(|c| c.vertex == *v)
+    }
+
+    /// Returns up to `count` vertices routing any of the best paths,
+    /// e.g. the hubs of [`Dex::hub_index_with`].
+    pub fn hubs(&self, count: usize) -> Vec<Vertex> {
+        self.vertices
+            .iter()
+            .filter(|v| v.routes > 0)
+            .take(count)
+            .map(|v| v.vertex)
+            .collect()
+    }
+}
+
+impl Dex {
+    /// Computes the centrality of the currencies over the best paths of
+    /// all the pairs, to tell the routing hubs.
+    #[instrument(level = "debug", skip(self))]
+    pub fn centrality(&self) -> Centrality {
+        let options = QueryOptions::new();
+        let index: BTreeMap<_, _> = self.vertices().enumerate().map(|(i, v)| (*v, i)).collect();
+        let mut through = vec![0; index.len()];
+        let mut hops = vec![vec![0.0f32; index.len()]; index.len()];
+        let mut routes = 0;
+        for src in self.vertices() {
+            for path in self.get_best_rates_from(src, &options).values() {
+                routes += 1;
+                for v in &path.path[1..path.len() - 1] {
+                    through[index[v]] += 1;
+                }
+                for hop in path.path.windows(2) {
+                    // The hops are taken as undirected, for the
+                    // symmetric matrix to converge.
+                    let (u, v) = (index[&hop[0]], index[&hop[1]]);
+                    hops[u][v] += 1.0;
+                    hops[v][u] += 1.0;
+                }
+            }
+        }
+        let eigenvector = power_iteration(&hops);
+        let mut vertices: Vec<_> = index
+            .iter()
+            .map(|(v, i)| VertexCentrality {
+                vertex: *v,
+                routes: through[*i],
+                betweenness: if routes == 0 {
+                    0.0
+                } else {
+                    through[*i] as f32 / routes as f32
+                },
+                eigenvector: eigenvector[*i],
+            })
+            .collect();
+        vertices.sort_by(|a, b| {
+            b.routes.cmp(&a.routes).then(
+                b.eigenvector
+                    .partial_cmp(&a.eigenvector)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+        });
+        debug!(routes, vertices = vertices.len(), "centrality");
+        Centrality { routes, vertices }
+    }
+}
+
+// Returns the principal eigenvector of the non-negative matrix, scaled
+// to the maximum of 1.0.
+fn power_iteration(matrix: &[Vec<f32>]) -> Vec<f32> {
+    let mut vector = vec![1.0; matrix.len()];
+    for _ in 0..MAX_ITERATIONS {
+        // The identity is added, for the bipartite graphs to converge.
+        let mut next: Vec<f32> = matrix
+            .iter()
+            .zip(&vector)
+            .map(|(row, x)| x + row.iter().zip(&vector).map(|(a, x)| a * x).sum::<f32>())
+            .collect();
+        let max = next.iter().copied().fold(0.0, f32::max);
+        if max == 0.0 {
+            return vec![0.0; matrix.len()];
+        }
+        next.iter_mut().for_each(|x| *x /= max);
+        let delta = next
+            .iter()
+            .zip(&vector)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+        vector = next;
+        if delta < TOLERANCE {
+            break;
+        }
+    }
+    vector
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,31 @@
+use crate::test::vertex;
+use crate::Dex;
+
+#[test]
+fn test_centrality() {
+    // The star around USD, with the EUR and GBP spoke better than USD
+    // only from GBP.
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("USD"), vertex("EUR"), 0.9);
+    dex.add_rate(vertex("USD"), vertex("GBP"), 0.8);
+    dex.add_rate(vertex("USD"), vertex("JPY"), 150.0);
+    dex.add_rate(vertex("EUR"), vertex("GBP"), 0.88);
+
+    let centrality = dex.centrality();
+    assert_eq!(centrality.routes(), 12);
+    let usd = centrality.get(&vertex("USD")).unwrap();
+    assert_eq!(usd.routes, 5);
+    assert!((usd.betweenness - 5.0 / 12.0).abs() < 1e-6);
+    assert_eq!(usd.eigenvector, 1.0);
+    assert_eq!(centrality.vertices()[0].vertex, vertex("USD"));
+    let jpy = centrality.get(&vertex("JPY")).unwrap();
+    assert_eq!(jpy.routes, 0);
+    assert!(jpy.eigenvector < 1.0);
+    assert_eq!(centrality.hubs(1), [vertex("USD")]);
+    assert!(!centrality.hubs(4).contains(&vertex("JPY")));
+    assert!(centrality
+        .to_string()
+        .starts_with("USD      5 routes  41.67% eigenvector 1.0000\n"));
+
+    assert_eq!(Dex::new().centrality().routes(), 0);
+}
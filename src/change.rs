@@ -0,0 +1,92 @@
+//! Edge change log
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::{Dex, Vertex};
+
+/// The maximum number of the edge changes kept, beyond which the
+/// readers behind are told to compare all the edges instead.
+const MAX_CHANGES: usize = 4_096;
+
+/// The identity of the next change log.
+static NEXT_LOG: AtomicU64 = AtomicU64::new(1);
+
+/// The position in the edge change log of the [`Dex`], see
+/// [`Dex::changed_since`].
+///
+/// The default one is of no graph, and is behind any graph.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Version {
+    log: u64,
+    seq: u64,
+}
+
+// The directed edges changed, added or removed, with the identity of
+// the graph.  The clone is another graph of its own.
+#[derive(Debug)]
+pub(crate) struct ChangeLog {
+    id: u64,
+    seq: u64,
+    changes: VecDeque<(Vertex, Vertex)>,
+}
+
+impl Default for ChangeLog {
+    fn default() -> Self {
+        Self {
+            id: NEXT_LOG.fetch_add(1, Ordering::Relaxed),
+            seq: 0,
+            changes: VecDeque::new(),
+        }
+    }
+}
+
+impl Clone for ChangeLog {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl ChangeLog {
+    pub(crate) fn push(&mut self, src: Vertex, dst: Vertex) {
+        if self.changes.len() == MAX_CHANGES {
+            self.changes.pop_front();
+        }
+        self.changes.push_back((src, dst));
+        self.seq += 1;
+    }
+
+    // Starts the log over, for the changes too many to log, e.g. all
+    // the rates normalized.
+    pub(crate) fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Dex {
+    /// Returns the current position in the edge change log.
+    pub fn version(&self) -> Version {
+        Version {
+            log: self.changes.id,
+            seq: self.changes.seq,
+        }
+    }
+
+    /// Returns the directed edges changed since the `version`, possibly
+    /// more than once, or `None` in case it's of another graph or too
+    /// far behind, so that the caller compares all the edges instead.
+    pub fn changed_since(
+        &self,
+        version: Version,
+    ) -> Option<impl Iterator<Item = &(Vertex, Vertex)>> {
+        let log = &self.changes;
+        let first = log.seq - log.changes.len() as u64;
+        if version.log != log.id || version.seq < first || version.seq > log.seq {
+            return None;
+        }
+        Some(log.changes.range((version.seq - first) as usize..))
+    }
+}
+
+#[cfg(test)]
+mod test;
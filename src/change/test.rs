@@ -0,0 +1,35 @@
+use super::{Version, MAX_CHANGES};
+use crate::Dex;
+
+#[test]
+fn test_changed_since() {
+    let mut dex = Dex::new();
+    let start = dex.version();
+    assert_eq!(dex.changed_since(start).unwrap().count(), 0);
+    assert!(dex.changed_since(Version::default()).is_none());
+
+    dex.add_rate('A', 'B', 2.0);
+    let version = dex.version();
+    dex.add_rate('B', 'C', 3.0);
+    let changed: Vec<_> = dex.changed_since(version).unwrap().copied().collect();
+    assert_eq!(
+        changed,
+        [('B'.into(), 'C'.into()), ('C'.into(), 'B'.into())]
+    );
+    assert_eq!(dex.changed_since(start).unwrap().count(), 4);
+
+    // The clone is another graph.
+    let clone = dex.clone();
+    assert!(clone.changed_since(dex.version()).is_none());
+
+    // Too far behind.
+    let version = dex.version();
+    for i in 0..MAX_CHANGES {
+        dex.add_rate('A', 'B', 2.0 + i as f32);
+    }
+    assert!(dex.changed_since(version).is_none());
+
+    let version = dex.version();
+    dex.normalize();
+    assert!(dex.changed_since(version).is_none());
+}
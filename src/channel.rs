@@ -0,0 +1,242 @@
+//! Bounded update channel with the backpressure policies
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use tracing::trace;
+
+use crate::fetch::{Quote, Source};
+
+/// The policy when the channel is full.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Policy {
+    /// Blocks the sender until there is room.
+    Block,
+    /// Drops the oldest pending update.
+    DropOldest,
+    /// Replaces the pending update of the same pair in place, and
+    /// blocks in case there is none.
+    Conflate,
+}
+
+/// The channel metrics.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChannelStats {
+    pub sent: u64,
+    /// The updates dropped by [`Policy::DropOldest`].
+    pub dropped: u64,
+    /// The updates replaced by [`Policy::Conflate`].
+    pub conflated: u64,
+    /// The times the sender was blocked.
+    pub blocked: u64,
+}
+
+/// The receiver is gone.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Disconnected;
+
+impl fmt::Display for Disconnected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("update channel disconnected")
+    }
+}
+
+impl Error for Disconnected {}
+
+struct State {
+    queue: VecDeque<Quote>,
+    stats: ChannelStats,
+    senders: usize,
+    receiver: bool,
+}
+
+struct Shared {
+    capacity: usize,
+    policy: Policy,
+    state: Mutex<State>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+/// Creates the update channel bounded to the `capacity` pending updates.
+pub fn channel(capacity: usize, policy: Policy) -> (UpdateSender, UpdateReceiver) {
+    assert!(capacity > 0);
+    let shared = Arc::new(Shared {
+        capacity,
+        policy,
+        state: Mutex::new(State {
+            queue: VecDeque::with_capacity(capacity),
+            stats: ChannelStats::default(),
+            senders: 1,
+            receiver: true,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    (
+        UpdateSender {
+            shared: shared.clone(),
+        },
+        UpdateReceiver { shared },
+    )
+}
+
+/// The sending half of the update channel, e.g. the feed handler.
+pub struct UpdateSender {
+    shared: Arc<Shared>,
+}
+
+impl fmt::Debug for UpdateSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdateSender")
+            .field("policy", &self.shared.policy)
+            .finish()
+    }
+}
+
+impl Clone for UpdateSender {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for UpdateSender {
+    fn drop(&mut self) {
+        self.shared.lock().senders -= 1;
+        self.shared.not_empty.notify_all();
+    }
+}
+
+impl UpdateSender {
+    /// Sends the update, applying the policy in case the channel is
+    /// full.
+    pub fn send(&self, quote: Quote) -> Result<(), Disconnected> {
+        let shared = &*self.shared;
+        let mut state = shared.lock();
+        if !state.receiver {
+            return Err(Disconnected);
+        }
+        if shared.policy == Policy::Conflate {
+            let pending = state.queue.iter_mut().find(|pending| {
+                (pending.src, pending.dst) == (quote.src, quote.dst)
+                    || (pending.src, pending.dst) == (quote.dst, quote.src)
+            });
+            if let Some(pending) = pending {
+                trace!(src = %quote.src, dst = %quote.dst, "conflated");
+                *pending = quote;
+                state.stats.sent += 1;
+                state.stats.conflated += 1;
+                return Ok(());
+            }
+        }
+        if state.queue.len() >= shared.capacity {
+            if shared.policy == Policy::DropOldest {
+                state.queue.pop_front();
+                state.stats.dropped += 1;
+            } else {
+                state.stats.blocked += 1;
+                while state.receiver && state.queue.len() >= shared.capacity {
+                    state = shared.not_full.wait(state).unwrap();
+                }
+                if !state.receiver {
+                    return Err(Disconnected);
+                }
+            }
+        }
+        state.queue.push_back(quote);
+        state.stats.sent += 1;
+        shared.not_empty.notify_one();
+        Ok(())
+    }
+}
+
+/// The receiving half of the update channel.
+///
+/// It's the [`Source`] of the [`Fetcher`](crate::fetch::Fetcher),
+/// draining the pending updates on each poll.
+pub struct UpdateReceiver {
+    shared: Arc<Shared>,
+}
+
+impl fmt::Debug for UpdateReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdateReceiver")
+            .field("policy", &self.shared.policy)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl Drop for UpdateReceiver {
+    fn drop(&mut self) {
+        self.shared.lock().receiver = false;
+        self.shared.not_full.notify_all();
+    }
+}
+
+impl UpdateReceiver {
+    pub fn stats(&self) -> ChannelStats {
+        self.shared.lock().stats
+    }
+
+    /// Returns the number of the pending updates.
+    pub fn len(&self) -> usize {
+        self.shared.lock().queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Waits up to the `timeout` for the update, and returns `None` on
+    /// the timeout.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Option<Quote>, Disconnected> {
+        let shared = &*self.shared;
+        let mut state = shared.lock();
+        if state.queue.is_empty() && state.senders > 0 {
+            state = shared.not_empty.wait_timeout(state, timeout).unwrap().0;
+        }
+        match state.queue.pop_front() {
+            Some(quote) => {
+                shared.not_full.notify_one();
+                Ok(Some(quote))
+            }
+            None if state.senders == 0 => Err(Disconnected),
+            None => Ok(None),
+        }
+    }
+
+    /// Takes all the pending updates without waiting.
+    pub fn drain(&self) -> Result<Vec<Quote>, Disconnected> {
+        let mut state = self.shared.lock();
+        if state.queue.is_empty() && state.senders == 0 {
+            return Err(Disconnected);
+        }
+        let quotes = state.queue.drain(..).collect();
+        self.shared.not_full.notify_all();
+        Ok(quotes)
+    }
+}
+
+impl Source for UpdateReceiver {
+    fn fetch(&mut self) -> io::Result<Vec<Quote>> {
+        self.drain()
+            .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e))
+    }
+}
+
+#[cfg(test)]
+mod test;
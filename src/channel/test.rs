@@ -0,0 +1,75 @@
+use std::thread;
+use std::time::Duration;
+
+use super::{channel, ChannelStats, Disconnected, Policy};
+use crate::fetch::Quote;
+
+fn quote(src: char, dst: char, rate: f32) -> Quote {
+    Quote {
+        src: src.into(),
+        dst: dst.into(),
+        rate,
+    }
+}
+
+#[test]
+fn test_drop_oldest() {
+    let (tx, rx) = channel(2, Policy::DropOldest);
+    for rate in [1.0, 2.0, 3.0] {
+        tx.send(quote('A', 'B', rate)).unwrap();
+    }
+    let quotes = rx.drain().unwrap();
+    assert_eq!(quotes, vec![quote('A', 'B', 2.0), quote('A', 'B', 3.0)]);
+    assert_eq!(
+        rx.stats(),
+        ChannelStats {
+            sent: 3,
+            dropped: 1,
+            ..ChannelStats::default()
+        }
+    );
+}
+
+#[test]
+fn test_conflate() {
+    let (tx, rx) = channel(2, Policy::Conflate);
+    tx.send(quote('A', 'B', 1.0)).unwrap();
+    tx.send(quote('B', 'C', 1.0)).unwrap();
+    tx.send(quote('A', 'B', 2.0)).unwrap();
+    tx.send(quote('B', 'A', 0.25)).unwrap();
+    assert_eq!(rx.len(), 2);
+    let quotes = rx.drain().unwrap();
+    assert_eq!(quotes, vec![quote('B', 'A', 0.25), quote('B', 'C', 1.0)]);
+    assert_eq!(rx.stats().conflated, 2);
+    assert_eq!(rx.stats().dropped, 0);
+}
+
+#[test]
+fn test_block() {
+    let (tx, rx) = channel(1, Policy::Block);
+    tx.send(quote('A', 'B', 1.0)).unwrap();
+    let sender = thread::spawn(move || {
+        tx.send(quote('A', 'B', 2.0)).unwrap();
+    });
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(
+        rx.recv_timeout(Duration::ZERO),
+        Ok(Some(quote('A', 'B', 1.0)))
+    );
+    assert_eq!(
+        rx.recv_timeout(Duration::from_secs(10)),
+        Ok(Some(quote('A', 'B', 2.0)))
+    );
+    sender.join().unwrap();
+
+    // The sender is gone.
+    assert_eq!(rx.recv_timeout(Duration::ZERO), Err(Disconnected));
+    assert_eq!(rx.drain(), Err(Disconnected));
+}
+
+#[test]
+fn test_disconnected_receiver() {
+    let (tx, rx) = channel(1, Policy::Block);
+    drop(rx);
+    assert_eq!(tx.send(quote('A', 'B', 1.0)), Err(Disconnected));
+}
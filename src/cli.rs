@@ -0,0 +1,975 @@
+//! Command line interface
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{env, fmt, fs};
+
+use tracing::{trace, warn};
+
+use super::{Dex, Vertex};
+use crate::alert::{Alerter, Webhook};
+use crate::auth::Auth;
+use crate::cancel::CancelToken;
+use crate::compare::Backend;
+use crate::config::{Config, ConfigError, Origin, ENV_PREFIX};
+use crate::csv::parse_line;
+use crate::daemon::Daemon;
+use crate::dashboard::Dashboard;
+use crate::frozen::FrozenDex;
+use crate::progress::{Progress, ProgressBar, Stage};
+use crate::query::QueryOptions;
+use crate::rng::Rng;
+use crate::server::{Graphs, Server, DEFAULT_GRAPH};
+use crate::shutdown::Shutdown;
+use crate::subgraph;
+use crate::table::{change_color, read_rates, write_rates, Rates, SortBy};
+use crate::term::{paint, CLEAR};
+use crate::tls::Acceptor;
+#[cfg(feature = "tls")]
+use crate::tls::{CertFiles, RustlsAcceptor};
+use crate::watch::ArbWatch;
+
+const USAGE: &str = "\
+Usage: best-rate [--config <FILE>] [--input <FILE>] [COMMAND]
+
+Commands:
+  pairs [--sort <pair|rate>] [--color] [--state <FILE>] [--progress]
+                            Print the best rate of all the pairs (default)
+  matrix [--base <A,B,..>] [--color] [--state <FILE>] [--progress]
+                            Print the best rate matrix of the currencies
+  convert <AMOUNT> <SRC> <DST> [--svg <FILE>]
+                            Convert the amount through the best path, and
+                            draw the route into the SVG file
+  report [--out <FILE>] [--progress]
+                            Write the JSON report of the pairs, the arbitrage
+                            and the trace of each query
+  snapshot <FILE>           Write the graph snapshot, in JSON to the .json file
+                            and in binary otherwise, loaded back by --input
+                            of the .snapshot.json or the .snapshot file
+  freeze <FILE>             Write the frozen graph file, mapped read-only and
+                            shared by the processes with --input of the
+                            .frozen file
+  centrality                Print the routing hubs by the share of the best paths
+  clusters                  Print the currency clusters by the connectivity
+  subgraph-query [--first <N>]
+                            Print the GraphQL query of the top pools
+  serve [--listen <ADDR>] [--base <A,B,..>] [--max-staleness <MS>]
+                            Serve the precomputed best rates over HTTP
+  visualize [--listen <ADDR>]
+                            Serve the page drawing the live graph, and the
+                            best path of the two clicked currencies
+  bench [--vertices <N>] [--edges <N>] [--queries <N>] [--seed <N>]
+                            Time the queries on the synthetic graph, or on
+                            the --input one
+  compare [--algo <bfs,spfa,dijkstra>]
+                            Compare the rates and the time of the search
+                            algorithms on all the pairs
+  dashboard --pairs <A/B,..> [--select <A/B>]
+                            Show the live best rates with the src,dst,rate
+                            updates of the standard input
+  watch-arb [--min-profit-bps <N>] [--webhook <URL>]
+                            Print the arbitrage as it opens and closes with
+                            the src,dst,rate updates of the standard input
+
+Options:
+  --color          Color the rates improved or worsened since the last run
+  --state <FILE>   Keep the rates of the run in the file for the --color
+  --progress       Show the progress of the computation on the standard error
+  --config <FILE>  Load the key = value settings, also by BEST_RATE_CONFIG
+  --input <FILE>   Load the src,dst,rate lines instead of the sample rates,
+                   the ECB euro reference rates of the .xml file, the
+                   graph snapshot of the .snapshot.json or the .snapshot
+                   file, the frozen graph of the .frozen file, or the
+                   subgraph pools of the .json file
+  --print-config   Print the resolved settings instead of running the command
+  --help           Print this message
+
+The settings are also taken from the BEST_RATE_<KEY> environment
+variables, e.g. BEST_RATE_LISTEN, overridden by the flags.  The serve
+and visualize commands serve HTTPS with the tls_cert and tls_key
+settings, reloaded once renewed, in the build with the tls feature.
+
+Ctrl-C stops the pairs, matrix and report commands, which print the
+results computed so far marked as partial.";
+
+/// The time to wait for the in-flight requests on the shutdown.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The address the visualize command listens on by default.
+const VISUALIZE_LISTEN: &str = "127.0.0.1:8787";
+
+/// The line marking the output interrupted by Ctrl-C.
+const PARTIAL: &str = "# partial results, interrupted";
+
+/// The minimum arbitrage cycle rate above 1.0 reported, 1 bp.
+const ARBITRAGE_EPSILON: f32 = 1e-4;
+
+/// The parsed command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cli {
+    config: Option<PathBuf>,
+    // The settings overridden by the flags, with the flag name.
+    flags: Vec<(&'static str, String, String)>,
+    print_config: bool,
+    table: TableOptions,
+    progress: bool,
+    command: Command,
+}
+
+// The table output options of the pairs and the matrix commands.
+#[derive(Clone, Debug, PartialEq)]
+struct TableOptions {
+    sort: SortBy,
+    color: bool,
+    state: Option<PathBuf>,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        Self {
+            sort: SortBy::Pair,
+            color: false,
+            state: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Help,
+    Pairs,
+    Matrix {
+        base: Option<Vec<Vertex>>,
+    },
+    Convert {
+        amount: f32,
+        src: Vertex,
+        dst: Vertex,
+        svg: Option<PathBuf>,
+    },
+    Report {
+        out: Option<PathBuf>,
+    },
+    Snapshot {
+        out: PathBuf,
+    },
+    Freeze {
+        out: PathBuf,
+    },
+    Centrality,
+    Clusters,
+    SubgraphQuery {
+        first: usize,
+    },
+    Serve,
+    Visualize,
+    Bench {
+        vertices: usize,
+        edges: usize,
+        queries: usize,
+        seed: u64,
+    },
+    Compare {
+        backends: Vec<Backend>,
+    },
+    Dashboard {
+        pairs: Vec<(Vertex, Vertex)>,
+        select: Option<(Vertex, Vertex)>,
+    },
+    WatchArb {
+        min_bps: f32,
+        webhook: Option<String>,
+    },
+}
+
+/// The invalid command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UsageError(String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n\n{USAGE}", self.0)
+    }
+}
+
+impl Error for UsageError {}
+
+impl Cli {
+    pub fn parse<I>(args: I) -> Result<Self, UsageError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        let mut config = None;
+        let mut flags = Vec::new();
+        let mut print_config = false;
+        let mut table = TableOptions::default();
+        let mut progress = false;
+        let mut command = None;
+        while let Some(arg) = args.next() {
+            match (arg.as_str(), &mut command) {
+                ("--help" | "-h", _) => command = Some(Command::Help),
+                ("--print-config", _) => print_config = true,
+                ("--config", _) => config = Some(value(&arg, args.next())?.into()),
+                ("--input", None | Some(Command::Compare { .. })) => {
+                    flags.push(("input", value(&arg, args.next())?, arg))
+                }
+                ("pairs", None) => command = Some(Command::Pairs),
+                ("matrix", None) => command = Some(Command::Matrix { base: None }),
+                ("--base", Some(Command::Matrix { base })) => {
+                    *base = Some(vertices(&value(&arg, args.next())?)?);
+                }
+                ("--sort", Some(Command::Pairs)) => {
+                    table.sort = value(&arg, args.next())?.parse().map_err(UsageError)?;
+                }
+                ("--color", Some(Command::Pairs | Command::Matrix { .. })) => table.color = true,
+                ("--state", Some(Command::Pairs | Command::Matrix { .. })) => {
+                    table.state = Some(value(&arg, args.next())?.into());
+                }
+                (
+                    "--progress",
+                    Some(Command::Pairs | Command::Matrix { .. } | Command::Report { .. }),
+                ) => {
+                    progress = true;
+                }
+                ("convert", None) => {
+                    let amount = value("amount", args.next())?;
+                    let amount = match amount.parse() {
+                        Ok(amount) if amount > 0.0 => amount,
+                        _ => return Err(UsageError(format!("invalid amount {amount:?}"))),
+                    };
+                    command = Some(Command::Convert {
+                        amount,
+                        src: vertex(&value("src", args.next())?)?,
+                        dst: vertex(&value("dst", args.next())?)?,
+                        svg: None,
+                    });
+                }
+                ("--svg", Some(Command::Convert { svg, .. })) => {
+                    *svg = Some(value(&arg, args.next())?.into());
+                }
+                ("report", None) => command = Some(Command::Report { out: None }),
+                ("--out", Some(Command::Report { out })) => {
+                    *out = Some(value(&arg, args.next())?.into());
+                }
+                ("snapshot", None) => {
+                    command = Some(Command::Snapshot {
+                        out: value("file", args.next())?.into(),
+                    })
+                }
+                ("freeze", None) => {
+                    command = Some(Command::Freeze {
+                        out: value("file", args.next())?.into(),
+                    })
+                }
+                ("centrality", None) => command = Some(Command::Centrality),
+                ("clusters", None) => command = Some(Command::Clusters),
+                ("subgraph-query", None) => command = Some(Command::SubgraphQuery { first: 100 }),
+                ("--first", Some(Command::SubgraphQuery { first })) => {
+                    *first = number(&arg, args.next())?;
+                }
+                ("serve", None) => command = Some(Command::Serve),
+                ("visualize", None) => command = Some(Command::Visualize),
+                ("--listen", Some(Command::Serve | Command::Visualize)) => {
+                    flags.push(("listen", value(&arg, args.next())?, arg));
+                }
+                ("--base", Some(Command::Serve)) => {
+                    let base = value(&arg, args.next())?;
+                    vertices(&base)?;
+                    flags.push(("base", base, arg));
+                }
+                ("--max-staleness", Some(Command::Serve)) => {
+                    let ms: u64 = number(&arg, args.next())?;
+                    flags.push(("max_staleness_ms", ms.to_string(), arg));
+                }
+                ("bench", None) => {
+                    command = Some(Command::Bench {
+                        vertices: 100_000,
+                        edges: 1_000_000,
+                        queries: 100,
+                        seed: 0,
+                    })
+                }
+                ("--vertices", Some(Command::Bench { vertices, .. })) => {
+                    *vertices = number(&arg, args.next())?;
+                    if *vertices < 2 {
+                        return Err(UsageError(format!("invalid {arg} value")));
+                    }
+                }
+                ("--edges", Some(Command::Bench { edges, .. })) => {
+                    *edges = number(&arg, args.next())?;
+                }
+                ("--queries", Some(Command::Bench { queries, .. })) => {
+                    *queries = number(&arg, args.next())?;
+                }
+                ("--seed", Some(Command::Bench { seed, .. })) => {
+                    *seed = number(&arg, args.next())?;
+                }
+                ("compare", None) => {
+                    command = Some(Command::Compare {
+                        backends: Backend::ALL.to_vec(),
+                    })
+                }
+                ("--algo", Some(Command::Compare { backends })) => {
+                    *backends = value(&arg, args.next())?
+                        .split(',')
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                        .map_err(UsageError)?;
+                }
+                ("dashboard", None) => {
+                    command = Some(Command::Dashboard {
+                        pairs: Vec::new(),
+                        select: None,
+                    })
+                }
+                ("--pairs", Some(Command::Dashboard { pairs, .. })) => {
+                    *pairs = value(&arg, args.next())?
+                        .split(',')
+                        .map(pair)
+                        .collect::<Result<_, _>>()?;
+                }
+                ("--select", Some(Command::Dashboard { select, .. })) => {
+                    *select = Some(pair(&value(&arg, args.next())?)?);
+                }
+                ("watch-arb", None) => {
+                    command = Some(Command::WatchArb {
+                        min_bps: 10.0,
+                        webhook: None,
+                    })
+                }
+                ("--min-profit-bps", Some(Command::WatchArb { min_bps, .. })) => {
+                    *min_bps = number(&arg, args.next())?;
+                    if !min_bps.is_finite() || *min_bps < 0.0 {
+                        return Err(UsageError(format!("invalid {arg} value")));
+                    }
+                }
+                ("--webhook", Some(Command::WatchArb { webhook, .. })) => {
+                    let url = value(&arg, args.next())?;
+                    Webhook::new(&url).map_err(|e| UsageError(format!("{e}")))?;
+                    *webhook = Some(url);
+                }
+                _ => return Err(UsageError(format!("unexpected argument {arg:?}"))),
+            }
+        }
+        if let Some(Command::Dashboard { pairs, select }) = &command {
+            if pairs.is_empty() {
+                return Err(UsageError("missing --pairs".to_string()));
+            }
+            if matches!(select, Some(select) if !pairs.contains(select)) {
+                return Err(UsageError("--select pair not in --pairs".to_string()));
+            }
+        }
+        Ok(Self {
+            config,
+            flags,
+            print_config,
+            table,
+            progress,
+            command: command.unwrap_or(Command::Pairs),
+        })
+    }
+
+    /// Resolves the settings, the flags over the `BEST_RATE_<KEY>`
+    /// variables of `vars` over the config file, and validates them.
+    pub fn config<I>(&self, vars: I) -> Result<Config, ConfigError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let vars: Vec<_> = vars.into_iter().collect();
+        let file = self.config.clone().or_else(|| {
+            let var = format!("{ENV_PREFIX}CONFIG");
+            vars.iter()
+                .find(|(key, _)| *key == var)
+                .map(|(_, value)| value.into())
+        });
+        // All the problems are reported at once.
+        let mut errors = Vec::new();
+        let mut config = Config::new();
+        if let Some(file) = file {
+            errors.extend(config.load_file(&file).err());
+        }
+        config.load_env(vars);
+        for (key, value, flag) in &self.flags {
+            errors.extend(config.set(key, value, Origin::Flag(flag.clone())).err());
+        }
+        errors.extend(config.validate().err());
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError::join(errors))
+        }
+    }
+
+    pub fn command(&self) -> &Command {
+        &self.command
+    }
+
+    /// Runs the command, and writes the result to `out`.
+    pub fn run<W: Write>(&self, out: &mut W) -> Result<(), Box<dyn Error>> {
+        if self.command == Command::Help {
+            writeln!(out, "{USAGE}")?;
+            return Ok(());
+        }
+        if let Command::SubgraphQuery { first } = self.command {
+            writeln!(out, "{}", subgraph::query(first))?;
+            return Ok(());
+        }
+        let config = self.config(env::vars())?;
+        if self.print_config {
+            write!(out, "{config}")?;
+            return Ok(());
+        }
+        if let Command::Bench {
+            vertices,
+            edges,
+            queries,
+            seed,
+        } = self.command
+        {
+            return bench(config.get("input"), vertices, edges, queries, seed, out);
+        }
+        if matches!(self.command, Command::Serve | Command::Visualize) {
+            return serve(&config, self.command == Command::Visualize, out);
+        }
+        let dex = load(config.get("input"))?;
+        trace!("{:#?}", dex);
+        match &self.command {
+            Command::Help
+            | Command::SubgraphQuery { .. }
+            | Command::Serve
+            | Command::Visualize
+            | Command::Bench { .. } => unreachable!(),
+            Command::Dashboard { pairs, select } => {
+                let mut dashboard = Dashboard::new(pairs.clone());
+                if let Some((src, dst)) = select {
+                    dashboard.select(src, dst);
+                }
+                dashboard_loop(dex, dashboard, io::stdin().lock(), out)?;
+            }
+            Command::WatchArb { min_bps, webhook } => {
+                let webhook = webhook.as_deref().map(Webhook::new).transpose()?;
+                watch_arb(dex, io::stdin().lock(), *min_bps, webhook, out)?;
+            }
+            Command::Pairs => {
+                let cancel = interrupt();
+                pairs(&dex, &self.table, &cancel, &mut self.progress_bar(), out)?;
+            }
+            Command::Matrix { base } => {
+                let cancel = interrupt();
+                let (matrix, partial) =
+                    match dex.try_matrix(base.as_deref(), &cancel, self.progress_bar()) {
+                        Ok(matrix) => (matrix, false),
+                        Err(timeout) => (timeout.into_partial(), true),
+                    };
+                let previous = self.table.previous()?;
+                writeln!(
+                    out,
+                    "{}",
+                    matrix.render(previous.as_ref(), self.table.color)
+                )?;
+                if partial {
+                    writeln!(out, "{PARTIAL}")?;
+                } else {
+                    self.table.save(&matrix.rates())?;
+                }
+            }
+            Command::Convert {
+                amount,
+                src,
+                dst,
+                svg,
+            } => {
+                convert(&dex, *amount, src, dst, svg.as_ref(), out)?;
+            }
+            Command::Centrality => writeln!(out, "{}", dex.centrality())?,
+            Command::Compare { backends } => writeln!(out, "{}", dex.compare(backends))?,
+            Command::Clusters => writeln!(out, "{}", dex.clusters())?,
+            Command::Report { out: None } => {
+                report(&dex, self.progress_bar(), out)?;
+            }
+            Command::Report { out: Some(path) } => {
+                let file = File::create(path).map_err(|e| format!("{}: {e}", path.display()))?;
+                let mut file = BufWriter::new(file);
+                report(&dex, self.progress_bar(), &mut file)?;
+                file.flush()?;
+                writeln!(out, "report written to {}", path.display())?;
+            }
+            Command::Snapshot { out: path } => {
+                let file = File::create(path).map_err(|e| format!("{}: {e}", path.display()))?;
+                let mut file = BufWriter::new(file);
+                if path.extension() == Some("json".as_ref()) {
+                    dex.save_snapshot(&mut file)?;
+                } else {
+                    dex.save_binary_snapshot(&mut file)?;
+                }
+                file.flush()?;
+                writeln!(out, "snapshot written to {}", path.display())?;
+            }
+            Command::Freeze { out: path } => {
+                let frozen = dex.freeze();
+                frozen
+                    .save_mapped(path)
+                    .map_err(|e| format!("{}: {e}", path.display()))?;
+                writeln!(
+                    out,
+                    "frozen graph of {} vertices and {} edges written to {}",
+                    frozen.vertex_count(),
+                    frozen.edge_count(),
+                    path.display()
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the callback drawing the progress bar on the standard
+    // error in case of --progress.
+    fn progress_bar(&self) -> impl FnMut(Progress) {
+        let mut bar = if self.progress {
+            Some(ProgressBar::new(io::stderr()))
+        } else {
+            None
+        };
+        move |progress| {
+            if let Some(bar) = &mut bar {
+                if let Err(e) = bar.update(progress) {
+                    warn!(%e, "progress");
+                }
+            }
+        }
+    }
+}
+
+impl TableOptions {
+    // Returns the rates of the last run, if any.
+    fn previous(&self) -> Result<Option<Rates>, Box<dyn Error>> {
+        let path = match &self.state {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+        let file = File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let rates =
+            read_rates(BufReader::new(file)).map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(Some(rates))
+    }
+
+    // Keeps the rates of the run for the next one.
+    fn save(&self, rates: &Rates) -> Result<(), Box<dyn Error>> {
+        if let Some(path) = &self.state {
+            let file = File::create(path).map_err(|e| format!("{}: {e}", path.display()))?;
+            let mut file = BufWriter::new(file);
+            write_rates(rates, &mut file)?;
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+// Prints the best rate of all the pairs, aligned in the columns.
+//
+// The pairs computed so far are printed, marked partial, once
+// cancelled.
+fn pairs<W: Write>(
+    dex: &Dex,
+    table: &TableOptions,
+    cancel: &CancelToken,
+    progress: &mut dyn FnMut(Progress),
+    out: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    let options = QueryOptions::new().with_cancel_token(cancel.clone());
+    let mut paths = Vec::new();
+    let mut partial = false;
+    let total = dex.vertices().count();
+    'src: for (i, src) in dex.vertices().enumerate() {
+        if cancel.is_cancelled() {
+            partial = true;
+            break;
+        }
+        for dst in dex.vertices() {
+            if src == dst {
+                continue;
+            }
+            match dex.try_get_best_rate_with(src, dst, &options) {
+                Ok(path) => paths.extend(path),
+                Err(_) => {
+                    partial = true;
+                    break 'src;
+                }
+            }
+        }
+        progress(Progress {
+            stage: Stage::Pairs,
+            done: i + 1,
+            total,
+        });
+    }
+    if table.sort == SortBy::Rate {
+        paths.sort_by(|a, b| b.rate.partial_cmp(&a.rate).unwrap_or(Ordering::Equal));
+    }
+    let width = dex
+        .vertices()
+        .map(|v| dex.display_name(v).len())
+        .max()
+        .unwrap_or_default();
+    let previous = table.previous()?;
+    let mut rates = Rates::new();
+    for path in &paths {
+        let (src, dst) = (path.path[0], *path.last());
+        let change = previous
+            .as_ref()
+            .and_then(|previous| change_color(path.rate, previous.get(&(src, dst)).copied()));
+        writeln!(
+            out,
+            "{:<width$} -> {:<width$}: {} ({})",
+            dex.display_name(&src),
+            dex.display_name(&dst),
+            paint(format!("{:8.4}", path.rate), change, table.color),
+            dex.display_path(path),
+        )?;
+        rates.insert((src, dst), path.rate);
+    }
+    if partial {
+        writeln!(out, "{PARTIAL}")?;
+        return Ok(());
+    }
+    table.save(&rates)
+}
+
+// Writes the JSON report, marked partial in case it's interrupted.
+fn report<W: Write>(dex: &Dex, progress: impl FnMut(Progress), out: &mut W) -> io::Result<()> {
+    let report = dex
+        .try_report(ARBITRAGE_EPSILON, &interrupt(), progress)
+        .unwrap_or_else(|timeout| {
+            warn!("report interrupted");
+            timeout.into_partial()
+        });
+    report.write_json(out)
+}
+
+// Returns the token cancelled on Ctrl-C, to stop the computation and
+// print the partial results instead of being killed.
+fn interrupt() -> CancelToken {
+    Shutdown::new().with_signals().cancel_token()
+}
+
+fn load(input: Option<&str>) -> Result<Dex, Box<dyn Error>> {
+    let mut dex = Dex::new();
+    match input {
+        Some(input) => {
+            let file = File::open(input).map_err(|e| format!("{input}: {e}"))?;
+            if input.ends_with(".xml") {
+                dex.load_ecb(BufReader::new(file))?;
+            } else if input.ends_with(".snapshot.json") {
+                dex = Dex::load_snapshot(BufReader::new(file))
+                    .map_err(|e| format!("{input}: {e}"))?;
+            } else if input.ends_with(".snapshot") {
+                dex = Dex::load_binary_snapshot(BufReader::new(file))
+                    .map_err(|e| format!("{input}: {e}"))?;
+            } else if input.ends_with(".frozen") {
+                dex = FrozenDex::open_mapped(Path::new(input))
+                    .map_err(|e| format!("{input}: {e}"))?
+                    .thaw();
+            } else if input.ends_with(".json") {
+                dex.load_subgraph(BufReader::new(file))?;
+            } else {
+                dex.load_csv(BufReader::new(file))?;
+            }
+        }
+        None => {
+            dex.add_rate('A', 'B', 1.4);
+            dex.add_rate('A', 'C', 0.1);
+            dex.add_rate('B', 'C', 0.2);
+            dex.add_rate('C', 'D', 0.2);
+            dex.add_rate('D', 'F', 2.5);
+        }
+    }
+    Ok(dex)
+}
+
+// Serves the named graphs, or the default one from the top level
+// settings, until the signal.  The page of each graph is printed to
+// open in case of `visualize`, listening on the local port by default.
+fn serve<W: Write>(config: &Config, visualize: bool, out: &mut W) -> Result<(), Box<dyn Error>> {
+    let mut names = config.names("graph");
+    if names.is_empty() {
+        names.push(DEFAULT_GRAPH);
+    }
+    let mut graphs = Graphs::new();
+    for name in names.iter().copied() {
+        let dex = load(config.graph(name, "input"))?;
+        let max_staleness = config
+            .parse_graph(name, "max_staleness_ms")?
+            .unwrap_or_default();
+        let alerters = Alerter::from_config(config, name, &dex)?;
+        let mut daemon = Daemon::new(dex).with_max_staleness(Duration::from_millis(max_staleness));
+        for alerter in alerters {
+            daemon = daemon.with_alerter(alerter);
+        }
+        if let Some(base) = config.graph(name, "base") {
+            let base =
+                vertices(base).map_err(|e| format!("graph {name}: invalid base: {}", e.0))?;
+            daemon = daemon.with_base(base);
+        }
+        graphs = graphs.graph(name, daemon.start());
+    }
+    let listen = match config.get("listen") {
+        Some(listen) => listen,
+        None if visualize => VISUALIZE_LISTEN,
+        None => return Err("missing listen setting".into()),
+    };
+    let acceptor = match (config.get("tls_cert"), config.get("tls_key")) {
+        (Some(cert), Some(key)) => Some(acceptor(cert, key)?),
+        _ => None,
+    };
+    let mut server = Server::bind(listen, graphs)?;
+    if let Some(auth) = Auth::from_config(config)? {
+        server = server.with_auth(auth);
+    }
+    let mut scheme = "http";
+    if let Some(acceptor) = acceptor {
+        server = server.with_acceptor(acceptor);
+        scheme = "https";
+    }
+    let shutdown = Shutdown::new().with_signals();
+    let addr = server.local_addr()?;
+    writeln!(out, "listening on {addr}")?;
+    if visualize {
+        for name in names {
+            writeln!(out, "open {scheme}://{addr}/graphs/{name}/visualize")?;
+        }
+    }
+    out.flush()?;
+    server.serve(&shutdown)?;
+    shutdown.finish(SHUTDOWN_TIMEOUT);
+    Ok(())
+}
+
+// Terminates the TLS with the certificate files.
+#[cfg(feature = "tls")]
+fn acceptor(cert: &str, key: &str) -> Result<Arc<dyn Acceptor>, Box<dyn Error>> {
+    let files = CertFiles::load(cert.as_ref(), key.as_ref())?;
+    Ok(Arc::new(RustlsAcceptor::new(files)?))
+}
+
+#[cfg(not(feature = "tls"))]
+fn acceptor(_cert: &str, _key: &str) -> Result<Arc<dyn Acceptor>, Box<dyn Error>> {
+    Err("TLS termination needs the build with the tls feature".into())
+}
+
+fn convert<W: Write>(
+    dex: &Dex,
+    amount: f32,
+    src: &Vertex,
+    dst: &Vertex,
+    svg: Option<&PathBuf>,
+    out: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    let options = QueryOptions::new().with_amount(amount);
+    let path = dex
+        .get_best_rate_with(src, dst, &options)
+        .ok_or_else(|| format!("no {src} -> {dst} path for {amount}"))?;
+    let execution = dex.simulate(&path, amount)?;
+    writeln!(
+        out,
+        "{} -> {} ({path})",
+        dex.format_amount(src, execution.amount_in()),
+        dex.format_amount(dst, execution.amount_out()),
+    )?;
+    for hop in execution.hops() {
+        writeln!(
+            out,
+            "  {} -> {}: {} -> {} (fee {})",
+            hop.src,
+            hop.dst,
+            dex.format_amount(&hop.src, hop.amount_in),
+            dex.format_amount(&hop.dst, hop.amount_out),
+            dex.format_amount(&hop.dst, hop.fee),
+        )?;
+    }
+    if let Some(svg) = svg {
+        fs::write(svg, dex.route_svg(&path, amount)?)
+            .map_err(|e| format!("{}: {e}", svg.display()))?;
+    }
+    Ok(())
+}
+
+// Applies the src,dst,rate updates of the `reader`, and redraws the
+// dashboard after each.  The invalid lines are skipped.
+fn dashboard_loop<R: BufRead, W: Write>(
+    mut dex: Dex,
+    mut dashboard: Dashboard,
+    reader: R,
+    out: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    dashboard.update(&dex);
+    write!(out, "{CLEAR}{}", dashboard.render(&dex))?;
+    out.flush()?;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_line(line) {
+            Ok((src, dst, rate)) => {
+                dex.add_rate(src, dst, rate);
+            }
+            Err(e) => {
+                warn!(line = i + 1, %e, "update skipped");
+                continue;
+            }
+        }
+        dashboard.update(&dex);
+        write!(out, "{CLEAR}{}", dashboard.render(&dex))?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+// Applies the src,dst,rate updates of the `reader`, and reports the
+// arbitrage opened and closed by each.  The invalid lines are skipped.
+fn watch_arb<R: BufRead, W: Write>(
+    mut dex: Dex,
+    reader: R,
+    min_bps: f32,
+    webhook: Option<Webhook>,
+    out: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    let mut watch = ArbWatch::new(min_bps);
+    let mut report = |dex: &Dex, out: &mut W| -> io::Result<()> {
+        for event in watch.check(dex) {
+            writeln!(out, "{event}")?;
+            if let Some(webhook) = &webhook {
+                if let Err(e) = webhook.post(&event.to_string()) {
+                    warn!(%e, "webhook failed");
+                }
+            }
+        }
+        out.flush()
+    };
+    report(&dex, out)?;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_line(line) {
+            Ok((src, dst, rate)) => {
+                dex.add_rate(src, dst, rate);
+            }
+            Err(e) => {
+                warn!(line = i + 1, %e, "update skipped");
+                continue;
+            }
+        }
+        report(&dex, out)?;
+    }
+    Ok(())
+}
+
+// Times the queries on the synthetic graph, or on the `input` one,
+// mapped as is in case of the frozen graph file.
+fn bench<W: Write>(
+    input: Option<&str>,
+    vertices: usize,
+    edges: usize,
+    queries: usize,
+    seed: u64,
+    out: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    let mut rng = Rng::new(seed);
+    let start = Instant::now();
+    let frozen = match input {
+        Some(input) if input.ends_with(".frozen") => {
+            let frozen =
+                FrozenDex::open_mapped(Path::new(input)).map_err(|e| format!("{input}: {e}"))?;
+            writeln!(out, "open:     {:?}", start.elapsed())?;
+            frozen
+        }
+        _ => {
+            let (dex, stage) = match input {
+                Some(_) => (load(input)?, "load:"),
+                None => (Dex::generate(vertices, edges, 0.01, &mut rng), "generate:"),
+            };
+            writeln!(out, "{stage:<10}{:?}", start.elapsed())?;
+            let start = Instant::now();
+            let frozen = dex.freeze();
+            writeln!(out, "freeze:   {:?}", start.elapsed())?;
+            frozen
+        }
+    };
+    writeln!(
+        out,
+        "graph:    {} vertices, {} edges",
+        frozen.vertex_count(),
+        frozen.edge_count(),
+    )?;
+    let vertices: Vec<_> = frozen.vertices().copied().collect();
+    if vertices.is_empty() {
+        return Err("empty graph".into());
+    }
+    let mut searcher = frozen.searcher();
+    let mut found = 0;
+    let start = Instant::now();
+    for _ in 0..queries {
+        let src = &vertices[rng.below(vertices.len())];
+        let dst = &vertices[rng.below(vertices.len())];
+        if searcher.get_best_rate(&frozen, src, dst).is_some() {
+            found += 1;
+        }
+    }
+    let elapsed = start.elapsed();
+    writeln!(
+        out,
+        "query:    {:?} per query ({found}/{queries} found)",
+        elapsed.checked_div(queries as u32).unwrap_or_default(),
+    )?;
+    let src = &vertices[rng.below(vertices.len())];
+    let start = Instant::now();
+    let rates = frozen.get_best_rates_from(src);
+    writeln!(
+        out,
+        "from:     {:?} ({} destinations)",
+        start.elapsed(),
+        rates.len(),
+    )?;
+    Ok(())
+}
+
+fn value(arg: &str, value: Option<String>) -> Result<String, UsageError> {
+    value.ok_or_else(|| UsageError(format!("missing {arg} value")))
+}
+
+fn number<T: FromStr>(arg: &str, value: Option<String>) -> Result<T, UsageError> {
+    let value = self::value(arg, value)?;
+    value
+        .parse()
+        .map_err(|_| UsageError(format!("invalid {arg} value {value:?}")))
+}
+
+fn vertex(value: &str) -> Result<Vertex, UsageError> {
+    value.trim().parse().map_err(|e| UsageError(format!("{e}")))
+}
+
+// Parses the `SRC/DST` pair.
+fn pair(value: &str) -> Result<(Vertex, Vertex), UsageError> {
+    match value.split_once('/') {
+        Some((src, dst)) => Ok((vertex(src)?, vertex(dst)?)),
+        None => Err(UsageError(format!("invalid pair {value:?}"))),
+    }
+}
+
+fn vertices(value: &str) -> Result<Vec<Vertex>, UsageError> {
+    value.split(',').map(vertex).collect()
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,413 @@
+use super::{Cli, Command};
+use crate::cancel::CancelToken;
+use crate::compare::Backend;
+use crate::dashboard::Dashboard;
+use crate::frozen::FrozenDex;
+use crate::term::CLEAR;
+use crate::test::vertex;
+use crate::Dex;
+
+fn parse(args: &str) -> Result<Cli, super::UsageError> {
+    Cli::parse(args.split_whitespace().map(String::from))
+}
+
+fn run(args: &str) -> String {
+    let mut out = Vec::new();
+    parse(args).unwrap().run(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(parse("").unwrap().command(), &Command::Pairs);
+    assert_eq!(parse("--help").unwrap().command(), &Command::Help);
+    assert_eq!(
+        parse("matrix --base USD,EUR").unwrap().command(),
+        &Command::Matrix {
+            base: Some(vec![vertex("USD"), vertex("EUR")]),
+        }
+    );
+    assert!(parse("matrix --base").is_err());
+    assert!(parse("pairs --base USD").is_err());
+    assert!(parse("unknown").is_err());
+    assert_eq!(
+        parse("convert 1500 USD JPY").unwrap().command(),
+        &Command::Convert {
+            amount: 1500.0,
+            src: vertex("USD"),
+            dst: vertex("JPY"),
+            svg: None,
+        }
+    );
+    assert!(parse("convert 1500 USD JPY --svg").is_err());
+    assert!(parse("pairs --svg route.svg").is_err());
+    assert!(parse("convert -1 USD JPY").is_err());
+    assert!(parse("convert 1500 USD").is_err());
+}
+
+#[test]
+fn test_pairs() {
+    let out = run("pairs");
+    assert!(out.starts_with("A -> B:   1.4000 (A -> B: 1.4)\n"));
+}
+
+#[test]
+fn test_matrix() {
+    let out = run("matrix --base A,B");
+    assert_eq!(
+        out,
+        "           A          B\n\
+         A     1.0000     1.4000\n\
+         B     2.0000     1.0000\n"
+    );
+}
+
+#[test]
+fn test_pairs_interrupted() {
+    let dex = super::load(None).unwrap();
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let mut out = Vec::new();
+    super::pairs(&dex, &Default::default(), &cancel, &mut |_| {}, &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "# partial results, interrupted\n"
+    );
+}
+
+#[test]
+fn test_table() {
+    assert!(parse("pairs --sort rate --color").is_ok());
+    assert!(parse("pairs --sort hops").is_err());
+    assert!(parse("matrix --sort rate").is_err());
+    assert!(parse("convert 1 A B --color").is_err());
+    assert!(parse("report --progress").is_ok());
+    assert!(parse("matrix --progress").is_ok());
+    assert!(parse("convert 1 A B --progress").is_err());
+
+    let out = run("pairs --sort rate");
+    let rates: Vec<f32> = out
+        .lines()
+        .map(|line| line.split(':').nth(1).unwrap().split_whitespace().next())
+        .map(|rate| rate.unwrap().parse().unwrap())
+        .collect();
+    assert!(rates.windows(2).all(|w| w[0] >= w[1]), "{out}");
+
+    let state = std::env::temp_dir().join(format!("best-rate-table-{}", std::process::id()));
+    let _ = std::fs::remove_file(&state);
+    let args = format!("matrix --base A,B --color --state {}", state.display());
+    let first = run(&args);
+    assert!(!first.contains('\x1b'));
+    std::fs::write(&state, "A,B,1.5\nB,A,3.0\n").unwrap();
+    let second = run(&args);
+    std::fs::remove_file(&state).unwrap();
+    assert!(second.contains("\x1b[31m    1.4000\x1b[0m"), "{second}");
+    assert!(second.contains("\x1b[31m    2.0000\x1b[0m"), "{second}");
+}
+
+#[test]
+fn test_centrality() {
+    let out = run("centrality");
+    assert_eq!(out.lines().count(), 5);
+    assert!(out.contains(" routes "), "{out}");
+}
+
+#[test]
+fn test_clusters() {
+    let out = run("clusters");
+    assert!(out.lines().count() >= 1);
+    assert!(out.contains(": "), "{out}");
+}
+
+#[test]
+fn test_convert() {
+    let out = run("convert 1000 A D");
+    assert_eq!(
+        out,
+        "1000.00 A -> 56.00 D (A -> B -> C -> D: 0.056)\n  \
+         A -> B: 1000.00 A -> 1400.00 B (fee 0.00 B)\n  \
+         B -> C: 1400.00 B -> 280.00 C (fee 0.00 C)\n  \
+         C -> D: 280.00 C -> 56.00 D (fee 0.00 D)\n"
+    );
+
+    let svg = std::env::temp_dir().join(format!("best-rate-route-{}.svg", std::process::id()));
+    run(&format!("convert 1000 A D --svg {}", svg.display()));
+    let drawing = std::fs::read_to_string(&svg).unwrap();
+    std::fs::remove_file(&svg).unwrap();
+    assert!(drawing.starts_with("<svg "));
+    assert!(drawing.contains(">C/D</text>"));
+}
+
+#[test]
+fn test_compare() {
+    assert_eq!(
+        parse("compare --algo bfs,dijkstra --input rates.csv")
+            .unwrap()
+            .command(),
+        &Command::Compare {
+            backends: vec![Backend::Bfs, Backend::Dijkstra],
+        }
+    );
+    assert_eq!(
+        parse("compare").unwrap().command(),
+        &Command::Compare {
+            backends: Backend::ALL.to_vec(),
+        }
+    );
+    assert!(parse("compare --algo bfs,astar").is_err());
+    assert!(parse("pairs --algo bfs").is_err());
+    assert!(parse("pairs --input rates.csv").is_err());
+    let out = run("compare --algo bfs,spfa");
+    assert!(out.starts_with("pair      "), "{out}");
+    assert!(
+        out.contains(
+            "
+A -> B     1.4000     1.4000
+"
+        ),
+        "{out}"
+    );
+    assert!(out.ends_with("\n0 disagreements of 20 queries\n"), "{out}");
+}
+
+#[test]
+fn test_bench() {
+    assert_eq!(
+        parse("bench --vertices 10 --edges 20 --seed 1")
+            .unwrap()
+            .command(),
+        &Command::Bench {
+            vertices: 10,
+            edges: 20,
+            queries: 100,
+            seed: 1,
+        }
+    );
+    assert!(parse("bench --vertices 1").is_err());
+    assert!(parse("bench --edges many").is_err());
+    let out = run("bench --vertices 10 --edges 20 --queries 5");
+    assert!(out.contains("graph:    10 vertices, "), "{out}");
+}
+
+#[test]
+fn test_report() {
+    assert_eq!(
+        parse("report --out report.json").unwrap().command(),
+        &Command::Report {
+            out: Some("report.json".into()),
+        }
+    );
+    assert!(parse("report --out").is_err());
+    let out = run("report");
+    assert!(out.starts_with("{\n  \"vertices\": 5,\n  \"edges\": 10,\n"));
+    assert!(out.contains("\"arbitrage\": [\n    {\"cycle\": [\"A\", \"B\", \"C\"]"));
+}
+
+#[test]
+fn test_snapshot() {
+    assert_eq!(
+        parse("snapshot graph.snapshot.json").unwrap().command(),
+        &Command::Snapshot {
+            out: "graph.snapshot.json".into(),
+        }
+    );
+    assert!(parse("snapshot").is_err());
+
+    let snapshot = std::env::temp_dir().join(format!(
+        "best-rate-graph-{}.snapshot.json",
+        std::process::id()
+    ));
+    let out = run(&format!("snapshot {}", snapshot.display()));
+    assert!(out.starts_with("snapshot written to "));
+    let pairs = run(&format!("--input {} pairs", snapshot.display()));
+    std::fs::remove_file(&snapshot).unwrap();
+    assert_eq!(pairs, run("pairs"));
+
+    // The binary snapshot otherwise.
+    let snapshot = snapshot.with_extension("");
+    run(&format!("snapshot {}", snapshot.display()));
+    let binary = std::fs::read(&snapshot).unwrap();
+    let pairs = run(&format!("--input {} pairs", snapshot.display()));
+    std::fs::remove_file(&snapshot).unwrap();
+    assert!(binary.starts_with(b"BRSB"));
+    assert_eq!(pairs, run("pairs"));
+}
+
+#[test]
+fn test_freeze() {
+    assert_eq!(
+        parse("freeze graph.frozen").unwrap().command(),
+        &Command::Freeze {
+            out: "graph.frozen".into(),
+        }
+    );
+    assert!(parse("freeze").is_err());
+
+    let path = std::env::temp_dir().join(format!("best-rate-{}.frozen", std::process::id()));
+    let out = run(&format!("freeze {}", path.display()));
+    let frozen = FrozenDex::open_mapped(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(out.starts_with("frozen graph of 5 vertices and 10 edges written to "));
+    let path = frozen.get_best_rate(&'A'.into(), &'D'.into()).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D: 0.056");
+}
+
+#[test]
+fn test_frozen_input() {
+    let path = std::env::temp_dir().join(format!("best-rate-input-{}.frozen", std::process::id()));
+    run(&format!("freeze {}", path.display()));
+    let pairs = run(&format!("--input {} pairs", path.display()));
+    let bench = run(&format!("--input {} bench --queries 5", path.display()));
+    let missing = path.with_file_name("best-rate-missing.frozen");
+    let error = parse(&format!("--input {} bench", missing.display()))
+        .unwrap()
+        .run(&mut Vec::new())
+        .unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(pairs, run("pairs"));
+    assert!(bench.starts_with("open:     "), "{bench}");
+    assert!(bench.contains("graph:    5 vertices, 10 edges"), "{bench}");
+    assert!(error.to_string().contains("missing.frozen: "), "{error}");
+}
+
+#[test]
+fn test_visualize() {
+    let cli = parse("visualize --listen 127.0.0.1:0").unwrap();
+    assert_eq!(cli.command(), &Command::Visualize);
+    assert!(parse("pairs --listen 127.0.0.1:0").is_err());
+}
+
+#[test]
+fn test_config() {
+    let cli = parse("serve --listen 0.0.0.0:80 --base USD,EUR --max-staleness 5").unwrap();
+    assert_eq!(cli.command(), &Command::Serve);
+    assert!(parse("serve --max-staleness soon").is_err());
+    assert!(parse("serve --base U\0").is_err());
+    assert!(parse("serve --seed 1").is_err());
+
+    let vars = [
+        ("BEST_RATE_LISTEN", "127.0.0.1:9000"),
+        ("BEST_RATE_INPUT", "rates.csv"),
+        ("HOME", "/root"),
+    ]
+    .map(|(key, value)| (key.to_string(), value.to_string()));
+    let config = cli.config(vars).unwrap();
+    assert_eq!(config.get("listen"), Some("0.0.0.0:80"));
+    assert_eq!(config.get("input"), Some("rates.csv"));
+    assert_eq!(config.parse::<u64>("max_staleness_ms").unwrap(), Some(5));
+    assert_eq!(
+        config.to_string(),
+        "base = \"USD,EUR\"  # flag --base\n\
+         input = \"rates.csv\"  # env BEST_RATE_INPUT\n\
+         listen = \"0.0.0.0:80\"  # flag --listen\n\
+         max_staleness_ms = \"5\"  # flag --max-staleness\n\
+         tls_cert = \"\"  # default\n\
+         tls_key = \"\"  # default\n"
+    );
+
+    let vars = [("BEST_RATE_CONFIG".to_string(), "missing.conf".to_string())];
+    assert!(cli.config(vars).is_err());
+
+    // All the problems at once.
+    let vars = [
+        ("BEST_RATE_LISTN", "127.0.0.1:9000"),
+        ("BEST_RATE_TLS_KEY", "key.pem"),
+    ]
+    .map(|(key, value)| (key.to_string(), value.to_string()));
+    let cli = parse("serve --listen 80 --base usd").unwrap();
+    let error = cli.config(vars).unwrap_err().to_string();
+    assert_eq!(error.lines().count(), 4, "{error}");
+    assert!(error.contains("did you mean BEST_RATE_LISTEN?"), "{error}");
+    assert!(error.contains("did you mean \"127.0.0.1:80\"?"), "{error}");
+    assert!(error.contains("did you mean \"USD\"?"), "{error}");
+    assert!(
+        error.contains("tls_key from env BEST_RATE_TLS_KEY"),
+        "{error}"
+    );
+    assert!(run("--print-config").contains("listen = "));
+}
+
+#[test]
+fn test_serve_tls() {
+    let vars = [
+        ("BEST_RATE_TLS_CERT", "missing-cert.pem"),
+        ("BEST_RATE_TLS_KEY", "missing-key.pem"),
+    ]
+    .map(|(key, value)| (key.to_string(), value.to_string()));
+    let cli = parse("serve --listen 127.0.0.1:0").unwrap();
+    let config = cli.config(vars).unwrap();
+    let error = super::serve(&config, false, &mut Vec::new()).unwrap_err();
+    if cfg!(feature = "tls") {
+        assert!(error.to_string().contains("missing-cert.pem"), "{error}");
+    } else {
+        assert!(error.to_string().contains("tls feature"), "{error}");
+    }
+}
+
+#[test]
+fn test_subgraph_query() {
+    assert_eq!(
+        parse("subgraph-query --first 10").unwrap().command(),
+        &Command::SubgraphQuery { first: 10 }
+    );
+    assert!(run("subgraph-query").contains("pools(first: 100,"));
+}
+
+#[test]
+fn test_watch_arb() {
+    assert_eq!(
+        parse("watch-arb --min-profit-bps 25").unwrap().command(),
+        &Command::WatchArb {
+            min_bps: 25.0,
+            webhook: None,
+        }
+    );
+    assert!(parse("watch-arb --min-profit-bps -1").is_err());
+    assert!(parse("watch-arb --webhook https://example.com").is_err());
+    assert!(parse("pairs --min-profit-bps 10").is_err());
+
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    let input = "C,A,0.2\nC,A\nC,A,0.19\n\nC,A,0.1\n";
+    let mut out = Vec::new();
+    super::watch_arb(dex, input.as_bytes(), 10.0, None, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<_> = out
+        .lines()
+        .map(|line| line.split_once(' ').unwrap().1)
+        .collect();
+    assert_eq!(lines.len(), 3, "{out}");
+    assert_eq!(lines[0], "open  A -> B -> C -> A: 2000.0 bps (rate 1.2)");
+    assert!(lines[1].starts_with("close A -> B -> C -> A after "));
+    assert!(lines[2].starts_with("open  A -> C -> B -> A: "));
+}
+
+#[test]
+fn test_dashboard() {
+    assert_eq!(
+        parse("dashboard --pairs A/D,B/C --select B/C")
+            .unwrap()
+            .command(),
+        &Command::Dashboard {
+            pairs: vec![('A'.into(), 'D'.into()), ('B'.into(), 'C'.into())],
+            select: Some(('B'.into(), 'C'.into())),
+        }
+    );
+    assert!(parse("dashboard").is_err());
+    assert!(parse("dashboard --pairs A-D").is_err());
+    assert!(parse("dashboard --pairs A/D --select B/C").is_err());
+
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    let dashboard = Dashboard::new(vec![('A'.into(), 'B'.into())]).with_color(false);
+    let mut out = Vec::new();
+    super::dashboard_loop(dex, dashboard, "A,B,2.5\nbad\n".as_bytes(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let frames: Vec<_> = out.split(CLEAR).skip(1).collect();
+    assert_eq!(frames.len(), 2);
+    assert!(
+        frames[1].contains("A/B                          2.500000  +25.000%"),
+        "{out}"
+    );
+}
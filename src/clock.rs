@@ -0,0 +1,68 @@
+//! Clock abstraction for the time-dependent features
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use super::Dex;
+
+/// The wall clock of the [`Dex`], e.g. of the provider update times.
+///
+/// The [`MockClock`] makes the time-dependent behavior, e.g. the
+/// provider staleness and the quote expiry, deterministic in the tests
+/// and the replays.
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The system wall clock.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// The clock moved by hand.  The clones share the time.
+#[derive(Clone, Debug)]
+pub struct MockClock(Arc<Mutex<SystemTime>>);
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        *self.0.lock().unwrap()
+    }
+}
+
+impl MockClock {
+    pub fn new(now: SystemTime) -> Self {
+        Self(Arc::new(Mutex::new(now)))
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        *self.0.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.0.lock().unwrap() += duration;
+    }
+}
+
+impl Dex {
+    /// Sets the clock, or the [`SystemClock`] in case of `None`.
+    pub fn set_clock(&mut self, clock: Option<Arc<dyn Clock>>) {
+        self.clock = clock;
+    }
+
+    /// Returns the current time of the clock.
+    pub fn now(&self) -> SystemTime {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemTime::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{Clock, MockClock, SystemClock};
+use crate::alert::{Alert, Alerter, Rule};
+use crate::edge::Edge;
+use crate::query::QueryOptions;
+use crate::Dex;
+
+#[test]
+fn test_mock_clock() {
+    let clock = MockClock::new(UNIX_EPOCH);
+    let shared = clock.clone();
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(shared.now(), UNIX_EPOCH + Duration::from_secs(60));
+    shared.set(UNIX_EPOCH);
+    assert_eq!(clock.now(), UNIX_EPOCH);
+    assert!(SystemClock.now() > UNIX_EPOCH);
+}
+
+#[test]
+fn test_dex_clock() {
+    let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let clock = MockClock::new(start);
+    let mut dex = Dex::new();
+    dex.set_clock(Some(Arc::new(clock.clone())));
+    assert_eq!(dex.now(), start);
+
+    let kraken = dex.register_provider("kraken", 0);
+    dex.add_provider_rate(kraken, 'A', 'B', 2.0);
+    assert_eq!(dex.provider(kraken).unwrap().updated_at(), Some(start));
+
+    // The staleness is checked against the mock time.
+    let max_age = Duration::from_secs(30);
+    let mut alerter = Alerter::new().rule(Rule::ProviderStale {
+        provider: kraken,
+        max_age,
+    });
+    assert!(alerter.check(&dex, dex.now()).is_empty());
+    clock.advance(Duration::from_secs(31));
+    assert!(matches!(
+        &alerter.check(&dex, dex.now())[..],
+        [Alert::ProviderStale { .. }]
+    ));
+
+    // So is the quote expiry.
+    dex.add_edge('B', 'C', Edge::new(3.0).with_timestamp(dex.now()));
+    let options = QueryOptions::new().with_quote_ttl(Duration::from_secs(5));
+    let path = dex
+        .get_best_rate_with(&'B'.into(), &'C'.into(), &options)
+        .unwrap();
+    assert!(!path.is_expired(dex.now()));
+    clock.advance(Duration::from_secs(6));
+    assert!(path.is_expired(dex.now()));
+
+    dex.set_clock(None);
+    assert!(dex.now() > SystemTime::now() - Duration::from_secs(60));
+}
@@ -0,0 +1,159 @@
+//! Currency clustering by connectivity
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex};
+
+/// The maximum number of the label propagation rounds.
+const MAX_ROUNDS: usize = 100;
+
+/// The group of the currencies more connected within than across, e.g.
+/// the fiat currencies or the tokens of the chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cluster {
+    /// The member with the most neighbors, to label the cluster with.
+    pub hub: Vertex,
+    pub members: Vec<Vertex>,
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let members: Vec<_> = self.members.iter().map(|v| v.to_string()).collect();
+        write!(f, "{}: {}", self.hub, members.join(", "))
+    }
+}
+
+/// The clusters of all the vertices, the largest first.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Clusters {
+    clusters: Vec<Cluster>,
+    index: BTreeMap<Vertex, usize>,
+}
+
+impl fmt::Display for Clusters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, cluster) in self.clusters.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{cluster}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Clusters {
+    pub fn clusters(&self) -> &[Cluster] {
+        &self.clusters
+    }
+
+    pub fn len(&self) -> usize {
+        self.clusters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clusters.is_empty()
+    }
+
+    /// Returns the index of the cluster of the vertex, e.g. to assign
+    /// the partition of [`Dex::partition`].
+    pub fn index(&self, v: &Vertex) -> Option<usize> {
+        self.index.get(v).copied()
+    }
+
+    pub fn cluster(&self, v: &Vertex) -> Option<&Cluster> {
+        self.index(v).map(|i| &self.clusters[i])
+    }
+}
+
+impl Dex {
+    /// Groups the currencies into the clusters by the connectivity.
+    ///
+    /// It propagates the labels, each vertex taking the label most
+    /// common among its neighbors in either direction, until they
+    /// settle.  The pairs quoted both ways count twice, and the ties
+    /// are broken by the vertex order for the stable result.
+    #[instrument(level = "debug", skip(self))]
+    pub fn clusters(&self) -> Clusters {
+        let vertices: Vec<_> = self.vertices().copied().collect();
+        let position: BTreeMap<_, _> = vertices.iter().enumerate().map(|(i, v)| (*v, i)).collect();
+        let mut neighbors = vec![BTreeMap::new(); vertices.len()];
+        for (src, edges) in &self.edges {
+            for dst in edges.keys() {
+                let (u, v) = (position[src], position[dst]);
+                *neighbors[u].entry(v).or_insert(0) += 1;
+                *neighbors[v].entry(u).or_insert(0) += 1;
+            }
+        }
+
+        let mut labels: Vec<_> = (0..vertices.len()).collect();
+        for round in 0..MAX_ROUNDS {
+            let mut changed = false;
+            for u in 0..vertices.len() {
+                let mut weights = BTreeMap::new();
+                for (v, weight) in &neighbors[u] {
+                    *weights.entry(labels[*v]).or_insert(0) += weight;
+                }
+                let max = match weights.values().max() {
+                    Some(max) => *max,
+                    None => continue,
+                };
+                if weights.get(&labels[u]) == Some(&max) {
+                    continue;
+                }
+                let label = weights.iter().find(|(_, w)| **w == max).map(|(l, _)| *l);
+                if let Some(label) = label {
+                    labels[u] = label;
+                    changed = true;
+                }
+            }
+            if !changed {
+                debug!(round, "settled");
+                break;
+            }
+        }
+
+        let mut members: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for (u, label) in labels.iter().enumerate() {
+            members.entry(*label).or_default().insert(u);
+        }
+        let mut clusters: Vec<_> = members
+            .into_values()
+            .map(|members| {
+                // The cluster has at least the vertex of its label.
+                let hub = members
+                    .iter()
+                    .max_by(|a, b| {
+                        neighbors[**a]
+                            .len()
+                            .cmp(&neighbors[**b].len())
+                            .then(b.cmp(a))
+                    })
+                    .unwrap();
+                Cluster {
+                    hub: vertices[*hub],
+                    members: members.iter().map(|u| vertices[*u]).collect(),
+                }
+            })
+            .collect();
+        clusters.sort_by(|a, b| {
+            b.members
+                .len()
+                .cmp(&a.members.len())
+                .then(a.hub.cmp(&b.hub))
+        });
+        let index = clusters
+            .iter()
+            .enumerate()
+            .flat_map(|(i, cluster)| cluster.members.iter().map(move |v| (*v, i)))
+            .collect();
+        debug!(clusters = clusters.len(), "clusters");
+        Clusters { clusters, index }
+    }
+}
+
+#[cfg(test)]
+mod test;
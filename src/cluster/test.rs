@@ -0,0 +1,36 @@
+use crate::test::vertex;
+use crate::Dex;
+
+#[test]
+fn test_clusters() {
+    // The fiat and the stablecoin clusters, bridged by USD -> USDT.
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("USD"), vertex("EUR"), 0.9);
+    dex.add_rate(vertex("USD"), vertex("JPY"), 150.0);
+    dex.add_rate(vertex("EUR"), vertex("JPY"), 165.0);
+    dex.add_rate(vertex("USDT"), vertex("USDC"), 1.0);
+    dex.add_rate(vertex("USDT"), vertex("DAI"), 1.0);
+    dex.add_rate(vertex("USDC"), vertex("DAI"), 1.0);
+    dex.add_rate(vertex("USD"), vertex("USDT"), 1.0);
+    dex.add_rate(vertex("GBP"), vertex("GBX"), 100.0);
+
+    let clusters = dex.clusters();
+    assert_eq!(clusters.len(), 3);
+    let fiat = clusters.cluster(&vertex("EUR")).unwrap();
+    assert_eq!(fiat.hub, vertex("USD"));
+    assert_eq!(fiat.members, [vertex("EUR"), vertex("JPY"), vertex("USD")]);
+    let stable = clusters.cluster(&vertex("DAI")).unwrap();
+    assert_eq!(stable.hub, vertex("USDT"));
+    assert_eq!(stable.members.len(), 3);
+    assert_eq!(clusters.index(&vertex("GBX")), Some(2));
+    assert_eq!(clusters.index(&vertex("XYZ")), None);
+    assert_eq!(
+        clusters.to_string(),
+        "USD: EUR, JPY, USD\nUSDT: DAI, USDC, USDT\nGBP: GBP, GBX"
+    );
+
+    let (map, partitions) = dex.partition(clusters.len(), |v| clusters.index(v).unwrap());
+    assert_eq!(partitions.len(), 3);
+    assert_eq!(map.cut().len(), 2);
+    assert!(Dex::new().clusters().is_empty());
+}
@@ -0,0 +1,256 @@
+//! A/B comparison of the search backends
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex};
+use crate::query::{Algorithm, QueryOptions};
+
+/// The relative rate difference tolerated between the backends, as
+/// they accumulate the rates in the different orders.
+const TOLERANCE: f32 = 1e-5;
+
+/// The minimum width of the rate columns.
+const WIDTH: usize = 10;
+
+/// The best rate search backend.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// [`Algorithm::Bfs`], the legacy default.
+    Bfs,
+    /// [`Algorithm::Spfa`].
+    Spfa,
+    /// The Dijkstra search over the frozen graph, see
+    /// [`FrozenDex::get_best_rate`].
+    ///
+    /// [`FrozenDex::get_best_rate`]: crate::frozen::FrozenDex::get_best_rate
+    Dijkstra,
+}
+
+impl Backend {
+    pub const ALL: [Self; 3] = [Self::Bfs, Self::Spfa, Self::Dijkstra];
+
+    // Returns the query algorithm of the backend, or `None` for the
+    // frozen graph.
+    fn algorithm(self) -> Option<Algorithm> {
+        match self {
+            Self::Bfs => Some(Algorithm::Bfs),
+            Self::Spfa => Some(Algorithm::Spfa),
+            Self::Dijkstra => None,
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bfs" => Ok(Self::Bfs),
+            "spfa" => Ok(Self::Spfa),
+            "dijkstra" => Ok(Self::Dijkstra),
+            _ => Err(format!(
+                "invalid algorithm {s:?}, expected bfs, spfa or dijkstra"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Bfs => "bfs",
+            Self::Spfa => "spfa",
+            Self::Dijkstra => "dijkstra",
+        })
+    }
+}
+
+/// The best rate of the pair by each backend compared.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    pub src: Vertex,
+    pub dst: Vertex,
+    /// The rate by each backend, in the backend order, or `None` in
+    /// case the backend found no path.
+    pub rates: Vec<Option<f32>>,
+}
+
+impl Row {
+    /// Checks if the backends disagree, either on the reachability or
+    /// on the rate beyond the rounding.
+    pub fn is_disagreement(&self) -> bool {
+        let found: Vec<f32> = self.rates.iter().flatten().copied().collect();
+        if found.len() != self.rates.len() {
+            return !found.is_empty();
+        }
+        let min = found.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = found.iter().copied().fold(0.0, f32::max);
+        differs(min, max)
+    }
+}
+
+/// Checks if the rates differ beyond the rounding.
+pub(crate) fn differs(a: f32, b: f32) -> bool {
+    (a - b).abs() > a.max(b) * TOLERANCE
+}
+
+/// The results and the timing of the backends on the same queries.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    backends: Vec<Backend>,
+    rows: Vec<Row>,
+    elapsed: Vec<Duration>,
+}
+
+impl Comparison {
+    pub fn backends(&self) -> &[Backend] {
+        &self.backends
+    }
+
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    /// Returns the rows the backends disagree on.
+    pub fn disagreements(&self) -> impl Iterator<Item = &Row> {
+        self.rows.iter().filter(|row| row.is_disagreement())
+    }
+
+    /// Returns the total time of all the queries by each backend, in
+    /// the backend order.
+    pub fn elapsed(&self) -> &[Duration] {
+        &self.elapsed
+    }
+
+    /// Returns the number of the paths found by each backend.
+    pub fn found(&self) -> Vec<usize> {
+        (0..self.backends.len())
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .filter(|row| row.rates[i].is_some())
+                    .count()
+            })
+            .collect()
+    }
+}
+
+/// The table of the rates by the pair, the disagreements marked with
+/// `*`, followed by the paths found and the time per query of each
+/// backend.
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| format!("{} -> {}", row.src, row.dst))
+            .collect();
+        let pad = pairs.iter().map(String::len).max().unwrap_or(0).max(5);
+        write!(f, "{:pad$}", "pair")?;
+        for backend in &self.backends {
+            write!(f, " {backend:>WIDTH$}")?;
+        }
+        writeln!(f)?;
+        for (pair, row) in pairs.iter().zip(&self.rows) {
+            write!(f, "{pair:pad$}")?;
+            for rate in &row.rates {
+                match rate {
+                    Some(rate) => write!(f, " {rate:>WIDTH$.4}")?,
+                    None => write!(f, " {:>WIDTH$}", "-")?,
+                }
+            }
+            if row.is_disagreement() {
+                write!(f, "  *")?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "{:pad$}", "found")?;
+        for found in self.found() {
+            let found = format!("{found}/{}", self.rows.len());
+            write!(f, " {found:>WIDTH$}")?;
+        }
+        writeln!(f)?;
+        write!(f, "{:pad$}", "query")?;
+        for elapsed in &self.elapsed {
+            let per_query = elapsed
+                .checked_div(self.rows.len() as u32)
+                .unwrap_or_default();
+            write!(f, " {:>WIDTH$}", format!("{per_query:?}"))?;
+        }
+        writeln!(f)?;
+        write!(
+            f,
+            "{} disagreements of {} queries",
+            self.disagreements().count(),
+            self.rows.len(),
+        )
+    }
+}
+
+impl Dex {
+    /// Runs the best rate query of every pair through each of the
+    /// `backends`, and compares the rates found.
+    ///
+    /// It's to validate the backends against each other, e.g. the new
+    /// one against the legacy BFS, before switching the default.  The
+    /// freeze of the graph for the Dijkstra is not timed.
+    #[instrument(level = "debug", skip(self))]
+    pub fn compare(&self, backends: &[Backend]) -> Comparison {
+        let vertices: Vec<_> = self.vertices().copied().collect();
+        let mut rows: Vec<Row> = vertices
+            .iter()
+            .flat_map(|src| {
+                vertices
+                    .iter()
+                    .filter(move |dst| *dst != src)
+                    .map(|dst| Row {
+                        src: *src,
+                        dst: *dst,
+                        rates: Vec::with_capacity(backends.len()),
+                    })
+            })
+            .collect();
+        let mut elapsed = Vec::with_capacity(backends.len());
+        for backend in backends {
+            let start;
+            match backend.algorithm() {
+                Some(algorithm) => {
+                    let options = QueryOptions::new().with_algorithm(algorithm);
+                    start = Instant::now();
+                    for row in &mut rows {
+                        let rate = self
+                            .get_best_rate_with(&row.src, &row.dst, &options)
+                            .map(|path| path.rate());
+                        row.rates.push(rate);
+                    }
+                }
+                None => {
+                    let frozen = self.freeze();
+                    let mut searcher = frozen.searcher();
+                    start = Instant::now();
+                    for row in &mut rows {
+                        let rate = searcher
+                            .get_best_rate(&frozen, &row.src, &row.dst)
+                            .map(|path| path.rate());
+                        row.rates.push(rate);
+                    }
+                }
+            }
+            let time = start.elapsed();
+            debug!(%backend, ?time, "compared");
+            elapsed.push(time);
+        }
+        Comparison {
+            backends: backends.to_vec(),
+            rows,
+            elapsed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,52 @@
+use super::{Backend, Row};
+use crate::Dex;
+
+#[test]
+fn test_backend() {
+    assert_eq!("bfs".parse(), Ok(Backend::Bfs));
+    assert_eq!("dijkstra".parse(), Ok(Backend::Dijkstra));
+    assert!("astar".parse::<Backend>().is_err());
+    assert_eq!(Backend::Spfa.to_string(), "spfa");
+    assert_eq!(format!("{:>5}", Backend::Bfs), "  bfs");
+}
+
+#[test]
+fn test_row_is_disagreement() {
+    let row = |rates: &[Option<f32>]| Row {
+        src: 'A'.into(),
+        dst: 'B'.into(),
+        rates: rates.to_vec(),
+    };
+    assert!(!row(&[Some(2.0), Some(2.0), Some(2.000001)]).is_disagreement());
+    assert!(!row(&[None, None]).is_disagreement());
+    assert!(row(&[Some(2.0), Some(2.1)]).is_disagreement());
+    assert!(row(&[Some(2.0), None]).is_disagreement());
+}
+
+#[test]
+fn test_compare() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_limit_order('C', 'D', 0.5, 100.0);
+
+    let comparison = dex.compare(&Backend::ALL);
+    assert_eq!(comparison.backends(), Backend::ALL);
+    assert_eq!(comparison.rows().len(), 12);
+    assert_eq!(comparison.elapsed().len(), 3);
+    assert_eq!(comparison.found(), vec![9, 9, 9]);
+    assert_eq!(comparison.disagreements().count(), 0);
+    let row = comparison
+        .rows()
+        .iter()
+        .find(|row| row.src == 'A'.into() && row.dst == 'D'.into())
+        .unwrap();
+    assert_eq!(row.rates, vec![Some(3.0); 3]);
+
+    let table = comparison.to_string();
+    assert!(table.starts_with("pair          bfs       spfa   dijkstra\n"));
+    assert!(table.contains("\nA -> D     3.0000     3.0000     3.0000\n"));
+    assert!(table.contains("\nD -> A          -          -          -\n"));
+    assert!(table.contains("\nfound        9/12       9/12       9/12\n"));
+    assert!(table.ends_with("\n0 disagreements of 12 queries"));
+}
@@ -0,0 +1,309 @@
+//! Concentrated liquidity pools, e.g. Uniswap V3
+
+use tracing::debug;
+
+use super::{Dex, Vertex};
+use crate::edge::{Edge, EdgeKind};
+
+/// The price ratio of the adjacent ticks.
+pub const TICK_BASE: f64 = 1.0001;
+
+/// Returns the price of the `tick`, `1.0001^tick`.
+pub fn tick_price(tick: i32) -> f64 {
+    TICK_BASE.powi(tick)
+}
+
+/// The concentrated liquidity pool of the fee tier.
+///
+/// The price is of the token0 in the token1, and the liquidity is
+/// provided within the tick ranges.  The swaps walk the ranges from
+/// the current price, with the liquidity active in each.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pool {
+    pub(crate) fee: f32,
+    pub(crate) sqrt_price: f64,
+    // The net liquidity added at each initialized tick, ordered by the
+    // square root price.
+    pub(crate) ticks: Vec<(f64, f64)>,
+}
+
+impl Pool {
+    /// Creates the pool at the `price` with the `fee` tier, e.g.
+    /// `0.003` for 30 bps.
+    pub fn new(price: f64, fee: f32) -> Self {
+        assert!(price > 0.0 && price.is_finite());
+        assert!((0.0..1.0).contains(&fee));
+        Self {
+            fee,
+            sqrt_price: price.sqrt(),
+            ticks: Vec::new(),
+        }
+    }
+
+    /// Adds the `liquidity` within the `lower..upper` tick range.
+    pub fn with_position(mut self, lower: i32, upper: i32, liquidity: f64) -> Self {
+        assert!(lower < upper && liquidity >= 0.0);
+        for (tick, net) in [(lower, liquidity), (upper, -liquidity)] {
+            let sqrt_price = tick_price(tick).sqrt();
+            let i = self.ticks.partition_point(|(p, _)| *p < sqrt_price);
+            match self.ticks.get_mut(i) {
+                Some((p, total)) if *p == sqrt_price => *total += net,
+                _ => self.ticks.insert(i, (sqrt_price, net)),
+            }
+        }
+        self
+    }
+
+    pub fn fee(&self) -> f32 {
+        self.fee
+    }
+
+    /// Returns the current price of the token0 in the token1.
+    pub fn price(&self) -> f64 {
+        self.sqrt_price * self.sqrt_price
+    }
+
+    /// Returns the liquidity active at the current price.
+    pub fn liquidity(&self) -> f64 {
+        self.ticks
+            .iter()
+            .take_while(|(p, _)| *p <= self.sqrt_price)
+            .map(|(_, net)| net)
+            .sum::<f64>()
+            .max(0.0)
+    }
+
+    /// Returns the amount received for the `amount` in, net of the fee
+    /// taken from the input, and the partial amount in case the ranges
+    /// run out of the liquidity.
+    pub fn quote(&self, amount: f64, zero_for_one: bool) -> f64 {
+        self.swap(amount * f64::from(1.0 - self.fee), zero_for_one)
+            .0
+    }
+
+    /// Returns the maximum amount in, the fee included, before the
+    /// ranges run out of the liquidity.
+    pub fn capacity(&self, zero_for_one: bool) -> f64 {
+        let mut capacity = 0.0;
+        self.walk(zero_for_one, |_, amount_in, _| {
+            capacity += amount_in;
+            true
+        });
+        capacity / f64::from(1.0 - self.fee)
+    }
+
+    // Returns the spot price of the direction.
+    fn spot(&self, zero_for_one: bool) -> f64 {
+        if zero_for_one {
+            self.price()
+        } else {
+            1.0 / self.price()
+        }
+    }
+
+    // Swaps the `amount` in after the fee, and returns the amount out
+    // and the square root price after the swap.
+    fn swap(&self, mut amount: f64, zero_for_one: bool) -> (f64, f64) {
+        let mut out = 0.0;
+        let mut sqrt_price = self.sqrt_price;
+        self.walk(zero_for_one, |range, amount_in, liquidity| {
+            let (from, to) = range;
+            if amount <= amount_in {
+                let next = if zero_for_one {
+                    1.0 / (1.0 / from + amount / liquidity)
+                } else {
+                    from + amount / liquidity
+                };
+                out += amount_out(from, next, liquidity, zero_for_one);
+                sqrt_price = next;
+                amount = 0.0;
+                return false;
+            }
+            out += amount_out(from, to, liquidity, zero_for_one);
+            amount -= amount_in;
+            sqrt_price = to;
+            true
+        });
+        (out, sqrt_price)
+    }
+
+    // Walks the ranges with the liquidity from the current price in the
+    // direction, and calls `f` with the square root price range, the
+    // amount in to cross it, and the liquidity, until `f` returns false.
+    fn walk<F>(&self, zero_for_one: bool, mut f: F)
+    where
+        F: FnMut((f64, f64), f64, f64) -> bool,
+    {
+        let mut sqrt_price = self.sqrt_price;
+        let mut liquidity = self.liquidity();
+        let mut i = self.ticks.partition_point(|(p, _)| *p <= sqrt_price);
+        loop {
+            let (next, net) = if zero_for_one {
+                match i.checked_sub(1) {
+                    Some(j) => (self.ticks[j].0, -self.ticks[j].1),
+                    None => return,
+                }
+            } else {
+                match self.ticks.get(i) {
+                    Some((p, net)) => (*p, *net),
+                    None => return,
+                }
+            };
+            if liquidity > 0.0 {
+                let amount_in = if zero_for_one {
+                    liquidity * (1.0 / next - 1.0 / sqrt_price)
+                } else {
+                    liquidity * (next - sqrt_price)
+                };
+                if !f((sqrt_price, next), amount_in, liquidity) {
+                    return;
+                }
+            }
+            sqrt_price = next;
+            liquidity = (liquidity + net).max(0.0);
+            if zero_for_one {
+                i -= 1;
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
+
+// The amount out for moving the square root price within the range.
+fn amount_out(from: f64, to: f64, liquidity: f64, zero_for_one: bool) -> f64 {
+    if zero_for_one {
+        liquidity * (from - to)
+    } else {
+        liquidity * (1.0 / from - 1.0 / to)
+    }
+}
+
+impl Edge {
+    /// Creates the concentrated liquidity edge with the single pool, of
+    /// the token0 to the token1 direction in case of `zero_for_one`.
+    pub(crate) fn concentrated(pool: Pool, zero_for_one: bool) -> Self {
+        let mut edge = Self {
+            kind: EdgeKind::Concentrated,
+            zero_for_one,
+            ..Self::new(1.0)
+        };
+        edge.add_pool(pool);
+        edge
+    }
+
+    /// Returns the pools of the fee tiers.
+    pub fn pools(&self) -> &[Pool] {
+        &self.pools
+    }
+
+    /// Adds the pool, or replaces the one of the same fee tier.
+    pub(crate) fn add_pool(&mut self, pool: Pool) {
+        assert!(self.kind == EdgeKind::Concentrated);
+        match self.pools.iter_mut().find(|p| p.fee == pool.fee) {
+            Some(p) => *p = pool,
+            None => self.pools.push(pool),
+        }
+        self.update_pools();
+    }
+
+    // Updates the rate with the best spot price, and the liquidity with
+    // the largest capacity of the pools.
+    pub(crate) fn update_pools(&mut self) {
+        let zero_for_one = self.zero_for_one;
+        if let Some(pool) = self.best_pool(None) {
+            self.rate = pool.spot(zero_for_one) as f32;
+        }
+        let capacity = self
+            .pools
+            .iter()
+            .map(|pool| pool.capacity(zero_for_one))
+            .fold(0.0, f64::max);
+        self.liquidity = Some(capacity as f32);
+    }
+
+    // Returns the pool with the most received for the `amount`, or at
+    // the spot price.
+    fn best_pool(&self, amount: Option<f32>) -> Option<&Pool> {
+        let zero_for_one = self.zero_for_one;
+        let received = |pool: &Pool| match amount {
+            Some(amount) => pool.quote(f64::from(amount), zero_for_one),
+            None => pool.spot(zero_for_one) * f64::from(1.0 - pool.fee),
+        };
+        self.pools.iter().max_by(|a, b| {
+            received(a)
+                .partial_cmp(&received(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+    }
+
+    // Returns the rate of the best pool for the `amount`, net of the
+    // fee tier in case of `net`.
+    pub(crate) fn pool_rate(&self, amount: Option<f32>, net: bool) -> f32 {
+        let pool = match self.best_pool(amount) {
+            Some(pool) => pool,
+            None => return 0.0,
+        };
+        let fee = if net { f64::from(1.0 - pool.fee) } else { 1.0 };
+        let rate = match amount {
+            Some(amount) if amount > 0.0 => {
+                let amount = f64::from(amount);
+                pool.swap(amount * fee, self.zero_for_one).0 / amount
+            }
+            _ => pool.spot(self.zero_for_one) * fee,
+        };
+        rate as f32
+    }
+
+    // Swaps the `amount` through the best pool, and moves its price.
+    pub(crate) fn swap_pool(&mut self, amount: f32) -> f32 {
+        let zero_for_one = self.zero_for_one;
+        let i = match self.best_pool(Some(amount)) {
+            Some(pool) => self.pools.iter().position(|p| p == pool).unwrap_or(0),
+            None => return 0.0,
+        };
+        let pool = &mut self.pools[i];
+        let amount_in = f64::from(amount) * f64::from(1.0 - pool.fee);
+        let (received, sqrt_price) = pool.swap(amount_in, zero_for_one);
+        pool.sqrt_price = sqrt_price;
+        self.update_pools();
+        received as f32
+    }
+}
+
+impl Dex {
+    /// Adds the concentrated liquidity `pool` of the `token0` and the
+    /// `token1`, and the other fee tiers of the pair are kept.
+    ///
+    /// The amount-aware quotes walk the tick ranges of each pool, and
+    /// take the best fee tier for the amount.  The pools take over any
+    /// other edge of the pair.
+    pub fn add_pool<V: Into<Vertex>>(&mut self, token0: V, token1: V, pool: Pool) {
+        let token0 = self.canonical(token0.into());
+        let token1 = self.canonical(token1.into());
+        assert!(token0 != token1);
+        debug!(%token0, %token1, fee = %pool.fee, price = %pool.price(), "pool");
+        let edges = self.edges.entry(token0).or_default();
+        let edge = match edges.get_mut(&token1) {
+            Some(edge) if edge.kind == EdgeKind::Concentrated && edge.zero_for_one => {
+                edge.add_pool(pool);
+                edge.clone()
+            }
+            _ => {
+                let edge = Edge::concentrated(pool, true);
+                edges.insert(token1, edge.clone());
+                edge
+            }
+        };
+        let reverse = edge.reverse();
+        self.edges
+            .entry(token1)
+            .or_default()
+            .insert(token0, reverse);
+        self.changes.push(token0, token1);
+        self.changes.push(token1, token0);
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,114 @@
+use super::*;
+use crate::query::QueryOptions;
+use crate::test::vertex;
+
+// The full range of the ticks.
+const MIN_TICK: i32 = -887_220;
+const MAX_TICK: i32 = 887_220;
+
+#[test]
+fn test_tick_price() {
+    assert_eq!(tick_price(0), 1.0);
+    assert!((tick_price(6932) - 2.0).abs() < 1e-3);
+    assert!((tick_price(-6932) - 0.5).abs() < 1e-3);
+}
+
+#[test]
+fn test_full_range_constant_product() {
+    // 100 ETH and 200,000 USDC in the full range.
+    let liquidity = (100.0f64 * 200_000.0).sqrt();
+    let pool = Pool::new(2000.0, 0.0).with_position(MIN_TICK, MAX_TICK, liquidity);
+    assert!((pool.liquidity() - liquidity).abs() < 1e-6);
+
+    let received = pool.quote(10.0, true);
+    let expected = 200_000.0 * 10.0 / (100.0 + 10.0);
+    assert!((received - expected).abs() < 1e-3 * expected);
+
+    let received = pool.quote(20_000.0, false);
+    let expected = 100.0 * 20_000.0 / (200_000.0 + 20_000.0);
+    assert!((received - expected).abs() < 1e-3 * expected);
+}
+
+#[test]
+fn test_walk_ranges() {
+    let price = 2000.0;
+    let tick = f64::ln(price) / TICK_BASE.ln();
+    let tick = tick as i32;
+    let narrow = Pool::new(price, 0.0).with_position(tick - 100, tick + 100, 10_000.0);
+    let wide = narrow
+        .clone()
+        .with_position(tick - 1000, tick + 1000, 10_000.0);
+
+    // The same liquidity at the price, until the narrow range runs out.
+    assert_eq!(narrow.liquidity(), 20_000.0 / 2.0);
+    assert_eq!(wide.liquidity(), 20_000.0);
+    let capacity = narrow.capacity(true);
+    assert!(capacity > 0.0 && capacity < wide.capacity(true));
+    assert!((narrow.quote(capacity * 2.0, true) - narrow.quote(capacity, true)).abs() < 1e-6);
+    assert!(wide.quote(capacity * 2.0, true) > narrow.quote(capacity * 2.0, true));
+
+    // The average price gets worse across the ranges.
+    let small = wide.quote(0.001, true) / 0.001;
+    let large = wide.quote(capacity * 2.0, true) / (capacity * 2.0);
+    assert!((small - price).abs() < 1e-2 * price);
+    assert!(large < small);
+}
+
+#[test]
+fn test_fee() {
+    let pool = Pool::new(1.0, 0.003).with_position(MIN_TICK, MAX_TICK, 1e9);
+    assert!((pool.quote(1.0, true) - 0.997).abs() < 1e-6);
+}
+
+#[test]
+fn test_add_pool_fee_tiers() {
+    let mut dex = Dex::new();
+    // The shallow 5 bps pool, and the deep 30 bps one.
+    dex.add_pool(
+        vertex("ETH"),
+        vertex("USDC"),
+        Pool::new(2000.0, 0.0005).with_position(MIN_TICK, MAX_TICK, 1_000.0),
+    );
+    dex.add_pool(
+        vertex("ETH"),
+        vertex("USDC"),
+        Pool::new(2000.0, 0.003).with_position(MIN_TICK, MAX_TICK, 1_000_000.0),
+    );
+    let eth = vertex("ETH");
+    let usdc = vertex("USDC");
+    let edge = &dex.edges[&eth][&usdc];
+    assert_eq!(edge.kind(), EdgeKind::Concentrated);
+    assert_eq!(edge.pools().len(), 2);
+    assert!((edge.rate() - 2000.0).abs() < 1e-2);
+    assert!((dex.edges[&usdc][&eth].rate() - 0.0005).abs() < 1e-8);
+
+    let small = QueryOptions::default().with_amount(0.01);
+    let path = dex.get_best_rate_with(&eth, &usdc, &small).unwrap();
+    assert!((path.rate() - 2000.0 * 0.9995).abs() < 1.0);
+
+    // The shallow pool holds about 0.02 ETH, and the deep one wins.
+    let large = QueryOptions::default().with_amount(10.0);
+    let path = dex.get_best_rate_with(&eth, &usdc, &large).unwrap();
+    assert!(path.rate() < 2000.0 * 0.997);
+    assert!(path.rate() > 2000.0 * 0.99);
+
+    let path = dex
+        .get_best_rate_with(&usdc, &eth, &QueryOptions::default().with_amount(1_000.0))
+        .unwrap();
+    assert!((path.rate() - 0.0005 * 0.997).abs() < 1e-5);
+}
+
+#[test]
+fn test_route_amount() {
+    let mut dex = Dex::new();
+    dex.add_pool(
+        vertex("ETH"),
+        vertex("USDC"),
+        Pool::new(2000.0, 0.0).with_position(MIN_TICK, MAX_TICK, 10_000.0),
+    );
+    let flow = dex.route_amount(&vertex("ETH"), &vertex("USDC"), 1.0);
+    // About 224 ETH and 447,214 USDC in the range.
+    let (x, y) = (10_000.0 / 2000f32.sqrt(), 10_000.0 * 2000f32.sqrt());
+    let expected = y * 1.0 / (x + 1.0);
+    assert!((flow.amount_out() - expected).abs() < 1e-2 * expected);
+}
@@ -0,0 +1,659 @@
+//! Layered configuration
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::Vertex;
+
+/// The prefix of the environment variables, e.g. `BEST_RATE_LISTEN`.
+pub const ENV_PREFIX: &str = "BEST_RATE_";
+
+/// The settings with the default values, empty for none.
+const SETTINGS: &[(&str, &str)] = &[
+    ("base", ""),
+    ("input", ""),
+    ("listen", "127.0.0.1:8080"),
+    ("max_staleness_ms", "10"),
+    ("tls_cert", ""),
+    ("tls_key", ""),
+];
+
+/// The sections of the named settings, e.g. `graph.fx.input` under the
+/// `[graph.fx]` section.
+///
+/// The graph settings override the top level ones of the named graph.
+const SECTIONS: &[(&str, &[&str])] = &[
+    (
+        "alert",
+        &[
+            "graph",
+            "max_age_ms",
+            "min_bps",
+            "pair",
+            "provider",
+            "threshold",
+            "webhook",
+        ],
+    ),
+    ("api_key", &["burst", "key", "rate_limit", "write"]),
+    ("graph", &["base", "input", "max_staleness_ms"]),
+];
+
+/// The section settings masked on print.
+const SECRETS: &[&str] = &["key"];
+
+/// The value kind of each setting, by the key within the section, if
+/// any, e.g. `base` of both the top level and the `[graph.<NAME>]`.
+const KINDS: &[(&str, Kind)] = &[
+    ("base", Kind::Currencies),
+    ("burst", Kind::Integer),
+    ("listen", Kind::Addr),
+    ("max_age_ms", Kind::Integer),
+    ("max_staleness_ms", Kind::Integer),
+    ("min_bps", Kind::Number),
+    ("rate_limit", Kind::Number),
+    ("threshold", Kind::Number),
+    ("write", Kind::Bool),
+];
+
+/// The maximum edit distance of the suggested name.
+const MAX_DISTANCE: usize = 2;
+
+// The value kind of the setting, the free text unless in the `KINDS`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Kind {
+    Addr,
+    Bool,
+    Currencies,
+    Integer,
+    Number,
+}
+
+/// Where the setting value comes from, the later overriding the
+/// earlier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    File(PathBuf),
+    Env(String),
+    Flag(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File(path) => write!(f, "file {}", path.display()),
+            Self::Env(var) => write!(f, "env {var}"),
+            Self::Flag(flag) => write!(f, "flag {flag}"),
+        }
+    }
+}
+
+/// The invalid configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigError(String);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for ConfigError {}
+
+impl ConfigError {
+    pub(crate) fn new(msg: String) -> Self {
+        Self(msg)
+    }
+
+    /// Joins the `errors` into the one, a line each.
+    pub fn join(errors: Vec<Self>) -> Self {
+        let lines: Vec<_> = errors.into_iter().map(|e| e.0).collect();
+        Self(lines.join("\n"))
+    }
+}
+
+/// The problem of the setting, found by [`Config::diagnostics`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The setting, e.g. `graph.fx.base`.
+    pub key: String,
+    pub origin: Origin,
+    pub message: String,
+    /// The fix, e.g. `did you mean "USD"?`.
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} from {}: {}", self.key, self.origin, self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", {suggestion}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The resolved settings, layered as the command line flags over the
+/// environment variables over the config file over the defaults.
+///
+/// The config file has the `key = value` lines, with the optional
+/// double quotes around the value and the `#` comments.  The keys
+/// under the `[<SECTION>.<NAME>]` section, e.g. `[graph.fx]`, are of
+/// the named item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    settings: BTreeMap<String, (String, Origin)>,
+    // The `BEST_RATE_<KEY>` variables of no setting.
+    unknown_vars: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            settings: SETTINGS
+                .iter()
+                .map(|(key, value)| (key.to_string(), (value.to_string(), Origin::Default)))
+                .collect(),
+            unknown_vars: Vec::new(),
+        }
+    }
+}
+
+/// Prints the resolved settings in the config file format, with the
+/// origin of each and the secrets masked.
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, (value, origin)) in &self.settings {
+            match section_key(key) {
+                Some((_, _, key)) if SECRETS.contains(&key) => {
+                    writeln!(f, "{key} = \"***\"  # {origin}")?
+                }
+                _ => writeln!(f, "{key} = {value:?}  # {origin}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `key` value.
+    pub fn set(&mut self, key: &str, value: &str, origin: Origin) -> Result<(), ConfigError> {
+        if section_key(key).is_none() && !self.settings.contains_key(key) {
+            let mut msg = format!("unknown setting {key:?}");
+            if let Some(known) = self.suggest_key(key) {
+                msg.push_str(&format!(", did you mean {known:?}?"));
+            }
+            return Err(ConfigError(msg));
+        }
+        self.settings
+            .insert(key.to_string(), (value.to_string(), origin));
+        Ok(())
+    }
+
+    /// Returns the names of the `section` items, e.g. the graphs with
+    /// the `[graph.<NAME>]` settings.
+    pub fn names(&self, section: &str) -> Vec<&str> {
+        let mut names: Vec<_> = self
+            .settings
+            .keys()
+            .filter_map(|key| section_key(key))
+            .filter(|(kind, ..)| *kind == section)
+            .map(|(_, name, _)| name)
+            .collect();
+        names.dedup();
+        names
+    }
+
+    /// Returns the `key` setting of the `name` item of the `section`.
+    pub fn section(&self, section: &str, name: &str, key: &str) -> Option<&str> {
+        self.get(&format!("{section}.{name}.{key}"))
+    }
+
+    /// Returns the `key` setting of the `graph`, falling back to the
+    /// top level one.
+    pub fn graph(&self, graph: &str, key: &str) -> Option<&str> {
+        let graph_key = format!("graph.{graph}.{key}");
+        match self.settings.get(&graph_key) {
+            Some((value, _)) if value.is_empty() => None,
+            Some((value, _)) => Some(value),
+            None => self.get(key),
+        }
+    }
+
+    /// Parses the `key` setting of the `graph`, falling back to the top
+    /// level one.
+    pub fn parse_graph<T: FromStr>(
+        &self,
+        graph: &str,
+        key: &str,
+    ) -> Result<Option<T>, ConfigError> {
+        let graph_key = format!("graph.{graph}.{key}");
+        if self.settings.contains_key(&graph_key) {
+            self.parse(&graph_key)
+        } else {
+            self.parse(key)
+        }
+    }
+
+    /// Returns the `key` value, or `None` for the unknown or the empty
+    /// one.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        match self.settings.get(key) {
+            Some((value, _)) if !value.is_empty() => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn origin(&self, key: &str) -> Option<&Origin> {
+        self.settings.get(key).map(|(_, origin)| origin)
+    }
+
+    /// Parses the `key` value.
+    pub fn parse<T: FromStr>(&self, key: &str) -> Result<Option<T>, ConfigError> {
+        match self.get(key) {
+            Some(value) => value.parse().map(Some).map_err(|_| {
+                ConfigError(format!(
+                    "invalid {key} value {value:?} from {}",
+                    self.settings[key].1
+                ))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Loads the config file.
+    pub fn load_file(&mut self, path: &Path) -> Result<(), ConfigError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| ConfigError(format!("{}: {e}", path.display())))?;
+        self.load_str(&content, path)
+    }
+
+    // Loads the config file `content`, read from the `path`.
+    //
+    // The lines in error are skipped, and reported all at once.
+    pub(crate) fn load_str(&mut self, content: &str, path: &Path) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        // The section of the lines, or `None` after the unknown one.
+        let mut section = Some(String::new());
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |msg: &str| ConfigError(format!("{}:{}: {msg}", path.display(), i + 1));
+            if line.starts_with('[') {
+                section = match parse_section(line) {
+                    Ok(name) => Some(format!("{name}.")),
+                    Err(msg) => {
+                        errors.push(error(&msg));
+                        None
+                    }
+                };
+                continue;
+            }
+            let (key, value) = match parse_setting(line) {
+                Ok(setting) => setting,
+                Err(msg) => {
+                    errors.push(error(msg));
+                    continue;
+                }
+            };
+            let section = match &section {
+                Some(section) => section,
+                None => continue,
+            };
+            let key = format!("{section}{key}");
+            if let Err(e) = self.set(&key, value, Origin::File(path.to_path_buf())) {
+                errors.push(error(&e.0));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::join(errors))
+        }
+    }
+
+    /// Loads the `BEST_RATE_<KEY>` environment variables out of `vars`,
+    /// e.g. `std::env::vars()`.
+    pub fn load_env<I>(&mut self, vars: I)
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        for (var, value) in vars {
+            let key = match var.strip_prefix(ENV_PREFIX) {
+                Some(key) => key.to_ascii_lowercase(),
+                None => continue,
+            };
+            match self.settings.get_mut(key.as_str()) {
+                Some(setting) => *setting = (value, Origin::Env(var)),
+                // The config file is taken by the caller.
+                None if key == "config" => {}
+                None => self.unknown_vars.push(var),
+            }
+        }
+    }
+
+    /// Checks all the settings against their kinds, and against each
+    /// other, and returns all the problems found.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for var in &self.unknown_vars {
+            let key = var[ENV_PREFIX.len()..].to_ascii_lowercase();
+            diagnostics.push(Diagnostic {
+                key: key.clone(),
+                origin: Origin::Env(var.clone()),
+                message: "unknown setting".to_string(),
+                suggestion: self.suggest_key(&key).map(|known| {
+                    let known = format!("{ENV_PREFIX}{}", known.to_ascii_uppercase());
+                    format!("did you mean {known}?")
+                }),
+            });
+        }
+        for (key, (value, origin)) in &self.settings {
+            let name = section_key(key).map_or(key.as_str(), |(_, _, name)| name);
+            let kind = match KINDS.iter().find(|(setting, _)| *setting == name) {
+                Some((_, kind)) if !value.is_empty() => *kind,
+                _ => continue,
+            };
+            for (message, suggestion) in check(kind, value) {
+                diagnostics.push(Diagnostic {
+                    key: key.clone(),
+                    origin: origin.clone(),
+                    message,
+                    suggestion,
+                });
+            }
+        }
+        diagnostics.extend(self.conflicts());
+        diagnostics
+    }
+
+    /// Checks the settings, and returns all the problems found as the
+    /// one error, see [`Config::diagnostics`].
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let diagnostics = self.diagnostics();
+        if diagnostics.is_empty() {
+            return Ok(());
+        }
+        let lines: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        Err(ConfigError(lines.join("\n")))
+    }
+
+    // Returns the settings conflicting with or missing for the others.
+    fn conflicts(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        // The problem of the `key`, set or missing, of the `origin`
+        // setting.
+        let mut conflict = |key: String, origin: &str, message: &str, suggestion: String| {
+            diagnostics.push(Diagnostic {
+                key,
+                origin: self.settings[origin].1.clone(),
+                message: message.to_string(),
+                suggestion: Some(suggestion),
+            });
+        };
+        match (self.get("tls_cert"), self.get("tls_key")) {
+            (Some(_), None) => conflict(
+                "tls_cert".to_string(),
+                "tls_cert",
+                "set without tls_key",
+                "set both or neither".to_string(),
+            ),
+            (None, Some(_)) => conflict(
+                "tls_key".to_string(),
+                "tls_key",
+                "set without tls_cert",
+                "set both or neither".to_string(),
+            ),
+            _ => {}
+        }
+        for name in self.names("api_key") {
+            let setting = |key: &str| format!("api_key.{name}.{key}");
+            let burst = setting("burst");
+            if self.get(&burst).is_some() && self.get(&setting("rate_limit")).is_none() {
+                conflict(
+                    burst.clone(),
+                    &burst,
+                    "ignored without rate_limit",
+                    format!("set {}", setting("rate_limit")),
+                );
+            }
+            if self.get(&setting("key")).is_none() {
+                conflict(
+                    setting("key"),
+                    self.section_origin("api_key", name),
+                    "missing",
+                    format!("set the key of the {name} api key"),
+                );
+            }
+        }
+        for name in self.names("alert") {
+            let setting = |key: &str| format!("alert.{name}.{key}");
+            let rules = ["pair", "min_bps", "provider"]
+                .iter()
+                .filter(|key| self.get(&setting(key)).is_some())
+                .count();
+            if rules != 1 {
+                conflict(
+                    format!("alert.{name}"),
+                    self.section_origin("alert", name),
+                    if rules == 0 {
+                        "no rule"
+                    } else {
+                        "more than one rule"
+                    },
+                    "set one of pair, min_bps or provider".to_string(),
+                );
+            }
+            if self.get(&setting("webhook")).is_none() {
+                conflict(
+                    setting("webhook"),
+                    self.section_origin("alert", name),
+                    "missing",
+                    format!("set the webhook of the {name} alert"),
+                );
+            }
+        }
+        diagnostics
+    }
+
+    // Returns the first setting of the `name` item of the `section`, as
+    // any setting of the item has the origin of the section.
+    fn section_origin(&self, section: &str, name: &str) -> &str {
+        self.settings
+            .range(format!("{section}.{name}.")..)
+            .map(|(key, _)| key.as_str())
+            .next()
+            .unwrap_or_default()
+    }
+
+    // Returns the known setting closest to the unknown `key`.
+    fn suggest_key(&self, key: &str) -> Option<String> {
+        match key.split_once('.') {
+            Some((section, rest)) => match SECTIONS.iter().find(|(kind, _)| *kind == section) {
+                Some((_, keys)) => {
+                    let (name, key) = rest.rsplit_once('.')?;
+                    let known = closest(key, keys.iter().copied())?;
+                    Some(format!("{section}.{name}.{known}"))
+                }
+                None => {
+                    let known = closest(section, SECTIONS.iter().map(|(kind, _)| *kind))?;
+                    Some(format!("{known}.{rest}"))
+                }
+            },
+            None => closest(key, SETTINGS.iter().map(|(setting, _)| *setting)).map(String::from),
+        }
+    }
+}
+
+// Parses the `[<SECTION>.<NAME>]` line, and returns the section name.
+fn parse_section(line: &str) -> Result<&str, String> {
+    let name = line
+        .trim_start_matches('[')
+        .strip_suffix(']')
+        .ok_or_else(|| "unterminated section".to_string())?
+        .trim();
+    let is_section = match name.split_once('.') {
+        Some((kind, name)) => {
+            SECTIONS.iter().any(|(section, _)| *section == kind)
+                && !name.is_empty()
+                && !name.contains('.')
+        }
+        None => false,
+    };
+    if is_section {
+        return Ok(name);
+    }
+    let mut msg = format!("unknown section {name:?}");
+    let kind = name.split('.').next().unwrap_or_default();
+    if let Some(known) = closest(kind, SECTIONS.iter().map(|(kind, _)| *kind)) {
+        msg.push_str(&format!(", expected [{known}.<NAME>]"));
+    }
+    Err(msg)
+}
+
+// Parses the `key = value` line, with the optional double quotes around
+// the value and the trailing comment.
+fn parse_setting(line: &str) -> Result<(&str, &str), &'static str> {
+    let (key, value) = line.split_once('=').ok_or("expected key = value")?;
+    let value = value.trim();
+    let value = match value.strip_prefix('"') {
+        Some(value) => value.strip_suffix('"').ok_or("unterminated string")?,
+        None => value.split('#').next().unwrap_or_default().trim(),
+    };
+    Ok((key.trim(), value))
+}
+
+// Checks the non-empty `value` of the `kind`, and returns the problems
+// with the suggested fix, if any.
+fn check(kind: Kind, value: &str) -> Vec<(String, Option<String>)> {
+    let suggest = |value: &str| Some(format!("did you mean {value:?}?"));
+    match kind {
+        Kind::Addr => {
+            let is_addr = matches!(
+                value.rsplit_once(':'),
+                Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok()
+            );
+            if is_addr {
+                return Vec::new();
+            }
+            let suggestion = match value.parse::<u16>() {
+                Ok(port) => suggest(&format!("127.0.0.1:{port}")),
+                Err(_) => None,
+            };
+            vec![(
+                format!("invalid address {value:?}, expected <HOST>:<PORT>"),
+                suggestion,
+            )]
+        }
+        Kind::Bool => {
+            let suggestion = match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => suggest("true"),
+                "false" | "no" | "off" | "0" => suggest("false"),
+                _ => None,
+            };
+            match value {
+                "true" | "false" => Vec::new(),
+                _ => vec![(
+                    format!("invalid boolean {value:?}, expected true or false"),
+                    suggestion,
+                )],
+            }
+        }
+        Kind::Currencies => value
+            .split(',')
+            .map(str::trim)
+            .filter_map(|code| {
+                if code.is_empty() {
+                    return Some(("empty currency code".to_string(), None));
+                }
+                if code.parse::<Vertex>().is_err() {
+                    return Some((format!("invalid currency code {code:?}"), None));
+                }
+                // The mixed case is valid, e.g. stETH.
+                let is_lower = code.chars().any(|c| c.is_ascii_lowercase())
+                    && code
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+                if is_lower {
+                    let upper = code.to_ascii_uppercase();
+                    return Some((
+                        format!("lower case currency code {code:?}"),
+                        suggest(&upper),
+                    ));
+                }
+                None
+            })
+            .collect(),
+        Kind::Integer => match value.parse::<u64>() {
+            Ok(_) => Vec::new(),
+            Err(_) => vec![(
+                format!("invalid integer {value:?}"),
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|n| n.is_finite() && *n >= 0.0)
+                    .and_then(|n| suggest(&(n.round() as u64).to_string())),
+            )],
+        },
+        Kind::Number => match value.parse::<f32>() {
+            Ok(n) if n.is_finite() && n > 0.0 => Vec::new(),
+            _ => vec![(format!("invalid number {value:?}, expected positive"), None)],
+        },
+    }
+}
+
+// Returns the candidate within the edit distance of the `name`, the
+// closest first.
+fn closest<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    candidates
+        .into_iter()
+        .map(|candidate| (distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= MAX_DISTANCE && *distance < name.len())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// Returns the Levenshtein distance of the two names.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// Splits the `<SECTION>.<NAME>.<KEY>` setting.
+fn section_key(key: &str) -> Option<(&str, &str, &str)> {
+    let (section, rest) = key.split_once('.')?;
+    let (name, key) = rest.split_once('.')?;
+    let (_, keys) = SECTIONS.iter().find(|(kind, _)| *kind == section)?;
+    if name.is_empty() || !keys.contains(&key) {
+        return None;
+    }
+    Some((section, name, key))
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use super::{Config, Diagnostic, Origin};
+
+#[test]
+fn test_load() {
+    let path = Path::new("best-rate.conf");
+    let mut config = Config::new();
+    config
+        .load_str(
+            "# daemon\n\
+             listen = \"0.0.0.0:8080\"\n\
+             max_staleness_ms = 5  # tight\n\
+             base =\n",
+            path,
+        )
+        .unwrap();
+    assert_eq!(config.get("listen"), Some("0.0.0.0:8080"));
+    assert_eq!(config.parse::<u64>("max_staleness_ms").unwrap(), Some(5));
+    assert_eq!(config.get("base"), None);
+    assert_eq!(config.origin("base"), Some(&Origin::File(path.into())));
+    assert_eq!(config.origin("input"), Some(&Origin::Default));
+
+    config.load_env([("BEST_RATE_LISTEN".to_string(), ":80".to_string())]);
+    assert_eq!(config.get("listen"), Some(":80"));
+    assert_eq!(
+        config.origin("listen"),
+        Some(&Origin::Env("BEST_RATE_LISTEN".into()))
+    );
+}
+
+#[test]
+fn test_error() {
+    let path = Path::new("best-rate.conf");
+    let mut config = Config::new();
+    let error = config.load_str("\nport = 80\n", path).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "best-rate.conf:2: unknown setting \"port\""
+    );
+    // The suggestion of the close one.
+    let error = config
+        .load_str("listn = :80\n[grahp.fx]\n", path)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "best-rate.conf:1: unknown setting \"listn\", did you mean \"listen\"?\n\
+         best-rate.conf:2: unknown section \"grahp.fx\", expected [graph.<NAME>]"
+    );
+    let error = config
+        .load_str("[graph.fx]\nbsae = USD\n[api_key.ops]\nky = k\n", path)
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("did you mean \"graph.fx.base\"?"));
+    assert!(error
+        .to_string()
+        .contains("did you mean \"api_key.ops.key\"?"));
+    assert!(config.load_str("listen", path).is_err());
+    assert!(config.load_str("listen = \"0.0.0.0", path).is_err());
+
+    config
+        .set("max_staleness_ms", "soon", Origin::Default)
+        .unwrap();
+    let error = config.parse::<u64>("max_staleness_ms").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid max_staleness_ms value \"soon\" from default"
+    );
+}
+
+#[test]
+fn test_graphs() {
+    let path = Path::new("best-rate.conf");
+    let mut config = Config::new();
+    config
+        .load_str(
+            "max_staleness_ms = 5\n\
+             [graph.fx]\n\
+             input = \"ecb.csv\"\n\
+             base = USD,EUR\n\
+             [graph.dex]\n\
+             max_staleness_ms = 1\n",
+            path,
+        )
+        .unwrap();
+    assert_eq!(config.names("graph"), ["dex", "fx"]);
+    assert_eq!(config.graph("fx", "input"), Some("ecb.csv"));
+    assert_eq!(config.graph("dex", "input"), None);
+    assert_eq!(
+        config.parse_graph::<u64>("fx", "max_staleness_ms").unwrap(),
+        Some(5)
+    );
+    assert_eq!(
+        config
+            .parse_graph::<u64>("dex", "max_staleness_ms")
+            .unwrap(),
+        Some(1)
+    );
+
+    assert!(config.load_str("[graph]", path).is_err());
+    assert!(config.load_str("[graph.fx", path).is_err());
+    assert!(config.load_str("[graph.fx]\nlisten = :80", path).is_err());
+}
+
+#[test]
+fn test_diagnostics() {
+    let path = Path::new("best-rate.conf");
+    let mut config = Config::new();
+    config
+        .load_str(
+            "listen = \"0.0.0.0:8080\"\n\
+             base = USD,stETH,0xdAC17F958D2ee523a2206206994597C13D831ec7@1\n\
+             [api_key.ops]\n\
+             key = k\n\
+             write = false\n\
+             rate_limit = 2.5\n\
+             [alert.jpy]\n\
+             pair = USD/JPY\n\
+             threshold = 0.01\n\
+             webhook = http://127.0.0.1:9000/hook\n",
+            path,
+        )
+        .unwrap();
+    assert_eq!(config.diagnostics(), []);
+    assert!(config.validate().is_ok());
+
+    config
+        .load_str(
+            "listen = 8080\n\
+             base = \"USD,,eur\"\n\
+             max_staleness_ms = 2.5\n\
+             tls_cert = cert.pem\n\
+             [graph.fx]\n\
+             base = JPY\n\
+             [api_key.ci]\n\
+             write = yes\n\
+             burst = 5\n\
+             [alert.arb]\n\
+             min_bps = 10\n\
+             provider = kraken\n",
+            path,
+        )
+        .unwrap();
+    config.load_env([
+        ("BEST_RATE_IMPUT".to_string(), "rates.csv".to_string()),
+        ("BEST_RATE_CONFIG".to_string(), "best-rate.conf".to_string()),
+    ]);
+    let diagnostics = config.diagnostics();
+    let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            "imput from env BEST_RATE_IMPUT: unknown setting, did you mean BEST_RATE_INPUT?",
+            "api_key.ci.write from file best-rate.conf: invalid boolean \"yes\", expected \
+             true or false, did you mean \"true\"?",
+            "base from file best-rate.conf: empty currency code",
+            "base from file best-rate.conf: lower case currency code \"eur\", did you mean \
+             \"EUR\"?",
+            "listen from file best-rate.conf: invalid address \"8080\", expected \
+             <HOST>:<PORT>, did you mean \"127.0.0.1:8080\"?",
+            "max_staleness_ms from file best-rate.conf: invalid integer \"2.5\", did you \
+             mean \"3\"?",
+            "tls_cert from file best-rate.conf: set without tls_key, set both or neither",
+            "api_key.ci.burst from file best-rate.conf: ignored without rate_limit, set \
+             api_key.ci.rate_limit",
+            "api_key.ci.key from file best-rate.conf: missing, set the key of the ci api key",
+            "alert.arb from file best-rate.conf: more than one rule, set one of pair, min_bps \
+             or provider",
+            "alert.arb.webhook from file best-rate.conf: missing, set the webhook of the arb \
+             alert",
+        ]
+    );
+    assert_eq!(
+        diagnostics[2],
+        Diagnostic {
+            key: "base".into(),
+            origin: Origin::File(path.into()),
+            message: "empty currency code".into(),
+            suggestion: None,
+        }
+    );
+    let error = config.validate().unwrap_err();
+    assert_eq!(error.to_string().lines().count(), diagnostics.len());
+}
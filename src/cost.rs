@@ -0,0 +1,49 @@
+//! Pluggable edge cost models
+
+use std::fmt::Debug;
+
+use super::Edge;
+
+/// The objective of the best rate search.
+///
+/// The value of each edge is multiplied along the path, and the path
+/// with the highest value wins, see [`QueryOptions::with_cost_model`].
+///
+/// [`QueryOptions::with_cost_model`]: crate::query::QueryOptions::with_cost_model
+pub trait CostModel: Debug + Send + Sync {
+    /// Returns the value of converting the `amount` of the source
+    /// currency, if known, through the `edge`.
+    fn value(&self, edge: &Edge, amount: Option<f32>) -> f32;
+}
+
+/// The rate net of the fees, the default objective.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NetRate;
+
+impl CostModel for NetRate {
+    fn value(&self, edge: &Edge, amount: Option<f32>) -> f32 {
+        edge.effective_rate(amount)
+    }
+}
+
+/// The net rate discounted by the edge risk, `exp(-aversion * risk)`.
+#[derive(Copy, Clone, Debug)]
+pub struct RiskAdjusted {
+    aversion: f32,
+}
+
+impl RiskAdjusted {
+    pub fn new(aversion: f32) -> Self {
+        assert!(aversion >= 0.0);
+        Self { aversion }
+    }
+}
+
+impl CostModel for RiskAdjusted {
+    fn value(&self, edge: &Edge, amount: Option<f32>) -> f32 {
+        edge.effective_rate(amount) * (-self.aversion * edge.risk()).exp()
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,35 @@
+use super::{CostModel, RiskAdjusted};
+use crate::query::QueryOptions;
+use crate::{Dex, Edge};
+
+#[derive(Debug)]
+struct FewerHops;
+
+impl CostModel for FewerHops {
+    fn value(&self, edge: &Edge, amount: Option<f32>) -> f32 {
+        edge.effective_rate(amount) * 0.5
+    }
+}
+
+#[test]
+fn test_cost_model() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_risk(1.0));
+    dex.add_edge('B', 'D', Edge::new(1.0));
+    dex.add_edge('A', 'C', Edge::new(1.5));
+    dex.add_edge('C', 'E', Edge::new(1.0));
+    dex.add_edge('E', 'D', Edge::new(1.0));
+    dex.add_edge('A', 'D', Edge::new(1.2));
+    let (src, dst) = ('A'.into(), 'D'.into());
+
+    let path = dex.get_best_rate(&src, &dst).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> D: 2");
+
+    let options = QueryOptions::new().with_cost_model(RiskAdjusted::new(1.0));
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> E -> D: 1.5");
+
+    let options = QueryOptions::new().with_cost_model(FewerHops);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> D: 1.2");
+}
@@ -0,0 +1,55 @@
+//! CSV rate loader
+
+use std::io::{self, BufRead};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex};
+
+impl Dex {
+    /// Loads the rates from the `src,dst,rate` lines.
+    ///
+    /// The blank lines and the lines starting with `#` are skipped.  It
+    /// returns the number of rates loaded.
+    #[instrument(level = "debug", skip_all, err)]
+    pub fn load_csv<R: BufRead>(&mut self, reader: R) -> io::Result<usize> {
+        let mut count = 0;
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (src, dst, rate) = parse_line(line).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {e}", i + 1))
+            })?;
+            if self.canonical(src) == self.canonical(dst) {
+                let msg = format!("line {}: rate between the aliases: {line:?}", i + 1);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+            self.add_rate(src, dst, rate);
+            count += 1;
+        }
+        debug!(%count, "loaded");
+        Ok(count)
+    }
+}
+
+pub(crate) fn parse_line(line: &str) -> Result<(Vertex, Vertex, f32), String> {
+    let fields: Vec<_> = line.split(',').map(str::trim).collect();
+    if fields.len() != 3 {
+        return Err(format!("expected src,dst,rate: {line:?}"));
+    }
+    let src: Vertex = fields[0].parse().map_err(|e| format!("{e}"))?;
+    let dst: Vertex = fields[1].parse().map_err(|e| format!("{e}"))?;
+    let rate: f32 = fields[2]
+        .parse()
+        .map_err(|e| format!("invalid rate {:?}: {e}", fields[2]))?;
+    if src == dst || !rate.is_finite() || rate <= 0.0 {
+        return Err(format!("invalid rate: {line:?}"));
+    }
+    Ok((src, dst, rate))
+}
+
+#[cfg(test)]
+mod test;
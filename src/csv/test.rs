@@ -0,0 +1,27 @@
+use crate::test::vertex;
+use crate::Dex;
+
+#[test]
+fn test_load_csv() {
+    let input = "# src,dst,rate\nUSD,EUR,0.5\n\nEUR, GBP, 0.25\n";
+    let mut dex = Dex::new();
+    assert_eq!(dex.load_csv(input.as_bytes()).unwrap(), 2);
+
+    let path = dex.get_best_rate(&vertex("USD"), &vertex("GBP")).unwrap();
+    assert_eq!(path.to_string(), "USD -> EUR -> GBP: 0.125");
+}
+
+#[test]
+fn test_load_csv_error() {
+    let mut dex = Dex::new();
+    let err = dex
+        .load_csv("USD,EUR,0.92\nUSD,EUR\n".as_bytes())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"line 2: expected src,dst,rate: "USD,EUR""#
+    );
+
+    let err = dex.load_csv("USD,USD,1.0\n".as_bytes()).unwrap_err();
+    assert_eq!(err.to_string(), r#"line 1: invalid rate: "USD,USD,1.0""#);
+}
@@ -0,0 +1,227 @@
+//! Cycle-consistency checker
+
+use std::fmt;
+
+use tracing::{debug, instrument, warn};
+
+use super::{Dex, Vertex};
+use crate::cancel::{CancelToken, Timeout};
+use crate::progress::{Progress, Stage};
+
+/// The maximum cycle length checked exhaustively.
+pub(crate) const MAX_CYCLE_LEN: usize = 4;
+
+/// The directed cycle with the rate product of its edges.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cycle {
+    edges: Vec<(Vertex, Vertex, f32)>,
+    rate: f32,
+    // The rate net of the fees, and the fixed fee, of each edge.
+    costs: Vec<(f32, f32)>,
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (src, _, _)) in self.edges.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" -> ")?;
+            }
+            write!(f, "{src}")?;
+        }
+        if let Some((src, _, _)) = self.edges.first() {
+            write!(f, " -> {src}")?;
+        }
+        write!(f, ": {}", self.rate)
+    }
+}
+
+impl Cycle {
+    /// Returns the edges of the cycle, as `(src, dst, rate)`.
+    pub fn edges(&self) -> &[(Vertex, Vertex, f32)] {
+        &self.edges
+    }
+
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// Returns the rate product of the cycle.
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    /// Returns the rate product net of the percentage fees of each
+    /// edge.
+    ///
+    /// The bid and ask spread is already in the directed edge rates.
+    pub fn net_rate(&self) -> f32 {
+        self.costs.iter().map(|(rate, _)| rate).product()
+    }
+
+    /// Returns the amount received for the `amount` of the first
+    /// currency going around the cycle, net of the percentage fees and
+    /// the fixed fees, e.g. the gas, of each hop.
+    pub fn amount_out(&self, amount: f32) -> f32 {
+        self.costs.iter().fold(amount, |amount, (rate, fixed_fee)| {
+            (amount * rate - fixed_fee).max(0.0)
+        })
+    }
+}
+
+impl Dex {
+    /// Reports cycles whose rate product deviates from 1.0 by more
+    /// than `epsilon`.
+    ///
+    /// It checks all the cycles up to four edges exhaustively, and
+    /// returns them ordered by the vertices.  The cycles through the
+    /// equivalent vertices are reported once, see
+    /// [`Dex::add_equivalence`].
+    #[instrument(level = "debug", skip(self))]
+    pub fn check_consistency(&self, epsilon: f32) -> Vec<Cycle> {
+        self.try_check_consistency(epsilon, &CancelToken::new(), &mut |_| {})
+            .unwrap_or_else(Timeout::into_partial)
+    }
+
+    // Checks the cycles with the progress of the start vertices, or
+    // returns the ones found so far once cancelled.
+    pub(crate) fn try_check_consistency(
+        &self,
+        epsilon: f32,
+        cancel: &CancelToken,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<Vec<Cycle>, Timeout<Vec<Cycle>>> {
+        let mut cycles = Vec::new();
+        let done = self.cycles(
+            MAX_CYCLE_LEN,
+            |cycle| {
+                if (cycle.rate - 1.0).abs() > epsilon {
+                    debug!(%cycle, "inconsistent cycle");
+                    cycles.push(cycle);
+                }
+            },
+            cancel,
+            progress,
+        );
+        cycles.sort_by(|a, b| {
+            let a = a.edges.iter().map(|(src, _, _)| src);
+            let b = b.edges.iter().map(|(src, _, _)| src);
+            a.cmp(b)
+        });
+        let cycles = self.dedup_equivalent(cycles);
+        if done {
+            Ok(cycles)
+        } else {
+            Err(Timeout::new(cycles))
+        }
+    }
+
+    /// Calls `f` with each simple directed cycle up to `max_len` edges,
+    /// and `progress` after each start vertex.
+    ///
+    /// Each cycle is visited once, starting from its smallest vertex.
+    /// Returns false in case it's cancelled before all the start
+    /// vertices.
+    pub(crate) fn cycles<F>(
+        &self,
+        max_len: usize,
+        mut f: F,
+        cancel: &CancelToken,
+        progress: &mut dyn FnMut(Progress),
+    ) -> bool
+    where
+        F: FnMut(Cycle),
+    {
+        let mut stack = Vec::new();
+        let total = self.edges.len();
+        for (i, start) in self.edges.keys().enumerate() {
+            if cancel.is_cancelled() {
+                warn!(done = i, total, "cycles cancelled");
+                return false;
+            }
+            self.walk_cycles(start, start, max_len, true, &mut stack, &mut |edges| {
+                f(self.cycle(edges))
+            });
+            progress(Progress {
+                stage: Stage::Arbitrage,
+                done: i + 1,
+                total,
+            });
+        }
+        true
+    }
+
+    /// Calls `f` with each simple directed cycle up to `max_len` edges
+    /// through the `src -> dst` edge, starting from its smallest vertex.
+    pub(crate) fn cycles_through<F>(&self, src: &Vertex, dst: &Vertex, max_len: usize, mut f: F)
+    where
+        F: FnMut(Cycle),
+    {
+        let rate = match self.edges.get(src).and_then(|edges| edges.get(dst)) {
+            Some(edge) => edge.rate,
+            None => return,
+        };
+        let mut stack = vec![(*src, *dst, rate)];
+        self.walk_cycles(src, dst, max_len, false, &mut stack, &mut |mut edges| {
+            let start = (0..edges.len()).min_by_key(|i| edges[*i].0).unwrap_or(0);
+            edges.rotate_left(start);
+            f(self.cycle(edges))
+        });
+    }
+
+    fn cycle(&self, edges: Vec<(Vertex, Vertex, f32)>) -> Cycle {
+        let rate = edges.iter().map(|(_, _, rate)| rate).product();
+        let costs = edges
+            .iter()
+            .map(|(src, dst, _)| {
+                let edge = &self.edges[src][dst];
+                (edge.effective_rate(None), edge.fixed_fee)
+            })
+            .collect();
+        Cycle { edges, rate, costs }
+    }
+
+    // Walks the cycles back to `start`, only through the vertices
+    // greater than `start` in case of `smallest`.
+    fn walk_cycles<F>(
+        &self,
+        start: &Vertex,
+        src: &Vertex,
+        max_len: usize,
+        smallest: bool,
+        stack: &mut Vec<(Vertex, Vertex, f32)>,
+        f: &mut F,
+    ) where
+        F: FnMut(Vec<(Vertex, Vertex, f32)>),
+    {
+        let edges = match self.edges.get(src) {
+            Some(edges) => edges,
+            None => return,
+        };
+        for (dst, edge) in edges {
+            if dst == start {
+                if !stack.is_empty() {
+                    let mut edges = stack.clone();
+                    edges.push((*src, *dst, edge.rate));
+                    f(edges);
+                }
+                continue;
+            }
+            if (smallest && dst < start)
+                || stack.len() + 2 > max_len
+                || stack.iter().any(|(src, _, _)| src == dst)
+            {
+                continue;
+            }
+            stack.push((*src, *dst, edge.rate));
+            self.walk_cycles(start, dst, max_len, smallest, stack, f);
+            stack.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
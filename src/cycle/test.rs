@@ -0,0 +1,45 @@
+use crate::Dex;
+
+#[test]
+fn test_consistent() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('A', 'C', 6.0);
+
+    assert!(dex.check_consistency(1e-6).is_empty());
+}
+
+#[test]
+fn test_inconsistent_triangle() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('A', 'C', 5.0);
+    dex.add_rate('C', 'D', 1.0);
+
+    let cycles = dex.check_consistency(0.01);
+    assert_eq!(cycles.len(), 2);
+    assert_eq!(cycles[0].to_string(), "A -> B -> C -> A: 1.2");
+    assert_eq!(cycles[0].edges()[2], ('C'.into(), 'A'.into(), 0.2));
+    assert_eq!(cycles[1].len(), 3);
+    assert!((cycles[1].rate() - 1.0 / 1.2).abs() < 1e-6);
+
+    assert!(dex.check_consistency(0.5).is_empty());
+}
+
+#[test]
+fn test_max_cycle_len() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1.0);
+    dex.add_rate('B', 'C', 1.0);
+    dex.add_rate('C', 'D', 1.0);
+    dex.add_rate('D', 'E', 1.0);
+    dex.add_rate('E', 'A', 2.0);
+
+    // Five edges cycle is out of the exhaustive check.
+    assert!(dex.check_consistency(0.01).is_empty());
+
+    dex.add_rate('D', 'A', 2.0);
+    assert_eq!(dex.check_consistency(0.01).len(), 2);
+}
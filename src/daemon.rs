@@ -0,0 +1,530 @@
+//! Precompute-and-serve daemon
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Bound;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+use tracing::{debug, instrument, warn};
+
+use super::{Dex, Path, Vertex};
+use crate::alert::Alerter;
+use crate::compare::differs;
+use crate::fetch::Fetcher;
+use crate::query::QueryOptions;
+
+/// The default staleness bound of the served rates.
+const MAX_STALENESS: Duration = Duration::from_millis(10);
+
+/// The default interval to poll the fetcher.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The coalescing of the update bursts before the recomputation.
+///
+/// The table is recomputed at most every `interval` since the first
+/// pending update, or as soon as the `max_pending` updates are pending.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Coalesce {
+    interval: Duration,
+    max_pending: u64,
+}
+
+impl Coalesce {
+    pub fn new(interval: Duration, max_pending: u64) -> Self {
+        assert!(max_pending > 0);
+        Self {
+            interval,
+            max_pending,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    pub fn max_pending(&self) -> u64 {
+        self.max_pending
+    }
+}
+
+/// The counts of the shadow comparisons, see [`Daemon::with_shadow`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShadowStats {
+    /// The tables the candidate is compared on.
+    pub tables: u64,
+    /// The pairs compared, found by either side.
+    pub compared: u64,
+    /// The pairs the candidate diverges on, either on the reachability
+    /// or on the rate beyond the rounding.
+    pub diverged: u64,
+}
+
+// The candidate query options run in the shadow of the primary ones.
+#[derive(Debug)]
+struct Shadow {
+    options: QueryOptions,
+    stats: Mutex<ShadowStats>,
+}
+
+/// The precomputed best rate paths of the graph version.
+#[derive(Clone, Debug)]
+pub struct Table {
+    paths: BTreeMap<(Vertex, Vertex), Path>,
+    version: u64,
+    computed_at: Instant,
+}
+
+impl Table {
+    pub fn get(&self, src: &Vertex, dst: &Vertex) -> Option<&Path> {
+        self.paths.get(&(*src, *dst))
+    }
+
+    /// Returns the paths ordered by the pair, after the `after` pair
+    /// for the pagination.
+    pub fn paths(&self, after: Option<(Vertex, Vertex)>) -> impl Iterator<Item = &Path> {
+        let start = match after {
+            Some(pair) => Bound::Excluded(pair),
+            None => Bound::Unbounded,
+        };
+        self.paths
+            .range((start, Bound::Unbounded))
+            .map(|(_, path)| path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Returns the graph version the table is computed from.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn computed_at(&self) -> Instant {
+        self.computed_at
+    }
+}
+
+/// The best rate path served by the [`Daemon`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Served {
+    pub path: Path,
+    /// The time since the graph changed without the change reflected
+    /// in the path, zero for the up-to-date one.
+    pub staleness: Duration,
+}
+
+/// The daemon maintaining the best rate paths of all the pairs, or of
+/// all the pairs from the base currencies, in the background.
+///
+/// The queries are served from the precomputed [`Table`] as long as
+/// it's within the staleness bound since the graph update, and are
+/// computed on demand otherwise.
+pub struct Daemon {
+    inner: Arc<Inner>,
+    // Moved into the worker on the start.
+    fetcher: Mutex<Option<Fetcher>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+struct Inner {
+    dex: RwLock<Dex>,
+    table: RwLock<Arc<Table>>,
+    state: Mutex<State>,
+    changed: Condvar,
+    base: Option<Vec<Vertex>>,
+    options: QueryOptions,
+    shadow: Option<Shadow>,
+    max_staleness: Duration,
+    poll_interval: Duration,
+    coalesce: Option<Coalesce>,
+    alerters: Mutex<Vec<Alerter>>,
+}
+
+// The graph version, bumped on each update, and the version of the
+// table.
+struct State {
+    version: u64,
+    computed: u64,
+    changed_at: Option<Instant>,
+    stopped: bool,
+}
+
+impl fmt::Debug for Daemon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Daemon")
+            .field("base", &self.inner.base)
+            .field("max_staleness", &self.inner.max_staleness)
+            .field("version", &self.version())
+            .field("running", &self.worker.is_some())
+            .finish()
+    }
+}
+
+impl Daemon {
+    pub fn new(dex: Dex) -> Self {
+        let table = Table {
+            paths: BTreeMap::new(),
+            version: 0,
+            computed_at: Instant::now(),
+        };
+        Self {
+            inner: Arc::new(Inner {
+                dex: RwLock::new(dex),
+                table: RwLock::new(Arc::new(table)),
+                state: Mutex::new(State {
+                    version: 1,
+                    computed: 0,
+                    changed_at: Some(Instant::now()),
+                    stopped: false,
+                }),
+                changed: Condvar::new(),
+                base: None,
+                options: QueryOptions::default(),
+                shadow: None,
+                max_staleness: MAX_STALENESS,
+                poll_interval: POLL_INTERVAL,
+                coalesce: None,
+                alerters: Mutex::new(Vec::new()),
+            }),
+            fetcher: Mutex::new(None),
+            worker: None,
+        }
+    }
+
+    /// Precomputes the paths from the `base` currencies only.
+    pub fn with_base(mut self, base: Vec<Vertex>) -> Self {
+        self.inner_mut().base = Some(base);
+        self
+    }
+
+    /// Sets the query options of the precomputed and the on-demand
+    /// paths.
+    pub fn with_options(mut self, options: QueryOptions) -> Self {
+        self.inner_mut().options = options;
+        self
+    }
+
+    /// Runs the `candidate` query options, e.g. the new search
+    /// algorithm, in the shadow of the primary ones.
+    ///
+    /// Each table is recomputed with the candidate on the same graph
+    /// snapshot after it's served, and the pairs the candidate
+    /// diverges on are logged with both paths.  The candidate paths
+    /// are never served.
+    pub fn with_shadow(mut self, candidate: QueryOptions) -> Self {
+        self.inner_mut().shadow = Some(Shadow {
+            options: candidate,
+            stats: Mutex::new(ShadowStats::default()),
+        });
+        self
+    }
+
+    /// Sets the staleness bound of the served paths.
+    pub fn with_max_staleness(mut self, max_staleness: Duration) -> Self {
+        self.inner_mut().max_staleness = max_staleness;
+        self
+    }
+
+    /// Polls the `fetcher` every `interval` for the provider rates.
+    pub fn with_fetcher(mut self, fetcher: Fetcher, interval: Duration) -> Self {
+        self.inner_mut().poll_interval = interval;
+        self.fetcher = Mutex::new(Some(fetcher));
+        self
+    }
+
+    /// Coalesces the update bursts before the recomputation, instead
+    /// of recomputing on each update.
+    pub fn with_coalesce(mut self, coalesce: Coalesce) -> Self {
+        self.inner_mut().coalesce = Some(coalesce);
+        self
+    }
+
+    /// Checks the `alerter` on each recomputation, i.e. after the
+    /// updates and the fetches.
+    pub fn with_alerter(mut self, alerter: Alerter) -> Self {
+        self.inner_mut().alerters.get_mut().unwrap().push(alerter);
+        self
+    }
+
+    pub fn base(&self) -> Option<&[Vertex]> {
+        self.inner.base.as_deref()
+    }
+
+    pub fn max_staleness(&self) -> Duration {
+        self.inner.max_staleness
+    }
+
+    pub fn options(&self) -> &QueryOptions {
+        &self.inner.options
+    }
+
+    /// Returns the shadow comparison counts so far, in case of the
+    /// shadow candidate.
+    pub fn shadow_stats(&self) -> Option<ShadowStats> {
+        let shadow = self.inner.shadow.as_ref()?;
+        Some(*shadow.stats.lock().unwrap())
+    }
+
+    // The settings are only changed before the start.
+    fn inner_mut(&mut self) -> &mut Inner {
+        Arc::get_mut(&mut self.inner).expect("daemon already started")
+    }
+
+    /// Starts the background worker.
+    pub fn start(mut self) -> Self {
+        let inner = self.inner.clone();
+        let fetcher = self.fetcher.lock().unwrap().take();
+        self.worker = Some(thread::spawn(move || inner.run(fetcher)));
+        self
+    }
+
+    /// Stops the background worker, and waits for it to exit.
+    pub fn stop(&mut self) {
+        self.inner.state.lock().unwrap().stopped = true;
+        self.inner.changed.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+
+    /// Returns the current graph version.
+    pub fn version(&self) -> u64 {
+        self.inner.state.lock().unwrap().version
+    }
+
+    /// Updates the graph, and wakes up the worker to recompute.
+    pub fn update<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Dex) -> R,
+    {
+        let result = f(&mut self.inner.dex.write().unwrap());
+        self.inner.changed();
+        result
+    }
+
+    /// Reads the graph.
+    pub fn read<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Dex) -> R,
+    {
+        f(&self.inner.dex.read().unwrap())
+    }
+
+    /// Returns the latest precomputed table.
+    pub fn table(&self) -> Arc<Table> {
+        self.inner.table.read().unwrap().clone()
+    }
+
+    /// Recomputes the table in the calling thread.
+    pub fn refresh(&self) {
+        self.inner.refresh();
+    }
+
+    /// Returns the best rate path, from the table in case it's within
+    /// the staleness bound, or computed on demand otherwise.
+    pub fn get_best_rate(&self, src: &Vertex, dst: &Vertex) -> Option<Served> {
+        let table = self.table();
+        let staleness = self.inner.staleness(table.version);
+        // The table is not computed yet at the version 0.
+        let is_precomputed = table.version > 0
+            && match &self.inner.base {
+                Some(base) => base.contains(src),
+                None => true,
+            };
+        if is_precomputed && staleness <= self.inner.max_staleness {
+            let path = table.get(src, dst)?.clone();
+            return Some(Served { path, staleness });
+        }
+        debug!(%src, %dst, ?staleness, "computed on demand");
+        let path = self.read(|dex| dex.get_best_rate_with(src, dst, &self.inner.options))?;
+        Some(Served {
+            path,
+            staleness: Duration::ZERO,
+        })
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl Inner {
+    fn changed(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.version += 1;
+        state.changed_at.get_or_insert_with(Instant::now);
+        self.changed.notify_all();
+    }
+
+    // Returns the time since the first update after the `version`.
+    fn staleness(&self, version: u64) -> Duration {
+        let state = self.state.lock().unwrap();
+        match state.changed_at {
+            Some(changed_at) if version < state.version => changed_at.elapsed(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    fn run(&self, mut fetcher: Option<Fetcher>) {
+        loop {
+            let mut state = self.state.lock().unwrap();
+            while !state.stopped && state.version == state.computed {
+                if fetcher.is_some() {
+                    state = self
+                        .changed
+                        .wait_timeout(state, self.poll_interval)
+                        .unwrap()
+                        .0;
+                    break;
+                }
+                state = self.changed.wait(state).unwrap();
+            }
+            if let Some(coalesce) = self.coalesce {
+                state = self.coalesce(state, coalesce);
+            }
+            if state.stopped {
+                return;
+            }
+            drop(state);
+            if let Some(fetcher) = fetcher.as_mut() {
+                if fetcher.poll(&mut self.dex.write().unwrap(), Instant::now()) > 0 {
+                    self.changed();
+                }
+            }
+            self.refresh();
+        }
+    }
+
+    // Waits for the pending updates to coalesce, except for the initial
+    // computation.
+    fn coalesce<'a>(
+        &self,
+        mut state: MutexGuard<'a, State>,
+        coalesce: Coalesce,
+    ) -> MutexGuard<'a, State> {
+        while !state.stopped && state.computed > 0 && state.version > state.computed {
+            let pending = state.version - state.computed;
+            let elapsed = match state.changed_at {
+                Some(changed_at) => changed_at.elapsed(),
+                None => break,
+            };
+            if pending >= coalesce.max_pending || elapsed >= coalesce.interval {
+                debug!(%pending, ?elapsed, "coalesced");
+                break;
+            }
+            state = self
+                .changed
+                .wait_timeout(state, coalesce.interval - elapsed)
+                .unwrap()
+                .0;
+        }
+        state
+    }
+
+    // Computes the table from the snapshot, so that the updates are not
+    // blocked during the computation.
+    #[instrument(level = "debug", skip(self))]
+    fn refresh(&self) {
+        let (dex, version) = {
+            let dex = self.dex.read().unwrap();
+            let version = self.state.lock().unwrap().version;
+            (dex.clone(), version)
+        };
+        if version <= self.table.read().unwrap().version {
+            return;
+        }
+        let sources: Vec<_> = match &self.base {
+            Some(base) => base.clone(),
+            None => dex.vertices().copied().collect(),
+        };
+        let paths = best_paths(&dex, &sources, &self.options);
+        debug!(%version, paths = paths.len(), "refreshed");
+        let computed = Arc::new(Table {
+            paths,
+            version,
+            computed_at: Instant::now(),
+        });
+        {
+            let mut table = self.table.write().unwrap();
+            if version <= table.version {
+                return;
+            }
+            *table = computed.clone();
+            let mut state = self.state.lock().unwrap();
+            state.computed = version;
+            if state.version == version {
+                state.changed_at = None;
+            }
+        }
+        for alerter in self.alerters.lock().unwrap().iter_mut() {
+            alerter.check(&dex, SystemTime::now());
+        }
+        if let Some(shadow) = &self.shadow {
+            shadow.compare(&dex, &sources, &computed);
+        }
+    }
+}
+
+impl Shadow {
+    // Recomputes the `table` with the candidate options on the same
+    // snapshot, and logs the pairs diverged.
+    #[instrument(level = "debug", skip_all, fields(version = table.version))]
+    fn compare(&self, dex: &Dex, sources: &[Vertex], table: &Table) {
+        let start = Instant::now();
+        let candidates = best_paths(dex, sources, &self.options);
+        let pairs: BTreeSet<_> = table.paths.keys().chain(candidates.keys()).collect();
+        let mut diverged = 0;
+        for (src, dst) in &pairs {
+            let primary = table.paths.get(&(*src, *dst));
+            let candidate = candidates.get(&(*src, *dst));
+            if let (Some(primary), Some(candidate)) = (primary, candidate) {
+                if !differs(primary.rate(), candidate.rate()) {
+                    continue;
+                }
+            }
+            diverged += 1;
+            let describe = |path: Option<&Path>| path.map_or("none".to_string(), Path::to_string);
+            warn!(
+                version = table.version,
+                %src,
+                %dst,
+                primary = %describe(primary),
+                candidate = %describe(candidate),
+                "shadow diverged",
+            );
+        }
+        debug!(pairs = pairs.len(), diverged, elapsed = ?start.elapsed(), "shadowed");
+        let mut stats = self.stats.lock().unwrap();
+        stats.tables += 1;
+        stats.compared += pairs.len() as u64;
+        stats.diverged += diverged;
+    }
+}
+
+// Returns the best rate paths from the `sources` to all the reachable
+// vertices, by the pair.
+fn best_paths(
+    dex: &Dex,
+    sources: &[Vertex],
+    options: &QueryOptions,
+) -> BTreeMap<(Vertex, Vertex), Path> {
+    let mut paths = BTreeMap::new();
+    for src in sources {
+        for (dst, path) in dex.get_best_rates_from(src, options) {
+            paths.insert((*src, dst), path);
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,167 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{Coalesce, Daemon, ShadowStats};
+use crate::alert::{Alert, Alerter, Rule};
+use crate::query::QueryOptions;
+use crate::Dex;
+
+fn dex() -> Dex {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex
+}
+
+#[test]
+fn test_refresh() {
+    let daemon = Daemon::new(dex()).with_max_staleness(Duration::from_secs(3600));
+    assert!(daemon.table().is_empty());
+    daemon.refresh();
+    let table = daemon.table();
+    assert_eq!(table.version(), daemon.version());
+    assert_eq!(table.len(), 6);
+
+    let served = daemon.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(served.path.rate(), 6.0);
+    assert_eq!(served.staleness, Duration::ZERO);
+
+    // Served from the stale table within the bound.
+    daemon.update(|dex| dex.add_rate('A', 'C', 7.0));
+    let served = daemon.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(served.path.rate(), 6.0);
+    assert!(daemon.get_best_rate(&'A'.into(), &'D'.into()).is_none());
+}
+
+#[test]
+fn test_max_staleness() {
+    let daemon = Daemon::new(dex()).with_max_staleness(Duration::ZERO);
+    daemon.refresh();
+    daemon.update(|dex| dex.add_rate('A', 'C', 7.0));
+    thread::sleep(Duration::from_millis(1));
+    let served = daemon.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(served.path.rate(), 7.0);
+    assert_eq!(served.staleness, Duration::ZERO);
+}
+
+#[test]
+fn test_base() {
+    let daemon = Daemon::new(dex())
+        .with_base(vec!['A'.into()])
+        .with_max_staleness(Duration::from_secs(3600));
+    daemon.refresh();
+    assert_eq!(daemon.table().len(), 2);
+
+    // Computed on demand.
+    let served = daemon.get_best_rate(&'C'.into(), &'A'.into()).unwrap();
+    assert!((served.path.rate() - 1.0 / 6.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_shadow() {
+    let mut dex = dex();
+    let venue = dex.register_provider("venue", 10);
+    dex.add_provider_rate(venue, 'A', 'C', 7.0);
+    let candidate = QueryOptions::new().with_excluded_sources([venue]);
+    let daemon = Daemon::new(dex)
+        .with_max_staleness(Duration::from_secs(3600))
+        .with_shadow(candidate.clone());
+    daemon.refresh();
+    assert_eq!(
+        daemon.shadow_stats(),
+        Some(ShadowStats {
+            tables: 1,
+            compared: 6,
+            diverged: 3,
+        })
+    );
+    // The primary paths are served.
+    let served = daemon.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(served.path.rate(), 7.0);
+
+    // No divergence once the candidate is the primary.
+    let daemon = Daemon::new(daemon.read(Dex::clone))
+        .with_options(candidate.clone())
+        .with_shadow(candidate);
+    daemon.refresh();
+    assert_eq!(daemon.shadow_stats().unwrap().diverged, 0);
+    let served = daemon.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(served.path.rate(), 6.0);
+    assert!(Daemon::new(Dex::new()).shadow_stats().is_none());
+}
+
+#[test]
+fn test_start() {
+    let mut daemon = Daemon::new(dex())
+        .with_max_staleness(Duration::from_secs(3600))
+        .start();
+    daemon.update(|dex| dex.add_rate('C', 'D', 5.0));
+    let version = daemon.version();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while daemon.table().version() < version {
+        assert!(Instant::now() < deadline);
+        thread::sleep(Duration::from_millis(1));
+    }
+    let served = daemon.get_best_rate(&'A'.into(), &'D'.into()).unwrap();
+    assert_eq!(served.path.rate(), 30.0);
+    daemon.stop();
+}
+
+#[test]
+fn test_coalesce() {
+    let mut daemon = Daemon::new(dex())
+        .with_max_staleness(Duration::from_secs(3600))
+        .with_coalesce(Coalesce::new(Duration::from_secs(3600), 3))
+        .start();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while daemon.table().version() == 0 {
+        assert!(Instant::now() < deadline);
+        thread::sleep(Duration::from_millis(1));
+    }
+    let computed = daemon.table().version();
+    daemon.update(|dex| dex.add_rate('C', 'D', 5.0));
+    daemon.update(|dex| dex.add_rate('C', 'D', 6.0));
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(daemon.table().version(), computed);
+
+    // The third pending update triggers the recomputation.
+    daemon.update(|dex| dex.add_rate('C', 'D', 7.0));
+    let version = daemon.version();
+    while daemon.table().version() < version {
+        assert!(Instant::now() < deadline);
+        thread::sleep(Duration::from_millis(1));
+    }
+    let served = daemon.get_best_rate(&'A'.into(), &'D'.into()).unwrap();
+    assert_eq!(served.path.rate(), 42.0);
+    daemon.stop();
+}
+
+#[test]
+fn test_alerter() {
+    let (tx, rx) = mpsc::channel();
+    let alerter = Alerter::new()
+        .rule(Rule::RateMove {
+            src: 'A'.into(),
+            dst: 'C'.into(),
+            threshold: 0.01,
+        })
+        .sink(tx);
+    let daemon = Daemon::new(dex()).with_alerter(alerter);
+    daemon.refresh();
+    assert!(rx.try_recv().is_err());
+
+    // Checked on the update by the worker.
+    let daemon = daemon.start();
+    daemon.update(|dex| dex.add_rate('A', 'C', 7.0));
+    let alert = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(
+        alert,
+        Alert::RateMoved {
+            src: 'A'.into(),
+            dst: 'C'.into(),
+            from: 6.0,
+            to: 7.0,
+        }
+    );
+}
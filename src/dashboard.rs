@@ -0,0 +1,196 @@
+//! Terminal dashboard
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{Duration, SystemTime};
+
+use super::{Dex, Vertex};
+use crate::term::{paint, reverse, Color};
+
+/// The default time the rate change is highlighted for.
+const HIGHLIGHT: Duration = Duration::from_secs(5);
+
+/// The terminal dashboard of the best rates of the pairs, the provider
+/// health, and the best path of the selected pair.
+///
+/// The frame is rendered as the plain text with the ANSI colors, the
+/// improved rate in green and the worsened one in red for the
+/// highlight period after the change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dashboard {
+    pairs: Vec<(Vertex, Vertex)>,
+    selected: usize,
+    highlight: Option<Duration>,
+    color: bool,
+    rows: BTreeMap<(Vertex, Vertex), Row>,
+}
+
+// The best rate of the pair, with the last change.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+struct Row {
+    rate: Option<f32>,
+    change: Option<f32>,
+    changed_at: Option<SystemTime>,
+}
+
+impl Dashboard {
+    pub fn new(pairs: Vec<(Vertex, Vertex)>) -> Self {
+        Self {
+            pairs,
+            selected: 0,
+            highlight: None,
+            color: true,
+            rows: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the time the rate change is highlighted for.
+    pub fn with_highlight(mut self, highlight: Duration) -> Self {
+        self.highlight = Some(highlight);
+        self
+    }
+
+    /// Enables the ANSI colors, the default.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn pairs(&self) -> &[(Vertex, Vertex)] {
+        &self.pairs
+    }
+
+    pub fn highlight(&self) -> Duration {
+        self.highlight.unwrap_or(HIGHLIGHT)
+    }
+
+    /// Returns the selected pair shown in the detail pane.
+    pub fn selected(&self) -> Option<(Vertex, Vertex)> {
+        self.pairs.get(self.selected).copied()
+    }
+
+    /// Selects the pair, and returns false in case it's not on the
+    /// dashboard.
+    pub fn select(&mut self, src: &Vertex, dst: &Vertex) -> bool {
+        match self.pairs.iter().position(|pair| *pair == (*src, *dst)) {
+            Some(i) => {
+                self.selected = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.pairs.is_empty() {
+            self.selected = (self.selected + 1) % self.pairs.len();
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        if !self.pairs.is_empty() {
+            self.selected = (self.selected + self.pairs.len() - 1) % self.pairs.len();
+        }
+    }
+
+    /// Updates the best rates of the pairs on the `dex`, and records the
+    /// changes at its [`Dex::now`].
+    pub fn update(&mut self, dex: &Dex) {
+        let now = dex.now();
+        for (src, dst) in &self.pairs {
+            let rate = dex.get_best_rate(src, dst).map(|path| path.rate());
+            let row = self.rows.entry((*src, *dst)).or_default();
+            if row.rate == rate {
+                continue;
+            }
+            row.change = match (row.rate, rate) {
+                (Some(from), Some(to)) => Some(to / from - 1.0),
+                _ => None,
+            };
+            row.rate = rate;
+            row.changed_at = Some(now);
+        }
+    }
+
+    /// Renders the frame of the last update.
+    pub fn render(&self, dex: &Dex) -> String {
+        let now = dex.now();
+        let mut frame = String::new();
+        let _ = writeln!(frame, "{:<24} {:>12} {:>9}", "PAIR", "RATE", "CHANGE");
+        for (i, (src, dst)) in self.pairs.iter().enumerate() {
+            let row = self.rows.get(&(*src, *dst)).copied().unwrap_or_default();
+            let pair = format!("{}/{}", dex.display_name(src), dex.display_name(dst));
+            let rate = match row.rate {
+                Some(rate) => format!("{rate:>12.6}"),
+                None => format!("{:>12}", "-"),
+            };
+            let change = match row.change {
+                Some(change) => format!("{:>+8.3}%", change * 100.0),
+                None => format!("{:>9}", ""),
+            };
+            let is_recent = matches!(
+                row.changed_at.map(|at| now.duration_since(at).unwrap_or_default()),
+                Some(age) if age <= self.highlight()
+            );
+            let color = match row.change {
+                Some(change) if is_recent && change > 0.0 => Some(Color::Green),
+                Some(change) if is_recent && change < 0.0 => Some(Color::Red),
+                _ => None,
+            };
+            let line = format!("{pair:<24} {} {}", paint(rate, color, self.color), change);
+            if i == self.selected {
+                let _ = writeln!(frame, "{}", reverse(line, self.color));
+            } else {
+                let _ = writeln!(frame, "{line}");
+            }
+        }
+
+        let _ = writeln!(frame, "\n{:<24} {:>12} {:>9}", "PROVIDER", "STATUS", "AGE");
+        for (_, provider) in dex.providers() {
+            let (status, color) = if provider.is_down() {
+                ("down", Some(Color::Red))
+            } else {
+                ("up", None)
+            };
+            let age = match provider.updated_at().map(|at| now.duration_since(at)) {
+                Some(Ok(age)) => format!("{}s", age.as_secs()),
+                _ => "-".to_string(),
+            };
+            let status = paint(format!("{status:>12}"), color, self.color);
+            let _ = writeln!(frame, "{:<24} {status} {age:>9}", provider.name());
+        }
+
+        if let Some((src, dst)) = self.selected() {
+            let _ = writeln!(
+                frame,
+                "\n{} -> {}",
+                dex.display_name(&src),
+                dex.display_name(&dst)
+            );
+            match dex.get_best_rate(&src, &dst) {
+                Some(path) => {
+                    let _ = writeln!(frame, "  {}", dex.display_path(&path));
+                    for (hop, rate) in path.path.windows(2).zip(path.rates()) {
+                        let _ = writeln!(
+                            frame,
+                            "  {:>12} -> {:<12} {rate}",
+                            dex.display_name(&hop[0]),
+                            dex.display_name(&hop[1]),
+                        );
+                    }
+                }
+                None => {
+                    let _ = writeln!(
+                        frame,
+                        "  {}",
+                        paint("no path", Some(Color::Dim), self.color)
+                    );
+                }
+            }
+        }
+        frame
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::Dashboard;
+use crate::clock::MockClock;
+use crate::test::vertex;
+use crate::Dex;
+
+#[test]
+fn test_dashboard() {
+    let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    let mut dex = Dex::new();
+    dex.set_clock(Some(Arc::new(clock.clone())));
+    let kraken = dex.register_provider("kraken", 0);
+    dex.add_provider_rate(kraken, vertex("USD"), vertex("EUR"), 0.5);
+    dex.add_rate(vertex("EUR"), vertex("JPY"), 160.0);
+
+    let mut dashboard = Dashboard::new(vec![
+        (vertex("USD"), vertex("JPY")),
+        (vertex("USD"), vertex("CNY")),
+    ])
+    .with_color(false);
+    dashboard.update(&dex);
+    clock.advance(Duration::from_secs(2));
+    assert_eq!(
+        dashboard.render(&dex),
+        "PAIR                             RATE    CHANGE\n\
+         USD/JPY                     80.000000          \n\
+         USD/CNY                             -          \n\
+         \n\
+         PROVIDER                       STATUS       AGE\n\
+         kraken                             up        2s\n\
+         \n\
+         USD -> JPY\n  \
+         USD -> EUR -> JPY: 80\n           \
+         USD -> EUR          0.5\n           \
+         EUR -> JPY          160\n"
+    );
+
+    dashboard.select_next();
+    assert_eq!(dashboard.selected(), Some((vertex("USD"), vertex("CNY"))));
+    assert!(dashboard.render(&dex).ends_with("USD -> CNY\n  no path\n"));
+    dashboard.select_next();
+    dashboard.select_prev();
+    assert!(!dashboard.select(&vertex("USD"), &vertex("GBP")));
+    assert!(dashboard.select(&vertex("USD"), &vertex("JPY")));
+}
+
+#[test]
+fn test_dashboard_highlight() {
+    let clock = MockClock::new(UNIX_EPOCH);
+    let mut dex = Dex::new();
+    dex.set_clock(Some(Arc::new(clock.clone())));
+    dex.add_rate(vertex("USD"), vertex("EUR"), 0.5);
+
+    let mut dashboard =
+        Dashboard::new(vec![(vertex("USD"), vertex("EUR"))]).with_highlight(Duration::from_secs(5));
+    dashboard.update(&dex);
+    dex.add_rate(vertex("USD"), vertex("EUR"), 0.55);
+    dashboard.update(&dex);
+    let frame = dashboard.render(&dex);
+    assert!(
+        frame.contains("\x1b[32m    0.550000\x1b[0m  +10.000%"),
+        "{frame:?}"
+    );
+    // The selected row is in the reverse video.
+    assert!(frame.contains("\x1b[7mUSD/EUR"), "{frame:?}");
+
+    clock.advance(Duration::from_secs(6));
+    let frame = dashboard.render(&dex);
+    assert!(frame.contains("     0.550000  +10.000%"), "{frame:?}");
+
+    dex.add_rate(vertex("USD"), vertex("EUR"), 0.5);
+    dashboard.update(&dex);
+    assert!(dashboard
+        .render(&dex)
+        .contains("\x1b[31m    0.500000\x1b[0m   -9.091%"));
+}
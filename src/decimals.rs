@@ -0,0 +1,86 @@
+//! Currency decimals
+
+use super::{Dex, Vertex};
+
+/// The default decimals of the currency not known.
+pub const DEFAULT_DECIMALS: u8 = 2;
+
+// The well-known currencies with the decimals other than the default.
+const KNOWN_DECIMALS: &[(&str, u8)] = &[
+    ("BHD", 3),
+    ("BTC", 8),
+    ("CLP", 0),
+    ("ETH", 18),
+    ("ISK", 0),
+    ("JOD", 3),
+    ("JPY", 0),
+    ("KRW", 0),
+    ("KWD", 3),
+    ("OMR", 3),
+    ("TND", 3),
+    ("USDC", 6),
+    ("USDT", 6),
+    ("VND", 0),
+    ("WBTC", 8),
+    ("WETH", 18),
+];
+
+/// The rounding of the amounts to the currency decimals.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Rounds toward zero, never to quote more than executable.
+    Truncate,
+    /// Rounds to the nearest, half away from zero.
+    Round,
+}
+
+impl Rounding {
+    fn apply(self, amount: f64) -> f64 {
+        match self {
+            Self::Truncate => amount.trunc(),
+            Self::Round => amount.round(),
+        }
+    }
+}
+
+impl Dex {
+    /// Sets the rounding of the simulated amounts to the currency
+    /// decimals at every hop, see [`Dex::simulate`].
+    pub fn set_rounding(&mut self, rounding: Option<Rounding>) {
+        self.rounding = rounding;
+    }
+
+    pub fn rounding(&self) -> Option<Rounding> {
+        self.rounding
+    }
+
+    /// Rounds the `amount` of the currency to its decimals.
+    pub fn round_amount(&self, v: &Vertex, amount: f32, rounding: Rounding) -> f32 {
+        let scale = 10f64.powi(i32::from(self.decimals(v)));
+        (rounding.apply(f64::from(amount) * scale) / scale) as f32
+    }
+
+    /// Sets the decimals of the currency, e.g. `0` for JPY.
+    pub fn set_decimals<V: Into<Vertex>>(&mut self, v: V, decimals: u8) {
+        self.decimals.insert(v.into(), decimals);
+    }
+
+    /// Returns the decimals of the currency, falling back to the
+    /// well-known ones and then to the default.
+    pub fn decimals(&self, v: &Vertex) -> u8 {
+        if let Some(decimals) = self.decimals.get(v) {
+            return *decimals;
+        }
+        KNOWN_DECIMALS
+            .binary_search_by(|(symbol, _)| (*symbol).cmp(v.as_str()))
+            .map_or(DEFAULT_DECIMALS, |i| KNOWN_DECIMALS[i].1)
+    }
+
+    /// Formats the `amount` of the currency with its decimals.
+    pub fn format_amount(&self, v: &Vertex, amount: f32) -> String {
+        format!("{amount:.0$} {v}", usize::from(self.decimals(v)))
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,35 @@
+use super::Rounding;
+use crate::test::vertex;
+use crate::Dex;
+
+#[test]
+fn test_decimals() {
+    let mut dex = Dex::new();
+    assert_eq!(dex.decimals(&vertex("USD")), 2);
+    assert_eq!(dex.decimals(&vertex("JPY")), 0);
+    assert_eq!(dex.decimals(&vertex("BTC")), 8);
+
+    dex.set_decimals(vertex("USD"), 4);
+    assert_eq!(dex.decimals(&vertex("USD")), 4);
+    assert_eq!(dex.format_amount(&vertex("USD"), 1.5), "1.5000 USD");
+    assert_eq!(dex.format_amount(&vertex("JPY"), 163_500.4), "163500 JPY");
+}
+
+#[test]
+fn test_rounding() {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("JPY"), vertex("USD"), 0.0065);
+    dex.add_rate(vertex("USD"), vertex("BTC"), 0.000016);
+    let (jpy, btc) = (vertex("JPY"), vertex("BTC"));
+    assert_eq!(dex.round_amount(&jpy, 1.5, Rounding::Truncate), 1.0);
+    assert_eq!(dex.round_amount(&jpy, 1.5, Rounding::Round), 2.0);
+
+    let path = dex.get_best_rate(&jpy, &btc).unwrap();
+    dex.set_rounding(Some(Rounding::Truncate));
+    let execution = dex.simulate(&path, 1_000_001.0).unwrap();
+    let usd = execution.hops()[0].amount_out;
+    assert!((usd - 6500.0).abs() < 1e-3, "{usd}");
+    assert!((execution.amount_out() - 0.104).abs() < 1e-8);
+    let dust = execution.dust();
+    assert!((dust[&vertex("USD")] - 0.0065).abs() < 1e-3, "{dust:?}");
+}
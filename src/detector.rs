@@ -0,0 +1,121 @@
+//! Incremental arbitrage detection
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::Sender;
+
+use tracing::{debug, instrument, warn};
+
+use super::{Dex, Vertex};
+use crate::cycle::{Cycle, MAX_CYCLE_LEN};
+
+/// The change of the arbitrage opportunity.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Opportunity {
+    /// The cycle became profitable, net of the fees.
+    Opened(Cycle),
+    /// The cycle, by its vertices, is no longer profitable.
+    Closed(Vec<Vertex>),
+}
+
+/// The incremental arbitrage detector.
+///
+/// Instead of re-scanning the whole graph, only the cycles through the
+/// updated edge are checked on each update, and the newly opened and
+/// closed opportunities are sent on the channel.
+#[derive(Debug)]
+pub struct ArbitrageDetector {
+    min_bps: f32,
+    open: BTreeMap<Vec<Vertex>, Cycle>,
+    sender: Sender<Opportunity>,
+}
+
+impl ArbitrageDetector {
+    /// Creates the detector of the cycles above `min_bps` net of the
+    /// fees, sending the changes to the `sender`.
+    pub fn new(min_bps: f32, sender: Sender<Opportunity>) -> Self {
+        Self {
+            min_bps,
+            open: BTreeMap::new(),
+            sender,
+        }
+    }
+
+    /// Returns the currently open opportunities.
+    pub fn open(&self) -> impl Iterator<Item = &Cycle> {
+        self.open.values()
+    }
+
+    /// Scans the whole `dex`, e.g. at the start.
+    #[instrument(level = "debug", skip_all)]
+    pub fn scan(&mut self, dex: &Dex) {
+        let mut current = BTreeMap::new();
+        for cycle in dex.find_arbitrage(self.min_bps, None) {
+            current.insert(vertices(&cycle), cycle);
+        }
+        let closed: Vec<_> = self
+            .open
+            .keys()
+            .filter(|key| !current.contains_key(*key))
+            .cloned()
+            .collect();
+        self.apply(closed, current);
+    }
+
+    /// Checks the cycles through the updated `src -> dst` pair, in
+    /// both directions, after the update of the `dex`.
+    #[instrument(level = "debug", skip(self, dex))]
+    pub fn on_update(&mut self, dex: &Dex, src: &Vertex, dst: &Vertex) {
+        let min_rate = 1.0 + self.min_bps / 10_000.0;
+        let mut current = BTreeMap::new();
+        for (src, dst) in [(src, dst), (dst, src)] {
+            dex.cycles_through(src, dst, MAX_CYCLE_LEN, |cycle| {
+                if cycle.net_rate() > min_rate {
+                    current.insert(vertices(&cycle), cycle);
+                }
+            });
+        }
+        let closed: Vec<_> = self
+            .open
+            .iter()
+            .filter(|(key, cycle)| !current.contains_key(*key) && through(cycle, src, dst))
+            .map(|(key, _)| key.clone())
+            .collect();
+        self.apply(closed, current);
+    }
+
+    fn apply(&mut self, closed: Vec<Vec<Vertex>>, current: BTreeMap<Vec<Vertex>, Cycle>) {
+        for key in closed {
+            self.open.remove(&key);
+            debug!(?key, "closed");
+            self.send(Opportunity::Closed(key));
+        }
+        for (key, cycle) in current {
+            if !self.open.contains_key(&key) {
+                debug!(%cycle, "opened");
+                self.send(Opportunity::Opened(cycle.clone()));
+            }
+            self.open.insert(key, cycle);
+        }
+    }
+
+    fn send(&self, opportunity: Opportunity) {
+        if self.sender.send(opportunity).is_err() {
+            warn!("opportunity receiver is gone");
+        }
+    }
+}
+
+fn vertices(cycle: &Cycle) -> Vec<Vertex> {
+    cycle.edges().iter().map(|(src, ..)| *src).collect()
+}
+
+// Checks if the cycle goes through the pair in either direction.
+fn through(cycle: &Cycle, a: &Vertex, b: &Vertex) -> bool {
+    cycle
+        .edges()
+        .iter()
+        .any(|(src, dst, _)| (src == a && dst == b) || (src == b && dst == a))
+}
+
+#[cfg(test)]
+mod test;
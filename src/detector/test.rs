@@ -0,0 +1,70 @@
+use std::sync::mpsc;
+
+use super::{ArbitrageDetector, Opportunity};
+use crate::Dex;
+
+#[test]
+fn test_detector() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 1.0 / 6.0);
+    dex.add_rate('C', 'D', 1.0);
+
+    let (tx, rx) = mpsc::channel();
+    let mut detector = ArbitrageDetector::new(10.0, tx);
+    detector.scan(&dex);
+    assert!(rx.try_recv().is_err());
+
+    dex.add_rate('C', 'A', 0.2);
+    detector.on_update(&dex, &'C'.into(), &'A'.into());
+    match rx.try_recv().unwrap() {
+        Opportunity::Opened(cycle) => assert_eq!(cycle.to_string(), "A -> B -> C -> A: 1.2"),
+        opportunity => panic!("unexpected {opportunity:?}"),
+    }
+    assert!(rx.try_recv().is_err());
+    assert_eq!(detector.open().count(), 1);
+
+    // The unrelated update.
+    dex.add_rate('C', 'D', 2.0);
+    detector.on_update(&dex, &'C'.into(), &'D'.into());
+    assert!(rx.try_recv().is_err());
+
+    // Still open, without the repeated notification.
+    dex.add_rate('A', 'B', 2.1);
+    detector.on_update(&dex, &'A'.into(), &'B'.into());
+    assert!(rx.try_recv().is_err());
+
+    dex.add_rate('B', 'C', 2.38);
+    detector.on_update(&dex, &'B'.into(), &'C'.into());
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        Opportunity::Closed(vec!['A'.into(), 'B'.into(), 'C'.into()])
+    );
+    assert_eq!(detector.open().count(), 0);
+}
+
+#[test]
+fn test_detector_matches_scan() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'D', 0.5);
+    dex.add_rate('D', 'A', 0.4);
+    dex.add_rate('A', 'C', 5.0);
+
+    let (tx, _rx) = mpsc::channel();
+    let mut detector = ArbitrageDetector::new(10.0, tx);
+    detector.on_update(&dex, &'D'.into(), &'A'.into());
+    let mut incremental: Vec<_> = detector.open().cloned().collect();
+    incremental.sort_by_key(|cycle| cycle.to_string());
+    let mut expected = dex.find_arbitrage(10.0, None);
+    expected.retain(|cycle| {
+        cycle.edges().iter().any(|(src, dst, _)| {
+            (src, dst) == (&'D'.into(), &'A'.into()) || (src, dst) == (&'A'.into(), &'D'.into())
+        })
+    });
+    expected.sort_by_key(|cycle| cycle.to_string());
+    assert!(!expected.is_empty());
+    assert_eq!(incremental, expected);
+}
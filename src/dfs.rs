@@ -0,0 +1,94 @@
+//! Depth-limited search
+
+use std::time::Instant;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path, Vertex};
+use crate::query::{Bounded, QueryOptions};
+
+impl Dex {
+    /// Returns the best rate path within `max_hops`, by the iterative
+    /// deepening depth first search.
+    ///
+    /// The memory is bounded by the depth, instead of the breadth of
+    /// [`Dex::get_best_rate_with`].  The deepening stops early once no
+    /// path is cut by the depth, and the result is flagged truncated
+    /// otherwise, or when timed out in the middle.
+    #[instrument(level = "debug", skip(self, options))]
+    pub fn get_best_rate_within(
+        &self,
+        src: &Vertex,
+        dst: &Vertex,
+        max_hops: usize,
+        options: &QueryOptions,
+    ) -> Bounded<Option<Path>> {
+        let start = Instant::now();
+        let mut search = Dfs {
+            dex: self,
+            dst,
+            options,
+            best: None,
+            truncated: false,
+        };
+        for depth in 1..=max_hops {
+            search.truncated = false;
+            if !search.visit(&Path::new(*src), depth, start) {
+                debug!(%depth, "timed out");
+                return Bounded::new(search.best, true);
+            }
+            if !search.truncated {
+                debug!(%depth, "exhausted");
+                break;
+            }
+        }
+        Bounded::new(search.best, search.truncated)
+    }
+}
+
+// The depth first search state.
+struct Dfs<'a> {
+    dex: &'a Dex,
+    dst: &'a Vertex,
+    options: &'a QueryOptions,
+    best: Option<Path>,
+    truncated: bool,
+}
+
+impl Dfs<'_> {
+    // Visits the paths extending `path` up to `depth` more hops, and
+    // returns false when timed out.
+    fn visit(&mut self, path: &Path, depth: usize, start: Instant) -> bool {
+        if self.options.is_expired(start) {
+            return false;
+        }
+        let edges = match self.dex.edges.get(path.last()) {
+            Some(edges) => edges,
+            None => return true,
+        };
+        for (vertex, edge) in edges {
+            if path.contains(vertex) || !self.options.is_routable(path, edge) {
+                continue;
+            }
+            if depth == 0 {
+                self.truncated = true;
+                return true;
+            }
+            let mut path = path.clone();
+            path.push(*vertex, edge, self.options);
+            if vertex == self.dst {
+                let score = self.options.score(&path);
+                match &self.best {
+                    Some(best) if score <= self.options.score(best) => {}
+                    _ => self.best = Some(path),
+                }
+            } else if !self.visit(&path, depth - 1, start) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,25 @@
+use crate::query::QueryOptions;
+use crate::Dex;
+
+#[test]
+fn test_get_best_rate_within() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1.4);
+    dex.add_rate('A', 'C', 0.1);
+    dex.add_rate('A', 'D', 0.05);
+    dex.add_rate('B', 'C', 0.2);
+    dex.add_rate('C', 'D', 0.2);
+
+    let options = QueryOptions::new();
+    let (src, dst) = ('A'.into(), 'D'.into());
+    let path = dex.get_best_rate_within(&src, &dst, 1, &options);
+    assert!(path.is_truncated());
+    assert_eq!(path.value().as_ref().unwrap().to_string(), "A -> D: 0.05");
+
+    let path = dex.get_best_rate_within(&src, &dst, 3, &options);
+    assert!(!path.is_truncated());
+    assert_eq!(path.into_value(), dex.get_best_rate(&src, &dst));
+
+    let path = dex.get_best_rate_within(&src, &'Z'.into(), 3, &options);
+    assert_eq!(path.into_value(), None);
+}
@@ -0,0 +1,109 @@
+//! ECB euro reference rates importer
+
+use std::io::{self, Read};
+use std::time::{Duration, SystemTime};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Edge, Vertex};
+
+/// The ECB daily reference rates feed.
+pub const ECB_DAILY_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-daily.xml";
+
+impl Dex {
+    /// Loads the ECB euro reference rates XML, e.g. [`ECB_DAILY_URL`],
+    /// as the `EUR -> <CURRENCY>` rates.
+    ///
+    /// The rates are stamped with the reference date, see
+    /// [`Edge::with_timestamp`].  Only the latest date is loaded out of
+    /// the historical feed.  It returns the number of rates loaded.
+    #[instrument(level = "debug", skip_all, err)]
+    pub fn load_ecb<R: Read>(&mut self, mut reader: R) -> io::Result<usize> {
+        let mut xml = String::new();
+        reader.read_to_string(&mut xml)?;
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let eur: Vertex = "EUR".parse().expect("valid symbol");
+        let mut date = None;
+        let mut count = 0;
+        for tag in xml.split('<').skip(1) {
+            let tag = match tag.split_once('>') {
+                Some((tag, _)) => tag,
+                None => return Err(invalid("unterminated tag".to_string())),
+            };
+            if !tag.starts_with("Cube") {
+                continue;
+            }
+            if let Some(time) = attribute(tag, "time") {
+                // The historical feed has the latest date first.
+                if date.is_some() {
+                    break;
+                }
+                date = Some(
+                    parse_date(time).ok_or_else(|| invalid(format!("invalid time {time:?}")))?,
+                );
+                continue;
+            }
+            let (currency, rate) = match (attribute(tag, "currency"), attribute(tag, "rate")) {
+                (Some(currency), Some(rate)) => (currency, rate),
+                _ => continue,
+            };
+            let currency: Vertex = currency.parse().map_err(|e| invalid(format!("{e}")))?;
+            let rate: f32 = match rate.parse() {
+                Ok(rate) if rate > 0.0 && f32::is_finite(rate) && currency != eur => rate,
+                _ => return Err(invalid(format!("invalid {currency} rate {rate:?}"))),
+            };
+            let mut edge = Edge::new(rate);
+            if let Some(date) = date {
+                edge = edge.with_timestamp(date);
+            }
+            self.insert_edge(eur, currency, edge);
+            count += 1;
+        }
+        debug!(%count, "loaded");
+        Ok(count)
+    }
+}
+
+// Returns the `name='value'` or `name="value"` attribute of the tag.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(i) = rest.find(name) {
+        let before = rest[..i].chars().last();
+        rest = &rest[i + name.len()..];
+        if !matches!(before, Some(c) if c.is_whitespace()) {
+            continue;
+        }
+        let value = rest.trim_start().strip_prefix('=')?.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+// Parses the `YYYY-MM-DD` date as the midnight UTC.
+fn parse_date(date: &str) -> Option<SystemTime> {
+    let mut fields = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = match (fields.next(), fields.next(), fields.next()) {
+        (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) => (year, month, day),
+        _ => return None,
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year < 1970 {
+        return None;
+    }
+    // The days since the epoch of the proleptic Gregorian calendar.
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(days as u64 * 86_400))
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,56 @@
+use std::time::{Duration, SystemTime};
+
+use super::{attribute, parse_date};
+use crate::test::vertex;
+use crate::Dex;
+
+const DAILY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01" xmlns="http://www.ecb.int/vocabulary/2002-08-01/eurofxref">
+	<gesmes:subject>Reference rates</gesmes:subject>
+	<gesmes:Sender>
+		<gesmes:name>European Central Bank</gesmes:name>
+	</gesmes:Sender>
+	<Cube>
+		<Cube time='2024-01-05'>
+			<Cube currency='USD' rate='1.0921'/>
+			<Cube currency='JPY' rate='158.18'/>
+			<Cube currency='GBP' rate='0.86130'/>
+		</Cube>
+		<Cube time='2024-01-04'>
+			<Cube currency='USD' rate='1.0953'/>
+		</Cube>
+	</Cube>
+</gesmes:Envelope>
+"#;
+
+#[test]
+fn test_load_ecb() {
+    let mut dex = Dex::new();
+    assert_eq!(dex.load_ecb(DAILY.as_bytes()).unwrap(), 3);
+
+    let path = dex.get_best_rate(&vertex("EUR"), &vertex("USD")).unwrap();
+    assert_eq!(path.rate(), 1.0921);
+    let path = dex.get_best_rate(&vertex("USD"), &vertex("JPY")).unwrap();
+    assert_eq!(path.to_string(), "USD -> EUR -> JPY: 144.84021");
+    assert_eq!(path.quoted_at(), parse_date("2024-01-05"));
+
+    let mut dex = Dex::new();
+    let xml = "<Cube currency='EUR' rate='1'/>";
+    assert!(dex.load_ecb(xml.as_bytes()).is_err());
+    assert!(dex.load_ecb("<Cube time='2024-13-01'>".as_bytes()).is_err());
+    assert!(dex.load_ecb("<Cube".as_bytes()).is_err());
+}
+
+#[test]
+fn test_parse_date() {
+    let day = |days: u64| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(days * 86_400));
+    assert_eq!(parse_date("1970-01-01"), day(0));
+    assert_eq!(parse_date("2000-03-01"), day(11_017));
+    assert_eq!(parse_date("2024-01-05"), day(19_727));
+    assert_eq!(parse_date("2024-1"), None);
+    assert_eq!(
+        attribute("Cube currency=\"USD\" rate='1.1'", "rate"),
+        Some("1.1")
+    );
+    assert_eq!(attribute("Cube xrate='1.1'", "rate"), None);
+}
@@ -0,0 +1,361 @@
+//! Edge between two vertices
+
+use std::time::{Duration, SystemTime};
+
+use crate::concentrated::Pool;
+use crate::provider::ProviderId;
+use crate::quote::Side;
+
+/// The conversion from the source to the destination currency.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edge {
+    pub(crate) rate: f32,
+    pub(crate) liquidity: Option<f32>,
+    pub(crate) fee: f32,
+    pub(crate) fixed_fee: f32,
+    pub(crate) delay: Duration,
+    pub(crate) latency: Duration,
+    pub(crate) risk: f32,
+    pub(crate) source: Option<ProviderId>,
+    pub(crate) side: Side,
+    pub(crate) kind: EdgeKind,
+    pub(crate) orders: Vec<Order>,
+    pub(crate) timestamp: Option<SystemTime>,
+    pub(crate) pools: Vec<Pool>,
+    pub(crate) zero_for_one: bool,
+    pub(crate) volatility: Option<f32>,
+}
+
+/// The resting limit order, valid up to the size in the source
+/// currency.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Order {
+    pub price: f32,
+    pub size: f32,
+}
+
+/// The type of the edge.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// The conversion within the same chain or venue.
+    Exchange,
+    /// The cross-chain bridge.
+    Bridge,
+    /// The resting limit orders, gone once filled.
+    LimitOrder,
+    /// The concentrated liquidity pools of the fee tiers, e.g. Uniswap
+    /// V3.
+    Concentrated,
+    /// The wrapping between the equivalent representations of the
+    /// asset, e.g. ETH and WETH.
+    Wrap,
+}
+
+impl Edge {
+    pub fn new(rate: f32) -> Self {
+        Self {
+            rate,
+            liquidity: None,
+            fee: 0.0,
+            fixed_fee: 0.0,
+            delay: Duration::ZERO,
+            latency: Duration::ZERO,
+            risk: 0.0,
+            source: None,
+            side: Side::Bid,
+            kind: EdgeKind::Exchange,
+            orders: Vec::new(),
+            timestamp: None,
+            pools: Vec::new(),
+            zero_for_one: true,
+            volatility: None,
+        }
+    }
+
+    /// Creates the cross-chain bridge edge.
+    ///
+    /// The `fixed_fee` is charged in the destination currency, on top
+    /// of the percentage `fee`, e.g. `0.001` for 10 bps.
+    pub fn bridge(rate: f32, fixed_fee: f32, fee: f32, delay: Duration) -> Self {
+        assert!(fixed_fee >= 0.0 && (0.0..1.0).contains(&fee));
+        Self {
+            fee,
+            fixed_fee,
+            delay,
+            kind: EdgeKind::Bridge,
+            ..Self::new(rate)
+        }
+    }
+
+    /// Creates the wrap edge at par, with the conversion `fee`.
+    pub(crate) fn wrap(fee: f32) -> Self {
+        assert!((0.0..1.0).contains(&fee));
+        Self {
+            fee,
+            kind: EdgeKind::Wrap,
+            ..Self::new(1.0)
+        }
+    }
+
+    /// Creates the limit order edge, with the single order.
+    pub(crate) fn limit_order(price: f32, size: f32) -> Self {
+        let mut edge = Self {
+            kind: EdgeKind::LimitOrder,
+            ..Self::new(price)
+        };
+        edge.add_order(price, size);
+        edge
+    }
+
+    /// Sets the available liquidity, the maximum amount of the source
+    /// currency convertible through the edge.
+    pub fn with_liquidity(mut self, liquidity: f32) -> Self {
+        assert!(liquidity >= 0.0);
+        self.liquidity = Some(liquidity);
+        self
+    }
+
+    /// Sets the percentage fee, e.g. `0.003` for 30 bps.
+    pub fn with_fee(mut self, fee: f32) -> Self {
+        assert!((0.0..1.0).contains(&fee));
+        self.fee = fee;
+        self
+    }
+
+    /// Sets the settlement delay for the conversion to complete.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Sets the expected execution latency of the venue, the time to
+    /// get the order filled.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Sets the risk weight, e.g. the counterparty exposure of the
+    /// venue.
+    pub fn with_risk(mut self, risk: f32) -> Self {
+        assert!(risk >= 0.0);
+        self.risk = risk;
+        self
+    }
+
+    /// Sets the source, e.g. the venue or the counterparty, of the edge.
+    pub fn with_source(mut self, source: ProviderId) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Sets the side of the book the rate is quoted at, the bid by
+    /// default.
+    pub fn with_side(mut self, side: Side) -> Self {
+        self.side = side;
+        self
+    }
+
+    /// Sets the time the rate was quoted, for the quote expiry of the
+    /// paths, see [`QueryOptions::with_quote_ttl`].
+    ///
+    /// [`QueryOptions::with_quote_ttl`]: crate::query::QueryOptions::with_quote_ttl
+    pub fn with_timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    pub fn liquidity(&self) -> Option<f32> {
+        self.liquidity
+    }
+
+    /// Returns the percentage fee, e.g. `0.001` for 10 bps.
+    pub fn fee(&self) -> f32 {
+        self.fee
+    }
+
+    /// Returns the fixed fee in the destination currency.
+    pub fn fixed_fee(&self) -> f32 {
+        self.fixed_fee
+    }
+
+    /// Returns the expected delay for the conversion to complete.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Returns the expected execution latency.
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    pub fn risk(&self) -> f32 {
+        self.risk
+    }
+
+    pub fn source(&self) -> Option<ProviderId> {
+        self.source
+    }
+
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    pub fn kind(&self) -> EdgeKind {
+        self.kind
+    }
+
+    pub fn is_bridge(&self) -> bool {
+        self.kind == EdgeKind::Bridge
+    }
+
+    /// Returns the resting limit orders, the best price first.
+    pub fn orders(&self) -> &[Order] {
+        &self.orders
+    }
+
+    /// Adds the limit order, and updates the rate with the best price
+    /// and the liquidity with the total size.
+    pub(crate) fn add_order(&mut self, price: f32, size: f32) {
+        assert!(self.kind == EdgeKind::LimitOrder);
+        assert!(price > 0.0 && size > 0.0);
+        let index = self.orders.partition_point(|order| order.price >= price);
+        self.orders.insert(index, Order { price, size });
+        self.update_orders();
+    }
+
+    /// Fills the limit orders with the `amount` of the source currency,
+    /// and removes the filled ones.
+    pub(crate) fn fill(&mut self, mut amount: f32) {
+        for order in &mut self.orders {
+            let filled = amount.min(order.size);
+            order.size -= filled;
+            amount -= filled;
+        }
+        self.orders.retain(|order| order.size > 0.0);
+        self.update_orders();
+    }
+
+    /// Converts the `amount` of the source currency through the edge,
+    /// consuming the liquidity, and returns the amount received.
+    pub(crate) fn consume(&mut self, amount: f32) -> f32 {
+        if self.kind == EdgeKind::Concentrated {
+            return self.swap_pool(amount);
+        }
+        let received = amount * self.effective_rate(Some(amount));
+        if self.kind == EdgeKind::LimitOrder {
+            self.fill(amount);
+        } else if let Some(liquidity) = self.liquidity.as_mut() {
+            *liquidity = (*liquidity - amount).max(0.0);
+        }
+        received
+    }
+
+    fn update_orders(&mut self) {
+        if let Some(order) = self.orders.first() {
+            self.rate = order.price;
+        }
+        self.liquidity = Some(self.orders.iter().map(|order| order.size).sum());
+    }
+
+    /// Returns the rate net of the fees for converting the `amount` of
+    /// the source currency.
+    ///
+    /// The fixed fee is only accounted for when the amount is known.
+    pub fn effective_rate(&self, amount: Option<f32>) -> f32 {
+        if self.kind == EdgeKind::Concentrated {
+            return self.pool_rate(amount, true);
+        }
+        let rate = self.gross_rate(amount) * (1.0 - self.fee);
+        match amount {
+            Some(amount) if self.fixed_fee != 0.0 => {
+                (amount * rate - self.fixed_fee).max(0.0) / amount
+            }
+            _ => rate,
+        }
+    }
+
+    /// Returns the fees in the destination currency charged for
+    /// converting the `amount` of the source currency.
+    pub fn fees(&self, amount: f32) -> f32 {
+        amount * (self.gross_rate(Some(amount)) - self.effective_rate(Some(amount)))
+    }
+
+    // Returns the rate before the fees.
+    pub(crate) fn gross_rate(&self, amount: Option<f32>) -> f32 {
+        match amount {
+            Some(amount) if self.kind == EdgeKind::LimitOrder => self.fill_rate(amount),
+            _ if self.kind == EdgeKind::Concentrated => self.pool_rate(amount, false),
+            _ => self.rate,
+        }
+    }
+
+    // Walks the limit orders to fill the `amount`, and returns the
+    // average price.
+    fn fill_rate(&self, amount: f32) -> f32 {
+        let mut remaining = amount;
+        let mut received = 0.0;
+        for order in &self.orders {
+            let filled = remaining.min(order.size);
+            received += filled * order.price;
+            remaining -= filled;
+            if remaining <= 0.0 {
+                break;
+            }
+        }
+        received / amount
+    }
+
+    /// Returns the reverse edge, with the liquidity and the fixed fee
+    /// in the destination currency.
+    ///
+    /// The limit orders are inverted as well, with the sizes in the
+    /// destination currency, the best price first.
+    pub fn reverse(&self) -> Self {
+        let mut reverse = Self {
+            rate: 1.0 / self.rate,
+            liquidity: self.liquidity.map(|liquidity| liquidity * self.rate),
+            fixed_fee: self.fixed_fee / self.rate,
+            zero_for_one: !self.zero_for_one,
+            side: self.side.reverse(),
+            ..self.clone()
+        };
+        match self.kind {
+            EdgeKind::Concentrated => reverse.update_pools(),
+            EdgeKind::LimitOrder => {
+                reverse.orders = self
+                    .orders
+                    .iter()
+                    .rev()
+                    .map(|order| Order {
+                        price: 1.0 / order.price,
+                        size: order.size * order.price,
+                    })
+                    .collect();
+                reverse.update_orders();
+            }
+            _ => {}
+        }
+        reverse
+    }
+
+    /// Checks if the edge is deep enough to convert the `amount` of
+    /// the source currency.
+    pub fn is_convertible(&self, amount: f32) -> bool {
+        match self.liquidity {
+            Some(liquidity) => liquidity >= amount,
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
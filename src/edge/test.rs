@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use super::Edge;
+use crate::query::QueryOptions;
+use crate::Dex;
+
+#[test]
+fn test_bridge_effective_rate() {
+    let edge = Edge::bridge(2.0, 10.0, 0.01, Duration::from_secs(600));
+    assert_eq!(edge.effective_rate(None), 1.98);
+    assert_eq!(edge.effective_rate(Some(100.0)), 1.88);
+    assert_eq!(edge.effective_rate(Some(1.0)), 0.0);
+    assert_eq!(edge.fees(100.0), 12.0);
+
+    let reverse = edge.reverse();
+    assert!(reverse.is_bridge());
+    assert_eq!(reverse.fixed_fee(), 5.0);
+    assert_eq!(reverse.delay(), Duration::from_secs(600));
+}
+
+#[test]
+fn test_cross_chain_path() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1.0);
+    dex.add_edge(
+        'A',
+        'C',
+        Edge::bridge(1.0, 1.0, 0.0, Duration::from_secs(600)),
+    );
+    dex.add_rate('C', 'B', 1.1);
+
+    let src = 'A'.into();
+    let dst = 'B'.into();
+    let path = dex.get_best_rate(&src, &dst).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> B: 1.1");
+    assert!(path.is_cross_chain());
+    assert_eq!(path.delay(), Duration::from_secs(600));
+
+    // The fixed fee eats the better rate for the small amount.
+    let options = QueryOptions::new().with_amount(5.0);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> B: 1");
+    assert!(!path.is_cross_chain());
+
+    let options = QueryOptions::new().with_amount(100.0);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.bridges(), 1);
+}
+
+#[test]
+fn test_limit_order_fill_rate() {
+    let mut edge = Edge::limit_order(2.0, 100.0);
+    edge.add_order(1.5, 100.0);
+    edge.add_order(2.5, 50.0);
+    assert_eq!(edge.rate(), 2.5);
+    assert_eq!(edge.liquidity(), Some(250.0));
+    assert_eq!(edge.effective_rate(None), 2.5);
+    assert_eq!(edge.effective_rate(Some(50.0)), 2.5);
+    assert_eq!(edge.effective_rate(Some(150.0)), 6.5 / 3.0);
+    assert!(!edge.is_convertible(251.0));
+
+    edge.fill(100.0);
+    assert_eq!(edge.orders().len(), 2);
+    assert_eq!(edge.rate(), 2.0);
+    assert_eq!(edge.liquidity(), Some(150.0));
+}
+
+#[test]
+fn test_limit_order_reverse() {
+    let mut edge = Edge::limit_order(2.0, 100.0);
+    edge.add_order(4.0, 50.0);
+
+    let reverse = edge.reverse();
+    let orders: Vec<_> = reverse
+        .orders()
+        .iter()
+        .map(|order| (order.price, order.size))
+        .collect();
+    assert_eq!(orders, [(0.5, 200.0), (0.25, 200.0)]);
+    assert_eq!(reverse.rate(), 0.5);
+    assert_eq!(reverse.liquidity(), Some(400.0));
+    assert_eq!(reverse.effective_rate(Some(400.0)), 0.375);
+    assert_eq!(reverse.reverse().orders(), edge.orders());
+}
+
+#[test]
+fn test_limit_order_route() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'C', 1.0);
+    dex.add_rate('C', 'B', 1.8);
+    dex.add_limit_order('A', 'B', 2.0, 100.0);
+    dex.add_limit_order('A', 'B', 1.9, 100.0);
+
+    // Limit orders are directed.
+    assert!(dex.get_best_rate(&'B'.into(), &'A'.into()).unwrap().len() == 3);
+
+    let src = 'A'.into();
+    let dst = 'B'.into();
+    let options = QueryOptions::new().with_amount(150.0);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> B: 1.9666667");
+
+    let options = QueryOptions::new().with_amount(250.0);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> B: 1.8");
+
+    // The filled orders are gone.
+    let flow = dex.route_amount(&src, &dst, 250.0);
+    assert_eq!(flow.routes().len(), 2);
+    assert_eq!(flow.routes()[0].1, 200.0);
+    assert_eq!(flow.routes()[1].0.to_string(), "A -> C -> B: 1.8");
+    assert_eq!(flow.amount_out(), 390.0 + 90.0);
+}
@@ -0,0 +1,112 @@
+//! Wrapped-token equivalence classes
+
+use std::collections::HashMap;
+
+use tracing::debug;
+
+use super::{Dex, Vertex};
+use crate::cycle::Cycle;
+use crate::edge::Edge;
+
+impl Dex {
+    /// Declares `wrapped` equivalent to `native`, e.g. WETH to ETH, with
+    /// the conversion `fee` each way, e.g. `0.001` for 10 bps.
+    ///
+    /// The routes cross between the two through the wrap edges at par,
+    /// which are not subject to the outlier guard.  The cycles only
+    /// differing by the wrapping are reported once by the consistency
+    /// check and the arbitrage detection, and the ones only wrapping
+    /// back and forth are never reported.
+    pub fn add_equivalence<V: Into<Vertex>>(&mut self, native: V, wrapped: V, fee: f32) {
+        let native = self.canonical(native.into());
+        let wrapped = self.canonical(wrapped.into());
+        assert!(native != wrapped);
+        debug!(%native, %wrapped, %fee, "equivalence");
+        let class = self.equivalence(&native);
+        let merged = self.equivalence(&wrapped);
+        for class_of in self.equivalences.values_mut() {
+            if *class_of == merged {
+                *class_of = class;
+            }
+        }
+        self.equivalences.insert(native, class);
+        self.equivalences.insert(wrapped, class);
+        let edge = Edge::wrap(fee);
+        self.edges
+            .entry(native)
+            .or_default()
+            .insert(wrapped, edge.reverse());
+        self.edges.entry(wrapped).or_default().insert(native, edge);
+        self.changes.push(native, wrapped);
+        self.changes.push(wrapped, native);
+    }
+
+    /// Checks if the vertices are the representations of the same asset.
+    pub fn is_equivalent(&self, a: &Vertex, b: &Vertex) -> bool {
+        self.equivalence(a) == self.equivalence(b)
+    }
+
+    /// Returns the vertices equivalent to `v`, including itself.
+    pub fn equivalents(&self, v: &Vertex) -> Vec<Vertex> {
+        let class = self.equivalence(v);
+        let mut equivalents: Vec<_> = self
+            .equivalences
+            .iter()
+            .filter(|(_, class_of)| **class_of == class)
+            .map(|(v, _)| *v)
+            .collect();
+        if equivalents.is_empty() {
+            equivalents.push(*v);
+        }
+        equivalents
+    }
+
+    // Returns the class of the vertex, identified by the first native
+    // vertex declared, or the vertex itself.
+    fn equivalence(&self, v: &Vertex) -> Vertex {
+        self.equivalences.get(v).copied().unwrap_or(*v)
+    }
+
+    // Drops the cycles within the single class, and keeps the highest
+    // net rate of the cycles with the same class sequence.
+    pub(crate) fn dedup_equivalent(&self, cycles: Vec<Cycle>) -> Vec<Cycle> {
+        if self.equivalences.is_empty() {
+            return cycles;
+        }
+        let mut best: HashMap<Vec<Vertex>, usize> = HashMap::new();
+        let mut kept: Vec<Option<Cycle>> = Vec::new();
+        for cycle in cycles {
+            let mut classes: Vec<_> = cycle
+                .edges()
+                .iter()
+                .map(|(src, _, _)| self.equivalence(src))
+                .collect();
+            classes.dedup();
+            if classes.len() > 1 && classes.first() == classes.last() {
+                classes.pop();
+            }
+            if classes.len() < 2 {
+                continue;
+            }
+            let start = (0..classes.len()).min_by_key(|i| classes[*i]).unwrap_or(0);
+            classes.rotate_left(start);
+            match best.get(&classes) {
+                Some(i) => {
+                    let other = kept[*i].as_ref().map_or(0.0, Cycle::net_rate);
+                    if cycle.net_rate() > other {
+                        debug!(%cycle, "equivalent cycle");
+                        kept[*i] = Some(cycle);
+                    }
+                }
+                None => {
+                    best.insert(classes, kept.len());
+                    kept.push(Some(cycle));
+                }
+            }
+        }
+        kept.into_iter().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod test;
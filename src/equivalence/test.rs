@@ -0,0 +1,58 @@
+use crate::test::vertex;
+use crate::Dex;
+
+fn dex() -> Dex {
+    let mut dex = Dex::new();
+    for eth in [vertex("ETH"), vertex("WETH")] {
+        dex.add_rate(eth, vertex("USD"), 2000.0);
+        dex.add_rate(eth, vertex("BTC"), 0.051);
+    }
+    dex.add_rate(vertex("BTC"), vertex("USD"), 40000.0);
+    dex
+}
+
+#[test]
+fn test_add_equivalence() {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("ETH"), vertex("USD"), 2000.0);
+    dex.add_rate(vertex("WETH"), vertex("DAI"), 2010.0);
+    dex.add_equivalence(vertex("ETH"), vertex("WETH"), 0.001);
+
+    let eth = vertex("ETH");
+    let weth = vertex("WETH");
+    assert!(dex.is_equivalent(&eth, &weth));
+    assert!(!dex.is_equivalent(&eth, &vertex("USD")));
+    assert_eq!(dex.equivalents(&weth), [eth, weth]);
+    assert_eq!(dex.equivalents(&vertex("USD")), [vertex("USD")]);
+
+    let path = dex.get_best_rate(&eth, &vertex("DAI")).unwrap();
+    assert_eq!(
+        path.to_string(),
+        format!("ETH -> WETH -> DAI: {}", path.rate())
+    );
+    assert!((path.rate() - 2010.0 * 0.999).abs() < 1e-2);
+}
+
+#[test]
+fn test_add_equivalence_merge() {
+    let mut dex = Dex::new();
+    dex.add_equivalence(vertex("BTC"), vertex("WBTC"), 0.002);
+    dex.add_equivalence(vertex("BTCB"), vertex("XBTC"), 0.0);
+    assert!(!dex.is_equivalent(&vertex("BTC"), &vertex("XBTC")));
+    dex.add_equivalence(vertex("WBTC"), vertex("BTCB"), 0.001);
+    assert!(dex.is_equivalent(&vertex("BTC"), &vertex("XBTC")));
+    assert_eq!(dex.equivalents(&vertex("BTCB")).len(), 4);
+}
+
+#[test]
+fn test_find_arbitrage_equivalent() {
+    let mut dex = dex();
+    let cycles = dex.find_arbitrage(10.0, None);
+    assert_eq!(cycles.len(), 2);
+
+    dex.add_equivalence(vertex("ETH"), vertex("WETH"), 0.0);
+    let cycles = dex.find_arbitrage(10.0, None);
+    assert_eq!(cycles.len(), 1);
+    assert!((cycles[0].net_rate() - 1.02).abs() < 1e-4);
+    assert_eq!(dex.check_consistency(1e-4).len(), 2);
+}
@@ -0,0 +1,224 @@
+//! Unified exchange adapter
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use tracing::{debug, instrument};
+
+use super::Vertex;
+use crate::edge::Order;
+use crate::fetch::{Quote, Source};
+
+/// The well known venue specific asset codes.
+const ALIASES: &[(&str, &str)] = &[
+    ("XBT", "BTC"),
+    ("XDG", "DOGE"),
+    ("XETH", "ETH"),
+    ("ZUSD", "USD"),
+];
+
+/// The traded market, with the venue native symbol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Market {
+    pub symbol: String,
+    pub base: Vertex,
+    pub quote: Vertex,
+}
+
+/// The top of the book of the market.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ticker {
+    pub symbol: String,
+    pub bid: f32,
+    pub ask: f32,
+}
+
+impl Ticker {
+    pub fn mid(&self) -> f32 {
+        (self.bid + self.ask) / 2.0
+    }
+}
+
+/// The order book of the market, the best price first on each side.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OrderBook {
+    pub bids: Vec<Order>,
+    pub asks: Vec<Order>,
+}
+
+/// The page of the paginated API, with the cursor of the next one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next: Option<String>,
+}
+
+/// The error of the exchange API.
+#[derive(Debug)]
+pub enum ExchangeError {
+    Network(io::Error),
+    /// Throttled by the venue, with the time to wait if told.
+    RateLimited(Option<Duration>),
+    /// The unexpected response.
+    Parse(String),
+    /// The error returned by the venue.
+    Exchange(String),
+}
+
+impl fmt::Display for ExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(e) => write!(f, "network: {e}"),
+            Self::RateLimited(Some(wait)) => write!(f, "rate limited for {wait:?}"),
+            Self::RateLimited(None) => write!(f, "rate limited"),
+            Self::Parse(msg) => write!(f, "unexpected response: {msg}"),
+            Self::Exchange(msg) => write!(f, "exchange: {msg}"),
+        }
+    }
+}
+
+impl Error for ExchangeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Network(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ExchangeError {
+    fn from(e: io::Error) -> Self {
+        Self::Network(e)
+    }
+}
+
+impl From<ExchangeError> for io::Error {
+    fn from(e: ExchangeError) -> Self {
+        match e {
+            ExchangeError::Network(e) => e,
+            ExchangeError::RateLimited(_) => io::Error::new(io::ErrorKind::WouldBlock, e),
+            ExchangeError::Parse(_) => io::Error::new(io::ErrorKind::InvalidData, e),
+            ExchangeError::Exchange(_) => io::Error::new(io::ErrorKind::ConnectionRefused, e),
+        }
+    }
+}
+
+/// The centralized exchange API, implemented by each venue adapter
+/// over its own wire format.
+///
+/// The shared glue, the symbol normalization, the pagination and the
+/// conversion into the provider rates, is in [`normalize`],
+/// [`paginate`] and [`ExchangeSource`].
+pub trait Exchange: Send {
+    fn name(&self) -> &str;
+
+    /// Returns the markets, with the assets normalized by [`normalize`].
+    fn fetch_markets(&mut self) -> Result<Vec<Market>, ExchangeError>;
+
+    /// Returns the tickers of the `markets`.
+    fn fetch_tickers(&mut self, markets: &[Market]) -> Result<Vec<Ticker>, ExchangeError>;
+
+    /// Returns the order book up to `depth` levels, or `None` in case
+    /// the venue doesn't provide one.
+    fn fetch_order_book(
+        &mut self,
+        _market: &Market,
+        _depth: usize,
+    ) -> Result<Option<OrderBook>, ExchangeError> {
+        Ok(None)
+    }
+}
+
+/// Normalizes the venue asset code, e.g. `xbt` and `XBT` into `BTC`.
+pub fn normalize(asset: &str) -> Result<Vertex, ExchangeError> {
+    let asset = asset.trim().to_ascii_uppercase();
+    let asset = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == asset)
+        .map_or(asset.as_str(), |(_, canonical)| canonical);
+    asset
+        .parse()
+        .map_err(|e| ExchangeError::Parse(format!("{e}")))
+}
+
+/// Collects all the pages, fetched with the cursor of the previous
+/// page, up to `max_pages`.
+pub fn paginate<T, F>(max_pages: usize, mut fetch: F) -> Result<Vec<T>, ExchangeError>
+where
+    F: FnMut(Option<&str>) -> Result<Page<T>, ExchangeError>,
+{
+    let mut items = Vec::new();
+    let mut cursor = None;
+    for _ in 0..max_pages {
+        let page = fetch(cursor.as_deref())?;
+        items.extend(page.items);
+        cursor = match page.next {
+            Some(next) => Some(next),
+            None => return Ok(items),
+        };
+    }
+    Err(ExchangeError::Parse(format!("more than {max_pages} pages")))
+}
+
+/// The [`Source`] of the mid rates of the exchange markets, for the
+/// [`Fetcher`](crate::fetch::Fetcher).
+///
+/// The markets are fetched once, and the tickers on each fetch.
+#[derive(Debug)]
+pub struct ExchangeSource<E> {
+    exchange: E,
+    markets: Option<HashMap<String, Market>>,
+}
+
+impl<E: Exchange> ExchangeSource<E> {
+    pub fn new(exchange: E) -> Self {
+        Self {
+            exchange,
+            markets: None,
+        }
+    }
+
+    pub fn exchange(&self) -> &E {
+        &self.exchange
+    }
+
+    #[instrument(level = "debug", skip(self), fields(exchange = self.exchange.name()))]
+    fn quotes(&mut self) -> Result<Vec<Quote>, ExchangeError> {
+        if self.markets.is_none() {
+            let markets = self.exchange.fetch_markets()?;
+            debug!(markets = markets.len(), "markets");
+            let markets = markets
+                .into_iter()
+                .map(|market| (market.symbol.clone(), market))
+                .collect();
+            self.markets = Some(markets);
+        }
+        let markets = self.markets.as_ref().unwrap();
+        let list: Vec<_> = markets.values().cloned().collect();
+        let tickers = self.exchange.fetch_tickers(&list)?;
+        Ok(tickers
+            .into_iter()
+            .filter(|ticker| ticker.bid > 0.0 && ticker.ask >= ticker.bid)
+            .filter_map(|ticker| {
+                let market = markets.get(&ticker.symbol)?;
+                Some(Quote {
+                    src: market.base,
+                    dst: market.quote,
+                    rate: ticker.mid(),
+                })
+            })
+            .collect())
+    }
+}
+
+impl<E: Exchange> Source for ExchangeSource<E> {
+    fn fetch(&mut self) -> io::Result<Vec<Quote>> {
+        self.quotes().map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test;
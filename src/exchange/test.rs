@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use super::{normalize, paginate, Exchange, ExchangeError, ExchangeSource, Market, Page, Ticker};
+use crate::fetch::Source;
+use crate::test::vertex;
+
+#[derive(Debug, Default)]
+struct Mock {
+    calls: usize,
+}
+
+impl Exchange for Mock {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn fetch_markets(&mut self) -> Result<Vec<Market>, ExchangeError> {
+        self.calls += 1;
+        let pages = [("XBTUSD", "xbt", "ZUSD"), ("ETHXBT", "eth", "xbt")];
+        paginate(10, |cursor| {
+            let i: usize = cursor.map_or(0, |cursor| cursor.parse().unwrap());
+            let (symbol, base, quote) = pages[i];
+            Ok(Page {
+                items: vec![Market {
+                    symbol: symbol.into(),
+                    base: normalize(base)?,
+                    quote: normalize(quote)?,
+                }],
+                next: (i + 1 < pages.len()).then(|| (i + 1).to_string()),
+            })
+        })
+    }
+
+    fn fetch_tickers(&mut self, _markets: &[Market]) -> Result<Vec<Ticker>, ExchangeError> {
+        Ok(vec![
+            Ticker {
+                symbol: "XBTUSD".into(),
+                bid: 99.0,
+                ask: 101.0,
+            },
+            Ticker {
+                symbol: "ETHXBT".into(),
+                bid: 0.05,
+                ask: 0.04,
+            },
+            Ticker {
+                symbol: "UNKNOWN".into(),
+                bid: 1.0,
+                ask: 1.0,
+            },
+        ])
+    }
+}
+
+#[test]
+fn test_source() {
+    let mut source = ExchangeSource::new(Mock::default());
+    let quotes = source.fetch().unwrap();
+    assert_eq!(quotes.len(), 1);
+    assert_eq!(quotes[0].src, vertex("BTC"));
+    assert_eq!(quotes[0].dst, vertex("USD"));
+    assert_eq!(quotes[0].rate, 100.0);
+    source.fetch().unwrap();
+    assert_eq!(source.exchange().calls, 1);
+    assert_eq!(
+        source
+            .exchange
+            .fetch_order_book(&source.markets.as_ref().unwrap()["XBTUSD"], 10)
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_paginate() {
+    let err = paginate(2, |_| {
+        Ok(Page {
+            items: vec![1],
+            next: Some("again".to_string()),
+        })
+    })
+    .unwrap_err();
+    assert_eq!(err.to_string(), "unexpected response: more than 2 pages");
+
+    let err: std::io::Error = ExchangeError::RateLimited(Some(Duration::from_secs(1))).into();
+    assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+    assert!(normalize("").is_err());
+}
@@ -0,0 +1,86 @@
+//! Venue failure and rerouting
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path};
+use crate::provider::ProviderId;
+use crate::query::QueryOptions;
+
+impl Dex {
+    /// Marks the provider, e.g. the venue, down until [`Dex::mark_up`].
+    ///
+    /// Its edges are disabled, and the pairs it quotes fall back to the
+    /// next highest priority provider, if any.  The rates it quotes in
+    /// the meantime are kept for the recovery.
+    #[instrument(level = "debug", skip(self))]
+    pub fn mark_down(&mut self, id: ProviderId) {
+        match self.provider_mut(id) {
+            Some(provider) if !provider.down => provider.down = true,
+            _ => return,
+        }
+        let mut disabled = Vec::new();
+        for (src, edges) in self.edges.iter_mut() {
+            let dsts: Vec<_> = edges
+                .iter()
+                .filter(|(_, edge)| edge.source == Some(id))
+                .map(|(dst, _)| *dst)
+                .collect();
+            for dst in dsts {
+                if let Some(edge) = edges.remove(&dst) {
+                    self.changes.push(*src, dst);
+                    disabled.push((*src, dst, edge));
+                }
+            }
+        }
+        debug!(disabled = disabled.len(), "down");
+        self.disabled.insert(id, disabled);
+        self.update_provider_rates(id);
+    }
+
+    /// Marks the provider up again, and restores its edges.
+    #[instrument(level = "debug", skip(self))]
+    pub fn mark_up(&mut self, id: ProviderId) {
+        match self.provider_mut(id) {
+            Some(provider) if provider.down => provider.down = false,
+            _ => return,
+        }
+        for (src, dst, edge) in self.disabled.remove(&id).unwrap_or_default() {
+            self.edges.entry(src).or_default().insert(dst, edge);
+            self.changes.push(src, dst);
+        }
+        self.update_provider_rates(id);
+    }
+
+    /// Checks if all the hops of the `path` are still available, e.g.
+    /// not through the venue marked down.
+    pub fn is_available(&self, path: &Path) -> bool {
+        path.path.windows(2).all(
+            |hop| matches!(self.edges.get(&hop[0]), Some(edges) if edges.contains_key(&hop[1])),
+        )
+    }
+
+    /// Recomputes the best rate path of the `path` pair, around the
+    /// venues marked down.
+    pub fn replan(&self, path: &Path, options: &QueryOptions) -> Option<Path> {
+        let src = path.path.first()?;
+        let path = self.get_best_rate_with(src, path.last(), options);
+        debug!(?path, "replanned");
+        path
+    }
+
+    // Updates the edges of the pairs quoted by the provider.
+    fn update_provider_rates(&mut self, id: ProviderId) {
+        let pairs: Vec<_> = self
+            .quotes
+            .iter()
+            .filter(|(_, quotes)| quotes.contains_key(&id))
+            .map(|(pair, _)| *pair)
+            .collect();
+        for pair in pairs {
+            self.update_provider_rate(pair);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,51 @@
+use crate::edge::Edge;
+use crate::query::QueryOptions;
+use crate::Dex;
+
+#[test]
+fn test_mark_down() {
+    let mut dex = Dex::new();
+    let venue = dex.register_provider("venue", 10);
+    let backup = dex.register_provider("backup", 1);
+    dex.add_provider_rate(venue, 'A', 'B', 2.0);
+    dex.add_provider_rate(backup, 'A', 'B', 1.9);
+    dex.add_edge('B', 'C', Edge::new(3.0).with_source(venue));
+    dex.add_rate('A', 'C', 5.0);
+
+    let (a, c) = ('A'.into(), 'C'.into());
+    let path = dex.get_best_rate(&a, &c).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C: 6");
+    assert!(dex.is_available(&path));
+
+    dex.mark_down(venue);
+    assert!(dex.provider(venue).unwrap().is_down());
+    assert!(!dex.is_available(&path));
+    let replanned = dex.replan(&path, &QueryOptions::new()).unwrap();
+    assert_eq!(replanned.to_string(), "A -> C: 5");
+    // The pair quoted by the venue falls back to the backup.
+    let path = dex.get_best_rate(&a, &'B'.into()).unwrap();
+    assert_eq!(path.to_string(), "A -> B: 1.9");
+
+    // The rates quoted in the meantime are kept for the recovery.
+    dex.add_provider_rate(venue, 'A', 'B', 2.1);
+    let path = dex.get_best_rate(&a, &'B'.into()).unwrap();
+    assert_eq!(path.to_string(), "A -> B: 1.9");
+
+    dex.mark_up(venue);
+    assert!(!dex.provider(venue).unwrap().is_down());
+    let path = dex.get_best_rate(&a, &c).unwrap();
+    assert!((path.rate() - 6.3).abs() < 1e-5);
+    assert_eq!(path.len(), 3);
+}
+
+#[test]
+fn test_mark_down_without_backup() {
+    let mut dex = Dex::new();
+    let venue = dex.register_provider("venue", 10);
+    dex.add_provider_rate(venue, 'A', 'B', 2.0);
+    dex.mark_down(venue);
+    assert!(dex.get_best_rate(&'A'.into(), &'B'.into()).is_none());
+    dex.mark_down(venue);
+    dex.mark_up(venue);
+    assert!(dex.get_best_rate(&'A'.into(), &'B'.into()).is_some());
+}
@@ -0,0 +1,341 @@
+//! Provider fetcher with the rate limiting and the circuit breaker
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::time::{Duration, Instant};
+
+use tracing::{debug, instrument, warn};
+
+use super::{Dex, Vertex};
+use crate::provider::ProviderId;
+
+/// The `src -> dst` rate fetched from the upstream.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quote {
+    pub src: Vertex,
+    pub dst: Vertex,
+    pub rate: f32,
+}
+
+/// The upstream source of the provider rates, e.g. the exchange API.
+pub trait Source: Send {
+    fn fetch(&mut self) -> io::Result<Vec<Quote>>;
+}
+
+impl<F> Source for F
+where
+    F: FnMut() -> io::Result<Vec<Quote>> + Send,
+{
+    fn fetch(&mut self) -> io::Result<Vec<Quote>> {
+        self()
+    }
+}
+
+/// The token bucket rate limit, with `per_second` requests on average
+/// and up to `burst` requests at once.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RateLimit {
+    per_second: f32,
+    burst: u32,
+}
+
+impl RateLimit {
+    pub fn new(per_second: f32, burst: u32) -> Self {
+        assert!(per_second > 0.0 && burst > 0);
+        Self { per_second, burst }
+    }
+
+    pub fn per_second(&self) -> f32 {
+        self.per_second
+    }
+
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+}
+
+// The token bucket, full at the start.
+#[derive(Clone, Debug)]
+pub(crate) struct Bucket {
+    limit: RateLimit,
+    tokens: f32,
+    refilled_at: Option<Instant>,
+}
+
+impl Bucket {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f32,
+            refilled_at: None,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        if let Some(refilled_at) = self.refilled_at {
+            let elapsed = now.saturating_duration_since(refilled_at).as_secs_f32();
+            self.tokens =
+                (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f32);
+        }
+        self.refilled_at = Some(now);
+    }
+
+    // Returns the time until the next token.
+    fn wait(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        let wait = (1.0 - self.tokens).max(0.0) / self.limit.per_second;
+        Duration::from_secs_f32(wait)
+    }
+
+    // Takes the token, or returns the time until the next one.
+    pub(crate) fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        match self.wait(now) {
+            Duration::ZERO => {
+                self.tokens -= 1.0;
+                Ok(())
+            }
+            wait => Err(wait),
+        }
+    }
+}
+
+/// The exponential backoff of the failed source, doubled from
+/// `initial` up to `max` on each consecutive failure.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        assert!(initial <= max);
+        Self { initial, max }
+    }
+
+    // Returns the delay after the `failures` consecutive failures.
+    fn delay(&self, failures: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(failures.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+/// The circuit breaker, opened after the `threshold` consecutive
+/// failures.
+///
+/// The open source is unhealthy and is not fetched for the `cooldown`,
+/// after which the single trial fetch closes it again on success.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    expire: bool,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        assert!(threshold > 0);
+        Self {
+            threshold,
+            cooldown,
+            expire: false,
+        }
+    }
+
+    /// Removes the provider rates from the graph when opened, so that
+    /// the stale rates are not routed through.
+    pub fn with_expire(mut self, expire: bool) -> Self {
+        self.expire = expire;
+        self
+    }
+
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    pub fn expire(&self) -> bool {
+        self.expire
+    }
+}
+
+struct Entry {
+    provider: ProviderId,
+    source: Box<dyn Source>,
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl Entry {
+    fn is_open(&self, breaker: Option<&CircuitBreaker>) -> bool {
+        match breaker {
+            Some(breaker) => self.failures >= breaker.threshold,
+            None => false,
+        }
+    }
+
+    // Records the failure, and returns true when the breaker is just
+    // opened.
+    fn fail(
+        &mut self,
+        now: Instant,
+        backoff: Option<&Backoff>,
+        breaker: Option<&CircuitBreaker>,
+    ) -> bool {
+        let was_open = self.is_open(breaker);
+        self.failures = self.failures.saturating_add(1);
+        let delay = match (breaker, backoff) {
+            (Some(breaker), _) if self.is_open(Some(breaker)) => Some(breaker.cooldown),
+            (_, Some(backoff)) => Some(backoff.delay(self.failures)),
+            _ => None,
+        };
+        self.retry_at = delay.map(|delay| now + delay);
+        !was_open && self.is_open(breaker)
+    }
+}
+
+/// The fetcher of the provider rates into the [`Dex`].
+///
+/// The sources of each provider are fetched within the one shared
+/// [`RateLimit`], so that the upstream APIs are not hit more often than
+/// allowed however often [`Fetcher::poll`] is called.  The failed
+/// source is retried after the [`Backoff`], and the [`CircuitBreaker`]
+/// marks the flapping one unhealthy.
+///
+/// The settings of the provider apply to all its sources, added before
+/// or after them.
+#[derive(Default)]
+pub struct Fetcher {
+    entries: Vec<Entry>,
+    buckets: HashMap<ProviderId, Bucket>,
+    backoffs: HashMap<ProviderId, Backoff>,
+    breakers: HashMap<ProviderId, CircuitBreaker>,
+}
+
+impl fmt::Debug for Fetcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fetcher")
+            .field("sources", &self.entries.len())
+            .finish()
+    }
+}
+
+impl Fetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the `source` of the `provider` rates, without the limit.
+    pub fn source<S: Source + 'static>(mut self, provider: ProviderId, source: S) -> Self {
+        self.entries.push(Entry {
+            provider,
+            source: Box::new(source),
+            failures: 0,
+            retry_at: None,
+        });
+        self
+    }
+
+    /// Sets the rate limit shared by all the sources of the `provider`.
+    pub fn with_rate_limit(mut self, provider: ProviderId, limit: RateLimit) -> Self {
+        self.buckets.insert(provider, Bucket::new(limit));
+        self
+    }
+
+    /// Sets the retry backoff to all the sources of the `provider`.
+    pub fn with_backoff(mut self, provider: ProviderId, backoff: Backoff) -> Self {
+        self.backoffs.insert(provider, backoff);
+        self
+    }
+
+    /// Sets the circuit breaker to all the sources of the `provider`.
+    pub fn with_circuit_breaker(mut self, provider: ProviderId, breaker: CircuitBreaker) -> Self {
+        self.breakers.insert(provider, breaker);
+        self
+    }
+
+    /// Checks if none of the `provider` sources has the open circuit
+    /// breaker.
+    pub fn is_healthy(&self, provider: ProviderId) -> bool {
+        let breaker = self.breakers.get(&provider);
+        self.entries
+            .iter()
+            .filter(|entry| entry.provider == provider)
+            .all(|entry| !entry.is_open(breaker))
+    }
+
+    /// Fetches the sources allowed at `now` into the `dex`, and returns
+    /// the number of the quotes added.
+    ///
+    /// The rate limited sources, and the failed ones within the backoff
+    /// or the cooldown, are skipped until the next poll.
+    #[instrument(level = "debug", skip_all)]
+    pub fn poll(&mut self, dex: &mut Dex, now: Instant) -> usize {
+        let mut count = 0;
+        for entry in &mut self.entries {
+            match entry.retry_at {
+                Some(retry_at) if retry_at > now => continue,
+                _ => {}
+            }
+            if let Some(bucket) = self.buckets.get_mut(&entry.provider) {
+                if let Err(wait) = bucket.take(now) {
+                    debug!(provider = ?entry.provider, ?wait, "rate limited");
+                    continue;
+                }
+            }
+            let breaker = self.breakers.get(&entry.provider);
+            match entry.source.fetch() {
+                Ok(quotes) => {
+                    if entry.is_open(breaker) {
+                        debug!(provider = ?entry.provider, "recovered");
+                    }
+                    entry.failures = 0;
+                    entry.retry_at = None;
+                    for quote in quotes {
+                        dex.add_provider_rate(entry.provider, quote.src, quote.dst, quote.rate);
+                        count += 1;
+                    }
+                }
+                Err(e) => {
+                    warn!(provider = ?entry.provider, %e, failures = entry.failures + 1, "fetch failed");
+                    let backoff = self.backoffs.get(&entry.provider);
+                    if entry.fail(now, backoff, breaker) {
+                        warn!(provider = ?entry.provider, "unhealthy");
+                        if matches!(breaker, Some(breaker) if breaker.expire) {
+                            dex.remove_provider_rates(entry.provider);
+                        }
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    /// Returns the time until any source is allowed to fetch again.
+    pub fn next_poll(&mut self, now: Instant) -> Duration {
+        let buckets = &mut self.buckets;
+        self.entries
+            .iter()
+            .map(|entry| {
+                let retry = match entry.retry_at {
+                    Some(retry_at) => retry_at.saturating_duration_since(now),
+                    None => Duration::ZERO,
+                };
+                match buckets.get_mut(&entry.provider) {
+                    Some(bucket) => bucket.wait(now).max(retry),
+                    None => retry,
+                }
+            })
+            .min()
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod test;
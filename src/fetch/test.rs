@@ -0,0 +1,142 @@
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::{Backoff, CircuitBreaker, Fetcher, Quote, RateLimit};
+use crate::mock::MockProvider;
+use crate::Dex;
+
+#[test]
+fn test_rate_limit() {
+    let mut dex = Dex::new();
+    let feed = dex.register_provider("feed", 1);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let mut fetcher = Fetcher::new()
+        .source(feed, move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+            Ok(vec![Quote {
+                src: 'A'.into(),
+                dst: 'B'.into(),
+                rate: 2.0,
+            }])
+        })
+        .with_rate_limit(feed, RateLimit::new(2.0, 2));
+
+    let now = Instant::now();
+    assert_eq!(fetcher.poll(&mut dex, now), 1);
+    assert_eq!(fetcher.poll(&mut dex, now), 1);
+    assert_eq!(fetcher.poll(&mut dex, now), 0);
+    assert_eq!(fetcher.next_poll(now), Duration::from_millis(500));
+    assert_eq!(fetcher.poll(&mut dex, now + Duration::from_millis(500)), 1);
+    assert_eq!(calls.load(Ordering::Relaxed), 3);
+    assert_eq!(
+        dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap().rate(),
+        2.0
+    );
+
+    // Refilled up to the burst only.
+    let later = now + Duration::from_secs(60);
+    assert_eq!(fetcher.poll(&mut dex, later), 1);
+    assert_eq!(fetcher.poll(&mut dex, later), 1);
+    assert_eq!(fetcher.poll(&mut dex, later), 0);
+}
+
+#[test]
+fn test_rate_limit_shared() {
+    let mut dex = Dex::new();
+    let feed = dex.register_provider("feed", 1);
+    let quote = |src: char, dst: char| {
+        move || {
+            Ok(vec![Quote {
+                src: src.into(),
+                dst: dst.into(),
+                rate: 2.0,
+            }])
+        }
+    };
+    // Set before the sources, and shared by them.
+    let mut fetcher = Fetcher::new()
+        .with_rate_limit(feed, RateLimit::new(1.0, 2))
+        .source(feed, quote('A', 'B'))
+        .source(feed, quote('B', 'C'));
+
+    let now = Instant::now();
+    assert_eq!(fetcher.poll(&mut dex, now), 2);
+    assert_eq!(fetcher.poll(&mut dex, now), 0);
+    assert_eq!(fetcher.next_poll(now), Duration::from_secs(1));
+    assert_eq!(fetcher.poll(&mut dex, now + Duration::from_secs(1)), 1);
+}
+
+#[test]
+fn test_fetch_failed() {
+    let mut dex = Dex::new();
+    let feed = dex.register_provider("feed", 1);
+    let mut fetcher = Fetcher::new().source(feed, || {
+        Err(io::Error::new(io::ErrorKind::ConnectionRefused, "down"))
+    });
+    assert_eq!(fetcher.poll(&mut dex, Instant::now()), 0);
+    assert_eq!(fetcher.next_poll(Instant::now()), Duration::ZERO);
+}
+
+#[test]
+fn test_backoff() {
+    let mut dex = Dex::new();
+    let feed = dex.register_provider("feed", 1);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let mut fetcher = Fetcher::new()
+        .with_backoff(
+            feed,
+            Backoff::new(Duration::from_secs(1), Duration::from_secs(3)),
+        )
+        .source(feed, move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+            Err(io::Error::new(io::ErrorKind::TimedOut, "timeout"))
+        });
+
+    let now = Instant::now();
+    for (secs, wait) in [(0, 1), (1, 2), (3, 3), (6, 3)] {
+        let now = now + Duration::from_secs(secs);
+        fetcher.poll(&mut dex, now);
+        assert_eq!(fetcher.next_poll(now), Duration::from_secs(wait));
+        fetcher.poll(&mut dex, now);
+    }
+    assert_eq!(calls.load(Ordering::Relaxed), 4);
+    assert!(fetcher.is_healthy(feed));
+}
+
+#[test]
+fn test_circuit_breaker() {
+    let mut dex = Dex::new();
+    let feed = dex.register_provider("feed", 1);
+    let mock = MockProvider::new()
+        .then_rate('A', 'B', 2.0)
+        .then_fail(io::ErrorKind::TimedOut)
+        .then_fail(io::ErrorKind::TimedOut)
+        .then_rate('A', 'B', 2.0);
+    let mut fetcher = Fetcher::new()
+        .source(feed, mock.clone())
+        .with_circuit_breaker(
+            feed,
+            CircuitBreaker::new(2, Duration::from_secs(10)).with_expire(true),
+        );
+
+    let now = Instant::now();
+    assert_eq!(fetcher.poll(&mut dex, now), 1);
+    fetcher.poll(&mut dex, now);
+    assert!(fetcher.is_healthy(feed));
+    assert!(dex.get_best_rate(&'A'.into(), &'B'.into()).is_some());
+    fetcher.poll(&mut dex, now);
+    assert!(!fetcher.is_healthy(feed));
+    assert!(dex.get_best_rate(&'A'.into(), &'B'.into()).is_none());
+    assert_eq!(fetcher.next_poll(now), Duration::from_secs(10));
+
+    // Half-open after the cooldown.
+    assert_eq!(fetcher.poll(&mut dex, now + Duration::from_secs(5)), 0);
+    assert_eq!(mock.calls(), 3);
+    assert_eq!(fetcher.poll(&mut dex, now + Duration::from_secs(10)), 1);
+    assert!(fetcher.is_healthy(feed));
+    assert!(dex.get_best_rate(&'A'.into(), &'B'.into()).is_some());
+}
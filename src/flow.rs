@@ -0,0 +1,109 @@
+//! Capacity-aware routing
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path, Vertex};
+
+/// The maximum number of paths to split the amount across.
+const MAX_PATHS: usize = 64;
+
+/// The amount converted through the multiple paths.
+#[derive(Clone, Debug, Default)]
+pub struct Flow {
+    routes: Vec<(Path, f32)>,
+    amount_in: f32,
+    amount_out: f32,
+}
+
+impl Flow {
+    /// Returns the paths with the amount of the source currency
+    /// converted through each of them.
+    pub fn routes(&self) -> &[(Path, f32)] {
+        &self.routes
+    }
+
+    /// Returns the amount of the source currency converted.
+    pub fn amount_in(&self) -> f32 {
+        self.amount_in
+    }
+
+    /// Returns the amount of the destination currency received.
+    pub fn amount_out(&self) -> f32 {
+        self.amount_out
+    }
+
+    /// Returns the blended rate across all the paths.
+    pub fn rate(&self) -> Option<f32> {
+        if self.amount_in > 0.0 {
+            Some(self.amount_out / self.amount_in)
+        } else {
+            None
+        }
+    }
+}
+
+impl Dex {
+    /// Converts the `amount` of `src` into `dst` across possibly
+    /// multiple paths, constrained by the edge liquidity.
+    ///
+    /// It repeatedly sends as much as possible through the best rate
+    /// path with the remaining liquidity, until the whole amount is
+    /// converted or there is no path left.
+    #[instrument(level = "debug", skip(self))]
+    pub fn route_amount(&self, src: &Vertex, dst: &Vertex, amount: f32) -> Flow {
+        assert!(amount > 0.0);
+        let mut residual = Dex {
+            edges: self.edges.clone(),
+            ..Dex::default()
+        };
+        let mut flow = Flow::default();
+        while flow.routes.len() < MAX_PATHS {
+            let remaining = amount - flow.amount_in;
+            if remaining <= amount * f32::EPSILON {
+                break;
+            }
+            let path = match residual.get_best_rate(src, dst) {
+                Some(path) => path,
+                None => break,
+            };
+            let (sent, received) = residual.consume(&path, remaining);
+            debug!(%path, %sent, %received, "route");
+            flow.amount_in += sent;
+            flow.amount_out += received;
+            flow.routes.push((path, sent));
+        }
+        flow
+    }
+
+    // Consumes the liquidity along the path up to `amount`, and
+    // returns the amount actually sent and received.
+    fn consume(&mut self, path: &Path, amount: f32) -> (f32, f32) {
+        let hops = path.path.windows(2);
+        let mut sent = amount;
+        let mut rate = 1.0;
+        for hop in hops.clone() {
+            let edge = &self.edges[&hop[0]][&hop[1]];
+            if let Some(liquidity) = edge.liquidity {
+                sent = sent.min(liquidity / rate);
+            }
+            rate *= edge.effective_rate(None);
+        }
+        let mut amount = sent;
+        for hop in hops {
+            let edges = self.edges.get_mut(&hop[0]).unwrap();
+            let edge = edges.get_mut(&hop[1]).unwrap();
+            let received = edge.consume(amount);
+            if let Some(liquidity) = edge.liquidity {
+                if liquidity <= amount * f32::EPSILON {
+                    edges.remove(&hop[1]);
+                }
+            }
+            self.changes.push(hop[0], hop[1]);
+            amount = received;
+        }
+        (sent, amount)
+    }
+}
+
+#[cfg(test)]
+mod test;
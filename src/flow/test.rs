@@ -0,0 +1,43 @@
+use crate::{Dex, Edge};
+
+#[test]
+fn test_single_path() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+
+    let flow = dex.route_amount(&'A'.into(), &'B'.into(), 100.0);
+    assert_eq!(flow.routes().len(), 1);
+    assert_eq!(flow.amount_in(), 100.0);
+    assert_eq!(flow.amount_out(), 200.0);
+    assert_eq!(flow.rate(), Some(2.0));
+}
+
+#[test]
+fn test_split_paths() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_liquidity(100.0));
+    dex.add_edge('A', 'C', Edge::new(1.0).with_liquidity(1_000.0));
+    dex.add_edge('C', 'B', Edge::new(1.5).with_liquidity(150.0));
+
+    let flow = dex.route_amount(&'A'.into(), &'B'.into(), 150.0);
+    assert_eq!(flow.routes().len(), 2);
+    assert_eq!(flow.routes()[0].0.to_string(), "A -> B: 2");
+    assert_eq!(flow.routes()[0].1, 100.0);
+    assert_eq!(flow.routes()[1].0.to_string(), "A -> C -> B: 1.5");
+    assert_eq!(flow.routes()[1].1, 50.0);
+    assert_eq!(flow.amount_in(), 150.0);
+    assert_eq!(flow.amount_out(), 275.0);
+}
+
+#[test]
+fn test_partial_fill() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_liquidity(100.0));
+    dex.add_edge('A', 'C', Edge::new(1.0).with_liquidity(1_000.0));
+    dex.add_edge('C', 'B', Edge::new(1.5).with_liquidity(150.0));
+
+    let flow = dex.route_amount(&'A'.into(), &'B'.into(), 1_000.0);
+    assert_eq!(flow.amount_in(), 250.0);
+    assert_eq!(flow.amount_out(), 425.0);
+    assert_eq!(flow.rate(), Some(1.7));
+}
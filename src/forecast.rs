@@ -0,0 +1,73 @@
+//! Pluggable rate forecasts
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::time::Duration;
+
+use super::{Edge, Vertex};
+
+/// The short-horizon model of the rates, adjusting the rate of each hop
+/// before the routing, see [`QueryOptions::with_forecast`].
+///
+/// [`QueryOptions::with_forecast`]: crate::query::QueryOptions::with_forecast
+pub trait RateForecast: Debug + Send + Sync {
+    /// Returns the `src -> dst` rate expected after the `horizon`, the
+    /// execution latency of the path up to and including the `edge`,
+    /// from the current `rate` net of the fees.
+    fn forecast(
+        &self,
+        src: &Vertex,
+        dst: &Vertex,
+        edge: &Edge,
+        rate: f32,
+        horizon: Duration,
+    ) -> f32;
+}
+
+/// The constant relative drift per second of the pairs, e.g. of the
+/// trend.
+///
+/// The drift of the pair applies to the `src -> dst` direction, and
+/// the reverse direction drifts the other way.
+#[derive(Clone, Debug, Default)]
+pub struct Drift {
+    drifts: HashMap<(Vertex, Vertex), f32>,
+}
+
+impl Drift {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `src -> dst` drift per second, e.g. `0.0001` for the
+    /// rate 1 bp higher every second.
+    pub fn pair<V: Into<Vertex>>(mut self, src: V, dst: V, drift: f32) -> Self {
+        assert!(drift > -1.0);
+        self.drifts.insert((src.into(), dst.into()), drift);
+        self
+    }
+}
+
+impl RateForecast for Drift {
+    fn forecast(
+        &self,
+        src: &Vertex,
+        dst: &Vertex,
+        _edge: &Edge,
+        rate: f32,
+        horizon: Duration,
+    ) -> f32 {
+        let growth = match (
+            self.drifts.get(&(*src, *dst)),
+            self.drifts.get(&(*dst, *src)),
+        ) {
+            (Some(drift), _) => 1.0 + drift,
+            (None, Some(drift)) => 1.0 / (1.0 + drift),
+            (None, None) => return rate,
+        };
+        rate * growth.powf(horizon.as_secs_f32())
+    }
+}
+
+#[cfg(test)]
+mod test;
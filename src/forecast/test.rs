@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use super::{Drift, RateForecast};
+use crate::query::QueryOptions;
+use crate::{Dex, Edge, Vertex};
+
+// The forecast of the rates halved.
+#[derive(Debug)]
+struct Crash(Vertex);
+
+impl RateForecast for Crash {
+    fn forecast(&self, _: &Vertex, dst: &Vertex, _: &Edge, rate: f32, _: Duration) -> f32 {
+        if *dst == self.0 {
+            rate / 2.0
+        } else {
+            rate
+        }
+    }
+}
+
+// The invalid forecast.
+#[derive(Debug)]
+struct Broken;
+
+impl RateForecast for Broken {
+    fn forecast(&self, _: &Vertex, _: &Vertex, _: &Edge, _: f32, _: Duration) -> f32 {
+        f32::NAN
+    }
+}
+
+#[test]
+fn test_forecast() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'D', 1.0);
+    dex.add_rate('A', 'C', 1.5);
+    dex.add_rate('C', 'D', 1.0);
+    let (a, d) = ('A'.into(), 'D'.into());
+
+    let options = QueryOptions::new().with_forecast(Crash('B'.into()));
+    let path = dex.get_best_rate_with(&a, &d, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> D: 1.5");
+    let options = QueryOptions::new().with_forecast(Crash('C'.into()));
+    let path = dex.get_best_rate_with(&a, &d, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> D: 2");
+    let options = QueryOptions::new().with_forecast(Crash('D'.into()));
+    let path = dex.get_best_rate_with(&a, &d, &options).unwrap();
+    assert_eq!(path.rates(), &[2.0, 0.5]);
+    assert_eq!(path.fees(), 0.0);
+
+    let options = QueryOptions::new().with_forecast(Broken);
+    let path = dex.get_best_rate_with(&a, &d, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> D: 2");
+}
+
+#[test]
+fn test_drift() {
+    let mut dex = Dex::new();
+    dex.add_edge(
+        'A',
+        'B',
+        Edge::new(2.0).with_latency(Duration::from_secs(10)),
+    );
+    dex.add_edge(
+        'B',
+        'C',
+        Edge::new(1.0).with_latency(Duration::from_secs(10)),
+    );
+    let (a, c) = ('A'.into(), 'C'.into());
+
+    let drift = Drift::new().pair('A', 'B', 0.01).pair('C', 'B', 0.01);
+    let options = QueryOptions::new().with_forecast(drift);
+    let path = dex.get_best_rate_with(&a, &c, &options).unwrap();
+    // The first hop drifts over 10 seconds, and the second over 20.
+    let rates = path.rates();
+    assert!((rates[0] - 2.0 * 1.01f32.powi(10)).abs() < 1e-4);
+    assert!((rates[1] - 1.01f32.powi(-20)).abs() < 1e-4);
+}
@@ -0,0 +1,538 @@
+//! Index-based frozen graph
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::Range;
+use std::sync::Arc;
+use std::thread;
+
+use tracing::{debug, instrument, warn};
+
+use self::mapped::Column;
+use super::{Dex, Path, Vertex, RATE_EPSILON};
+use crate::edge::Edge;
+use crate::quote::Quote;
+
+/// The maximum number of the Bellman-Ford rounds for the potentials,
+/// beyond which the spanning tree potentials are taken instead.
+const MAX_ROUNDS: usize = 100;
+
+/// The read-only snapshot of [`Dex`] for the large graphs.
+///
+/// The vertices are indexed, and the edges are stored in the compressed
+/// sparse row format with the rates net of the percentage fee, and the
+/// rates before it for [`Path::fees`].  Each edge also has the reduced
+/// cost for the search, see [`FrozenDex::get_best_rate`].  The quotes
+/// of the paths are of the net rates, without the provenance.
+///
+/// The edges are shared, so that the clone is cheap and the searches
+/// can run across the threads, see [`FrozenDex::get_best_rates_from`],
+/// and also across the processes through the mapped file, see
+/// [`FrozenDex::open_mapped`].
+#[derive(Clone, Debug, Default)]
+pub struct FrozenDex {
+    vertices: Vec<Vertex>,
+    index: HashMap<Vertex, u32>,
+    edges: Arc<Edges>,
+    pool: Arc<delta::Pool>,
+}
+
+impl PartialEq for FrozenDex {
+    fn eq(&self, other: &Self) -> bool {
+        self.vertices == other.vertices && self.edges == other.edges
+    }
+}
+
+// The edges in the compressed sparse row format, the outgoing edges of
+// the vertex `u` at `offsets[u]..offsets[u + 1]`.
+#[derive(Debug, Default, PartialEq)]
+struct Edges {
+    offsets: Column<u32>,
+    targets: Column<u32>,
+    rates: Column<f32>,
+    gross_rates: Column<f32>,
+    costs: Column<f64>,
+    // Any cost is negative, only in case of the arbitrage.
+    negative: bool,
+}
+
+impl Edges {
+    fn range(&self, u: usize) -> Range<usize> {
+        self.offsets[u] as usize..self.offsets[u + 1] as usize
+    }
+
+    // Calculates the reduced costs with the shortest path potentials,
+    // for the non-negative reduced costs, or with the potentials along
+    // the breadth first spanning tree of each component in case of the
+    // arbitrage.
+    //
+    // Each hop costs the extra `RATE_EPSILON`, to prefer the shorter
+    // path over the rates equal within it.
+    fn costs(&self) -> Vec<f64> {
+        let n = self.offsets.len() - 1;
+        let (potentials, shortest) = match self.shortest_potentials() {
+            Some(potentials) => (potentials, true),
+            None => (self.tree_potentials(), false),
+        };
+        let mut costs = Vec::with_capacity(self.targets.len());
+        for u in 0..n {
+            for i in self.range(u) {
+                let v = self.targets[i] as usize;
+                let mut cost = potentials[u] - potentials[v] - f64::from(self.rates[i]).ln();
+                // Only negative within the rate epsilon.
+                if shortest {
+                    cost = cost.max(0.0);
+                }
+                costs.push(cost + f64::from(RATE_EPSILON));
+            }
+        }
+        costs
+    }
+
+    // The Bellman-Ford distances from the virtual source connected to
+    // all the vertices, or `None` in case of the arbitrage cycle, or of
+    // no convergence within `MAX_ROUNDS`.  The cycles within the rate
+    // epsilon are not considered the arbitrage.
+    fn shortest_potentials(&self) -> Option<Vec<f64>> {
+        let n = self.offsets.len() - 1;
+        let epsilon = f64::from(RATE_EPSILON);
+        let mut potentials = vec![0.0; n];
+        for _ in 0..MAX_ROUNDS.min(n + 1) {
+            let mut relaxed = false;
+            for u in 0..n {
+                for i in self.range(u) {
+                    let v = self.targets[i] as usize;
+                    let potential = potentials[u] - f64::from(self.rates[i]).ln();
+                    if potential < potentials[v] - epsilon {
+                        potentials[v] = potential;
+                        relaxed = true;
+                    }
+                }
+            }
+            if !relaxed {
+                return Some(potentials);
+            }
+        }
+        debug!("arbitrage cycle");
+        None
+    }
+
+    fn tree_potentials(&self) -> Vec<f64> {
+        let n = self.offsets.len() - 1;
+        let mut potentials = vec![f64::NAN; n];
+        let mut queue = VecDeque::new();
+        for root in 0..n {
+            if !potentials[root].is_nan() {
+                continue;
+            }
+            potentials[root] = 0.0;
+            queue.push_back(root);
+            while let Some(u) = queue.pop_front() {
+                for i in self.range(u) {
+                    let v = self.targets[i] as usize;
+                    if potentials[v].is_nan() {
+                        potentials[v] = potentials[u] - f64::from(self.rates[i]).ln();
+                        queue.push_back(v);
+                    }
+                }
+            }
+        }
+        potentials
+    }
+}
+
+// The indexed binary min-heap of the vertices by cost, with the
+// decrease-key operation to keep a single entry per vertex.
+#[derive(Debug)]
+struct Queue {
+    heap: Vec<(f64, u32)>,
+    positions: Vec<u32>,
+}
+
+impl Queue {
+    const NONE: u32 = u32::MAX;
+
+    fn new(n: usize) -> Self {
+        Self {
+            heap: Vec::with_capacity(n),
+            positions: vec![Self::NONE; n],
+        }
+    }
+
+    // Empties the heap, keeping the allocations.
+    fn clear(&mut self) {
+        for (_, vertex) in self.heap.drain(..) {
+            self.positions[vertex as usize] = Self::NONE;
+        }
+    }
+
+    // Pushes the vertex, or moves it up with the lower cost.
+    fn push(&mut self, vertex: u32, cost: f64) {
+        let i = match self.positions[vertex as usize] {
+            Self::NONE => {
+                self.heap.push((cost, vertex));
+                self.heap.len() - 1
+            }
+            i => {
+                self.heap[i as usize].0 = cost;
+                i as usize
+            }
+        };
+        self.sift_up(i);
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        let last = self.heap.pop()?;
+        let (_, vertex) = match self.heap.first_mut() {
+            Some(first) => std::mem::replace(first, last),
+            None => last,
+        };
+        self.positions[vertex as usize] = Self::NONE;
+        if !self.heap.is_empty() {
+            self.sift_down(0);
+        }
+        Some(vertex)
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        let entry = self.heap[i];
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if self.heap[parent].0 <= entry.0 {
+                break;
+            }
+            self.heap[i] = self.heap[parent];
+            self.positions[self.heap[i].1 as usize] = i as u32;
+            i = parent;
+        }
+        self.heap[i] = entry;
+        self.positions[entry.1 as usize] = i as u32;
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        let entry = self.heap[i];
+        loop {
+            let mut child = 2 * i + 1;
+            if child >= self.heap.len() {
+                break;
+            }
+            if child + 1 < self.heap.len() && self.heap[child + 1].0 < self.heap[child].0 {
+                child += 1;
+            }
+            if entry.0 <= self.heap[child].0 {
+                break;
+            }
+            self.heap[i] = self.heap[child];
+            self.positions[self.heap[i].1 as usize] = i as u32;
+            i = child;
+        }
+        self.heap[i] = entry;
+        self.positions[entry.1 as usize] = i as u32;
+    }
+}
+
+impl Dex {
+    /// Freezes the graph into the index-based read-only snapshot.
+    #[instrument(level = "debug", skip(self))]
+    pub fn freeze(&self) -> FrozenDex {
+        let vertices: Vec<_> = self.edges.keys().copied().collect();
+        let index: HashMap<_, _> = vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (*v, i as u32))
+            .collect();
+        let mut offsets = Vec::with_capacity(vertices.len() + 1);
+        let mut targets = Vec::new();
+        let mut rates = Vec::new();
+        let mut gross_rates = Vec::new();
+        offsets.push(0);
+        for edges in self.edges.values() {
+            for (dst, edge) in edges {
+                targets.push(index[dst]);
+                rates.push(edge.effective_rate(None));
+                gross_rates.push(edge.gross_rate(None));
+            }
+            offsets.push(targets.len() as u32);
+        }
+        debug!(vertices = vertices.len(), edges = targets.len(), "frozen");
+        let mut edges = Edges {
+            offsets: offsets.into(),
+            targets: targets.into(),
+            rates: rates.into(),
+            gross_rates: gross_rates.into(),
+            costs: Column::default(),
+            negative: false,
+        };
+        edges.costs = edges.costs().into();
+        edges.negative = edges.costs.iter().any(|cost| *cost < 0.0);
+        FrozenDex {
+            vertices,
+            index,
+            edges: Arc::new(edges),
+            pool: Arc::default(),
+        }
+    }
+}
+
+impl FrozenDex {
+    pub fn vertices(&self) -> impl Iterator<Item = &Vertex> {
+        self.vertices.iter()
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.targets.len()
+    }
+
+    /// Returns the [`Dex`] of the frozen edges, e.g. to serve the graph
+    /// frozen by another process.
+    ///
+    /// The edges are copied out of the mapping as the plain rates with
+    /// the percentage fee, without the provenance.
+    pub fn thaw(&self) -> Dex {
+        let mut dex = Dex::new();
+        for (u, src) in self.vertices.iter().enumerate() {
+            let edges = dex.edges.entry(*src).or_default();
+            for i in self.edges.range(u) {
+                let dst = self.vertices[self.edges.targets[i] as usize];
+                let (rate, gross_rate) = (self.edges.rates[i], self.edges.gross_rates[i]);
+                let fee = if gross_rate > 0.0 {
+                    (1.0 - rate / gross_rate).max(0.0)
+                } else {
+                    0.0
+                };
+                edges.insert(dst, Edge::new(gross_rate).with_fee(fee));
+            }
+        }
+        debug!(vertices = self.vertices.len(), "thawed");
+        dex
+    }
+
+    /// Returns the best rate path from `src` to `dst`.
+    ///
+    /// It's the Dijkstra search over the reduced costs.  The cost of
+    /// the edge is `-ln(rate)`, which is negative for the rates above
+    /// 1.0.  The reduced cost cancels the best rates out of it with the
+    /// shortest path potentials, and is never negative.  The result is
+    /// exact, up to [`RATE_EPSILON`] per hop, unless the graph has the
+    /// arbitrage.  In that case, the potentials are implied by the
+    /// spanning tree, and the result is within the mispricing against
+    /// it.
+    ///
+    /// As [`Dex::get_best_rate`], the path of the rate overflowing or
+    /// underflowing the `f32` is dropped without the error.
+    pub fn get_best_rate(&self, src: &Vertex, dst: &Vertex) -> Option<Path> {
+        self.searcher().get_best_rate(self, src, dst).cloned()
+    }
+
+    /// Returns the [`Searcher`] with the buffers sized for the graph.
+    pub fn searcher(&self) -> Searcher {
+        Searcher::new(self.vertices.len())
+    }
+
+    /// Returns the best rates from `src` to all the reachable vertices.
+    ///
+    /// The large graph is searched by the delta-stepping across all
+    /// the available cores, with the same result as the Dijkstra search
+    /// regardless of the cores.
+    pub fn get_best_rates_from(&self, src: &Vertex) -> BTreeMap<Vertex, f32> {
+        let src = match self.index.get(src) {
+            Some(src) => *src,
+            None => return BTreeMap::new(),
+        };
+        let threads = thread::available_parallelism().map_or(1, usize::from);
+        let labels = if self.edge_count() >= delta::MIN_EDGES {
+            self.delta_stepping(src, threads)
+        } else {
+            self.search(src, None)
+        };
+        labels
+            .iter()
+            .enumerate()
+            .filter(|(i, label)| *i as u32 != src && label.rate > 0.0)
+            .map(|(i, label)| (self.vertices[i], label.rate))
+            .collect()
+    }
+
+    fn search(&self, src: u32, dst: Option<u32>) -> Vec<Label> {
+        let mut searcher = self.searcher();
+        searcher.search(self, src, dst);
+        searcher.labels
+    }
+}
+
+/// The reusable search state of [`FrozenDex`], for the repeated
+/// queries without the heap allocation.
+///
+/// The buffers are sized for the graph on the first query, and only
+/// the vertices touched by the query are reset for the next one, so
+/// that the steady state queries allocate nothing.
+#[derive(Debug)]
+pub struct Searcher {
+    labels: Vec<Label>,
+    queue: Queue,
+    touched: Vec<u32>,
+    hops: Vec<u32>,
+    path: Option<Path>,
+}
+
+impl Searcher {
+    fn new(n: usize) -> Self {
+        Self {
+            labels: vec![Label::default(); n],
+            queue: Queue::new(n),
+            touched: Vec::with_capacity(n),
+            hops: Vec::with_capacity(n),
+            path: None,
+        }
+    }
+
+    /// Returns the best rate path from `src` to `dst` of the `dex`, as
+    /// [`FrozenDex::get_best_rate`].
+    ///
+    /// The path is borrowed from the searcher and overwritten by the
+    /// next query.
+    pub fn get_best_rate(&mut self, dex: &FrozenDex, src: &Vertex, dst: &Vertex) -> Option<&Path> {
+        let src = *dex.index.get(src)?;
+        let dst = *dex.index.get(dst)?;
+        if src == dst {
+            return None;
+        }
+        self.search(dex, src, Some(dst));
+        self.path(dex, src, dst)
+    }
+
+    // Each vertex is settled only once, which also keeps the paths
+    // simple.
+    fn search(&mut self, dex: &FrozenDex, src: u32, dst: Option<u32>) {
+        self.reset(dex.vertices.len());
+        let Self {
+            labels,
+            queue,
+            touched,
+            ..
+        } = self;
+        labels[src as usize] = Label {
+            cost: 0.0,
+            rate: 1.0,
+            parent: u32::MAX,
+            settled: false,
+        };
+        touched.push(src);
+        queue.push(src, 0.0);
+        while let Some(vertex) = queue.pop() {
+            let u = vertex as usize;
+            labels[u].settled = true;
+            if Some(vertex) == dst {
+                break;
+            }
+            let Label { cost, rate, .. } = labels[u];
+            for i in dex.edges.range(u) {
+                let v = dex.edges.targets[i];
+                let label = &mut labels[v as usize];
+                let cost = cost + dex.edges.costs[i];
+                if !label.settled && cost < label.cost {
+                    if overflows(rate, dex.edges.rates[i]) {
+                        warn!(vertex = %dex.vertices[v as usize], %rate, "path dropped");
+                        continue;
+                    }
+                    if label.cost == f64::INFINITY {
+                        touched.push(v);
+                    }
+                    *label = Label {
+                        cost,
+                        rate: rate * dex.edges.rates[i],
+                        parent: vertex,
+                        settled: false,
+                    };
+                    queue.push(v, cost);
+                }
+            }
+        }
+    }
+
+    // Resets the labels touched by the last query, or resizes the
+    // buffers for the other graph.
+    fn reset(&mut self, n: usize) {
+        if self.labels.len() != n {
+            *self = Self::new(n);
+            return;
+        }
+        self.queue.clear();
+        for v in self.touched.drain(..) {
+            self.labels[v as usize] = Label::default();
+        }
+    }
+
+    fn path(&mut self, dex: &FrozenDex, src: u32, dst: u32) -> Option<&Path> {
+        self.hops.clear();
+        self.hops.push(dst);
+        let mut v = dst;
+        while v != src {
+            v = self.labels[v as usize].parent;
+            if v == u32::MAX {
+                return None;
+            }
+            self.hops.push(v);
+        }
+        // The settled vertices are on the parent chain only once, and
+        // skip the duplicate check of `Path::insert`.
+        let n = dex.vertices.len();
+        let src = dex.vertices[src as usize];
+        let path = self.path.get_or_insert_with(|| Path {
+            path: Vec::with_capacity(n),
+            rates: Vec::with_capacity(n),
+            quotes: Vec::with_capacity(n),
+            ..Path::new(src)
+        });
+        path.reset(src);
+        for hop in self.hops.windows(2).rev() {
+            let (u, v) = (hop[1] as usize, hop[0]);
+            let range = dex.edges.range(u);
+            let i = range.start + dex.edges.targets[range].iter().position(|t| *t == v)?;
+            path.path.push(dex.vertices[v as usize]);
+            path.rates.push(dex.edges.rates[i]);
+            path.quotes.push(Quote::new(dex.edges.rates[i]));
+            path.rate *= dex.edges.rates[i];
+            path.gross_rate *= dex.edges.gross_rates[i];
+            path.value *= dex.edges.rates[i];
+        }
+        Some(path)
+    }
+}
+
+// Checks if the `rate` extended by the `hop` is infinite, NaN, or
+// underflowed to zero, as [`Path`] dropped by the search of the `Dex`.
+// The cost is `f64` and doesn't overflow with the rate.
+fn overflows(rate: f32, hop: f32) -> bool {
+    let next = rate * hop;
+    !next.is_finite() || (next == 0.0 && rate > 0.0 && hop > 0.0)
+}
+
+// The search state of each vertex.
+#[derive(Copy, Clone, Debug)]
+struct Label {
+    cost: f64,
+    rate: f32,
+    parent: u32,
+    settled: bool,
+}
+
+impl Default for Label {
+    fn default() -> Self {
+        Self {
+            cost: f64::INFINITY,
+            rate: 0.0,
+            parent: u32::MAX,
+            settled: false,
+        }
+    }
+}
+
+mod delta;
+mod mapped;
+
+#[cfg(test)]
+mod test;
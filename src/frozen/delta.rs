@@ -0,0 +1,245 @@
+//! Delta-stepping parallel search
+
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use tracing::{debug, instrument, warn};
+
+use super::{overflows, Edges, FrozenDex, Label};
+
+/// The minimum number of edges to search by the delta-stepping.
+pub(super) const MIN_EDGES: usize = 100_000;
+
+/// The minimum number of vertices to relax across the threads, below
+/// which the relaxation stays on the calling thread.
+const MIN_FRONTIER: usize = 256;
+
+// The vertex to relax, with its cost and rate.
+type Entry = (u32, f64, f32);
+
+// The relaxation request, the vertex with the new cost, rate and
+// parent.
+type Request = (u32, f64, f32, u32);
+
+// The light or the heavy edges of the chunk of the vertices to relax,
+// and where to send the requests back to, by the chunk index.
+struct Job {
+    edges: Arc<Edges>,
+    entries: Vec<Entry>,
+    light: bool,
+    delta: f64,
+    chunk: usize,
+    requests: Sender<(usize, Vec<Request>)>,
+}
+
+impl FrozenDex {
+    // The delta-stepping search.
+    //
+    // The vertices are bucketed by `cost / delta`.  The vertices of the
+    // current bucket are scanned by the light edges repeatedly until
+    // the bucket is empty, with the vertex improved within the bucket
+    // scanned again, and then by the heavy edges.  The vertices are
+    // settled only once their bucket is final, so that the labels are
+    // the same as the Dijkstra search.
+    //
+    // It needs the non-negative costs, and the graph of the arbitrage
+    // is searched by the Dijkstra search instead.  The requests are
+    // applied in the vertex order of the scan regardless of the
+    // `threads`, for the same result on any host.
+    #[instrument(level = "debug", skip(self))]
+    pub(super) fn delta_stepping(&self, src: u32, threads: usize) -> Vec<Label> {
+        if self.edges.negative {
+            return self.search(src, None);
+        }
+        let delta = self.edges.delta();
+        let n = self.vertices.len();
+        let mut labels = vec![Label::default(); n];
+        // The cost each vertex was last scanned at, to scan the vertex
+        // only once per improvement.
+        let mut scanned = vec![f64::INFINITY; n];
+        let mut buckets = vec![vec![src]];
+        labels[src as usize] = Label {
+            cost: 0.0,
+            rate: 1.0,
+            parent: u32::MAX,
+            settled: false,
+        };
+        let mut i = 0;
+        while i < buckets.len() {
+            let mut bucket = Vec::new();
+            while !buckets[i].is_empty() {
+                let mut frontier = Vec::new();
+                for v in buckets[i].split_off(0) {
+                    let label = labels[v as usize];
+                    let cost = &mut scanned[v as usize];
+                    if label.settled || label.cost >= *cost {
+                        continue;
+                    }
+                    if *cost == f64::INFINITY {
+                        bucket.push(v);
+                    }
+                    *cost = label.cost;
+                    frontier.push((v, label.cost, label.rate));
+                }
+                let requests = self
+                    .pool
+                    .relax(&self.edges, &frontier, true, delta, threads);
+                for request in requests {
+                    update(&mut labels, &mut buckets, request, delta);
+                }
+            }
+            // The heavy edges of the final labels of the bucket, to the
+            // later buckets only.
+            bucket.sort_unstable();
+            let frontier: Vec<_> = bucket
+                .iter()
+                .map(|v| (*v, labels[*v as usize].cost, labels[*v as usize].rate))
+                .collect();
+            let requests = self
+                .pool
+                .relax(&self.edges, &frontier, false, delta, threads);
+            for request in requests {
+                update(&mut labels, &mut buckets, request, delta);
+            }
+            for v in bucket {
+                labels[v as usize].settled = true;
+            }
+            i += 1;
+        }
+        debug!(buckets = buckets.len(), %delta, "searched");
+        labels
+    }
+}
+
+impl Edges {
+    // Returns the bucket width, the average absolute edge cost.
+    pub(super) fn delta(&self) -> f64 {
+        let sum: f64 = self.costs.iter().map(|cost| cost.abs()).sum();
+        (sum / self.costs.len().max(1) as f64).max(f64::MIN_POSITIVE)
+    }
+
+    // Returns the requests for the light, at most `delta`, or the heavy
+    // edges of the `entries`.
+    fn relax(&self, entries: &[Entry], light: bool, delta: f64) -> Vec<Request> {
+        let mut requests = Vec::new();
+        for &(u, cost, rate) in entries {
+            for i in self.range(u as usize) {
+                if (self.costs[i] <= delta) == light {
+                    let v = self.targets[i];
+                    if overflows(rate, self.rates[i]) {
+                        warn!(%v, %rate, "path dropped");
+                        continue;
+                    }
+                    requests.push((v, cost + self.costs[i], rate * self.rates[i], u));
+                }
+            }
+        }
+        requests
+    }
+}
+
+// Applies the request, in case it's better than the current label.
+fn update(labels: &mut [Label], buckets: &mut Vec<Vec<u32>>, request: Request, delta: f64) {
+    let (v, cost, rate, parent) = request;
+    let label = labels[v as usize];
+    if label.settled || cost >= label.cost {
+        return;
+    }
+    labels[v as usize] = Label {
+        cost,
+        rate,
+        parent,
+        settled: false,
+    };
+    let i = (cost / delta) as usize;
+    if buckets.len() <= i {
+        buckets.resize_with(i + 1, Vec::new);
+    }
+    buckets[i].push(v);
+}
+
+/// The threads scanning the edges for the relaxation requests, started
+/// on the first delta-stepping search of the graph, and shared by the
+/// searches until the graph is dropped.
+#[derive(Default)]
+pub(super) struct Pool {
+    jobs: Mutex<Option<(Sender<Job>, usize)>>,
+}
+
+impl fmt::Debug for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let threads = self.jobs.lock().unwrap().as_ref().map(|(_, n)| *n);
+        f.debug_struct("Pool").field("threads", &threads).finish()
+    }
+}
+
+impl Pool {
+    // Returns the job queue, starting the `threads` on the first call.
+    fn jobs(&self, threads: usize) -> (Sender<Job>, usize) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let (tx, threads) = jobs.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::channel::<Job>();
+            let rx = Arc::new(Mutex::new(rx));
+            for _ in 0..threads {
+                let rx = Arc::clone(&rx);
+                thread::spawn(move || work(&rx));
+            }
+            debug!(threads, "started");
+            (tx, threads)
+        });
+        (tx.clone(), *threads)
+    }
+
+    // Relaxes the light or the heavy edges of the `entries`, split
+    // across the threads unless it's small, and returns the requests in
+    // the order of the `entries`.
+    fn relax(
+        &self,
+        edges: &Arc<Edges>,
+        entries: &[Entry],
+        light: bool,
+        delta: f64,
+        threads: usize,
+    ) -> Vec<Request> {
+        if threads < 2 || entries.len() < MIN_FRONTIER {
+            return edges.relax(entries, light, delta);
+        }
+        let (jobs, threads) = self.jobs(threads);
+        let (tx, rx) = mpsc::channel();
+        let size = entries.len() / threads + 1;
+        let mut chunks = 0;
+        for (chunk, entries) in entries.chunks(size).enumerate() {
+            let job = Job {
+                edges: Arc::clone(edges),
+                entries: entries.to_vec(),
+                light,
+                delta,
+                chunk,
+                requests: tx.clone(),
+            };
+            jobs.send(job).expect("worker thread is alive");
+            chunks += 1;
+        }
+        let mut results = vec![Vec::new(); chunks];
+        for _ in 0..chunks {
+            let (chunk, requests) = rx.recv().expect("worker thread is alive");
+            results[chunk] = requests;
+        }
+        results.concat()
+    }
+}
+
+// Runs the jobs until the pool is dropped.
+fn work(jobs: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = match jobs.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => break,
+        };
+        let requests = job.edges.relax(&job.entries, job.light, job.delta);
+        // The search is gone in case of the send error.
+        let _ = job.requests.send((job.chunk, requests));
+    }
+}
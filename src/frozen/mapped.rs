@@ -0,0 +1,431 @@
+//! Memory-mapped frozen graph file
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::Arc;
+use std::{fmt, mem};
+
+use tracing::{debug, instrument};
+
+use super::{Edges, FrozenDex};
+use crate::Vertex;
+
+/// The magic bytes of the frozen graph file.
+const MAGIC: &[u8; 8] = b"BRFROZEN";
+
+/// The format version of the frozen graph file.
+const VERSION: u32 = 1;
+
+/// The length of the header, the magic, the version, the vertex count,
+/// the edge count and the vertex names length.
+const HEADER_LEN: usize = 32;
+
+/// The alignment of the columns in the file.
+const ALIGN: usize = 8;
+
+// The plain little endian numbers of the columns, valid for any bit
+// pattern.
+pub(super) trait Plain: Copy {
+    fn write_le<W: Write>(self, writer: &mut W) -> io::Result<()>;
+}
+
+impl Plain for u32 {
+    fn write_le<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+impl Plain for f32 {
+    fn write_le<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+impl Plain for f64 {
+    fn write_le<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_le_bytes())
+    }
+}
+
+// The column of the edges, either owned or in the file mapped
+// read-only.
+pub(super) enum Column<T> {
+    Owned(Vec<T>),
+    Mapped {
+        map: Arc<Map>,
+        offset: usize,
+        len: usize,
+        _marker: PhantomData<T>,
+    },
+}
+
+impl<T> Column<T> {
+    fn mapped(map: &Arc<Map>, offset: usize, len: usize) -> Self {
+        Self::Mapped {
+            map: map.clone(),
+            offset,
+            len,
+            _marker: PhantomData,
+        }
+    }
+
+    fn is_mapped(&self) -> bool {
+        matches!(self, Self::Mapped { .. })
+    }
+}
+
+impl<T> Default for Column<T> {
+    fn default() -> Self {
+        Self::Owned(Vec::new())
+    }
+}
+
+impl<T> From<Vec<T>> for Column<T> {
+    fn from(values: Vec<T>) -> Self {
+        Self::Owned(values)
+    }
+}
+
+impl<T: Plain> Deref for Column<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Self::Owned(values) => values,
+            // SAFETY: the column is within the map and aligned for `T`,
+            // as checked by `FrozenDex::open_mapped`, and any bit
+            // pattern is valid for `T`.
+            Self::Mapped {
+                map, offset, len, ..
+            } => unsafe { std::slice::from_raw_parts(map.ptr.add(*offset).cast(), *len) },
+        }
+    }
+}
+
+impl<T: Plain + fmt::Debug> fmt::Debug for Column<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: Plain + PartialEq> PartialEq for Column<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+// The file mapped read-only, or read into the memory where the mapping
+// isn't supported.
+pub(super) struct Map {
+    ptr: *const u8,
+    len: usize,
+    #[cfg(not(all(unix, target_pointer_width = "64")))]
+    _buf: Vec<u64>,
+}
+
+// SAFETY: the map is read-only.
+unsafe impl Send for Map {}
+unsafe impl Sync for Map {}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Map").field("len", &self.len).finish()
+    }
+}
+
+impl Map {
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: the map is `len` bytes long, and lives as long as
+        // `self`.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+impl Map {
+    fn new(file: &File, len: usize) -> io::Result<Self> {
+        use std::os::raw::{c_int, c_void};
+        use std::os::unix::io::AsRawFd;
+
+        const PROT_READ: c_int = 1;
+        const MAP_SHARED: c_int = 1;
+
+        extern "C" {
+            fn mmap(
+                addr: *mut c_void,
+                len: usize,
+                prot: c_int,
+                flags: c_int,
+                fd: c_int,
+                offset: i64,
+            ) -> *mut c_void;
+        }
+
+        // SAFETY: the file is mapped read-only, and unmapped on drop.
+        let ptr = unsafe {
+            mmap(
+                std::ptr::null_mut(),
+                len,
+                PROT_READ,
+                MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        // MAP_FAILED.
+        if ptr as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            ptr: ptr.cast(),
+            len,
+        })
+    }
+}
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+impl Drop for Map {
+    fn drop(&mut self) {
+        use std::os::raw::{c_int, c_void};
+
+        extern "C" {
+            fn munmap(addr: *mut c_void, len: usize) -> c_int;
+        }
+
+        // SAFETY: the map is no longer referred by any column.
+        unsafe {
+            munmap(self.ptr as *mut c_void, self.len);
+        }
+    }
+}
+
+#[cfg(not(all(unix, target_pointer_width = "64")))]
+impl Map {
+    fn new(mut file: &File, len: usize) -> io::Result<Self> {
+        use std::io::Read;
+
+        // The words for the alignment of the columns.
+        let mut buf = vec![0u64; align(len) / ALIGN];
+        // SAFETY: the buffer is at least `len` bytes long.
+        let bytes = unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), len) };
+        file.read_exact(bytes)?;
+        Ok(Self {
+            ptr: buf.as_ptr().cast(),
+            len,
+            _buf: buf,
+        })
+    }
+}
+
+// The layout of the file, the byte offsets of the columns.
+#[derive(Debug)]
+struct Layout {
+    offsets: usize,
+    targets: usize,
+    rates: usize,
+    gross_rates: usize,
+    costs: usize,
+    len: usize,
+}
+
+impl Layout {
+    fn new(vertices: usize, edges: usize, names: usize) -> Self {
+        let offsets = align(HEADER_LEN + names);
+        let targets = offsets + (vertices + 1) * mem::size_of::<u32>();
+        let rates = targets + edges * mem::size_of::<u32>();
+        let gross_rates = rates + edges * mem::size_of::<f32>();
+        let costs = align(gross_rates + edges * mem::size_of::<f32>());
+        let len = costs + edges * mem::size_of::<f64>();
+        Self {
+            offsets,
+            targets,
+            rates,
+            gross_rates,
+            costs,
+            len,
+        }
+    }
+}
+
+fn align(offset: usize) -> usize {
+    (offset + ALIGN - 1) & !(ALIGN - 1)
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl FrozenDex {
+    /// Writes the graph in the file format mapped by
+    /// [`FrozenDex::open_mapped`].
+    ///
+    /// The file has the vertex names, followed by the edges in the
+    /// compressed sparse row format as is, in the little endian and
+    /// aligned for the direct use out of the mapping.
+    #[instrument(level = "debug", skip_all, err)]
+    pub fn write_mapped<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        let mut names = Vec::new();
+        for v in &self.vertices {
+            let name = v.to_string();
+            names.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            names.extend_from_slice(name.as_bytes());
+        }
+        let edges = &self.edges;
+        let layout = Layout::new(self.vertices.len(), edges.targets.len(), names.len());
+        let vertices = u32::try_from(self.vertices.len())
+            .map_err(|_| invalid(format!("too many vertices {}", self.vertices.len())))?;
+
+        writer.write_all(MAGIC)?;
+        VERSION.write_le(&mut writer)?;
+        vertices.write_le(&mut writer)?;
+        writer.write_all(&(edges.targets.len() as u64).to_le_bytes())?;
+        writer.write_all(&(names.len() as u64).to_le_bytes())?;
+        writer.write_all(&names)?;
+        writer.write_all(&[0; ALIGN][..layout.offsets - HEADER_LEN - names.len()])?;
+        for offset in edges.offsets.iter() {
+            offset.write_le(&mut writer)?;
+        }
+        for target in edges.targets.iter() {
+            target.write_le(&mut writer)?;
+        }
+        for rate in edges.rates.iter() {
+            rate.write_le(&mut writer)?;
+        }
+        for rate in edges.gross_rates.iter() {
+            rate.write_le(&mut writer)?;
+        }
+        let end = layout.gross_rates + edges.gross_rates.len() * mem::size_of::<f32>();
+        writer.write_all(&[0; ALIGN][..layout.costs - end])?;
+        for cost in edges.costs.iter() {
+            cost.write_le(&mut writer)?;
+        }
+        writer.flush()?;
+        debug!(len = layout.len, "written");
+        Ok(())
+    }
+
+    /// Writes the graph file for [`FrozenDex::open_mapped`] to `path`.
+    ///
+    /// The file is written aside and renamed over `path`, so that the
+    /// processes which mapped the previous one keep it intact until
+    /// they open the new one.
+    pub fn save_mapped(&self, path: &Path) -> io::Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        self.write_mapped(File::create(&tmp)?)?;
+        fs::rename(&tmp, path)
+    }
+
+    /// Opens the graph file written by [`FrozenDex::save_mapped`],
+    /// mapped read-only into the memory.
+    ///
+    /// The edges are searched right out of the mapping, so that the
+    /// processes opening the same file share a single copy of them in
+    /// the page cache, and only the vertex index is built per process.
+    /// The file is read into the memory instead on the platforms
+    /// without the mapping.
+    ///
+    /// The file must not be modified while mapped, but replaced by
+    /// [`FrozenDex::save_mapped`].
+    #[instrument(level = "debug", err)]
+    pub fn open_mapped(path: &Path) -> io::Result<Self> {
+        if cfg!(target_endian = "big") {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "frozen graph file is little endian only",
+            ));
+        }
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| invalid("frozen graph file too large".to_string()))?;
+        if len < HEADER_LEN {
+            return Err(invalid("not a best-rate frozen graph file".to_string()));
+        }
+        let map = Arc::new(Map::new(&file, len)?);
+        let bytes = map.bytes();
+        if &bytes[..MAGIC.len()] != MAGIC {
+            return Err(invalid("not a best-rate frozen graph file".to_string()));
+        }
+        let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let long = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        let version = word(8);
+        if version != VERSION {
+            return Err(invalid(format!(
+                "frozen graph file version {version} is not the supported {VERSION}, \
+                 freeze the graph again"
+            )));
+        }
+        let n = word(12) as usize;
+        let (m, names) = match (usize::try_from(long(16)), usize::try_from(long(24))) {
+            (Ok(m), Ok(names)) if m <= len && names <= len => (m, names),
+            _ => return Err(invalid("frozen graph file truncated".to_string())),
+        };
+        let layout = Layout::new(n, m, names);
+        if layout.len != len {
+            return Err(invalid(format!(
+                "frozen graph file of {len} bytes, expected {}",
+                layout.len
+            )));
+        }
+
+        let mut vertices = Vec::with_capacity(n);
+        let mut names = &bytes[HEADER_LEN..HEADER_LEN + names];
+        for i in 0..n {
+            let len = names
+                .get(..4)
+                .map(|len| 4 + u32::from_le_bytes(len.try_into().unwrap()) as usize);
+            let v = len
+                .and_then(|len| names.get(4..len))
+                .and_then(|name| std::str::from_utf8(name).ok())
+                .and_then(|name| name.parse::<Vertex>().ok());
+            match (v, len) {
+                (Some(v), Some(len)) => {
+                    vertices.push(v);
+                    names = &names[len..];
+                }
+                _ => return Err(invalid(format!("invalid vertex {i}"))),
+            }
+        }
+        let index: HashMap<_, _> = vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (*v, i as u32))
+            .collect();
+
+        let mut edges = Edges {
+            offsets: Column::mapped(&map, layout.offsets, n + 1),
+            targets: Column::mapped(&map, layout.targets, m),
+            rates: Column::mapped(&map, layout.rates, m),
+            gross_rates: Column::mapped(&map, layout.gross_rates, m),
+            costs: Column::mapped(&map, layout.costs, m),
+            negative: false,
+        };
+        edges.negative = edges.costs.iter().any(|cost| *cost < 0.0);
+        let offsets = &edges.offsets;
+        if offsets[0] != 0
+            || offsets[n] as usize != m
+            || offsets.windows(2).any(|w| w[0] > w[1])
+            || edges.targets.iter().any(|v| *v as usize >= n)
+        {
+            return Err(invalid("invalid frozen graph edges".to_string()));
+        }
+        debug!(vertices = n, edges = m, "mapped");
+        Ok(Self {
+            vertices,
+            index,
+            edges: Arc::new(edges),
+            pool: Arc::default(),
+        })
+    }
+
+    /// Checks if the edges are in the mapped file, see
+    /// [`FrozenDex::open_mapped`].
+    pub fn is_mapped(&self) -> bool {
+        self.edges.targets.is_mapped()
+    }
+}
@@ -0,0 +1,240 @@
+use crate::query::QueryOptions;
+use crate::rng::Rng;
+use crate::{Dex, Edge};
+
+use super::FrozenDex;
+use crate::test::vertex;
+
+#[test]
+fn test_frozen() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1.4);
+    dex.add_rate('A', 'C', 0.1);
+    dex.add_rate('A', 'D', 0.055);
+    dex.add_rate('B', 'C', 0.2);
+    dex.add_rate('C', 'D', 0.2);
+    dex.add_rate('D', 'F', 2.5);
+
+    let frozen = dex.freeze();
+    assert_eq!(frozen.vertex_count(), 5);
+    assert_eq!(frozen.edge_count(), 12);
+    let path = frozen.get_best_rate(&'A'.into(), &'D'.into()).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D: 0.056");
+    assert!(frozen.get_best_rate(&'A'.into(), &'Z'.into()).is_none());
+}
+
+#[test]
+fn test_frozen_arbitrage() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 0.2);
+    dex.add_rate('C', 'D', 1.0);
+
+    let frozen = dex.freeze();
+    let path = frozen.get_best_rate(&'A'.into(), &'D'.into()).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D: 6");
+    let path = frozen.get_best_rate(&'C'.into(), &'B'.into()).unwrap();
+    assert_eq!(path.to_string(), "C -> A -> B: 0.4");
+}
+
+#[test]
+fn test_frozen_fees() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_fee(0.01));
+    dex.add_edge('B', 'C', Edge::new(3.0).with_fee(0.005));
+
+    let frozen = dex.freeze();
+    for dst in ['B', 'C'] {
+        let (src, dst) = ('A'.into(), dst.into());
+        let path = dex.get_best_rate(&src, &dst).unwrap();
+        let frozen = frozen.get_best_rate(&src, &dst).unwrap();
+        assert_eq!(frozen.rate(), path.rate());
+        assert!((frozen.fees() - path.fees()).abs() < 1e-6);
+    }
+    let path = frozen.get_best_rate(&'A'.into(), &'B'.into()).unwrap();
+    assert!((path.fees() - 0.01).abs() < 1e-6);
+
+    let thawed = frozen.thaw();
+    assert_eq!(thawed.freeze(), frozen);
+    let path = thawed.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    let fees = dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap().fees();
+    assert!((path.fees() - fees).abs() < 1e-6);
+}
+
+#[test]
+fn test_frozen_matches_dex() {
+    let dex = Dex::generate(20, 40, 0.0, &mut Rng::new(3));
+    let frozen = dex.freeze();
+    let src = vertex("V0");
+    let paths = dex.get_best_rates_from(&src, &QueryOptions::new());
+    let rates = frozen.get_best_rates_from(&src);
+    assert_eq!(rates.len(), paths.len());
+    for (dst, path) in paths {
+        assert!((rates[&dst] / path.rate() - 1.0).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn test_frozen_with_fees() {
+    let mut dex = Dex::generate(200, 800, 0.0, &mut Rng::new(5));
+    let mut rng = Rng::new(9);
+    for edges in dex.edges.values_mut() {
+        for edge in edges.values_mut() {
+            edge.fee = 0.0005 * (1 + rng.below(20)) as f32;
+        }
+    }
+    let frozen = dex.freeze();
+    for src in ["V0", "V1", "V199"] {
+        let src = vertex(src);
+        let paths = dex.get_best_rates_from(&src, &QueryOptions::new());
+        let rates = frozen.get_best_rates_from(&src);
+        assert_eq!(rates.len(), paths.len());
+        for (dst, path) in paths {
+            assert!((rates[&dst] / path.rate() - 1.0).abs() < 1e-4);
+        }
+    }
+}
+
+#[test]
+fn test_mapped() {
+    let dex = Dex::generate(200, 800, 0.0, &mut Rng::new(5));
+    let frozen = dex.freeze();
+    let path = std::env::temp_dir().join(format!("best-rate-mapped-{}", std::process::id()));
+    frozen.save_mapped(&path).unwrap();
+    let mapped = FrozenDex::open_mapped(&path).unwrap();
+    // The other process mapping the same file.
+    let other = FrozenDex::open_mapped(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(mapped.is_mapped());
+    assert!(!frozen.is_mapped());
+    assert_eq!(mapped, frozen);
+    let src = vertex("V0");
+    assert_eq!(
+        mapped.get_best_rates_from(&src),
+        frozen.get_best_rates_from(&src)
+    );
+    let dst = vertex("V7");
+    assert_eq!(
+        other.get_best_rate(&src, &dst),
+        frozen.get_best_rate(&src, &dst)
+    );
+
+    // The empty graph.
+    let empty = Dex::new().freeze();
+    let mut file = Vec::new();
+    empty.write_mapped(&mut file).unwrap();
+    std::fs::write(&path, &file).unwrap();
+    let mapped = FrozenDex::open_mapped(&path).unwrap();
+    assert_eq!(mapped, empty);
+
+    // The invalid files.
+    let open = |bytes: &[u8]| {
+        std::fs::write(&path, bytes).unwrap();
+        FrozenDex::open_mapped(&path).unwrap_err().to_string()
+    };
+    let mut file = Vec::new();
+    frozen.write_mapped(&mut file).unwrap();
+    assert_eq!(open(b"best-rate"), "not a best-rate frozen graph file");
+    assert_eq!(
+        open(&file[..file.len() - 8]),
+        format!(
+            "frozen graph file of {} bytes, expected {}",
+            file.len() - 8,
+            file.len()
+        )
+    );
+    let mut version = file.clone();
+    version[8] = 2;
+    assert_eq!(
+        open(&version),
+        "frozen graph file version 2 is not the supported 1, freeze the graph again"
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_frozen_overflow() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1e-30);
+    dex.add_rate('B', 'C', 1e-30);
+    // Directed only, for the non-negative costs of the delta-stepping.
+    for (src, dst) in [('B', 'A'), ('C', 'B')] {
+        let edges = dex.edges.get_mut(&src.into()).unwrap();
+        edges.remove(&dst.into());
+    }
+    let (a, c) = ('A'.into(), 'C'.into());
+    assert!(dex.get_best_rate(&a, &c).is_none());
+
+    let frozen = dex.freeze();
+    assert!(!frozen.edges.negative);
+    assert!(frozen.get_best_rate(&a, &c).is_none());
+    let rates = frozen.get_best_rates_from(&a);
+    assert_eq!(rates.keys().collect::<Vec<_>>(), [&'B'.into()]);
+    let delta = frozen.delta_stepping(frozen.index[&a], 1);
+    assert_eq!(delta[frozen.index[&c] as usize].cost, f64::INFINITY);
+}
+
+#[test]
+fn test_delta_stepping() {
+    let mut dex = Dex::generate(3_000, 12_000, 0.0, &mut Rng::new(7));
+    let mut rng = Rng::new(9);
+    for edges in dex.edges.values_mut() {
+        for edge in edges.values_mut() {
+            edge.fee = 0.0005 * (1 + rng.below(20)) as f32;
+        }
+    }
+    let frozen = dex.freeze();
+    assert!(!frozen.edges.negative);
+    for src in ["V0", "V1", "V2999"] {
+        let src = frozen.index[&vertex(src)];
+        let dijkstra = frozen.search(src, None);
+        for threads in [1, 2, 4] {
+            let delta = frozen.delta_stepping(src, threads);
+            for (a, b) in dijkstra.iter().zip(&delta) {
+                assert_eq!((a.cost, a.rate, a.parent), (b.cost, b.rate, b.parent));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_delta_stepping_arbitrage() {
+    let dex = Dex::generate(2_000, 8_000, 0.01, &mut Rng::new(7));
+    let frozen = dex.freeze();
+    assert!(frozen.edges.negative);
+    let src = frozen.index[&vertex("V0")];
+    let dijkstra = frozen.search(src, None);
+    let delta = frozen.delta_stepping(src, 4);
+    for (a, b) in dijkstra.iter().zip(&delta) {
+        assert_eq!((a.cost, a.rate, a.parent), (b.cost, b.rate, b.parent));
+    }
+}
+
+#[test]
+fn test_searcher() {
+    let dex = Dex::generate(200, 800, 0.01, &mut Rng::new(11));
+    let frozen = dex.freeze();
+    let vertices: Vec<_> = frozen.vertices().copied().collect();
+    let mut searcher = frozen.searcher();
+    for src in &vertices[..10] {
+        for dst in &vertices[..10] {
+            let path = frozen.get_best_rate(src, dst);
+            assert_eq!(searcher.get_best_rate(&frozen, src, dst), path.as_ref());
+            if let Some(path) = path {
+                assert_eq!(
+                    path.to_string(),
+                    searcher
+                        .get_best_rate(&frozen, src, dst)
+                        .unwrap()
+                        .to_string()
+                );
+            }
+        }
+    }
+    // The other graph resizes the buffers.
+    let mut small = Dex::new();
+    small.add_rate('A', 'B', 2.0);
+    let path = searcher.get_best_rate(&small.freeze(), &'A'.into(), &'B'.into());
+    assert_eq!(path.unwrap().to_string(), "A -> B: 2");
+}
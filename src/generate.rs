@@ -0,0 +1,68 @@
+//! Synthetic graph generator
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Edge, Vertex};
+use crate::rng::Rng;
+
+impl Dex {
+    /// Generates the random connected graph with the `vertices`, named
+    /// `V0`, `V1`, and so on, and about the `edges` pairs.
+    ///
+    /// The vertices have the log-normal prices, and the rate of each
+    /// pair is the price ratio with the log-normal `noise`.
+    #[instrument(level = "debug", skip(rng))]
+    pub fn generate(vertices: usize, edges: usize, noise: f64, rng: &mut Rng) -> Self {
+        assert!(vertices >= 2 && noise >= 0.0);
+        let prices: Vec<f64> = (0..vertices).map(|_| rng.normal().exp()).collect();
+        let mut dex = Self::new();
+        let add = |dex: &mut Self, src: usize, dst: usize, rng: &mut Rng| {
+            let rate = prices[src] / prices[dst] * (noise * rng.normal()).exp();
+            dex.insert_edge(vertex(src), vertex(dst), Edge::new(rate as f32));
+        };
+        // The random spanning tree first to keep the graph connected.
+        for dst in 1..vertices {
+            let src = rng.below(dst);
+            add(&mut dex, src, dst, rng);
+        }
+        for _ in vertices - 1..edges {
+            let src = rng.below(vertices);
+            let dst = rng.below(vertices);
+            if src != dst {
+                add(&mut dex, src, dst, rng);
+            }
+        }
+        debug!(vertices = dex.edges.len(), "generated");
+        dex
+    }
+
+    /// Returns the copy with each rate perturbed by the log-normal
+    /// `volatility`, e.g. for the Monte Carlo simulations.
+    ///
+    /// Both directions of the pair move together.
+    pub fn perturb(&self, volatility: f64, rng: &mut Rng) -> Self {
+        let mut dex = self.clone();
+        let mut shocks = Vec::new();
+        for (src, edges) in &self.edges {
+            for dst in edges.keys().filter(|dst| src < *dst) {
+                shocks.push((*src, *dst, (volatility * rng.normal()).exp() as f32));
+            }
+        }
+        for (src, dst, shock) in shocks {
+            if let Some(edge) = dex.edges.get_mut(&src).and_then(|e| e.get_mut(&dst)) {
+                edge.rate *= shock;
+            }
+            if let Some(edge) = dex.edges.get_mut(&dst).and_then(|e| e.get_mut(&src)) {
+                edge.rate /= shock;
+            }
+        }
+        dex
+    }
+}
+
+fn vertex(i: usize) -> Vertex {
+    format!("V{i}").parse().expect("short vertex symbol")
+}
+
+#[cfg(test)]
+mod test;
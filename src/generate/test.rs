@@ -0,0 +1,26 @@
+use crate::query::QueryOptions;
+use crate::rng::Rng;
+use crate::test::vertex;
+use crate::Dex;
+
+#[test]
+fn test_generate_seeded() {
+    let dex = Dex::generate(50, 200, 0.01, &mut Rng::new(1));
+    assert_eq!(dex.vertices().count(), 50);
+    assert_eq!(dex, Dex::generate(50, 200, 0.01, &mut Rng::new(1)));
+    assert_ne!(dex, Dex::generate(50, 200, 0.01, &mut Rng::new(2)));
+
+    // Connected.
+    let paths = dex.get_best_rates_from(&vertex("V0"), &QueryOptions::new());
+    assert_eq!(paths.len(), 49);
+}
+
+#[test]
+fn test_perturb_seeded() {
+    let dex = Dex::generate(10, 20, 0.0, &mut Rng::new(1));
+    let a = dex.perturb(0.05, &mut Rng::new(7));
+    let b = dex.perturb(0.05, &mut Rng::new(7));
+    assert_eq!(a, b);
+    assert_ne!(a, dex);
+    assert!(dex.approx_eq(&a, 0.5));
+}
@@ -0,0 +1,147 @@
+//! Indexed graph interoperability
+
+use std::collections::HashMap;
+
+#[cfg(feature = "petgraph")]
+use petgraph::graph::NodeIndex;
+#[cfg(feature = "petgraph")]
+use petgraph::Graph;
+use tracing::{debug, instrument};
+
+use super::{Dex, Edge, Vertex};
+
+/// The node and the edge lists of the graph, with the edges indexing
+/// into the nodes.
+///
+/// It's the layout of the `petgraph::Graph`, so that the graph
+/// algorithm crates run on the same data.  The `petgraph` feature
+/// converts the [`Dex`] from and into the `petgraph::Graph<Vertex,
+/// Edge>` through it, e.g.
+///
+/// ```text
+/// let graph = petgraph::Graph::from(&dex);
+/// let sccs = petgraph::algo::kosaraju_scc(&graph);
+/// ```
+///
+/// The nodes are in the vertex order, and the edges are directed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IndexedGraph {
+    nodes: Vec<Vertex>,
+    edges: Vec<(u32, u32, Edge)>,
+    // The index of the first node of each vertex.
+    index: HashMap<Vertex, u32>,
+}
+
+impl IndexedGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the node, and returns its index.
+    pub fn add_node(&mut self, v: Vertex) -> u32 {
+        let i = self.nodes.len() as u32;
+        self.nodes.push(v);
+        self.index.entry(v).or_insert(i);
+        i
+    }
+
+    /// Adds the directed `src -> dst` edge of the node indices.
+    pub fn add_edge(&mut self, src: u32, dst: u32, edge: Edge) {
+        assert!((src as usize) < self.nodes.len() && (dst as usize) < self.nodes.len());
+        self.edges.push((src, dst, edge));
+    }
+
+    pub fn nodes(&self) -> &[Vertex] {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> &[(u32, u32, Edge)] {
+        &self.edges
+    }
+
+    /// Returns the index of the node.
+    pub fn index(&self, v: &Vertex) -> Option<u32> {
+        self.index.get(v).copied()
+    }
+}
+
+impl From<&Dex> for IndexedGraph {
+    #[instrument(level = "debug", skip_all)]
+    fn from(dex: &Dex) -> Self {
+        let mut graph = Self::new();
+        for v in dex.edges.keys() {
+            graph.add_node(*v);
+        }
+        for (src, edges) in dex.edges.values().enumerate() {
+            for (dst, edge) in edges {
+                let dst = graph.index[dst];
+                graph.add_edge(src as u32, dst, edge.clone());
+            }
+        }
+        debug!(
+            nodes = graph.nodes.len(),
+            edges = graph.edges.len(),
+            "indexed"
+        );
+        graph
+    }
+}
+
+/// Loads the graph as is, the directed edges without the reverse ones
+/// and without the outlier guard, e.g. back from the graph algorithm
+/// crates.  The self loop and the zero rate edges are skipped.
+impl From<&IndexedGraph> for Dex {
+    fn from(graph: &IndexedGraph) -> Self {
+        let mut dex = Dex::new();
+        for v in &graph.nodes {
+            dex.edges.entry(*v).or_default();
+        }
+        for (src, dst, edge) in &graph.edges {
+            let (src, dst) = (graph.nodes[*src as usize], graph.nodes[*dst as usize]);
+            if src == dst || edge.rate() == 0.0 {
+                continue;
+            }
+            dex.edges.entry(src).or_default().insert(dst, edge.clone());
+        }
+        dex
+    }
+}
+
+/// The directed graph of the [`IndexedGraph`] layout.
+#[cfg(feature = "petgraph")]
+impl From<&Dex> for Graph<Vertex, Edge> {
+    fn from(dex: &Dex) -> Self {
+        let indexed = IndexedGraph::from(dex);
+        let mut graph = Graph::with_capacity(indexed.nodes.len(), indexed.edges.len());
+        for v in indexed.nodes {
+            graph.add_node(v);
+        }
+        for (src, dst, edge) in indexed.edges {
+            graph.add_edge(
+                NodeIndex::new(src as usize),
+                NodeIndex::new(dst as usize),
+                edge,
+            );
+        }
+        graph
+    }
+}
+
+/// Loads the graph as is, as [`Dex::from`] the [`IndexedGraph`].
+#[cfg(feature = "petgraph")]
+impl From<&Graph<Vertex, Edge>> for Dex {
+    fn from(graph: &Graph<Vertex, Edge>) -> Self {
+        let mut indexed = IndexedGraph::new();
+        for v in graph.node_weights() {
+            indexed.add_node(*v);
+        }
+        for edge in graph.raw_edges() {
+            let (src, dst) = (edge.source().index(), edge.target().index());
+            indexed.add_edge(src as u32, dst as u32, edge.weight.clone());
+        }
+        Dex::from(&indexed)
+    }
+}
+
+#[cfg(test)]
+mod test;
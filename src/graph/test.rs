@@ -0,0 +1,87 @@
+#[cfg(feature = "petgraph")]
+use petgraph::Graph;
+
+use super::IndexedGraph;
+use crate::edge::Edge;
+use crate::Dex;
+
+#[test]
+fn test_indexed_graph() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_limit_order('C', 'D', 0.5, 100.0);
+
+    let graph = IndexedGraph::from(&dex);
+    assert_eq!(
+        graph.nodes(),
+        &['A'.into(), 'B'.into(), 'C'.into(), 'D'.into()]
+    );
+    assert_eq!(graph.edges().len(), 5);
+    let (a, b) = (
+        graph.index(&'A'.into()).unwrap(),
+        graph.index(&'B'.into()).unwrap(),
+    );
+    let (_, _, edge) = graph
+        .edges()
+        .iter()
+        .find(|(src, dst, _)| (*src, *dst) == (a, b))
+        .unwrap();
+    assert_eq!(edge.rate(), 2.0);
+    assert_eq!(graph.index(&'Z'.into()), None);
+
+    // The round trip keeps the directed edges as is.
+    let back = Dex::from(&graph);
+    assert_eq!(back, dex);
+    assert_eq!(
+        back.get_best_rate(&'A'.into(), &'D'.into())
+            .unwrap()
+            .to_string(),
+        dex.get_best_rate(&'A'.into(), &'D'.into())
+            .unwrap()
+            .to_string()
+    );
+}
+
+#[test]
+fn test_indexed_graph_directed() {
+    let mut graph = IndexedGraph::new();
+    let a = graph.add_node('A'.into());
+    let b = graph.add_node('B'.into());
+    let c = graph.add_node('C'.into());
+    graph.add_edge(a, b, Edge::new(2.0));
+    graph.add_edge(b, c, Edge::new(3.0));
+    graph.add_edge(c, c, Edge::new(1.0));
+
+    let dex = Dex::from(&graph);
+    assert_eq!(dex.vertices().count(), 3);
+    let path = dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C: 6");
+    assert!(dex.get_best_rate(&'C'.into(), &'A'.into()).is_none());
+}
+
+#[cfg(feature = "petgraph")]
+#[test]
+fn test_petgraph() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('D', 'E', 1.5);
+
+    let graph = Graph::from(&dex);
+    assert_eq!((graph.node_count(), graph.edge_count()), (5, 6));
+    assert_eq!(petgraph::algo::kosaraju_scc(&graph).len(), 2);
+    let a = graph
+        .node_indices()
+        .find(|i| graph[*i] == 'A'.into())
+        .unwrap();
+    let b = graph
+        .node_indices()
+        .find(|i| graph[*i] == 'B'.into())
+        .unwrap();
+    let edge = graph.find_edge(a, b).unwrap();
+    assert_eq!(graph[edge].rate(), 2.0);
+
+    // The round trip keeps the directed edges as is.
+    assert_eq!(Dex::from(&graph), dex);
+}
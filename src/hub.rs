@@ -0,0 +1,129 @@
+//! Transit hub precomputation
+
+use std::collections::HashMap;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex};
+use crate::progress::{Progress, Stage};
+use crate::query::QueryOptions;
+
+/// The precomputed best rates into and out of the hub vertices.
+///
+/// Most of the routes between the currencies go through a few hubs,
+/// e.g. USD, USDT or ETH.  The point query is answered by the two table
+/// lookups per hub, `src -> hub` and `hub -> dst`, instead of the
+/// search, at the cost of the memory of two rates per vertex and hub.
+///
+/// The index is the snapshot of the graph, and is rebuilt after the
+/// update.
+#[derive(Clone, Debug)]
+pub struct HubIndex {
+    hubs: Vec<Vertex>,
+    // The best rate of each vertex into each hub, zero if unreachable.
+    to_hubs: HashMap<Vertex, Vec<f32>>,
+    // The best rate of each hub into each vertex, by the vertex.
+    from_hubs: HashMap<Vertex, Vec<f32>>,
+}
+
+impl HubIndex {
+    pub fn hubs(&self) -> &[Vertex] {
+        &self.hubs
+    }
+
+    /// Returns the best `src -> dst` rate through any hub, or `None` in
+    /// case no hub connects them.
+    ///
+    /// The route through the hub is not necessarily the best one, and
+    /// the caller falls back to [`Dex::get_best_rate`] for `None`.
+    pub fn rate(&self, src: &Vertex, dst: &Vertex) -> Option<f32> {
+        if src == dst {
+            return Some(1.0);
+        }
+        let to_hubs = self.to_hubs.get(src)?;
+        let from_hubs = self.from_hubs.get(dst)?;
+        to_hubs
+            .iter()
+            .zip(from_hubs)
+            .map(|(to, from)| to * from)
+            .filter(|rate| *rate > 0.0)
+            .reduce(f32::max)
+    }
+
+    /// Returns the hub of the best `src -> dst` route.
+    pub fn hub(&self, src: &Vertex, dst: &Vertex) -> Option<&Vertex> {
+        let to_hubs = self.to_hubs.get(src)?;
+        let from_hubs = self.from_hubs.get(dst)?;
+        let (i, rate) = to_hubs
+            .iter()
+            .zip(from_hubs)
+            .map(|(to, from)| to * from)
+            .enumerate()
+            .reduce(|best, next| if next.1 > best.1 { next } else { best })?;
+        if rate > 0.0 {
+            Some(&self.hubs[i])
+        } else {
+            None
+        }
+    }
+}
+
+impl Dex {
+    /// Precomputes the [`HubIndex`] through the `count` vertices with
+    /// the most edges.
+    pub fn hub_index(&self, count: usize) -> HubIndex {
+        let mut vertices: Vec<_> = self
+            .edges
+            .iter()
+            .map(|(v, edges)| (edges.len(), *v))
+            .collect();
+        vertices.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        let hubs = vertices.into_iter().take(count).map(|(_, v)| v).collect();
+        self.hub_index_with(hubs)
+    }
+
+    /// Precomputes the [`HubIndex`] through the given `hubs`.
+    pub fn hub_index_with(&self, hubs: Vec<Vertex>) -> HubIndex {
+        self.hub_index_with_progress(hubs, |_| {})
+    }
+
+    /// Precomputes the [`HubIndex`] through the given `hubs`, and calls
+    /// `progress` after each hub.
+    #[instrument(level = "debug", skip(self, progress))]
+    pub fn hub_index_with_progress<F>(&self, hubs: Vec<Vertex>, mut progress: F) -> HubIndex
+    where
+        F: FnMut(Progress),
+    {
+        let options = QueryOptions::new();
+        let mut to_hubs: HashMap<_, _> = self
+            .vertices()
+            .map(|v| (*v, vec![0.0; hubs.len()]))
+            .collect();
+        let mut from_hubs = to_hubs.clone();
+        for (i, hub) in hubs.iter().enumerate() {
+            for (v, rate) in self.valuations(hub) {
+                to_hubs.get_mut(&v).unwrap()[i] = rate;
+            }
+            for (v, path) in self.get_best_rates_from(hub, &options) {
+                from_hubs.get_mut(&v).unwrap()[i] = path.rate();
+            }
+            if let Some(rates) = from_hubs.get_mut(hub) {
+                rates[i] = 1.0;
+            }
+            progress(Progress {
+                stage: Stage::HubIndex,
+                done: i + 1,
+                total: hubs.len(),
+            });
+        }
+        debug!(hubs = hubs.len(), vertices = to_hubs.len(), "hub index");
+        HubIndex {
+            hubs,
+            to_hubs,
+            from_hubs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
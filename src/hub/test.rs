@@ -0,0 +1,29 @@
+use crate::test::vertex;
+use crate::Dex;
+
+#[test]
+fn test_hub_index() {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("EUR"), vertex("USD"), 1.1);
+    dex.add_rate(vertex("GBP"), vertex("USD"), 1.3);
+    dex.add_rate(vertex("JPY"), vertex("USD"), 0.01);
+    dex.add_rate(vertex("CNY"), vertex("KRW"), 180.0);
+
+    let index = dex.hub_index(1);
+    assert_eq!(index.hubs(), &[vertex("USD")]);
+
+    let (eur, gbp, jpy, usd) = (vertex("EUR"), vertex("GBP"), vertex("JPY"), vertex("USD"));
+    assert_eq!(index.rate(&eur, &usd), Some(1.1));
+    assert_eq!(index.rate(&usd, &jpy), Some(100.0));
+    assert_eq!(index.rate(&eur, &eur), Some(1.0));
+    assert_eq!(index.hub(&gbp, &jpy), Some(&usd));
+    assert!((index.rate(&gbp, &jpy).unwrap() - 130.0).abs() < 1e-3);
+    assert_eq!(
+        index.rate(&eur, &gbp),
+        Some(dex.get_best_rate(&eur, &gbp).unwrap().rate())
+    );
+
+    assert_eq!(index.rate(&vertex("CNY"), &usd), None);
+    assert_eq!(index.hub(&vertex("CNY"), &usd), None);
+    assert_eq!(index.rate(&vertex("XXX"), &usd), None);
+}
@@ -0,0 +1,161 @@
+//! Johnson's all-pairs best rates for the sparse graphs
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex, RATE_EPSILON};
+use crate::cancel::CancelToken;
+use crate::progress::{Progress, Stage};
+
+/// The edge density, the edges over the squared vertices, below which
+/// the graph is considered sparse.
+pub(crate) const SPARSE_DENSITY: f32 = 0.05;
+
+// The negative log rate distance, totally ordered for the heap.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Distance(f64);
+
+impl Eq for Distance {}
+
+impl PartialOrd for Distance {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Distance {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl Dex {
+    /// Returns the edge density, the directed edges over the squared
+    /// vertices.
+    pub fn density(&self) -> f32 {
+        let vertices = self.edges.len() as f32;
+        if vertices == 0.0 {
+            return 0.0;
+        }
+        let edges: usize = self.edges.values().map(|edges| edges.len()).sum();
+        edges as f32 / (vertices * vertices)
+    }
+
+    // Johnson's algorithm over the negative log net rates, from each of
+    // `srcs` to each of `dsts`.
+    //
+    // The edges are reweighted to be non-negative by the Bellman-Ford
+    // potentials, and Dijkstra's algorithm runs per source in
+    // O(E log V).  Returns `None` in case of the arbitrage cycle, the
+    // negative cycle, which Johnson's algorithm doesn't support.  The
+    // `progress` is called after each source, and the rows of only the
+    // sources done are returned once cancelled.
+    #[instrument(level = "debug", skip_all)]
+    pub(crate) fn johnson(
+        &self,
+        srcs: &[Vertex],
+        dsts: &[Vertex],
+        cancel: &CancelToken,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Option<Vec<Vec<Option<f32>>>> {
+        let index: HashMap<_, _> = self
+            .edges
+            .keys()
+            .enumerate()
+            .map(|(i, v)| (*v, i))
+            .collect();
+        let mut adjacency = vec![Vec::new(); index.len()];
+        for (src, edges) in &self.edges {
+            for (dst, edge) in edges {
+                let rate = f64::from(edge.effective_rate(None));
+                if rate > 0.0 {
+                    adjacency[index[src]].push((index[dst], -rate.ln()));
+                }
+            }
+        }
+
+        // The potentials from the virtual source connected to all the
+        // vertices with the zero weight edges.  The cycles within the
+        // rate epsilon are not considered the arbitrage.
+        let epsilon = f64::from(RATE_EPSILON);
+        let mut potentials = vec![0.0; adjacency.len()];
+        for round in 0..=adjacency.len() {
+            let mut relaxed = false;
+            for (u, edges) in adjacency.iter().enumerate() {
+                for (v, weight) in edges {
+                    if potentials[u] + weight < potentials[*v] - epsilon {
+                        potentials[*v] = potentials[u] + weight;
+                        relaxed = true;
+                    }
+                }
+            }
+            if !relaxed {
+                break;
+            }
+            if round == adjacency.len() {
+                debug!("arbitrage cycle");
+                return None;
+            }
+        }
+        for (u, edges) in adjacency.iter_mut().enumerate() {
+            for (v, weight) in edges {
+                *weight = (*weight + potentials[u] - potentials[*v]).max(0.0);
+            }
+        }
+
+        let total = srcs.len();
+        let rates = srcs
+            .iter()
+            .take_while(|_| !cancel.is_cancelled())
+            .enumerate()
+            .map(|(i, src)| {
+                let distances = index
+                    .get(src)
+                    .map(|src| dijkstra(&adjacency, *src))
+                    .unwrap_or_default();
+                let row = dsts
+                    .iter()
+                    .map(|dst| {
+                        if src == dst {
+                            return Some(1.0);
+                        }
+                        let (s, d) = (*index.get(src)?, *index.get(dst)?);
+                        let distance = distances[d]?;
+                        Some((-(distance - potentials[s] + potentials[d])).exp() as f32)
+                    })
+                    .collect();
+                progress(Progress {
+                    stage: Stage::Matrix,
+                    done: i + 1,
+                    total,
+                });
+                row
+            })
+            .collect();
+        Some(rates)
+    }
+}
+
+// Returns the distance to each vertex over the non-negative weights.
+fn dijkstra(adjacency: &[Vec<(usize, f64)>], src: usize) -> Vec<Option<f64>> {
+    let mut distances = vec![None; adjacency.len()];
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((Distance(0.0), src)));
+    while let Some(Reverse((Distance(distance), u))) = heap.pop() {
+        if distances[u].is_some() {
+            continue;
+        }
+        distances[u] = Some(distance);
+        for (v, weight) in &adjacency[u] {
+            if distances[*v].is_none() {
+                heap.push(Reverse((Distance(distance + weight), *v)));
+            }
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,53 @@
+use crate::cancel::CancelToken;
+use crate::rng::Rng;
+use crate::Dex;
+
+#[test]
+fn test_johnson() {
+    let mut rng = Rng::new(1);
+    let dex = Dex::generate(60, 70, 0.0, &mut rng);
+    assert!(dex.density() < super::SPARSE_DENSITY);
+
+    let vertices: Vec<_> = dex.vertices().copied().collect();
+    assert!(dex
+        .johnson(&vertices, &vertices, &CancelToken::new(), &mut |_| {})
+        .is_some());
+    let matrix = dex.matrix(None);
+    let expected = dex.search_matrix(&vertices, &CancelToken::new(), &mut |_| {});
+    for (i, src) in vertices.iter().enumerate() {
+        for (j, dst) in vertices.iter().enumerate() {
+            let rate = matrix.rate(src, dst).unwrap();
+            let expected = expected[i][j].unwrap();
+            assert!((rate / expected - 1.0).abs() < 1e-4, "{src} -> {dst}");
+        }
+    }
+}
+
+#[test]
+fn test_johnson_arbitrage() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 0.2);
+    dex.add_rate('D', 'E', 1.5);
+
+    let vertices: Vec<_> = dex.vertices().copied().collect();
+    assert!(dex
+        .johnson(&vertices, &vertices, &CancelToken::new(), &mut |_| {})
+        .is_none());
+
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('D', 'E', 1.5);
+    let rates = dex
+        .johnson(
+            &['A'.into()],
+            &['C'.into(), 'E'.into()],
+            &CancelToken::new(),
+            &mut |_| {},
+        )
+        .unwrap();
+    assert!((rates[0][0].unwrap() - 6.0).abs() < 1e-5);
+    assert_eq!(rates[0][1], None);
+}
@@ -0,0 +1,246 @@
+//! Minimal JSON parser for the importers
+
+use std::fmt;
+
+/// The parsed JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// The invalid JSON, with the byte offset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseJsonError {
+    offset: usize,
+    msg: &'static str,
+}
+
+impl fmt::Display for ParseJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.msg, self.offset)
+    }
+}
+
+impl std::error::Error for ParseJsonError {}
+
+impl Json {
+    pub fn parse(s: &str) -> Result<Self, ParseJsonError> {
+        let mut parser = Parser { s, offset: 0 };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.offset < s.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    /// Returns the member of the object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Self::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the number, or the numeric string as many APIs quote
+    /// the decimals.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            Self::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    offset: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &'static str) -> ParseJsonError {
+        ParseJsonError {
+            offset: self.offset,
+            msg,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.offset).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.offset += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), ParseJsonError> {
+        if self.s[self.offset..].starts_with(literal) {
+            self.offset += literal.len();
+            Ok(())
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, ParseJsonError> {
+        self.whitespace();
+        match self.peek() {
+            Some(b'n') => self.expect("null").map(|_| Json::Null),
+            Some(b't') => self.expect("true").map(|_| Json::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'[') => {
+                self.offset += 1;
+                let mut values = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b']') {
+                    self.offset += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.offset += 1,
+                        Some(b']') => {
+                            self.offset += 1;
+                            return Ok(Json::Array(values));
+                        }
+                        _ => return Err(self.error("expected , or ]")),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.offset += 1;
+                let mut members = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b'}') {
+                    self.offset += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.whitespace();
+                    let key = self.string()?;
+                    self.whitespace();
+                    self.expect(":")?;
+                    members.push((key, self.value()?));
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.offset += 1,
+                        Some(b'}') => {
+                            self.offset += 1;
+                            return Ok(Json::Object(members));
+                        }
+                        _ => return Err(self.error("expected , or }")),
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.offset;
+                while matches!(
+                    self.peek(),
+                    Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                ) {
+                    self.offset += 1;
+                }
+                self.s[start..self.offset]
+                    .parse()
+                    .map(Json::Number)
+                    .map_err(|_| ParseJsonError {
+                        offset: start,
+                        msg: "invalid number",
+                    })
+            }
+            _ => Err(self.error("expected value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseJsonError> {
+        self.expect("\"")?;
+        let mut s = String::new();
+        loop {
+            let rest = &self.s[self.offset..];
+            let i = rest
+                .find(['"', '\\'])
+                .ok_or_else(|| self.error("unterminated string"))?;
+            s.push_str(&rest[..i]);
+            self.offset += i + 1;
+            if rest.as_bytes()[i] == b'"' {
+                return Ok(s);
+            }
+            let escape = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.offset += 1;
+            match escape {
+                b'"' => s.push('"'),
+                b'\\' => s.push('\\'),
+                b'/' => s.push('/'),
+                b'b' => s.push('\u{8}'),
+                b'f' => s.push('\u{c}'),
+                b'n' => s.push('\n'),
+                b'r' => s.push('\r'),
+                b't' => s.push('\t'),
+                b'u' => {
+                    let code = self.hex()?;
+                    let c = if (0xd800..0xdc00).contains(&code) {
+                        self.expect("\\u")?;
+                        match self.hex()? {
+                            low @ 0xdc00..=0xdfff => {
+                                char::from_u32(0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00))
+                            }
+                            _ => None,
+                        }
+                    } else {
+                        char::from_u32(code)
+                    };
+                    s.push(c.ok_or_else(|| self.error("invalid unicode escape"))?);
+                }
+                _ => return Err(self.error("invalid escape")),
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, ParseJsonError> {
+        let hex = self
+            .s
+            .get(self.offset..self.offset + 4)
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let code =
+            u32::from_str_radix(hex, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.offset += 4;
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,35 @@
+use super::Json;
+
+#[test]
+fn test_parse() {
+    let json = Json::parse(
+        r#" {"pools": [{"id": "0x1", "fee": 3000, "price": "1.5", "ok": true, "x": null}],
+            "s": "a\"\\\n\u00e9\ud83d\ude00", "n": -1.5e3, "e": {}, "a": []} "#,
+    )
+    .unwrap();
+    let pool = &json.get("pools").unwrap().as_array().unwrap()[0];
+    assert_eq!(pool.get("id").unwrap().as_str(), Some("0x1"));
+    assert_eq!(pool.get("fee").unwrap().as_f64(), Some(3000.0));
+    assert_eq!(pool.get("price").unwrap().as_f64(), Some(1.5));
+    assert_eq!(pool.get("ok").unwrap().as_bool(), Some(true));
+    assert_eq!(pool.get("x"), Some(&Json::Null));
+    assert_eq!(json.get("s").unwrap().as_str(), Some("a\"\\\né😀"));
+    assert_eq!(json.get("n").unwrap().as_f64(), Some(-1500.0));
+    assert_eq!(json.get("e"), Some(&Json::Object(Vec::new())));
+    assert_eq!(json.get("a"), Some(&Json::Array(Vec::new())));
+    assert_eq!(json.get("missing"), None);
+}
+
+#[test]
+fn test_parse_error() {
+    assert_eq!(
+        Json::parse("[1, 2").unwrap_err().to_string(),
+        "expected , or ] at 5"
+    );
+    assert!(Json::parse("{\"a\" 1}").is_err());
+    assert!(Json::parse("\"abc").is_err());
+    assert!(Json::parse("\"\\x\"").is_err());
+    assert!(Json::parse("1 2").is_err());
+    assert!(Json::parse("--1").is_err());
+    assert!(Json::parse("").is_err());
+}
@@ -0,0 +1,866 @@
+//! Dex example
+
+#![forbid(missing_debug_implementations)]
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use tracing::{debug, instrument, trace, warn};
+
+use crate::cancel::Timeout;
+use crate::change::ChangeLog;
+use crate::clock::Clock;
+use crate::decimals::Rounding;
+use crate::edge::{Edge, EdgeKind};
+use crate::outlier::{Outlier, OutlierGuard};
+use crate::peg::PegGroup;
+use crate::pool::SearchBuffers;
+use crate::provider::{Provider, ProviderId};
+use crate::query::{Algorithm, Bounded, QueryOptions, RateOverflow};
+use crate::quote::Quote;
+use crate::token::Token;
+use crate::volatility::RateHistory;
+
+pub mod alert;
+pub mod alias;
+pub mod arbitrage;
+pub mod auth;
+pub mod backbone;
+pub mod batch;
+pub mod builder;
+pub mod cache;
+pub mod cancel;
+pub mod centrality;
+pub mod change;
+pub mod channel;
+pub mod cli;
+pub mod clock;
+pub mod cluster;
+pub mod compare;
+pub mod concentrated;
+pub mod config;
+pub mod cost;
+pub mod csv;
+pub mod cycle;
+pub mod daemon;
+pub mod dashboard;
+pub mod decimals;
+pub mod detector;
+pub mod dfs;
+pub mod ecb;
+pub mod edge;
+pub mod equivalence;
+pub mod exchange;
+pub mod failover;
+pub mod fetch;
+pub mod flow;
+pub mod forecast;
+pub mod frozen;
+pub mod generate;
+pub mod graph;
+pub mod hub;
+pub mod johnson;
+pub mod json;
+pub mod matrix;
+pub mod mock;
+pub mod money;
+pub mod normalize;
+pub mod outlier;
+pub mod pair;
+pub mod paper;
+pub mod pareto;
+pub mod parquet;
+pub mod partition;
+pub mod peg;
+pub mod pool;
+pub mod progress;
+pub mod provider;
+pub mod query;
+pub mod quote;
+pub mod rebalance;
+pub mod replay;
+pub mod report;
+pub mod retain;
+pub mod rfq;
+pub mod rng;
+pub mod server;
+pub mod shutdown;
+pub mod simulate;
+pub mod snapshot;
+pub mod spfa;
+pub mod spread;
+pub mod subgraph;
+pub mod svg;
+pub mod table;
+pub mod term;
+pub mod tls;
+pub mod token;
+pub mod u256;
+pub mod valuation;
+pub mod visualize;
+pub mod volatility;
+pub mod warm;
+pub mod watch;
+
+/// The maximum length of the vertex symbol in bytes.
+pub const MAX_SYMBOL_LEN: usize = 16;
+
+// The vertex length, large enough for the token address with the chain
+// ID, see [`Token`](crate::token::Token).
+const VERTEX_LEN: usize = 32;
+
+/// The currency, identified by the symbol, e.g. `USD` or `A`, or by the
+/// token address on the chain, see [`Token`](crate::token::Token).
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct Vertex([u8; VERTEX_LEN]);
+
+impl From<char> for Vertex {
+    fn from(v: char) -> Self {
+        let mut symbol = [0; VERTEX_LEN];
+        v.encode_utf8(&mut symbol);
+        Self(symbol)
+    }
+}
+
+impl TryFrom<&str> for Vertex {
+    type Error = ParseVertexError;
+
+    fn try_from(v: &str) -> Result<Self, Self::Error> {
+        v.parse()
+    }
+}
+
+impl FromStr for Vertex {
+    type Err = ParseVertexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("0x") && s.contains('@') {
+            return s.parse::<Token>().map(Self::from);
+        }
+        if s.is_empty() || s.len() > MAX_SYMBOL_LEN || s.contains('\0') {
+            return Err(ParseVertexError(s.to_string()));
+        }
+        let mut symbol = [0; VERTEX_LEN];
+        symbol[..s.len()].copy_from_slice(s.as_bytes());
+        Ok(Self(symbol))
+    }
+}
+
+impl fmt::Display for Vertex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.token() {
+            Some(token) => f.pad(&token.to_string()),
+            None => f.pad(self.as_str()),
+        }
+    }
+}
+
+impl fmt::Debug for Vertex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Vertex").field(&self.to_string()).finish()
+    }
+}
+
+impl Vertex {
+    /// Returns the symbol, or the empty string in case of the token
+    /// address vertex.
+    pub fn as_str(&self) -> &str {
+        let len = self.0.iter().position(|b| *b == 0).unwrap_or(VERTEX_LEN);
+        // It's always the valid UTF-8, as it's created from `&str`.
+        std::str::from_utf8(&self.0[..len]).unwrap()
+    }
+}
+
+/// The invalid vertex symbol, e.g. empty or too long.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseVertexError(String);
+
+impl fmt::Display for ParseVertexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid vertex symbol {:?}", self.0)
+    }
+}
+
+impl Error for ParseVertexError {}
+
+#[derive(Debug)]
+pub struct Path {
+    path: Vec<Vertex>,
+    rates: Vec<f32>,
+    // The quote of each hop, also of the oldest timestamp instead of
+    // the field, to keep the path small.
+    quotes: Vec<Quote>,
+    rate: f32,
+    gross_rate: f32,
+    value: f32,
+    risk: f32,
+    // The delay and the latency in seconds, and the bridges count, to
+    // keep the path small.
+    delay: f32,
+    latency: f32,
+    bridges: u32,
+    // The liquidity in the source currency, the smallest of the hop
+    // liquidities converted back by the rate before the hop.
+    liquidity: f32,
+    expires_at: Option<SystemTime>,
+}
+
+impl Clone for Path {
+    fn clone(&self) -> Self {
+        let mut path = Self::new(self.path[0]);
+        path.clone_from(self);
+        path
+    }
+
+    // Reuses the vertices and the rates allocations, for the recycled
+    // search paths.
+    fn clone_from(&mut self, source: &Self) {
+        self.path.clone_from(&source.path);
+        self.rates.clone_from(&source.rates);
+        self.quotes.clone_from(&source.quotes);
+        self.rate = source.rate;
+        self.gross_rate = source.gross_rate;
+        self.value = source.value;
+        self.delay = source.delay;
+        self.risk = source.risk;
+        self.latency = source.latency;
+        self.bridges = source.bridges;
+        self.liquidity = source.liquidity;
+        self.expires_at = source.expires_at;
+    }
+}
+
+impl PartialEq for Path {
+    fn eq(&self, other: &Self) -> bool {
+        self.rate == other.rate
+    }
+}
+
+impl PartialOrd for Path {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.rate.partial_cmp(&other.rate)
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, vertex) in self.path.iter().take(10).enumerate() {
+            if i != 0 {
+                let _ = f.write_fmt(format_args!(" -> "));
+            }
+            let _ = f.write_fmt(format_args!("{}", vertex));
+        }
+        f.write_fmt(format_args!(": {}", self.rate))
+    }
+}
+
+impl Path {
+    pub fn new(src: Vertex) -> Self {
+        Self {
+            path: vec![src],
+            rates: Vec::new(),
+            quotes: Vec::new(),
+            rate: 1.0,
+            gross_rate: 1.0,
+            value: 1.0,
+            risk: 0.0,
+            delay: 0.0,
+            latency: 0.0,
+            bridges: 0,
+            liquidity: f32::INFINITY,
+            expires_at: None,
+        }
+    }
+
+    // Resets to the empty path from `src`, keeping the allocations.
+    pub(crate) fn reset(&mut self, src: Vertex) {
+        self.path.clear();
+        self.path.push(src);
+        self.rates.clear();
+        self.quotes.clear();
+        self.rate = 1.0;
+        self.gross_rate = 1.0;
+        self.value = 1.0;
+        self.delay = 0.0;
+        self.risk = 0.0;
+        self.latency = 0.0;
+        self.bridges = 0;
+        self.liquidity = f32::INFINITY;
+        self.expires_at = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.path.len()
+    }
+
+    pub fn contains(&self, v: &Vertex) -> bool {
+        self.path.contains(v)
+    }
+
+    pub fn last(&self) -> &Vertex {
+        assert!(!self.is_empty());
+        self.path.last().unwrap()
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    /// Returns the rate of each hop, net of the fees.
+    pub fn rates(&self) -> &[f32] {
+        &self.rates
+    }
+
+    /// Returns the quote of each hop, the rate before the fees with the
+    /// provider and the time it's from, see [`Dex::provenance`].
+    pub fn quotes(&self) -> &[Quote] {
+        &self.quotes
+    }
+
+    /// Returns the expected amount received at each hop for the
+    /// `amount` of the source currency.
+    ///
+    /// The rates are for the amount queried with, see
+    /// [`QueryOptions::with_amount`].
+    pub fn amounts_out(&self, amount: f32) -> Vec<f32> {
+        self.rates
+            .iter()
+            .scan(amount, |amount, rate| {
+                *amount *= rate;
+                Some(*amount)
+            })
+            .collect()
+    }
+
+    /// Returns the guaranteed minimum amount received at each hop with
+    /// the slippage `tolerance`, e.g. `0.005` for 50 bps, as the
+    /// `amountOutMin` of each swap.  The last one is the overall
+    /// minimum.
+    pub fn min_amounts_out(&self, amount: f32, tolerance: f32) -> Vec<f32> {
+        assert!((0.0..1.0).contains(&tolerance));
+        self.amounts_out(amount)
+            .into_iter()
+            .map(|amount| amount * (1.0 - tolerance))
+            .collect()
+    }
+
+    /// Returns the overall minimum amount received, see
+    /// [`Path::min_amounts_out`].
+    pub fn min_amount_out(&self, amount: f32, tolerance: f32) -> Option<f32> {
+        self.min_amounts_out(amount, tolerance).last().copied()
+    }
+
+    /// Returns the total fees, the fraction of the rate before the
+    /// fees.
+    pub fn fees(&self) -> f32 {
+        if self.gross_rate > 0.0 {
+            1.0 - self.rate / self.gross_rate
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the total expected delay of the path.
+    pub fn delay(&self) -> Duration {
+        Duration::from_secs_f32(self.delay)
+    }
+
+    /// Returns the total expected execution latency of the path.
+    pub fn latency(&self) -> Duration {
+        Duration::from_secs_f32(self.latency)
+    }
+
+    /// Returns the composite risk score, the sum of the edge risks.
+    pub fn risk(&self) -> f32 {
+        self.risk
+    }
+
+    /// Returns the largest amount of the source currency the path takes
+    /// within the liquidity of every hop, in case any hop has the
+    /// liquidity.
+    pub fn liquidity(&self) -> Option<f32> {
+        if self.liquidity.is_finite() {
+            Some(self.liquidity)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of the bridges the path crosses.
+    pub fn bridges(&self) -> usize {
+        self.bridges as usize
+    }
+
+    /// Returns the time of the oldest quote used, in case any edge is
+    /// timestamped.
+    pub fn quoted_at(&self) -> Option<SystemTime> {
+        self.quotes.iter().filter_map(|quote| quote.timestamp).min()
+    }
+
+    /// Returns the time the path can be trusted until, the oldest quote
+    /// plus the TTL, see [`QueryOptions::with_quote_ttl`].
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at
+    }
+
+    /// Checks if the path is expired at `now`.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        match self.expires_at {
+            Some(expires_at) => now >= expires_at,
+            None => false,
+        }
+    }
+
+    /// Checks if the path crosses the chains through the bridges.
+    pub fn is_cross_chain(&self) -> bool {
+        self.bridges != 0
+    }
+
+    // Returns the overflow in case the accumulated rate is infinite,
+    // NaN, or underflowed to zero with all the hop rates positive.
+    pub(crate) fn overflow(&self) -> Option<RateOverflow> {
+        let underflow = self.rate == 0.0 && self.rates.iter().all(|rate| *rate > 0.0);
+        if self.rate.is_finite() && !underflow {
+            return None;
+        }
+        Some(RateOverflow {
+            path: self.path.clone(),
+            rate: self.rate,
+        })
+    }
+
+    pub fn insert(&mut self, v: Vertex, rate: f32) -> bool {
+        if self.contains(&v) {
+            return false;
+        }
+        self.path.push(v);
+        self.rates.push(rate);
+        self.quotes.push(Quote::new(rate));
+        self.rate *= rate;
+        self.gross_rate *= rate;
+        self.value *= rate;
+        true
+    }
+
+    // Extends the path with the edge, net of the fees charged for
+    // converting the query amount of the path source currency, and
+    // adjusted by the forecast if any.
+    fn push(&mut self, v: Vertex, edge: &Edge, options: &QueryOptions) {
+        if self.contains(&v) {
+            return;
+        }
+        let amount = options.amount().map(|amount| amount * self.rate);
+        let rate = edge.effective_rate(amount);
+        let adjusted = options.forecast(self, &v, edge, rate);
+        let drift = if rate > 0.0 { adjusted / rate } else { 1.0 };
+        if let Some(liquidity) = edge.liquidity {
+            self.liquidity = self.liquidity.min(liquidity / self.rate);
+        }
+        self.path.push(v);
+        self.rates.push(adjusted);
+        self.quotes.push(edge.quote());
+        self.rate *= adjusted;
+        self.gross_rate *= edge.gross_rate(amount) * drift;
+        self.value *= options.value(edge, amount) * drift;
+        self.delay += edge.delay.as_secs_f32();
+        self.latency += edge.latency.as_secs_f32();
+        self.risk += edge.risk;
+        if edge.is_bridge() {
+            self.bridges += 1;
+        }
+        if edge.timestamp.is_some() {
+            let quoted_at = self.quoted_at();
+            self.expires_at = options
+                .quote_ttl()
+                .and_then(|ttl| quoted_at.map(|quoted_at| quoted_at + ttl));
+        }
+    }
+}
+
+/// The relative rate difference tolerated by the [`Dex`] equality.
+pub const RATE_EPSILON: f32 = 1e-6;
+
+// The best rate paths by the destination.
+type Paths = BTreeMap<Vertex, Path>;
+
+#[derive(Clone, Debug, Default)]
+pub struct Dex {
+    // The sorted adjacency for the deterministic iteration and the
+    // tie-breaking across runs.
+    edges: BTreeMap<Vertex, BTreeMap<Vertex, Edge>>,
+    outlier_guard: Option<OutlierGuard>,
+    providers: Vec<Provider>,
+    quotes: HashMap<(Vertex, Vertex), BTreeMap<ProviderId, f32>>,
+    decimals: HashMap<Vertex, u8>,
+    rounding: Option<Rounding>,
+    aliases: HashMap<Vertex, Vertex>,
+    labels: HashMap<Vertex, String>,
+    equivalences: BTreeMap<Vertex, Vertex>,
+    pegs: BTreeMap<String, PegGroup>,
+    disabled: BTreeMap<ProviderId, Vec<(Vertex, Vertex, Edge)>>,
+    clock: Option<Arc<dyn Clock>>,
+    history: Option<RateHistory>,
+    changes: ChangeLog,
+}
+
+/// The structural equality, the same vertices and the same directed
+/// edges with the rates equal within [`RATE_EPSILON`].
+///
+/// The providers and the settings are not compared.
+impl PartialEq for Dex {
+    fn eq(&self, other: &Self) -> bool {
+        self.approx_eq(other, RATE_EPSILON)
+    }
+}
+
+impl Dex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn vertices(&self) -> impl Iterator<Item = &Vertex> {
+        self.edges.keys()
+    }
+
+    /// Checks the structural equality with the rates equal within the
+    /// relative `epsilon`.
+    pub fn approx_eq(&self, other: &Self, epsilon: f32) -> bool {
+        self.edges.len() == other.edges.len()
+            && self
+                .edges
+                .iter()
+                .zip(&other.edges)
+                .all(|((a, a_edges), (b, b_edges))| {
+                    a == b
+                        && a_edges.len() == b_edges.len()
+                        && a_edges.iter().all(|(dst, a)| match b_edges.get(dst) {
+                            Some(b) => {
+                                let diff = (a.rate - b.rate).abs();
+                                diff <= epsilon * a.rate.abs().max(b.rate.abs())
+                                    && a == &Edge {
+                                        rate: a.rate,
+                                        ..b.clone()
+                                    }
+                            }
+                            None => false,
+                        })
+                })
+    }
+
+    pub fn set_outlier_guard(&mut self, guard: Option<OutlierGuard>) {
+        self.outlier_guard = guard;
+    }
+
+    /// Adds the `src -> dst` rate, as well as the reverse rate.
+    ///
+    /// It returns the detected outlier in case the outlier guard is set.
+    /// The outlier rate is not added in case the guard rejects it.
+    pub fn add_rate<V: Into<Vertex>>(&mut self, src: V, dst: V, rate: f32) -> Option<Outlier> {
+        self.insert_edge(src.into(), dst.into(), Edge::new(rate))
+    }
+
+    /// Adds the `src -> dst` edge, as well as the reverse edge.
+    pub fn add_edge<V: Into<Vertex>>(&mut self, src: V, dst: V, edge: Edge) -> Option<Outlier> {
+        self.insert_edge(src.into(), dst.into(), edge)
+    }
+
+    /// Adds the resting limit order converting `src` into `dst` at the
+    /// `price`, valid up to the `size` of `src`.
+    ///
+    /// The limit orders are directed, and take over any other edge of
+    /// the `src -> dst` direction.
+    pub fn add_limit_order<V: Into<Vertex>>(&mut self, src: V, dst: V, price: f32, size: f32) {
+        let src = self.canonical(src.into());
+        let dst = self.canonical(dst.into());
+        assert!(src != dst);
+        self.edges.entry(dst).or_default();
+        let edges = self.edges.entry(src).or_default();
+        match edges.get_mut(&dst) {
+            Some(edge) if edge.kind == EdgeKind::LimitOrder => edge.add_order(price, size),
+            _ => {
+                edges.insert(dst, Edge::limit_order(price, size));
+            }
+        }
+        self.changes.push(src, dst);
+    }
+
+    fn insert_edge(&mut self, src: Vertex, dst: Vertex, mut edge: Edge) -> Option<Outlier> {
+        assert!(src != dst && edge.rate != 0.0);
+        let src = self.canonical(src);
+        let dst = self.canonical(dst);
+        if src == dst {
+            warn!(%src, "ignored rate between the aliases");
+            return None;
+        }
+        let outlier = self
+            .outlier_guard
+            .and_then(|guard| Some((guard, self.check_rate(&src, &dst, edge.rate, &guard)?)));
+        if let Some((guard, outlier)) = outlier {
+            warn!(%src, %dst, %outlier, reject = guard.is_reject(), "outlier rate");
+            if guard.is_reject() {
+                return Some(outlier);
+            }
+        }
+        edge.volatility = self.record_rate(src, dst, edge.rate);
+        let reverse = edge.reverse();
+        let entry = self.edges.entry(src).or_default();
+        entry.insert(dst, edge);
+        let entry = self.edges.entry(dst).or_default();
+        entry.insert(src, reverse);
+        self.changes.push(src, dst);
+        self.changes.push(dst, src);
+        outlier.map(|(_, outlier)| outlier)
+    }
+
+    /// Returns the best rate path from `src` to `dst`.
+    ///
+    /// The path of the rate overflowing or underflowing the `f32` is
+    /// dropped without the error, and the result might be the worse
+    /// path, see [`Dex::checked_get_best_rate`] to detect it.
+    pub fn get_best_rate(&self, src: &Vertex, dst: &Vertex) -> Option<Path> {
+        self.get_best_rate_with(src, dst, &QueryOptions::default())
+    }
+
+    /// Returns the best rate path with the query `options`, unchecked
+    /// for the rate overflow as [`Dex::get_best_rate`].
+    #[instrument(level = "debug", skip(self), ret)]
+    pub fn get_best_rate_with(
+        &self,
+        src: &Vertex,
+        dst: &Vertex,
+        options: &QueryOptions,
+    ) -> Option<Path> {
+        self.try_get_best_rate_with(src, dst, options)
+            .unwrap_or_else(Timeout::into_partial)
+    }
+
+    /// Returns the best rate path, or the [`Timeout`] error with the
+    /// best one found so far in case of the timeout or the
+    /// cancellation, see [`QueryOptions::with_timeout`].
+    pub fn try_get_best_rate_with(
+        &self,
+        src: &Vertex,
+        dst: &Vertex,
+        options: &QueryOptions,
+    ) -> Result<Option<Path>, Timeout<Option<Path>>> {
+        let src = &self.canonical(*src);
+        let dst = &self.canonical(*dst);
+        self.search(src, Some(dst), options)
+            .map(|paths| paths.into_value().remove(dst))
+            .map_err(|timeout| timeout.map(|mut paths| paths.remove(dst)))
+    }
+
+    /// Returns the best rate path, flagged in case the search was
+    /// truncated by the memory budget, see
+    /// [`QueryOptions::with_max_queue_len`].
+    pub fn get_best_rate_bounded(
+        &self,
+        src: &Vertex,
+        dst: &Vertex,
+        options: &QueryOptions,
+    ) -> Bounded<Option<Path>> {
+        let src = &self.canonical(*src);
+        let dst = &self.canonical(*dst);
+        match self.search(src, Some(dst), options) {
+            Ok(paths) => paths.map(|mut paths| paths.remove(dst)),
+            Err(timeout) => Bounded::new(timeout.into_partial().remove(dst), true),
+        }
+    }
+
+    /// Returns the best rate path, or the [`RateOverflow`] error in case
+    /// any path was dropped by the rate overflow, instead of the result
+    /// which might have missed it.
+    pub fn checked_get_best_rate(
+        &self,
+        src: &Vertex,
+        dst: &Vertex,
+        options: &QueryOptions,
+    ) -> Result<Option<Path>, RateOverflow> {
+        let bounded = self.get_best_rate_bounded(src, dst, options);
+        match bounded.overflow() {
+            Some(overflow) => Err(overflow.clone()),
+            None => Ok(bounded.into_value()),
+        }
+    }
+
+    /// Returns the best rate paths from `src` to all the reachable
+    /// vertices, unchecked for the rate overflow as
+    /// [`Dex::get_best_rate`], see [`Dex::checked_get_best_rates_from`].
+    pub fn get_best_rates_from(
+        &self,
+        src: &Vertex,
+        options: &QueryOptions,
+    ) -> BTreeMap<Vertex, Path> {
+        self.try_get_best_rates_from(src, options)
+            .unwrap_or_else(Timeout::into_partial)
+    }
+
+    /// Returns the best rate paths from `src`, or the [`RateOverflow`]
+    /// error in case any path was dropped by the rate overflow.
+    pub fn checked_get_best_rates_from(
+        &self,
+        src: &Vertex,
+        options: &QueryOptions,
+    ) -> Result<BTreeMap<Vertex, Path>, RateOverflow> {
+        let src = &self.canonical(*src);
+        let paths = match self.search(src, None, options) {
+            Ok(paths) => paths,
+            Err(timeout) => Bounded::new(timeout.into_partial(), true),
+        };
+        if let Some(overflow) = paths.overflow() {
+            return Err(overflow.clone());
+        }
+        let mut paths = paths.into_value();
+        paths.remove(src);
+        Ok(paths)
+    }
+
+    /// Returns the best rate paths from `src`, or the [`Timeout`] error
+    /// with the ones found so far.
+    pub fn try_get_best_rates_from(
+        &self,
+        src: &Vertex,
+        options: &QueryOptions,
+    ) -> Result<BTreeMap<Vertex, Path>, Timeout<BTreeMap<Vertex, Path>>> {
+        let src = &self.canonical(*src);
+        let remove = |mut paths: BTreeMap<Vertex, Path>| {
+            paths.remove(src);
+            paths
+        };
+        self.search(src, None, options)
+            .map(|paths| remove(paths.into_value()))
+            .map_err(|timeout| timeout.map(remove))
+    }
+
+    // Breath first traversal to find the best rate.
+    //
+    // It finds the best rate path to `dst`, or to all the vertices in
+    // case of `None`.  The timeout and the cancellation are checked
+    // every `CHECK_INTERVAL` paths.  The search state is kept in the
+    // thread's [`SearchBuffers`] for the next query.
+    fn search(
+        &self,
+        src: &Vertex,
+        dst: Option<&Vertex>,
+        options: &QueryOptions,
+    ) -> Result<Bounded<Paths>, Timeout<Paths>> {
+        if options.algorithm() == Algorithm::Spfa {
+            return self.spfa(src, dst, options);
+        }
+        SearchBuffers::with(|buffers| self.search_with(buffers, src, dst, options))
+    }
+
+    fn search_with(
+        &self,
+        buffers: &mut SearchBuffers,
+        src: &Vertex,
+        dst: Option<&Vertex>,
+        options: &QueryOptions,
+    ) -> Result<Bounded<Paths>, Timeout<Paths>> {
+        const CHECK_INTERVAL: usize = 64;
+        let start = Instant::now();
+        let max_queue_len = options.max_queue_len().unwrap_or(usize::MAX);
+        let mut best_paths = BTreeMap::new();
+        let mut truncated = false;
+        let mut overflow = None;
+
+        buffers.reset(self.edges.len());
+        let path = buffers.copy(&Path::new(*src));
+        buffers.queue.push_back(path);
+        let mut count = 0;
+        while let Some(path) = buffers.queue.pop_front() {
+            debug_assert!(path.len() < 100);
+            trace!(%path, "queue.pop_front()");
+
+            count += 1;
+            if count % CHECK_INTERVAL == 0 && options.is_expired(start) {
+                warn!(%src, %count, "search timed out");
+                return Err(Timeout::new(best_paths));
+            }
+
+            // The visited vertex check.
+            //
+            // It drops the vertex in case the newly calculated rate
+            // is more than what we have in the visited labels.  The
+            // constrained resources, e.g. the delay under the deadline,
+            // should be no worse either to drop the vertex.
+            let label = options.label(&path);
+            let labels = buffers.visited(*path.last());
+            if labels.iter().any(|current| current.dominates(&label)) {
+                // Current one is better.  Skip this vertex.
+                buffers.recycle(path);
+                continue;
+            } else if !labels.is_empty() {
+                // New one is better.  Continue the process.
+                trace!(?labels, %path, "new rate is better than current rate");
+                labels.retain(|current| !label.dominates(current));
+            }
+            labels.push(label);
+
+            let is_dst = dst == Some(path.last());
+            if !is_dst {
+                // Continues the breath first search by pushing the new
+                // vertex into to the `queue`.
+                if let Some(vertices) = self.edges.get(path.last()) {
+                    for (vertex, edge) in vertices {
+                        if !path.contains(vertex) && options.is_routable(&path, edge) {
+                            if buffers.queue.len() >= max_queue_len {
+                                truncated = true;
+                                continue;
+                            }
+                            let mut path = buffers.copy(&path);
+                            path.push(*vertex, edge, options);
+                            if let Some(e) = path.overflow() {
+                                warn!(%e, "path dropped");
+                                overflow.get_or_insert(e);
+                                buffers.recycle(path);
+                                continue;
+                            }
+                            trace!(%path, "queue.push_back");
+                            buffers.queue.push_back(path);
+                        }
+                    }
+                }
+            }
+
+            // Update the rate in case the newly calculated rate
+            // is better than what we have.
+            if is_dst || dst.is_none() {
+                match best_paths.get(path.last()) {
+                    Some(current_path) if options.score(&path) <= options.score(current_path) => {
+                        debug!(%path, %current_path, "use the current path");
+                        buffers.recycle(path);
+                    }
+                    current_path => {
+                        if let Some(current_path) = current_path {
+                            debug!(%path, %current_path, "use the new path");
+                        }
+                        if let Some(current_path) = best_paths.insert(*path.last(), path) {
+                            buffers.recycle(current_path);
+                        }
+                    }
+                }
+            } else {
+                buffers.recycle(path);
+            }
+        }
+
+        if truncated {
+            warn!(%src, %max_queue_len, "search truncated");
+        }
+        Ok(Bounded::new(best_paths, truncated).with_overflow(overflow))
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,230 @@
+//! Conversion matrix
+
+use std::fmt;
+
+use tracing::instrument;
+
+use super::{Dex, Vertex};
+use crate::cancel::{CancelToken, Timeout};
+use crate::johnson::SPARSE_DENSITY;
+use crate::progress::{Progress, Stage};
+use crate::query::QueryOptions;
+use crate::table::{change_color, Rates};
+use crate::term::paint;
+
+/// The best rates between the currencies.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matrix {
+    vertices: Vec<Vertex>,
+    rates: Vec<Vec<Option<f32>>>,
+}
+
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(None, false))
+    }
+}
+
+impl Matrix {
+    /// Renders the matrix as the [`Display`](fmt::Display), with the
+    /// rates improved since the `previous` ones in green and the
+    /// worsened ones in red in case of `color`.
+    pub fn render(&self, previous: Option<&Rates>, color: bool) -> String {
+        let width = self
+            .vertices
+            .iter()
+            .map(|v| v.to_string().len())
+            .max()
+            .unwrap_or_default();
+        let column = width.max(10);
+        let mut s = format!("{:width$}", "");
+        for dst in &self.vertices {
+            s.push_str(&format!(" {dst:>column$}"));
+        }
+        for (src, rates) in self.vertices.iter().zip(&self.rates) {
+            s.push_str(&format!("\n{src:width$}"));
+            for (dst, rate) in self.vertices.iter().zip(rates) {
+                match rate {
+                    Some(rate) => {
+                        let previous = previous.and_then(|rates| rates.get(&(*src, *dst)));
+                        let cell = format!("{rate:>column$.4}");
+                        let change = change_color(*rate, previous.copied());
+                        s.push_str(&format!(" {}", paint(cell, change, color)));
+                    }
+                    None => s.push_str(&format!(" {:>column$}", "-")),
+                }
+            }
+        }
+        s
+    }
+
+    /// Returns the rates of the pairs, without the diagonal.
+    pub fn rates(&self) -> Rates {
+        let mut rates = Rates::new();
+        for (src, row) in self.vertices.iter().zip(&self.rates) {
+            for (dst, rate) in self.vertices.iter().zip(row) {
+                if let (true, Some(rate)) = (src != dst, rate) {
+                    rates.insert((*src, *dst), *rate);
+                }
+            }
+        }
+        rates
+    }
+
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    /// Returns the best `src -> dst` rate.
+    pub fn rate(&self, src: &Vertex, dst: &Vertex) -> Option<f32> {
+        let i = self.vertices.iter().position(|v| v == src)?;
+        let j = self.vertices.iter().position(|v| v == dst)?;
+        self.rates[i][j]
+    }
+
+    /// Returns the index of the vertex in the rows and the columns.
+    pub fn index(&self, v: &Vertex) -> Option<usize> {
+        self.vertices.iter().position(|u| u == v)
+    }
+
+    /// Returns the rates in the row-major order, with `NaN` for the
+    /// unreachable pairs.
+    ///
+    /// It's the layout of the `ndarray::Array2::from_shape_vec` of the
+    /// `(n, n)` shape, with the row and the column `i` of the vertex
+    /// `vertices()[i]`, for the linear algebra on the rate surface.
+    pub fn to_row_major(&self) -> Vec<f64> {
+        self.rates
+            .iter()
+            .flatten()
+            .map(|rate| rate.map_or(f64::NAN, f64::from))
+            .collect()
+    }
+}
+
+impl Dex {
+    /// Returns the best rates between the `vertices`, or between all
+    /// the vertices in case of `None`.
+    ///
+    /// The sparse graph, below [`SPARSE_DENSITY`] edge density, is
+    /// computed by Johnson's algorithm instead of the search per source
+    /// unless it has the arbitrage cycle.
+    ///
+    /// [`SPARSE_DENSITY`]: crate::johnson::SPARSE_DENSITY
+    #[instrument(level = "debug", skip(self))]
+    pub fn matrix(&self, vertices: Option<&[Vertex]>) -> Matrix {
+        self.matrix_with_progress(vertices, |_| {})
+    }
+
+    /// Returns the [`Dex::matrix`], and calls `progress` as each row is
+    /// computed.
+    pub fn matrix_with_progress<F>(&self, vertices: Option<&[Vertex]>, progress: F) -> Matrix
+    where
+        F: FnMut(Progress),
+    {
+        self.try_matrix(vertices, &CancelToken::new(), progress)
+            .unwrap_or_else(Timeout::into_partial)
+    }
+
+    /// Returns the [`Dex::matrix`], or the [`Timeout`] error with the
+    /// rows computed so far once the `cancel` token is cancelled.  The
+    /// rest of the rows are left unreachable.
+    pub fn try_matrix<F>(
+        &self,
+        vertices: Option<&[Vertex]>,
+        cancel: &CancelToken,
+        mut progress: F,
+    ) -> Result<Matrix, Timeout<Matrix>>
+    where
+        F: FnMut(Progress),
+    {
+        let vertices = match vertices {
+            Some(vertices) => vertices.to_vec(),
+            None => self.vertices().copied().collect(),
+        };
+        let mut rates = None;
+        if self.density() < SPARSE_DENSITY {
+            rates = self.johnson(&vertices, &vertices, cancel, &mut progress);
+        }
+        let mut rates = match rates {
+            Some(rates) => rates,
+            None => self.search_matrix(&vertices, cancel, &mut progress),
+        };
+        let done = rates.len();
+        rates.resize(vertices.len(), vec![None; vertices.len()]);
+        let matrix = Matrix { vertices, rates };
+        if done < matrix.vertices.len() {
+            Err(Timeout::new(matrix))
+        } else {
+            Ok(matrix)
+        }
+    }
+
+    /// Returns the rates of the direct edges between the `vertices`, or
+    /// between all the vertices in case of `None`, net of the fees.
+    pub fn adjacency_matrix(&self, vertices: Option<&[Vertex]>) -> Matrix {
+        let vertices = match vertices {
+            Some(vertices) => vertices.to_vec(),
+            None => self.vertices().copied().collect(),
+        };
+        let rates = vertices
+            .iter()
+            .map(|src| {
+                let edges = self.edges.get(src);
+                vertices
+                    .iter()
+                    .map(|dst| {
+                        if src == dst {
+                            Some(1.0)
+                        } else {
+                            let edge = edges?.get(dst)?;
+                            Some(edge.effective_rate(None))
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Matrix { vertices, rates }
+    }
+
+    // The best rates by the search from each of the `vertices`, of only
+    // the rows done once cancelled.
+    pub(crate) fn search_matrix(
+        &self,
+        vertices: &[Vertex],
+        cancel: &CancelToken,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Vec<Vec<Option<f32>>> {
+        let options = QueryOptions::new().with_cancel_token(cancel.clone());
+        let mut rates = Vec::with_capacity(vertices.len());
+        for src in vertices {
+            if cancel.is_cancelled() {
+                break;
+            }
+            let paths = match self.try_get_best_rates_from(src, &options) {
+                Ok(paths) => paths,
+                Err(_) => break,
+            };
+            let row = vertices
+                .iter()
+                .map(|dst| {
+                    if src == dst {
+                        Some(1.0)
+                    } else {
+                        paths.get(dst).map(|path| path.rate)
+                    }
+                })
+                .collect();
+            rates.push(row);
+            progress(Progress {
+                stage: Stage::Matrix,
+                done: rates.len(),
+                total: vertices.len(),
+            });
+        }
+        rates
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,74 @@
+use crate::test::vertex;
+use crate::Dex;
+
+#[test]
+fn test_matrix() {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("USD"), vertex("EUR"), 0.5);
+    dex.add_rate(vertex("EUR"), vertex("GBP"), 0.5);
+    dex.add_rate(vertex("JPY"), vertex("CNY"), 0.05);
+
+    let usd = vertex("USD");
+    let gbp = vertex("GBP");
+    let matrix = dex.matrix(Some(&[usd, vertex("EUR"), gbp, vertex("JPY")]));
+    assert_eq!(matrix.rate(&usd, &gbp), Some(0.25));
+    assert_eq!(matrix.rate(&gbp, &usd), Some(4.0));
+    assert_eq!(matrix.rate(&usd, &vertex("JPY")), None);
+    assert_eq!(
+        matrix.to_string(),
+        "           USD        EUR        GBP        JPY\n\
+         USD     1.0000     0.5000     0.2500          -\n\
+         EUR     2.0000     1.0000     0.5000          -\n\
+         GBP     4.0000     2.0000     1.0000          -\n\
+         JPY          -          -          -     1.0000"
+    );
+
+    let matrix = dex.matrix(None);
+    assert_eq!(matrix.vertices().len(), 5);
+}
+
+#[test]
+fn test_matrix_row_major() {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("USD"), vertex("EUR"), 0.5);
+    dex.add_rate(vertex("EUR"), vertex("GBP"), 0.5);
+    dex.add_rate(vertex("JPY"), vertex("CNY"), 0.05);
+
+    let vertices = [vertex("USD"), vertex("EUR"), vertex("GBP"), vertex("JPY")];
+    let matrix = dex.matrix(Some(&vertices));
+    assert_eq!(matrix.index(&vertex("GBP")), Some(2));
+    assert_eq!(matrix.index(&vertex("CNY")), None);
+    let rates = matrix.to_row_major();
+    assert_eq!(rates.len(), 16);
+    assert_eq!(rates[..3], [1.0, 0.5, 0.25]);
+    assert!(rates[3].is_nan());
+    assert_eq!(rates[2 * 4], 4.0);
+
+    let adjacency = dex.adjacency_matrix(Some(&vertices));
+    assert_eq!(adjacency.vertices(), &vertices);
+    assert_eq!(adjacency.rate(&vertex("USD"), &vertex("EUR")), Some(0.5));
+    assert_eq!(adjacency.rate(&vertex("USD"), &vertex("GBP")), None);
+    assert_eq!(adjacency.rate(&vertex("GBP"), &vertex("GBP")), Some(1.0));
+    assert_eq!(dex.adjacency_matrix(None).vertices().len(), 5);
+}
+
+#[test]
+fn test_matrix_unknown_vertex() {
+    // The sparse chain, computed by Johnson's algorithm.
+    let mut dex = Dex::new();
+    for i in 0..50 {
+        dex.add_rate(
+            vertex(&format!("V{i}")),
+            vertex(&format!("V{}", i + 1)),
+            1.01,
+        );
+    }
+    assert!(dex.density() < crate::johnson::SPARSE_DENSITY);
+
+    let (v0, v2, zz) = (vertex("V0"), vertex("V2"), vertex("ZZ"));
+    let matrix = dex.matrix(Some(&[v0, v2, zz]));
+    assert!((matrix.rate(&v0, &v2).unwrap() - 1.0201).abs() < 1e-5);
+    assert_eq!(matrix.rate(&v0, &zz), None);
+    assert_eq!(matrix.rate(&zz, &v0), None);
+    assert_eq!(matrix.rate(&zz, &zz), Some(1.0));
+}
@@ -0,0 +1,119 @@
+//! Mock rate provider
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use tracing::debug;
+
+use super::Vertex;
+use crate::fetch::{Quote, Source};
+
+/// The [`Source`] replaying the scripted updates, for the tests without
+/// the network access.
+///
+/// Each fetch takes the next step of the script, the quotes or the
+/// failure, after the simulated latency.  It returns no quote once the
+/// script runs out.  The clones share the script, so that the test
+/// keeps one to push the steps, e.g. to inject the failure, while the
+/// other is polled by the [`Fetcher`](crate::fetch::Fetcher).
+#[derive(Clone, Debug, Default)]
+pub struct MockProvider {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    steps: VecDeque<Step>,
+    latency: Duration,
+    calls: usize,
+}
+
+#[derive(Debug)]
+enum Step {
+    Quotes(Vec<Quote>),
+    Fail(io::ErrorKind),
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the step returning the `quotes`.
+    pub fn then_quotes(self, quotes: Vec<Quote>) -> Self {
+        self.push_quotes(quotes);
+        self
+    }
+
+    /// Adds the step returning the `src -> dst` rate.
+    pub fn then_rate<V: Into<Vertex>>(self, src: V, dst: V, rate: f32) -> Self {
+        self.then_quotes(vec![Quote {
+            src: src.into(),
+            dst: dst.into(),
+            rate,
+        }])
+    }
+
+    /// Adds the step failing with the error `kind`.
+    pub fn then_fail(self, kind: io::ErrorKind) -> Self {
+        self.push_fail(kind);
+        self
+    }
+
+    /// Sets the simulated latency of each fetch.
+    pub fn with_latency(self, latency: Duration) -> Self {
+        self.lock().latency = latency;
+        self
+    }
+
+    pub fn push_quotes(&self, quotes: Vec<Quote>) {
+        self.lock().steps.push_back(Step::Quotes(quotes));
+    }
+
+    pub fn push_fail(&self, kind: io::ErrorKind) {
+        self.lock().steps.push_back(Step::Fail(kind));
+    }
+
+    pub fn latency(&self) -> Duration {
+        self.lock().latency
+    }
+
+    /// Returns the number of the fetches so far.
+    pub fn calls(&self) -> usize {
+        self.lock().calls
+    }
+
+    /// Returns the number of the steps not fetched yet.
+    pub fn pending(&self) -> usize {
+        self.lock().steps.len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl Source for MockProvider {
+    fn fetch(&mut self) -> io::Result<Vec<Quote>> {
+        let (step, latency) = {
+            let mut state = self.lock();
+            state.calls += 1;
+            (state.steps.pop_front(), state.latency)
+        };
+        if !latency.is_zero() {
+            thread::sleep(latency);
+        }
+        debug!(?step, "mock fetch");
+        match step {
+            Some(Step::Quotes(quotes)) => Ok(quotes),
+            Some(Step::Fail(kind)) => Err(io::Error::new(kind, "mock failure")),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,59 @@
+use std::io;
+use std::time::{Duration, Instant};
+
+use super::MockProvider;
+use crate::fetch::{Fetcher, Quote, Source};
+use crate::Dex;
+
+#[test]
+fn test_mock_provider() {
+    let mut mock = MockProvider::new()
+        .then_rate('A', 'B', 2.0)
+        .then_fail(io::ErrorKind::TimedOut);
+    assert_eq!(mock.pending(), 2);
+    assert_eq!(mock.fetch().unwrap()[0].rate, 2.0);
+    let err = mock.fetch().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    assert!(mock.fetch().unwrap().is_empty());
+    assert_eq!(mock.calls(), 3);
+    assert_eq!(mock.pending(), 0);
+}
+
+#[test]
+fn test_mock_provider_fetcher() {
+    let mut dex = Dex::new();
+    let feed = dex.register_provider("feed", 1);
+    let mock = MockProvider::new().then_rate('A', 'B', 2.0);
+    let mut fetcher = Fetcher::new().source(feed, mock.clone());
+
+    let now = Instant::now();
+    assert_eq!(fetcher.poll(&mut dex, now), 1);
+    assert_eq!(fetcher.poll(&mut dex, now), 0);
+    mock.push_fail(io::ErrorKind::ConnectionRefused);
+    mock.push_quotes(vec![Quote {
+        src: 'A'.into(),
+        dst: 'B'.into(),
+        rate: 2.5,
+    }]);
+    assert_eq!(fetcher.poll(&mut dex, now), 0);
+    assert_eq!(
+        dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap().rate(),
+        2.0
+    );
+    assert_eq!(fetcher.poll(&mut dex, now), 1);
+    assert_eq!(
+        dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap().rate(),
+        2.5
+    );
+    assert_eq!(mock.calls(), 4);
+}
+
+#[test]
+fn test_mock_provider_latency() {
+    let latency = Duration::from_millis(20);
+    let mut mock = MockProvider::new().with_latency(latency);
+    assert_eq!(mock.latency(), latency);
+    let start = Instant::now();
+    mock.fetch().unwrap();
+    assert!(start.elapsed() >= latency);
+}
@@ -0,0 +1,216 @@
+//! Currency-typed amounts
+
+use std::fmt;
+use std::iter::Sum;
+use std::marker::PhantomData;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use tracing::instrument;
+
+use super::{Dex, Vertex};
+use crate::query::{InvalidAmount, QueryOptions};
+
+/// The currency known at the compile time, the type parameter of the
+/// [`Money`].
+///
+/// The well-known currencies are defined here, and the others are by
+/// implementing the trait on the unit struct, e.g.
+///
+/// ```text
+/// #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// struct Sgd;
+///
+/// impl Currency for Sgd {
+///     const CODE: &'static str = "SGD";
+/// }
+/// ```
+pub trait Currency: Copy + fmt::Debug {
+    const CODE: &'static str;
+
+    fn vertex() -> Vertex {
+        Self::CODE.parse().expect("valid currency code")
+    }
+}
+
+macro_rules! currencies {
+    ($($code:ident),*) => {
+        $(
+            #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+            pub struct $code;
+
+            impl Currency for $code {
+                const CODE: &'static str = stringify!($code);
+            }
+        )*
+    };
+}
+
+currencies!(AUD, BTC, CAD, CHF, CNY, ETH, EUR, GBP, JPY, KRW, USD, USDC, USDT);
+
+/// The amount of the currency `C`.
+///
+/// The amounts are added, subtracted and compared only within the same
+/// currency, and converted into the other currency only through the
+/// [`Dex`], so that the compiler catches the `Money<USD>` passed where
+/// the `Money<EUR>` is expected.
+pub struct Money<C> {
+    amount: f32,
+    currency: PhantomData<C>,
+}
+
+impl<C: Currency> Money<C> {
+    pub fn new(amount: f32) -> Self {
+        Self {
+            amount,
+            currency: PhantomData,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Self::new(0.0)
+    }
+
+    pub fn amount(&self) -> f32 {
+        self.amount
+    }
+
+    /// Returns the vertex of the currency.
+    pub fn currency(&self) -> Vertex {
+        C::vertex()
+    }
+}
+
+// The traits are implemented by hand, as the derives bound the `C` as
+// well, which the marker types don't need.
+impl<C> Copy for Money<C> {}
+
+impl<C> Clone for Money<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C: Currency> fmt::Debug for Money<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Money")
+            .field(&self.amount)
+            .field(&C::CODE)
+            .finish()
+    }
+}
+
+impl<C: Currency> fmt::Display for Money<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.amount, f)?;
+        write!(f, " {}", C::CODE)
+    }
+}
+
+impl<C: Currency> Default for Money<C> {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<C> PartialEq for Money<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.amount == other.amount
+    }
+}
+
+impl<C> PartialOrd for Money<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.amount.partial_cmp(&other.amount)
+    }
+}
+
+impl<C: Currency> Add for Money<C> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.amount + other.amount)
+    }
+}
+
+impl<C: Currency> AddAssign for Money<C> {
+    fn add_assign(&mut self, other: Self) {
+        self.amount += other.amount;
+    }
+}
+
+impl<C: Currency> Sub for Money<C> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.amount - other.amount)
+    }
+}
+
+impl<C: Currency> SubAssign for Money<C> {
+    fn sub_assign(&mut self, other: Self) {
+        self.amount -= other.amount;
+    }
+}
+
+impl<C: Currency> Neg for Money<C> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.amount)
+    }
+}
+
+impl<C: Currency> Mul<f32> for Money<C> {
+    type Output = Self;
+
+    fn mul(self, factor: f32) -> Self {
+        Self::new(self.amount * factor)
+    }
+}
+
+impl<C: Currency> Div<f32> for Money<C> {
+    type Output = Self;
+
+    fn div(self, divisor: f32) -> Self {
+        Self::new(self.amount / divisor)
+    }
+}
+
+impl<C: Currency> Sum for Money<C> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl Dex {
+    /// Quotes the `amount` into the currency `D` through the best path
+    /// for the amount, e.g. `dex.quote::<USD, EUR>(Money::new(100.0))`.
+    #[instrument(level = "debug", skip(self))]
+    pub fn quote<S: Currency, D: Currency>(&self, amount: Money<S>) -> Option<Money<D>> {
+        let (src, dst) = (S::vertex(), D::vertex());
+        if src == dst {
+            return Some(Money::new(amount.amount));
+        }
+        let options = QueryOptions::new().with_amount(amount.amount);
+        self.get_best_rate_with(&src, &dst, &options)
+            .map(|path| Money::new(amount.amount * path.rate()))
+    }
+
+    /// Returns the total value of the routable `holdings` in the base
+    /// currency `B`, see [`Dex::value_portfolio`].
+    pub fn value_portfolio_in<B, V>(&self, holdings: &[(V, f32)]) -> Result<Money<B>, InvalidAmount>
+    where
+        B: Currency,
+        V: Into<Vertex> + Copy + fmt::Debug,
+    {
+        let holdings: Vec<(Vertex, f32)> = holdings
+            .iter()
+            .map(|(asset, amount)| ((*asset).into(), *amount))
+            .collect();
+        let portfolio = self.value_portfolio(&holdings, B::vertex())?;
+        Ok(Money::new(portfolio.total()))
+    }
+}
+
+#[cfg(test)]
+mod test;
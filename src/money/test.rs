@@ -0,0 +1,56 @@
+use super::{Currency, Money, EUR, GBP, JPY, USD};
+use crate::test::vertex;
+use crate::Dex;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Sgd;
+
+impl Currency for Sgd {
+    const CODE: &'static str = "SGD";
+}
+
+#[test]
+fn test_money() {
+    let a = Money::<USD>::new(1.5);
+    let b = Money::<USD>::new(2.0);
+    assert_eq!(a + b, Money::new(3.5));
+    assert_eq!(b - a, Money::new(0.5));
+    assert_eq!(-a, Money::new(-1.5));
+    assert_eq!(a * 2.0, Money::new(3.0));
+    assert_eq!(b / 4.0, Money::new(0.5));
+    assert!(a < b);
+    assert_eq!([a, b].into_iter().sum::<Money<USD>>(), Money::new(3.5));
+    let mut c = Money::<USD>::default();
+    c += b;
+    c -= a;
+    assert_eq!(c.amount(), 0.5);
+    assert_eq!(a.currency(), vertex("USD"));
+    assert_eq!(a.to_string(), "1.5 USD");
+    assert_eq!(format!("{:.2}", a), "1.50 USD");
+    assert_eq!(format!("{:?}", a), "Money(1.5, \"USD\")");
+    assert_eq!(Money::<Sgd>::new(1.0).to_string(), "1 SGD");
+}
+
+#[test]
+fn test_quote() {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("EUR"), vertex("USD"), 1.1);
+    dex.add_rate(vertex("USD"), vertex("JPY"), 150.0);
+
+    let usd: Money<USD> = dex.quote::<EUR, _>(Money::new(100.0)).unwrap();
+    assert_eq!(usd, Money::new(110.0));
+    let jpy = dex.quote::<_, JPY>(usd).unwrap();
+    assert_eq!(jpy, Money::new(110.0 * 150.0));
+    assert_eq!(dex.quote::<USD, USD>(usd), Some(usd));
+    assert_eq!(dex.quote::<USD, GBP>(usd), None);
+    assert_eq!(dex.quote::<Sgd, USD>(Money::new(1.0)), None);
+
+    let total: Money<USD> = dex
+        .value_portfolio_in(&[
+            (vertex("EUR"), 100.0),
+            (vertex("USD"), 10.0),
+            (vertex("GBP"), 1.0),
+        ])
+        .unwrap();
+    assert_eq!(total, Money::new(120.0));
+}
@@ -0,0 +1,79 @@
+//! Arbitrage-free normalization
+
+use std::collections::BTreeMap;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex};
+
+const MAX_ITERATIONS: usize = 1000;
+const TOLERANCE: f64 = 1e-9;
+
+impl Dex {
+    /// Adjusts rates minimally to remove internal inconsistencies.
+    ///
+    /// It fits the price of each vertex in log space with the least
+    /// squares, and replaces each edge rate with the ratio of the
+    /// fitted prices.  The rate product of any cycle is 1.0 after the
+    /// normalization, which gives the self-consistent rate surface for
+    /// display or accounting.
+    ///
+    /// The provider quotes of each pair are scaled by the same
+    /// adjustment as its edge, so that the fallback to another provider
+    /// keeps the rates consistent.
+    #[instrument(level = "debug", skip(self))]
+    pub fn normalize(&mut self) {
+        let prices = self.fit_prices();
+        for ((src, dst), quotes) in self.quotes.iter_mut() {
+            let edge = match self.edges.get(src).and_then(|edges| edges.get(dst)) {
+                Some(edge) => edge,
+                None => continue,
+            };
+            let adjustment = (prices[src] - prices[dst]).exp() / f64::from(edge.rate);
+            for rate in quotes.values_mut() {
+                *rate = (f64::from(*rate) * adjustment) as f32;
+            }
+        }
+        for (src, edges) in self.edges.iter_mut() {
+            for (dst, edge) in edges.iter_mut() {
+                edge.rate = (prices[src] - prices[dst]).exp() as f32;
+            }
+        }
+        self.changes.reset();
+    }
+
+    // Gauss-Seidel iterations over the normal equations of
+    // `ln(rate(src, dst)) = price(src) - price(dst)`.
+    fn fit_prices(&self) -> BTreeMap<Vertex, f64> {
+        let mut adjacency: BTreeMap<Vertex, Vec<(Vertex, f64)>> = BTreeMap::new();
+        for (src, edges) in &self.edges {
+            for (dst, edge) in edges {
+                let log_rate = f64::from(edge.rate).ln();
+                adjacency.entry(*src).or_default().push((*dst, log_rate));
+                adjacency.entry(*dst).or_default().push((*src, -log_rate));
+            }
+        }
+        let mut prices: BTreeMap<Vertex, f64> = self.edges.keys().map(|v| (*v, 0.0)).collect();
+        for i in 0..MAX_ITERATIONS {
+            let mut delta: f64 = 0.0;
+            for (v, neighbors) in &adjacency {
+                let sum: f64 = neighbors
+                    .iter()
+                    .map(|(u, log_rate)| prices[u] + log_rate)
+                    .sum();
+                let price = sum / neighbors.len() as f64;
+                let current = prices.get_mut(v).unwrap();
+                delta = delta.max((price - *current).abs());
+                *current = price;
+            }
+            if delta < TOLERANCE {
+                debug!(iterations = i + 1, "converged");
+                break;
+            }
+        }
+        prices
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,57 @@
+use crate::Dex;
+
+#[test]
+fn test_inconsistent_triangle() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 0.2);
+    dex.normalize();
+
+    let a = 'A'.into();
+    let c = 'C'.into();
+    let path = dex.get_best_rate(&a, &c).unwrap();
+    let reverse = dex.get_best_rate(&c, &a).unwrap();
+    assert!((path.rate() * reverse.rate() - 1.0).abs() < 1e-5);
+
+    // The cycle error, 1.2, is spread evenly over the three edges.
+    assert!((path.rate() - 6.0 / 1.2f32.powf(2.0 / 3.0)).abs() < 1e-4);
+}
+
+#[test]
+fn test_consistent_unchanged() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1.4);
+    dex.add_rate('B', 'C', 0.2);
+    dex.add_rate('A', 'C', 0.28);
+    dex.add_rate('C', 'D', 0.2);
+    dex.normalize();
+
+    let path = dex.get_best_rate(&'A'.into(), &'D'.into()).unwrap();
+    assert!((path.rate() - 0.056).abs() < 1e-6);
+    let path = dex.get_best_rate(&'B'.into(), &'C'.into()).unwrap();
+    assert!((path.rate() - 0.2).abs() < 1e-6);
+}
+
+#[test]
+fn test_provider_quotes() {
+    let mut dex = Dex::new();
+    let primary = dex.register_provider("primary", 10);
+    let backup = dex.register_provider("backup", 1);
+    dex.add_provider_rate(primary, 'A', 'B', 2.0);
+    dex.add_provider_rate(backup, 'A', 'B', 2.2);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 0.2);
+    dex.normalize();
+
+    let a = 'A'.into();
+    let b = 'B'.into();
+    let normalized = dex.get_best_rate(&a, &b).unwrap().rate();
+    assert!((normalized - 2.0 / 1.2f32.powf(1.0 / 3.0)).abs() < 1e-4);
+
+    // The backup quote keeps its 10% spread over the normalized rate,
+    // instead of the one before the normalization.
+    dex.remove_provider_rate(primary, 'A', 'B');
+    let path = dex.get_best_rate(&a, &b).unwrap();
+    assert!((path.rate() - normalized * 1.1).abs() < 1e-4);
+}
@@ -0,0 +1,119 @@
+//! Outlier quote detection
+
+use std::fmt;
+
+use super::{Dex, Vertex};
+
+/// Guards the incoming rates against the fat-fingered feed ticks.
+///
+/// The rate is flagged when it deviates more than `factor` from the
+/// current rate of the same edge, or from the cross-rate implied by
+/// the `base` currency, e.g. `src -> base -> dst`.
+#[derive(Copy, Clone, Debug)]
+pub struct OutlierGuard {
+    factor: f32,
+    base: Option<Vertex>,
+    reject: bool,
+}
+
+impl OutlierGuard {
+    /// Creates a guard flagging rates deviating more than `factor`,
+    /// e.g. `2.0` flags the rate more than double or less than half
+    /// of the reference rate.
+    pub fn new(factor: f32) -> Self {
+        assert!(factor > 1.0);
+        Self {
+            factor,
+            base: None,
+            reject: false,
+        }
+    }
+
+    /// Checks the rate against the cross-rate through `base` as well.
+    pub fn base<V: Into<Vertex>>(mut self, base: V) -> Self {
+        self.base = Some(base.into());
+        self
+    }
+
+    /// Rejects the outlier rate instead of just flagging it.
+    pub fn reject(mut self, reject: bool) -> Self {
+        self.reject = reject;
+        self
+    }
+
+    pub fn is_reject(&self) -> bool {
+        self.reject
+    }
+
+    fn is_outlier(&self, rate: f32, reference: f32) -> bool {
+        let deviation = rate / reference;
+        deviation > self.factor || deviation < 1.0 / self.factor
+    }
+}
+
+/// The detected outlier rate.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Outlier {
+    /// Deviates from the current rate of the edge.
+    Current { rate: f32, current: f32 },
+    /// Deviates from the cross-rate implied by the base currency.
+    CrossRate {
+        rate: f32,
+        base: Vertex,
+        implied: f32,
+    },
+}
+
+impl fmt::Display for Outlier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Current { rate, current } => {
+                write!(f, "rate {rate} deviates from the current rate {current}")
+            }
+            Self::CrossRate {
+                rate,
+                base,
+                implied,
+            } => write!(
+                f,
+                "rate {rate} deviates from the cross-rate {implied} via {base}"
+            ),
+        }
+    }
+}
+
+impl Dex {
+    /// Checks the `src -> dst` rate against the guard.
+    pub fn check_rate(
+        &self,
+        src: &Vertex,
+        dst: &Vertex,
+        rate: f32,
+        guard: &OutlierGuard,
+    ) -> Option<Outlier> {
+        let edges = self.edges.get(src);
+        if let Some(current) = edges.and_then(|edges| edges.get(dst)) {
+            if guard.is_outlier(rate, current.rate) {
+                return Some(Outlier::Current {
+                    rate,
+                    current: current.rate,
+                });
+            }
+        }
+        let base = guard.base.filter(|base| base != src && base != dst)?;
+        let src_base = edges.and_then(|edges| edges.get(&base))?;
+        let base_dst = self.edges.get(&base).and_then(|edges| edges.get(dst))?;
+        let implied = src_base.rate * base_dst.rate;
+        if guard.is_outlier(rate, implied) {
+            return Some(Outlier::CrossRate {
+                rate,
+                base,
+                implied,
+            });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test;
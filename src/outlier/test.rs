@@ -0,0 +1,73 @@
+use super::{Outlier, OutlierGuard};
+use crate::Dex;
+
+#[test]
+fn test_current_rate_flagged() {
+    let mut dex = Dex::new();
+    dex.set_outlier_guard(Some(OutlierGuard::new(2.0)));
+    assert_eq!(dex.add_rate('A', 'B', 1.4), None);
+    assert_eq!(dex.add_rate('A', 'B', 1.5), None);
+
+    let outlier = dex.add_rate('A', 'B', 14.0);
+    assert_eq!(
+        outlier,
+        Some(Outlier::Current {
+            rate: 14.0,
+            current: 1.5,
+        })
+    );
+    let path = dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap();
+    assert_eq!(path.rate(), 14.0);
+}
+
+#[test]
+fn test_current_rate_rejected() {
+    let mut dex = Dex::new();
+    dex.set_outlier_guard(Some(OutlierGuard::new(2.0).reject(true)));
+    dex.add_rate('A', 'B', 1.4);
+
+    assert!(dex.add_rate('A', 'B', 0.014).is_some());
+    let path = dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap();
+    assert_eq!(path.rate(), 1.4);
+}
+
+#[test]
+fn test_cross_rate_rejected() {
+    let mut dex = Dex::new();
+    dex.set_outlier_guard(Some(OutlierGuard::new(1.5).base('U').reject(true)));
+    dex.add_rate('A', 'U', 2.0);
+    dex.add_rate('U', 'B', 3.0);
+
+    let outlier = dex.add_rate('A', 'B', 60.0);
+    assert_eq!(
+        outlier,
+        Some(Outlier::CrossRate {
+            rate: 60.0,
+            base: 'U'.into(),
+            implied: 6.0,
+        })
+    );
+    let path = dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap();
+    assert_eq!(path.rate(), 6.0);
+
+    assert_eq!(dex.add_rate('A', 'B', 6.5), None);
+    let path = dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap();
+    assert_eq!(path.rate(), 6.5);
+}
+
+#[test]
+fn test_provider_rate_rejected() {
+    let mut dex = Dex::new();
+    dex.set_outlier_guard(Some(OutlierGuard::new(2.0).reject(true)));
+    let primary = dex.register_provider("primary", 2);
+    let backup = dex.register_provider("backup", 1);
+    dex.add_provider_rate(primary, 'A', 'B', 1.4);
+
+    assert!(dex.add_provider_rate(backup, 'A', 'B', 0.014).is_some());
+    let path = dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap();
+    assert_eq!(path.rate(), 1.4);
+
+    // The rejected rate doesn't take over the pair.
+    dex.remove_provider_rate(primary, 'A', 'B');
+    assert!(dex.get_best_rate(&'A'.into(), &'B'.into()).is_none());
+}
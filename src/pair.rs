@@ -0,0 +1,85 @@
+//! Best overall pair discovery
+
+use std::fmt;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path, Vertex};
+use crate::query::QueryOptions;
+
+/// The best conversion of any of the sources into any of the
+/// destinations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BestPair {
+    pub path: Path,
+    /// The value received over the value spent, both in the base
+    /// currency, e.g. `0.998` for losing 20 bps to the fees and the
+    /// spreads.
+    pub efficiency: f32,
+}
+
+impl fmt::Display for BestPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:.4} efficiency)", self.path, self.efficiency)
+    }
+}
+
+impl BestPair {
+    pub fn src(&self) -> &Vertex {
+        &self.path.path[0]
+    }
+
+    pub fn dst(&self) -> &Vertex {
+        self.path.last()
+    }
+}
+
+impl Dex {
+    /// Returns the best conversion of any of the `srcs` into any of the
+    /// `dsts`, e.g. funding from USD or EUR into JPY or CNY.
+    ///
+    /// The rates of the different pairs aren't comparable as is, so the
+    /// paths are compared by their efficiency, the value received over
+    /// the value spent in the `base` currency by [`Dex::valuations`].
+    /// The currencies without the value in the base are skipped, as
+    /// well as the pairs of the same currency.
+    #[instrument(level = "debug", skip(self, options))]
+    pub fn get_best_pair(
+        &self,
+        srcs: &[Vertex],
+        dsts: &[Vertex],
+        base: &Vertex,
+        options: &QueryOptions,
+    ) -> Option<BestPair> {
+        let valuations = self.valuations(base);
+        let mut best: Option<BestPair> = None;
+        for src in srcs {
+            let src_value = match valuations.get(src) {
+                Some(value) if *value > 0.0 => value,
+                _ => {
+                    debug!(%src, "no value");
+                    continue;
+                }
+            };
+            let paths = self.get_best_rates_from(src, options);
+            for dst in dsts.iter().filter(|dst| *dst != src) {
+                let (path, dst_value) = match (paths.get(dst), valuations.get(dst)) {
+                    (Some(path), Some(value)) => (path, value),
+                    _ => continue,
+                };
+                let efficiency = path.rate * dst_value / src_value;
+                if matches!(&best, Some(best) if best.efficiency >= efficiency) {
+                    continue;
+                }
+                best = Some(BestPair {
+                    path: path.clone(),
+                    efficiency,
+                });
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,42 @@
+use crate::query::QueryOptions;
+use crate::test::vertex;
+use crate::{Dex, Edge, Vertex};
+
+#[test]
+fn test_get_best_pair() {
+    let mut dex = Dex::new();
+    dex.add_edge(
+        vertex("USD"),
+        vertex("JPY"),
+        Edge::new(150.0).with_fee(0.001),
+    );
+    dex.add_edge(vertex("EUR"), vertex("USD"), Edge::new(1.1).with_fee(0.001));
+    dex.add_edge(vertex("EUR"), vertex("CNY"), Edge::new(7.7).with_fee(0.002));
+    dex.add_edge(vertex("USD"), vertex("CNY"), Edge::new(7.0).with_fee(0.01));
+
+    let srcs: Vec<Vertex> = vec![vertex("USD"), vertex("EUR")];
+    let dsts: Vec<Vertex> = vec![vertex("JPY"), vertex("CNY")];
+    let usd = vertex("USD");
+    let options = QueryOptions::new();
+    let best = dex.get_best_pair(&srcs, &dsts, &usd, &options).unwrap();
+    // Either source as EUR is valued through USD.
+    assert_eq!(best.dst(), &vertex("JPY"));
+    assert!((best.efficiency - 0.999 * 0.999).abs() < 1e-4);
+    assert!(best.to_string().ends_with(" (0.9980 efficiency)"));
+
+    dex.add_edge(
+        vertex("USD"),
+        vertex("JPY"),
+        Edge::new(150.0).with_fee(0.01),
+    );
+    let best = dex.get_best_pair(&srcs, &dsts, &usd, &options).unwrap();
+    assert_eq!((best.src(), best.dst()), (&vertex("EUR"), &vertex("CNY")));
+    assert!(best.efficiency < 1.0);
+
+    assert!(dex
+        .get_best_pair(&srcs, &dsts, &vertex("XYZ"), &options)
+        .is_none());
+    assert!(dex
+        .get_best_pair(&srcs[..1], &srcs[..1], &usd, &options)
+        .is_none());
+}
@@ -0,0 +1,215 @@
+//! Paper trading against the live quotes
+
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path, Vertex};
+use crate::query::QueryOptions;
+use crate::simulate::{Execution, ExecutionError};
+
+/// The hypothetical trade filled by the [`PaperTrader`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trade {
+    pub at: SystemTime,
+    pub path: Vec<Vertex>,
+    pub execution: Execution,
+}
+
+impl Trade {
+    pub fn src(&self) -> &Vertex {
+        &self.path[0]
+    }
+
+    pub fn dst(&self) -> &Vertex {
+        &self.path[self.path.len() - 1]
+    }
+}
+
+/// The paper trading summary.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PaperStats {
+    pub trades: usize,
+    /// The profit and loss in the base currency, marked to the live
+    /// valuations.
+    pub pnl: f32,
+    /// The average slippage from the quoted rate, weighted by the
+    /// trade amount.
+    pub slippage: f32,
+    /// The worst slippage of any trade.
+    pub max_slippage: f32,
+}
+
+/// The paper trader, which sizes and "executes" the routed paths
+/// against the live quotes without the real orders.
+///
+/// The fills are simulated with [`Dex::simulate`], so the liquidity is
+/// not consumed and the live graph is left intact.  The hypothetical
+/// balances, the trades and the fill quality are tracked over time to
+/// evaluate the router decisions.
+#[derive(Clone, Debug)]
+pub struct PaperTrader {
+    base: Vertex,
+    balances: BTreeMap<Vertex, f32>,
+    initial: BTreeMap<Vertex, f32>,
+    max_fraction: f32,
+    trades: Vec<Trade>,
+}
+
+impl PaperTrader {
+    /// Creates the trader valued in the `base` currency, with the
+    /// initial `balances`.
+    pub fn new<I>(base: Vertex, balances: I) -> Self
+    where
+        I: IntoIterator<Item = (Vertex, f32)>,
+    {
+        let balances: BTreeMap<_, _> = balances.into_iter().collect();
+        Self {
+            base,
+            initial: balances.clone(),
+            balances,
+            max_fraction: 1.0,
+            trades: Vec::new(),
+        }
+    }
+
+    /// Limits each trade to the `fraction` of the source balance.
+    pub fn with_max_fraction(mut self, fraction: f32) -> Self {
+        assert!(fraction > 0.0 && fraction <= 1.0);
+        self.max_fraction = fraction;
+        self
+    }
+
+    pub fn base(&self) -> &Vertex {
+        &self.base
+    }
+
+    pub fn balance(&self, v: &Vertex) -> f32 {
+        self.balances.get(v).copied().unwrap_or_default()
+    }
+
+    pub fn balances(&self) -> &BTreeMap<Vertex, f32> {
+        &self.balances
+    }
+
+    pub fn trades(&self) -> &[Trade] {
+        &self.trades
+    }
+
+    /// Routes up to the `amount` of `src` into `dst` with the live
+    /// quotes of the `dex` and fills it on paper.
+    ///
+    /// Returns `None` in case there is no route or no balance.
+    pub fn trade(
+        &mut self,
+        dex: &Dex,
+        src: &Vertex,
+        dst: &Vertex,
+        amount: f32,
+        now: SystemTime,
+    ) -> Result<Option<&Trade>, ExecutionError> {
+        let amount = self.size(src, amount);
+        if amount <= 0.0 {
+            return Ok(None);
+        }
+        let options = QueryOptions::new().with_amount(amount);
+        match dex.get_best_rate_with(src, dst, &options) {
+            Some(path) => self.execute(dex, &path, amount, now).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Fills the suggested `path` with up to the `amount` of the source
+    /// currency on paper.
+    ///
+    /// The amount is sized down to the balance and to the shallowest
+    /// hop of the path.
+    #[instrument(level = "debug", skip(self, dex, path), fields(%path))]
+    pub fn execute(
+        &mut self,
+        dex: &Dex,
+        path: &Path,
+        amount: f32,
+        now: SystemTime,
+    ) -> Result<&Trade, ExecutionError> {
+        let src = path.path[0];
+        let mut amount = self.size(&src, amount);
+        let execution = loop {
+            match dex.simulate(path, amount) {
+                Err(ExecutionError::InsufficientLiquidity {
+                    amount: hop_amount,
+                    liquidity,
+                    ..
+                }) if liquidity > 0.0 && liquidity < hop_amount => {
+                    amount *= liquidity / hop_amount;
+                    debug!(%amount, "sized down");
+                }
+                result => break result?,
+            }
+        };
+        *self.balances.entry(src).or_default() -= execution.amount_in();
+        *self.balances.entry(*path.last()).or_default() += execution.amount_out();
+        debug!(
+            amount_in = execution.amount_in(),
+            amount_out = execution.amount_out(),
+            slippage = execution.slippage(),
+            "paper trade"
+        );
+        self.trades.push(Trade {
+            at: now,
+            path: path.path.clone(),
+            execution,
+        });
+        Ok(self.trades.last().unwrap())
+    }
+
+    /// Returns the value of the balances in the base currency, marked
+    /// to the live valuations of the `dex`.
+    ///
+    /// The currencies without the route into the base are not counted.
+    pub fn value(&self, dex: &Dex) -> f32 {
+        value(&self.balances, &dex.valuations(&self.base))
+    }
+
+    /// Returns the summary of the trades so far, with the profit and
+    /// loss marked to the live valuations of the `dex`.
+    pub fn stats(&self, dex: &Dex) -> PaperStats {
+        let valuations = dex.valuations(&self.base);
+        let pnl = value(&self.balances, &valuations) - value(&self.initial, &valuations);
+        let volume: f32 = self.trades.iter().map(|t| t.execution.amount_in()).sum();
+        let slippage = if volume > 0.0 {
+            self.trades
+                .iter()
+                .map(|t| t.execution.slippage() * t.execution.amount_in())
+                .sum::<f32>()
+                / volume
+        } else {
+            0.0
+        };
+        PaperStats {
+            trades: self.trades.len(),
+            pnl,
+            slippage,
+            max_slippage: self
+                .trades
+                .iter()
+                .map(|t| t.execution.slippage())
+                .fold(0.0, f32::max),
+        }
+    }
+
+    fn size(&self, src: &Vertex, amount: f32) -> f32 {
+        amount.min(self.balance(src) * self.max_fraction)
+    }
+}
+
+fn value(balances: &BTreeMap<Vertex, f32>, valuations: &BTreeMap<Vertex, f32>) -> f32 {
+    balances
+        .iter()
+        .filter_map(|(v, balance)| valuations.get(v).map(|rate| balance * rate))
+        .sum()
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,63 @@
+use std::time::SystemTime;
+
+use super::PaperTrader;
+use crate::{Dex, Edge};
+
+#[test]
+fn test_trade() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_limit_order('B', 'C', 3.0, 100.0);
+    dex.add_limit_order('B', 'C', 2.0, 100.0);
+
+    let now = SystemTime::UNIX_EPOCH;
+    let mut trader = PaperTrader::new('C'.into(), [('A'.into(), 150.0)]);
+    let trade = trader
+        .trade(&dex, &'A'.into(), &'C'.into(), 100.0, now)
+        .unwrap()
+        .unwrap();
+    assert_eq!(trade.src(), &'A'.into());
+    assert_eq!(trade.dst(), &'C'.into());
+    assert_eq!(trade.execution.amount_out(), 500.0);
+    assert_eq!(trader.balance(&'A'.into()), 50.0);
+    assert_eq!(trader.balance(&'C'.into()), 500.0);
+
+    // The live graph is left intact.
+    assert_eq!(
+        dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap().rate(),
+        6.0
+    );
+
+    // Sized down to the remaining balance.
+    let trade = trader
+        .trade(&dex, &'A'.into(), &'C'.into(), 100.0, now)
+        .unwrap()
+        .unwrap();
+    assert_eq!(trade.execution.amount_in(), 50.0);
+    assert!(trader
+        .trade(&dex, &'A'.into(), &'C'.into(), 100.0, now)
+        .unwrap()
+        .is_none());
+
+    let stats = trader.stats(&dex);
+    assert_eq!(stats.trades, 2);
+    assert_eq!(stats.pnl, 800.0 - 900.0);
+    // Routed with the amount, so filled at the quoted depth.
+    assert_eq!(stats.max_slippage, 0.0);
+}
+
+#[test]
+fn test_size_to_liquidity() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_edge('B', 'C', Edge::new(3.0).with_liquidity(100.0));
+
+    let path = dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    let mut trader = PaperTrader::new('A'.into(), [('A'.into(), 1000.0)]).with_max_fraction(0.5);
+    let trade = trader
+        .execute(&dex, &path, 1000.0, SystemTime::UNIX_EPOCH)
+        .unwrap();
+    assert_eq!(trade.execution.amount_in(), 50.0);
+    assert_eq!(trade.execution.amount_out(), 300.0);
+    assert_eq!(trader.stats(&dex).pnl, 0.0);
+}
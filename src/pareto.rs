@@ -0,0 +1,97 @@
+//! Multi-objective routing
+
+use std::collections::hash_map::HashMap;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use tracing::{debug, instrument, trace};
+
+use super::{Dex, Path, Vertex};
+use crate::query::QueryOptions;
+
+// The objectives of the path, the rate, the hops, the fees, the risk
+// and the liquidity.
+#[derive(Copy, Clone, Debug)]
+struct Objectives {
+    rate: f32,
+    hops: usize,
+    fees: f32,
+    risk: f32,
+    liquidity: f32,
+}
+
+impl Objectives {
+    fn new(path: &Path) -> Self {
+        Self {
+            rate: path.rate,
+            hops: path.len() - 1,
+            fees: path.fees(),
+            risk: path.risk,
+            liquidity: path.liquidity,
+        }
+    }
+
+    // Checks if it's no worse than the `other` in all the objectives.
+    fn dominates(&self, other: &Self) -> bool {
+        self.rate >= other.rate
+            && self.hops <= other.hops
+            && self.fees <= other.fees
+            && self.risk <= other.risk
+            && self.liquidity >= other.liquidity
+    }
+}
+
+impl Dex {
+    /// Returns the Pareto set of the paths from `src` to `dst` over the
+    /// rate, the hops, the total fees, the risk and the liquidity, the
+    /// best rate first.
+    ///
+    /// None of the paths is worse than another one in all of them, so
+    /// that the caller can pick the trade-off.  The paths with equal
+    /// objectives are reported once.
+    #[instrument(level = "debug", skip(self, options))]
+    pub fn pareto_routes(&self, src: &Vertex, dst: &Vertex, options: &QueryOptions) -> Vec<Path> {
+        let start = Instant::now();
+        let mut visited: HashMap<Vertex, Vec<Objectives>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        let mut routes: Vec<(Objectives, Path)> = Vec::new();
+
+        queue.push_back(Path::new(*src));
+        while let Some(path) = queue.pop_front() {
+            if options.is_expired(start) {
+                debug!(routes = routes.len(), "timed out");
+                break;
+            }
+            let objectives = Objectives::new(&path);
+            let labels = visited.entry(*path.last()).or_default();
+            if labels.iter().any(|label| label.dominates(&objectives)) {
+                continue;
+            }
+            labels.retain(|label| !objectives.dominates(label));
+            labels.push(objectives);
+
+            if path.last() == dst {
+                trace!(%path, "route");
+                routes.retain(|(label, _)| !objectives.dominates(label));
+                routes.push((objectives, path));
+                continue;
+            }
+            if let Some(edges) = self.edges.get(path.last()) {
+                for (vertex, edge) in edges {
+                    if !path.contains(vertex) && options.is_routable(&path, edge) {
+                        let mut path = path.clone();
+                        path.push(*vertex, edge, options);
+                        queue.push_back(path);
+                    }
+                }
+            }
+        }
+
+        let mut routes: Vec<_> = routes.into_iter().map(|(_, path)| path).collect();
+        routes.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+        routes
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,46 @@
+use crate::query::QueryOptions;
+use crate::{Dex, Edge};
+
+#[test]
+fn test_pareto_routes() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'D', Edge::new(1.0).with_fee(0.01));
+    dex.add_edge('A', 'B', Edge::new(2.0));
+    dex.add_edge('B', 'D', Edge::new(0.5).with_risk(1.0));
+    dex.add_edge('A', 'C', Edge::new(4.0).with_fee(0.02));
+    dex.add_edge('C', 'D', Edge::new(0.25).with_fee(0.02));
+
+    let routes = dex.pareto_routes(&'A'.into(), &'D'.into(), &QueryOptions::new());
+    let routes: Vec<_> = routes.iter().map(ToString::to_string).collect();
+    // A -> C -> D is worse than A -> D in all the objectives.
+    assert_eq!(routes, ["A -> B -> D: 1", "A -> D: 0.99"]);
+}
+
+#[test]
+fn test_pareto_routes_liquidity() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'D', Edge::new(1.0).with_liquidity(100.0));
+    dex.add_edge('A', 'B', Edge::new(2.0).with_liquidity(1_000.0));
+    dex.add_edge('B', 'D', Edge::new(0.45).with_liquidity(1_000.0));
+    dex.add_edge('A', 'C', Edge::new(1.0).with_liquidity(50.0));
+    dex.add_edge('C', 'D', Edge::new(0.9));
+
+    let routes = dex.pareto_routes(&'A'.into(), &'D'.into(), &QueryOptions::new());
+    let liquidity: Vec<_> = routes.iter().map(|path| path.liquidity()).collect();
+    // A -> B -> D is kept for the depth, and A -> C -> D is worse than
+    // A -> D in all the objectives.
+    assert_eq!(routes[0].to_string(), "A -> D: 1");
+    assert_eq!(routes[1].to_string(), "A -> B -> D: 0.9");
+    assert_eq!(liquidity, [Some(100.0), Some(500.0)]);
+}
+
+#[test]
+fn test_path_fees() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_fee(0.5));
+    dex.add_edge('B', 'C', Edge::new(2.0).with_fee(0.5));
+
+    let path = dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(path.rate(), 1.0);
+    assert_eq!(path.fees(), 0.75);
+}
@@ -0,0 +1,401 @@
+//! Parquet export
+
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path};
+use crate::cycle::Cycle;
+use crate::report::Report;
+
+const MAGIC: &[u8] = b"PAR1";
+
+/// The columnar table written as the single row group Parquet file.
+///
+/// The columns are required, and written in the plain encoding without
+/// the compression, which all the Parquet readers, e.g. DuckDB and
+/// Spark, take.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Table {
+    columns: Vec<(String, Column)>,
+}
+
+/// The column values.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Column {
+    Utf8(Vec<String>),
+    Int64(Vec<i64>),
+    Double(Vec<f64>),
+    /// The milliseconds since the Unix epoch.
+    Timestamp(Vec<i64>),
+}
+
+impl Column {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Utf8(values) => values.len(),
+            Self::Int64(values) | Self::Timestamp(values) => values.len(),
+            Self::Double(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The physical type, and the converted type if any.
+    fn types(&self) -> (i32, Option<i32>) {
+        const INT64: i32 = 2;
+        const DOUBLE: i32 = 5;
+        const BYTE_ARRAY: i32 = 6;
+        const UTF8: i32 = 0;
+        const TIMESTAMP_MILLIS: i32 = 9;
+        match self {
+            Self::Utf8(_) => (BYTE_ARRAY, Some(UTF8)),
+            Self::Int64(_) => (INT64, None),
+            Self::Double(_) => (DOUBLE, None),
+            Self::Timestamp(_) => (INT64, Some(TIMESTAMP_MILLIS)),
+        }
+    }
+
+    // The values in the plain encoding.
+    fn plain(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        match self {
+            Self::Utf8(values) => {
+                for value in values {
+                    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                    data.extend_from_slice(value.as_bytes());
+                }
+            }
+            Self::Int64(values) | Self::Timestamp(values) => {
+                values
+                    .iter()
+                    .for_each(|value| data.extend_from_slice(&value.to_le_bytes()));
+            }
+            Self::Double(values) => {
+                values
+                    .iter()
+                    .for_each(|value| data.extend_from_slice(&value.to_le_bytes()));
+            }
+        }
+        data
+    }
+}
+
+impl Table {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the column, of the same length as the others.
+    pub fn column(mut self, name: &str, column: Column) -> Self {
+        if let Some((_, first)) = self.columns.first() {
+            assert_eq!(first.len(), column.len());
+        }
+        self.columns.push((name.to_string(), column));
+        self
+    }
+
+    pub fn columns(&self) -> &[(String, Column)] {
+        &self.columns
+    }
+
+    /// Returns the number of the rows.
+    pub fn len(&self) -> usize {
+        self.columns.first().map_or(0, |(_, column)| column.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the table as the Parquet file.
+    #[instrument(level = "debug", skip_all, err)]
+    pub fn write_parquet<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let mut file = MAGIC.to_vec();
+        let mut chunks = Vec::new();
+        for (_, column) in &self.columns {
+            let data = column.plain();
+            let mut header = Compact::default();
+            header.i32(1, 0); // DATA_PAGE
+            header.i32(2, data.len() as i32);
+            header.i32(3, data.len() as i32);
+            header.begin_struct(5);
+            header.i32(1, column.len() as i32);
+            header.i32(2, 0); // PLAIN
+            header.i32(3, 3); // RLE
+            header.i32(4, 3); // RLE
+            header.end_struct();
+            header.stop();
+            let offset = file.len() as i64;
+            let size = (header.buf.len() + data.len()) as i64;
+            file.extend_from_slice(&header.buf);
+            file.extend_from_slice(&data);
+            chunks.push((offset, size));
+        }
+
+        let rows = self.len() as i64;
+        let mut footer = Compact::default();
+        footer.i32(1, 1);
+        footer.begin_list(2, Compact::STRUCT, self.columns.len() + 1);
+        footer.begin_element();
+        footer.binary(4, b"schema");
+        footer.i32(5, self.columns.len() as i32);
+        footer.end_struct();
+        for (name, column) in &self.columns {
+            let (physical, converted) = column.types();
+            footer.begin_element();
+            footer.i32(1, physical);
+            footer.i32(3, 0); // REQUIRED
+            footer.binary(4, name.as_bytes());
+            if let Some(converted) = converted {
+                footer.i32(6, converted);
+            }
+            footer.end_struct();
+        }
+        footer.i64(3, rows);
+        footer.begin_list(4, Compact::STRUCT, 1);
+        footer.begin_element();
+        footer.begin_list(1, Compact::STRUCT, self.columns.len());
+        for ((name, column), (offset, size)) in self.columns.iter().zip(&chunks) {
+            footer.begin_element();
+            footer.i64(2, *offset);
+            footer.begin_struct(3);
+            footer.i32(1, column.types().0);
+            footer.begin_list(2, Compact::I32, 1);
+            footer.element_i32(0); // PLAIN
+            footer.begin_list(3, Compact::BINARY, 1);
+            footer.element_binary(name.as_bytes());
+            footer.i32(4, 0); // UNCOMPRESSED
+            footer.i64(5, column.len() as i64);
+            footer.i64(6, *size);
+            footer.i64(7, *size);
+            footer.i64(9, *offset);
+            footer.end_struct();
+            footer.end_struct();
+        }
+        footer.i64(2, chunks.iter().map(|(_, size)| size).sum());
+        footer.i64(3, rows);
+        footer.end_struct();
+        footer.binary(6, b"best-rate");
+        footer.stop();
+
+        file.extend_from_slice(&footer.buf);
+        file.extend_from_slice(&(footer.buf.len() as u32).to_le_bytes());
+        file.extend_from_slice(MAGIC);
+        debug!(rows, bytes = file.len(), "parquet");
+        out.write_all(&file)
+    }
+}
+
+// The Thrift compact protocol encoder of the Parquet metadata.
+#[derive(Default)]
+struct Compact {
+    buf: Vec<u8>,
+    last: i16,
+    stack: Vec<i16>,
+}
+
+impl Compact {
+    const I32: u8 = 5;
+    const I64: u8 = 6;
+    const BINARY: u8 = 8;
+    const LIST: u8 = 9;
+    const STRUCT: u8 = 12;
+
+    fn field(&mut self, id: i16, kind: u8) {
+        let delta = id - self.last;
+        if (1..=15).contains(&delta) {
+            self.buf.push((delta as u8) << 4 | kind);
+        } else {
+            self.buf.push(kind);
+            self.varint(zigzag(i64::from(id)));
+        }
+        self.last = id;
+    }
+
+    fn varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.buf.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        self.buf.push(n as u8);
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, Self::I32);
+        self.varint(zigzag(i64::from(value)));
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, Self::I64);
+        self.varint(zigzag(value));
+    }
+
+    fn binary(&mut self, id: i16, value: &[u8]) {
+        self.field(id, Self::BINARY);
+        self.element_binary(value);
+    }
+
+    fn begin_struct(&mut self, id: i16) {
+        self.field(id, Self::STRUCT);
+        self.begin_element();
+    }
+
+    fn end_struct(&mut self) {
+        self.stop();
+        self.last = self.stack.pop().unwrap_or_default();
+    }
+
+    fn begin_list(&mut self, id: i16, kind: u8, len: usize) {
+        self.field(id, Self::LIST);
+        if len < 15 {
+            self.buf.push((len as u8) << 4 | kind);
+        } else {
+            self.buf.push(0xf0 | kind);
+            self.varint(len as u64);
+        }
+    }
+
+    // Begins the struct element of the list.
+    fn begin_element(&mut self) {
+        self.stack.push(self.last);
+        self.last = 0;
+    }
+
+    fn element_i32(&mut self, value: i32) {
+        self.varint(zigzag(i64::from(value)));
+    }
+
+    fn element_binary(&mut self, value: &[u8]) {
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value);
+    }
+
+    fn stop(&mut self) {
+        self.buf.push(0);
+    }
+}
+
+fn zigzag(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
+// The milliseconds since the Unix epoch.
+fn millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as i64)
+}
+
+impl Report {
+    /// Returns the best rate path of each pair, as the `src`, `dst`,
+    /// `rate`, `hops` and `path` columns.
+    pub fn pairs_table(&self) -> Table {
+        let paths = self.paths();
+        Table::new()
+            .column(
+                "src",
+                Column::Utf8(paths.iter().map(|p| p.path[0].to_string()).collect()),
+            )
+            .column(
+                "dst",
+                Column::Utf8(paths.iter().map(|p| p.last().to_string()).collect()),
+            )
+            .column(
+                "rate",
+                Column::Double(paths.iter().map(|p| f64::from(p.rate)).collect()),
+            )
+            .column(
+                "hops",
+                Column::Int64(paths.iter().map(|p| p.len() as i64 - 1).collect()),
+            )
+            .column("path", Column::Utf8(paths.iter().map(route).collect()))
+    }
+
+    /// Returns the arbitrage cycles, as the `cycle`, `len`, `rate` and
+    /// `net_rate` columns.
+    pub fn arbitrage_table(&self) -> Table {
+        let cycles = self.arbitrage();
+        let cycle = |cycle: &Cycle| {
+            let mut vertices: Vec<_> = cycle.edges().iter().map(|(src, ..)| *src).collect();
+            vertices.extend(vertices.first().copied());
+            let names: Vec<_> = vertices.iter().map(|v| v.to_string()).collect();
+            names.join(" -> ")
+        };
+        Table::new()
+            .column("cycle", Column::Utf8(cycles.iter().map(cycle).collect()))
+            .column(
+                "len",
+                Column::Int64(cycles.iter().map(|c| c.len() as i64).collect()),
+            )
+            .column(
+                "rate",
+                Column::Double(cycles.iter().map(|c| f64::from(c.rate())).collect()),
+            )
+            .column(
+                "net_rate",
+                Column::Double(cycles.iter().map(|c| f64::from(c.net_rate())).collect()),
+            )
+    }
+}
+
+fn route(path: &Path) -> String {
+    let names: Vec<_> = path.path.iter().map(|v| v.to_string()).collect();
+    names.join(" -> ")
+}
+
+impl Dex {
+    /// Returns the rates of all the edges as of `at`, as the `time`,
+    /// `src`, `dst`, `rate` and `effective_rate` columns.
+    ///
+    /// The time is of the edge timestamp, or `at` if none, so that the
+    /// tables of the snapshots taken over time make the rate history.
+    pub fn rates_table(&self, at: SystemTime) -> Table {
+        let edges: Vec<_> = self
+            .edges
+            .iter()
+            .flat_map(|(src, edges)| edges.iter().map(move |(dst, edge)| (src, dst, edge)))
+            .collect();
+        Table::new()
+            .column(
+                "time",
+                Column::Timestamp(
+                    edges
+                        .iter()
+                        .map(|(_, _, edge)| millis(edge.timestamp().unwrap_or(at)))
+                        .collect(),
+                ),
+            )
+            .column(
+                "src",
+                Column::Utf8(edges.iter().map(|(src, ..)| src.to_string()).collect()),
+            )
+            .column(
+                "dst",
+                Column::Utf8(edges.iter().map(|(_, dst, _)| dst.to_string()).collect()),
+            )
+            .column(
+                "rate",
+                Column::Double(
+                    edges
+                        .iter()
+                        .map(|(_, _, edge)| f64::from(edge.rate()))
+                        .collect(),
+                ),
+            )
+            .column(
+                "effective_rate",
+                Column::Double(
+                    edges
+                        .iter()
+                        .map(|(_, _, edge)| f64::from(edge.effective_rate(None)))
+                        .collect(),
+                ),
+            )
+    }
+}
+
+#[cfg(test)]
+mod test;
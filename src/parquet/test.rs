@@ -0,0 +1,200 @@
+use std::fs::{self, File};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ::parquet::file::reader::{FileReader, SerializedFileReader};
+use ::parquet::record::{Row, RowAccessor};
+
+use super::{zigzag, Column, Compact, Table};
+use crate::edge::Edge;
+use crate::Dex;
+
+// Returns the footer of the Parquet file.
+fn footer(file: &[u8]) -> &[u8] {
+    assert_eq!(&file[..4], b"PAR1");
+    assert_eq!(&file[file.len() - 4..], b"PAR1");
+    let mut len = [0; 4];
+    len.copy_from_slice(&file[file.len() - 8..file.len() - 4]);
+    let len = u32::from_le_bytes(len) as usize;
+    &file[file.len() - 8 - len..file.len() - 8]
+}
+
+// Reads back the table written as the Parquet file.
+fn read_back(table: &Table) -> (Vec<String>, Vec<Row>) {
+    let path = std::env::temp_dir().join(format!(
+        "best-rate-{}-{}.parquet",
+        std::process::id(),
+        table
+            .columns()
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join("-"),
+    ));
+    table
+        .write_parquet(&mut File::create(&path).unwrap())
+        .unwrap();
+    let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+    let metadata = reader.metadata().file_metadata();
+    assert_eq!(metadata.num_rows(), table.len() as i64);
+    let names = metadata
+        .schema_descr()
+        .columns()
+        .iter()
+        .map(|column| column.name().to_string())
+        .collect();
+    let rows = reader
+        .get_row_iter(None)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    fs::remove_file(path).unwrap();
+    (names, rows)
+}
+
+#[test]
+fn test_compact() {
+    assert_eq!(zigzag(0), 0);
+    assert_eq!(zigzag(-1), 1);
+    assert_eq!(zigzag(1), 2);
+    assert_eq!(zigzag(-64), 127);
+
+    let mut compact = Compact::default();
+    compact.i32(1, 1);
+    compact.i64(3, 300);
+    compact.begin_struct(20);
+    compact.binary(1, b"a");
+    compact.end_struct();
+    compact.begin_list(21, Compact::I32, 2);
+    compact.element_i32(0);
+    compact.element_i32(-1);
+    compact.stop();
+    assert_eq!(
+        compact.buf,
+        [
+            0x15, 0x02, // i32 field 1
+            0x26, 0xd8, 0x04, // i64 field 3, delta 2
+            0x0c, 0x28, // struct field 20, long form
+            0x18, 0x01, b'a', 0x00, // binary field 1, stop
+            0x19, 0x25, 0x00, 0x01, // list field 21, 2 i32
+            0x00,
+        ]
+    );
+}
+
+#[test]
+fn test_table() {
+    let table = Table::new()
+        .column("name", Column::Utf8(vec!["A".into(), "BC".into()]))
+        .column("value", Column::Double(vec![1.5, -2.0]));
+    assert_eq!(table.len(), 2);
+    assert_eq!(table.columns()[1].0, "value");
+
+    let mut file = Vec::new();
+    table.write_parquet(&mut file).unwrap();
+    // The plain encoded values follow the page headers.
+    let data = [1, 0, 0, 0, b'A', 2, 0, 0, 0, b'B', b'C'];
+    assert!(file.windows(data.len()).any(|w| w == data));
+    let data: Vec<_> = [1.5f64, -2.0]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    assert!(file.windows(data.len()).any(|w| w == data));
+    let metadata = footer(&file);
+    assert!(metadata.windows(5).any(|w| w == b"value"));
+    assert!(metadata.ends_with(b"best-rate\x00"));
+
+    let mut file = Vec::new();
+    Table::new().write_parquet(&mut file).unwrap();
+    assert_eq!(footer(&file).len(), file.len() - 12);
+}
+
+#[test]
+fn test_table_read_back() {
+    let table = Table::new()
+        .column("name", Column::Utf8(vec!["A".into(), "BC".into()]))
+        .column("count", Column::Int64(vec![1, -20]))
+        .column("value", Column::Double(vec![1.5, -2.0]))
+        .column("time", Column::Timestamp(vec![0, 1_500]));
+    let (names, rows) = read_back(&table);
+    assert_eq!(names, ["name", "count", "value", "time"]);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].get_string(0).unwrap(), "A");
+    assert_eq!(rows[1].get_string(0).unwrap(), "BC");
+    assert_eq!(rows[1].get_long(1).unwrap(), -20);
+    assert_eq!(rows[0].get_double(2).unwrap(), 1.5);
+    assert_eq!(rows[1].get_double(2).unwrap(), -2.0);
+    assert_eq!(rows[1].get_timestamp_millis(3).unwrap(), 1_500);
+
+    let (names, rows) = read_back(&Table::new());
+    assert!(names.is_empty());
+    assert!(rows.is_empty());
+}
+
+#[test]
+#[should_panic]
+fn test_table_length_mismatch() {
+    let _ = Table::new()
+        .column("a", Column::Int64(vec![1]))
+        .column("b", Column::Int64(vec![1, 2]));
+}
+
+#[test]
+fn test_report_tables() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 0.2);
+
+    let report = dex.report(0.0001);
+    let pairs = report.pairs_table();
+    assert_eq!(pairs.len(), 6);
+    let names: Vec<_> = pairs
+        .columns()
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(names, ["src", "dst", "rate", "hops", "path"]);
+    let arbitrage = report.arbitrage_table();
+    assert_eq!(arbitrage.len(), report.arbitrage().len());
+    match &arbitrage.columns()[0].1 {
+        Column::Utf8(cycles) => assert_eq!(cycles[0], "A -> B -> C -> A"),
+        column => panic!("{column:?}"),
+    }
+    let (names, rows) = read_back(&pairs);
+    assert_eq!(names, ["src", "dst", "rate", "hops", "path"]);
+    let row = rows
+        .iter()
+        .find(|row| row.get_string(0).unwrap() == "A" && row.get_string(1).unwrap() == "C")
+        .unwrap();
+    assert_eq!(row.get_double(2).unwrap(), 6.0);
+    assert_eq!(row.get_long(3).unwrap(), 2);
+    assert_eq!(row.get_string(4).unwrap(), "A -> B -> C");
+    let (_, rows) = read_back(&arbitrage);
+    assert_eq!(rows[0].get_string(0).unwrap(), "A -> B -> C -> A");
+}
+
+#[test]
+fn test_rates_table() {
+    let at = UNIX_EPOCH + Duration::from_secs(1_000);
+    let quoted = UNIX_EPOCH + Duration::from_millis(1_500);
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_timestamp(quoted));
+    dex.add_rate('B', 'C', 4.0);
+
+    let table = dex.rates_table(at);
+    assert_eq!(table.len(), 4);
+    assert_eq!(
+        table.columns()[0].1,
+        Column::Timestamp(vec![1_500, 1_500, 1_000_000, 1_000_000])
+    );
+    assert_eq!(
+        table.columns()[3].1,
+        Column::Double(vec![2.0, 0.5, 4.0, 0.25])
+    );
+    let (names, rows) = read_back(&dex.rates_table(SystemTime::now()));
+    assert_eq!(names, ["time", "src", "dst", "rate", "effective_rate"]);
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[0].get_timestamp_millis(0).unwrap(), 1_500);
+    assert_eq!(rows[0].get_string(1).unwrap(), "A");
+    assert_eq!(rows[0].get_double(3).unwrap(), 2.0);
+}
@@ -0,0 +1,209 @@
+//! Graph partitioning for the distributed query serving
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Edge, Path, Vertex};
+use crate::query::QueryOptions;
+
+/// The boundary-vertex summary of the partition, the best rates between
+/// its boundary vertices within the partition.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub rates: BTreeMap<(Vertex, Vertex), f32>,
+}
+
+/// The partition served by the process, local or remote.
+pub trait Shard: Debug + Send + Sync {
+    /// Returns the boundary-vertex summary.
+    fn summary(&self) -> Summary;
+
+    /// Returns the best rates from `src` within the partition.
+    fn rates_from(&self, src: &Vertex) -> BTreeMap<Vertex, f32>;
+
+    /// Returns the best rates into `dst` within the partition.
+    fn rates_to(&self, dst: &Vertex) -> BTreeMap<Vertex, f32>;
+}
+
+/// The partition metadata, the partition of each vertex and the cut
+/// edges between the partitions.
+#[derive(Clone, Debug, Default)]
+pub struct PartitionMap {
+    assignment: BTreeMap<Vertex, usize>,
+    cut: Vec<(Vertex, Vertex, Edge)>,
+}
+
+impl PartitionMap {
+    pub fn partition(&self, v: &Vertex) -> Option<usize> {
+        self.assignment.get(v).copied()
+    }
+
+    /// Returns the directed edges between the partitions.
+    pub fn cut(&self) -> &[(Vertex, Vertex, Edge)] {
+        &self.cut
+    }
+}
+
+/// The partition of the graph, with the edges within the partition.
+#[derive(Clone, Debug)]
+pub struct Partition {
+    dex: Dex,
+    boundary: BTreeSet<Vertex>,
+}
+
+impl Partition {
+    pub fn dex(&self) -> &Dex {
+        &self.dex
+    }
+
+    /// Returns the vertices with the cut edges.
+    pub fn boundary(&self) -> &BTreeSet<Vertex> {
+        &self.boundary
+    }
+}
+
+impl Shard for Partition {
+    fn summary(&self) -> Summary {
+        let options = QueryOptions::new();
+        let mut rates = BTreeMap::new();
+        for src in &self.boundary {
+            let paths = self.dex.get_best_rates_from(src, &options);
+            for dst in &self.boundary {
+                if let Some(path) = paths.get(dst) {
+                    rates.insert((*src, *dst), path.rate());
+                }
+            }
+        }
+        Summary { rates }
+    }
+
+    fn rates_from(&self, src: &Vertex) -> BTreeMap<Vertex, f32> {
+        self.dex
+            .get_best_rates_from(src, &QueryOptions::new())
+            .into_iter()
+            .map(|(v, path)| (v, path.rate()))
+            .collect()
+    }
+
+    fn rates_to(&self, dst: &Vertex) -> BTreeMap<Vertex, f32> {
+        self.dex.valuations(dst)
+    }
+}
+
+/// The coordinator stitching the cross-partition routes.
+///
+/// The route is searched on the overlay graph of the boundary vertices,
+/// connected by the partition summaries and the cut edges, plus the
+/// source and the destination connected to the boundary of their
+/// partitions.  The resulting path is the sequence of the waypoints,
+/// each leg resolved within the partition.
+#[derive(Debug)]
+pub struct Coordinator {
+    map: PartitionMap,
+    shards: Vec<Box<dyn Shard>>,
+    summaries: Vec<Summary>,
+}
+
+impl Coordinator {
+    pub fn new(map: PartitionMap) -> Self {
+        Self {
+            map,
+            shards: Vec::new(),
+            summaries: Vec::new(),
+        }
+    }
+
+    /// Adds the shard of the next partition.
+    pub fn shard<S: Shard + 'static>(mut self, shard: S) -> Self {
+        self.summaries.push(shard.summary());
+        self.shards.push(Box::new(shard));
+        self
+    }
+
+    /// Refreshes the boundary-vertex summaries, e.g. after the update.
+    pub fn refresh(&mut self) {
+        self.summaries = self.shards.iter().map(|shard| shard.summary()).collect();
+    }
+
+    /// Returns the best `src -> dst` route across the partitions, with
+    /// the boundary vertices as the waypoints.
+    #[instrument(level = "debug", skip(self))]
+    pub fn get_best_rate(&self, src: &Vertex, dst: &Vertex) -> Option<Path> {
+        let src_shard = self.shards.get(self.map.partition(src)?)?;
+        let dst_shard = self.shards.get(self.map.partition(dst)?)?;
+
+        let mut overlay = Dex::new();
+        let mut add = |src: Vertex, dst: Vertex, edge: Edge| {
+            if src != dst && edge.rate > 0.0 {
+                overlay.edges.entry(dst).or_default();
+                overlay.edges.entry(src).or_default().insert(dst, edge);
+            }
+        };
+        for summary in &self.summaries {
+            for ((src, dst), rate) in &summary.rates {
+                add(*src, *dst, Edge::new(*rate));
+            }
+        }
+        for (src, dst, edge) in &self.map.cut {
+            add(*src, *dst, edge.clone());
+        }
+        for (v, rate) in src_shard.rates_from(src) {
+            add(*src, v, Edge::new(rate));
+        }
+        for (v, rate) in dst_shard.rates_to(dst) {
+            add(v, *dst, Edge::new(rate));
+        }
+        debug!(vertices = overlay.edges.len(), "overlay");
+        overlay.get_best_rate(src, dst)
+    }
+}
+
+impl Dex {
+    /// Partitions the graph into the `count` partitions by the
+    /// `assign`ed partition of each vertex.
+    ///
+    /// Each partition keeps the edges within it, and the edges across
+    /// the partitions are kept in the [`PartitionMap`] for the
+    /// [`Coordinator`].
+    #[instrument(level = "debug", skip(self, assign))]
+    pub fn partition<F>(&self, count: usize, assign: F) -> (PartitionMap, Vec<Partition>)
+    where
+        F: Fn(&Vertex) -> usize,
+    {
+        let mut map = PartitionMap::default();
+        let mut partitions = vec![
+            Partition {
+                dex: Dex::new(),
+                boundary: BTreeSet::new(),
+            };
+            count
+        ];
+        for v in self.vertices() {
+            let i = assign(v);
+            assert!(i < count);
+            map.assignment.insert(*v, i);
+            partitions[i].dex.edges.entry(*v).or_default();
+        }
+        for (src, edges) in &self.edges {
+            let i = map.assignment[src];
+            for (dst, edge) in edges {
+                let j = map.assignment[dst];
+                if i == j {
+                    let edges = partitions[i].dex.edges.get_mut(src).unwrap();
+                    edges.insert(*dst, edge.clone());
+                } else {
+                    partitions[i].boundary.insert(*src);
+                    partitions[j].boundary.insert(*dst);
+                    map.cut.push((*src, *dst, edge.clone()));
+                }
+            }
+        }
+        debug!(cut = map.cut.len(), "partitioned");
+        (map, partitions)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,63 @@
+use crate::{Dex, Vertex};
+
+fn assign(v: &Vertex) -> usize {
+    if v.as_str() < "M" {
+        0
+    } else {
+        1
+    }
+}
+
+#[test]
+fn test_partition() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('A', 'C', 5.0);
+    dex.add_rate('C', 'X', 1.5);
+    dex.add_rate('X', 'Y', 2.0);
+    dex.add_rate('Y', 'Z', 0.5);
+
+    let (map, partitions) = dex.partition(2, assign);
+    assert_eq!(map.partition(&'A'.into()), Some(0));
+    assert_eq!(map.partition(&'Y'.into()), Some(1));
+    assert_eq!(map.cut().len(), 2);
+    assert_eq!(partitions[0].boundary().len(), 1);
+    assert_eq!(partitions[0].dex().vertices().count(), 3);
+    assert_eq!(partitions[1].boundary().iter().next(), Some(&'X'.into()));
+}
+
+#[test]
+fn test_coordinator() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('A', 'C', 5.0);
+    dex.add_rate('C', 'X', 1.5);
+    dex.add_rate('B', 'Y', 1.0);
+    dex.add_rate('X', 'Y', 2.0);
+    dex.add_rate('Y', 'Z', 0.5);
+    dex.add_rate('D', 'E', 1.0);
+
+    let (map, partitions) = dex.partition(2, assign);
+    let mut coordinator = super::Coordinator::new(map);
+    for partition in partitions {
+        coordinator = coordinator.shard(partition);
+    }
+
+    for (src, dst) in [('A', 'Z'), ('Z', 'A'), ('A', 'C'), ('Y', 'X')] {
+        let (src, dst) = (src.into(), dst.into());
+        let path = coordinator.get_best_rate(&src, &dst).unwrap();
+        let expected = dex.get_best_rate(&src, &dst).unwrap();
+        assert!((path.rate() / expected.rate() - 1.0).abs() < 1e-6);
+    }
+    let path = coordinator.get_best_rate(&'A'.into(), &'Z'.into()).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> X -> Z: 9");
+
+    assert!(coordinator
+        .get_best_rate(&'A'.into(), &'E'.into())
+        .is_none());
+    assert!(coordinator
+        .get_best_rate(&'A'.into(), &'Q'.into())
+        .is_none());
+}
@@ -0,0 +1,126 @@
+//! Pegged-asset groups
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path, Vertex};
+use crate::query::QueryOptions;
+
+/// The group of the assets pegged to the same reference, e.g. the USD
+/// stablecoins.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PegGroup {
+    members: Vec<Vertex>,
+    tolerance: f32,
+}
+
+impl PegGroup {
+    /// Returns the members, the reference first.
+    pub fn members(&self) -> &[Vertex] {
+        &self.members
+    }
+
+    pub fn reference(&self) -> &Vertex {
+        &self.members[0]
+    }
+
+    /// Returns the relative deviation from the reference tolerated for
+    /// the member to be on the peg.
+    pub fn tolerance(&self) -> f32 {
+        self.tolerance
+    }
+}
+
+impl Dex {
+    /// Groups the pegged `members` under the `name`, e.g. `USD` for the
+    /// USD stablecoins, with the first member as the reference.
+    ///
+    /// The member is on the peg while its best rate to the reference
+    /// deviates from 1.0 by `tolerance` or less, e.g. `0.005` for 50
+    /// bps.
+    pub fn add_peg_group<V>(&mut self, name: &str, members: &[V], tolerance: f32)
+    where
+        V: Into<Vertex> + Copy,
+    {
+        assert!(!members.is_empty() && tolerance >= 0.0);
+        let members = members
+            .iter()
+            .map(|v| self.canonical((*v).into()))
+            .collect();
+        self.pegs
+            .insert(name.to_string(), PegGroup { members, tolerance });
+    }
+
+    pub fn peg_group(&self, name: &str) -> Option<&PegGroup> {
+        self.pegs.get(name)
+    }
+
+    /// Returns the members of the group currently on the peg.
+    pub fn pegged(&self, name: &str) -> Vec<Vertex> {
+        let group = match self.pegs.get(name) {
+            Some(group) => group,
+            None => return Vec::new(),
+        };
+        let reference = group.reference();
+        group
+            .members
+            .iter()
+            .filter(|v| {
+                if *v == reference {
+                    return true;
+                }
+                let rate = self.get_best_rate(v, reference).map(|path| path.rate());
+                let is_pegged = matches!(rate, Some(rate) if (rate - 1.0).abs() <= group.tolerance);
+                if !is_pegged {
+                    debug!(%v, %reference, ?rate, "off the peg");
+                }
+                is_pegged
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Returns the best rate path from any member of the group on the
+    /// peg to `dst`.
+    #[instrument(level = "debug", skip(self, options))]
+    pub fn get_best_rate_from_group(
+        &self,
+        group: &str,
+        dst: &Vertex,
+        options: &QueryOptions,
+    ) -> Option<Path> {
+        self.pegged(group)
+            .iter()
+            .filter(|src| *src != dst)
+            .filter_map(|src| self.get_best_rate_with(src, dst, options))
+            .fold(None, best)
+    }
+
+    /// Returns the best rate path from `src` to any member of the group
+    /// on the peg.
+    #[instrument(level = "debug", skip(self, options))]
+    pub fn get_best_rate_to_group(
+        &self,
+        src: &Vertex,
+        group: &str,
+        options: &QueryOptions,
+    ) -> Option<Path> {
+        let dsts = self.pegged(group);
+        if dsts.contains(src) {
+            return Some(Path::new(*src));
+        }
+        let paths = self.get_best_rates_from(src, options);
+        dsts.iter()
+            .filter_map(|dst| paths.get(dst).cloned())
+            .fold(None, best)
+    }
+}
+
+fn best(best: Option<Path>, path: Path) -> Option<Path> {
+    match best {
+        Some(best) if best.rate() >= path.rate() => Some(best),
+        _ => Some(path),
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,59 @@
+use crate::query::QueryOptions;
+use crate::test::vertex;
+use crate::Dex;
+
+fn dex() -> Dex {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("USDT"), vertex("USDC"), 0.9995);
+    dex.add_rate(vertex("DAI"), vertex("USDC"), 1.0002);
+    dex.add_rate(vertex("UST"), vertex("USDC"), 0.2);
+    dex.add_rate(vertex("ETH"), vertex("USDC"), 2000.0);
+    dex.add_rate(vertex("ETH"), vertex("USDT"), 2003.0);
+    dex.add_rate(vertex("ETH"), vertex("DAI"), 1999.0);
+    dex.add_rate(vertex("ETH"), vertex("UST"), 10000.0);
+    dex.add_peg_group(
+        "USD",
+        &[vertex("USDC"), vertex("USDT"), vertex("DAI"), vertex("UST")],
+        0.005,
+    );
+    dex
+}
+
+#[test]
+fn test_pegged() {
+    let dex = dex();
+    let group = dex.peg_group("USD").unwrap();
+    assert_eq!(group.reference(), &vertex("USDC"));
+    assert_eq!(group.members().len(), 4);
+    let pegged: Vec<_> = dex.pegged("USD").iter().map(|v| v.to_string()).collect();
+    assert_eq!(pegged, ["USDC", "USDT", "DAI"]);
+    assert!(dex.pegged("EUR").is_empty());
+}
+
+#[test]
+fn test_get_best_rate_to_group() {
+    let dex = dex();
+    let options = QueryOptions::default();
+    let path = dex
+        .get_best_rate_to_group(&vertex("ETH"), "USD", &options)
+        .unwrap();
+    // The off-peg UST is not picked in spite of the rate.
+    assert_eq!(path.to_string(), "ETH -> USDT: 2003");
+    let path = dex
+        .get_best_rate_to_group(&vertex("DAI"), "USD", &options)
+        .unwrap();
+    assert_eq!(path.rate(), 1.0);
+    assert!(dex
+        .get_best_rate_to_group(&vertex("ETH"), "EUR", &options)
+        .is_none());
+}
+
+#[test]
+fn test_get_best_rate_from_group() {
+    let dex = dex();
+    let options = QueryOptions::default();
+    let path = dex
+        .get_best_rate_from_group("USD", &vertex("ETH"), &options)
+        .unwrap();
+    assert!(path.to_string().starts_with("DAI -> ETH"));
+}
@@ -0,0 +1,77 @@
+//! Search buffer pool
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+use super::{Path, Vertex};
+use crate::query::Label;
+
+/// The maximum number of the paths kept for the reuse.
+const MAX_FREE: usize = 4096;
+
+thread_local! {
+    static BUFFERS: RefCell<SearchBuffers> = RefCell::new(SearchBuffers::default());
+}
+
+// The search queue, the visited labels, and the recycled paths, reused
+// across the queries so that the paths extended in the steady state
+// don't allocate.
+#[derive(Debug, Default)]
+pub(crate) struct SearchBuffers {
+    pub(crate) queue: VecDeque<Path>,
+    free: Vec<Path>,
+    visited: HashMap<Vertex, Vec<Label>>,
+}
+
+impl SearchBuffers {
+    // Calls `f` with the buffers of the thread, or with the new ones in
+    // case they're already in use, e.g. by the nested search.
+    pub(crate) fn with<F, R>(f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        BUFFERS.with(|buffers| match buffers.try_borrow_mut() {
+            Ok(mut buffers) => f(&mut buffers),
+            Err(_) => f(&mut Self::default()),
+        })
+    }
+
+    // Clears the buffers for the new search of the graph of `vertices`.
+    pub(crate) fn reset(&mut self, vertices: usize) {
+        while let Some(path) = self.queue.pop_front() {
+            self.recycle(path);
+        }
+        // The visited labels are cleared in place to keep the
+        // allocations, unless they're for the much larger graph.
+        if self.visited.len() > 2 * vertices + 64 {
+            self.visited = HashMap::new();
+        } else {
+            self.visited.values_mut().for_each(Vec::clear);
+        }
+    }
+
+    // Returns the visited labels of the vertex.
+    pub(crate) fn visited(&mut self, v: Vertex) -> &mut Vec<Label> {
+        self.visited.entry(v).or_default()
+    }
+
+    // Returns the copy of the `path`, in the recycled one if any.
+    pub(crate) fn copy(&mut self, path: &Path) -> Path {
+        match self.free.pop() {
+            Some(mut copy) => {
+                copy.clone_from(path);
+                copy
+            }
+            None => path.clone(),
+        }
+    }
+
+    pub(crate) fn recycle(&mut self, path: Path) {
+        if self.free.len() < MAX_FREE {
+            self.free.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
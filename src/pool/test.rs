@@ -0,0 +1,50 @@
+use super::SearchBuffers;
+use crate::query::QueryOptions;
+use crate::{Dex, Path};
+
+#[test]
+fn test_search_buffers_reuse() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1.4);
+    dex.add_rate('A', 'C', 0.1);
+    dex.add_rate('A', 'D', 0.055);
+    dex.add_rate('B', 'C', 0.2);
+    dex.add_rate('C', 'D', 0.2);
+    dex.add_rate('D', 'F', 2.5);
+
+    let first = dex.get_best_rate(&'A'.into(), &'F'.into()).unwrap();
+    for _ in 0..3 {
+        let path = dex.get_best_rate(&'A'.into(), &'F'.into()).unwrap();
+        assert_eq!(path.to_string(), first.to_string());
+        let path = dex.get_best_rate(&'F'.into(), &'A'.into()).unwrap();
+        assert_eq!(path.to_string(), "F -> D -> C -> A: 20");
+    }
+
+    // The stale state of the other graph doesn't leak into the search.
+    let mut other = Dex::new();
+    other.add_rate('A', 'F', 0.1);
+    let path = other.get_best_rate(&'A'.into(), &'F'.into()).unwrap();
+    assert_eq!(path.to_string(), "A -> F: 0.1");
+    let options = QueryOptions::new();
+    let mut buffers = SearchBuffers::default();
+    for _ in 0..2 {
+        let paths = dex
+            .search_with(&mut buffers, &'A'.into(), None, &options)
+            .unwrap()
+            .into_value();
+        assert_eq!(paths.len(), 5);
+        assert!(buffers.queue.is_empty());
+    }
+}
+
+#[test]
+fn test_search_buffers_copy() {
+    let mut buffers = SearchBuffers::default();
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    let long = dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap();
+    buffers.recycle(long);
+    let path = buffers.copy(&Path::new('C'.into()));
+    assert_eq!(path.to_string(), "C: 1");
+    assert_eq!(path.len(), 1);
+}
@@ -0,0 +1,124 @@
+//! Progress of the long computations
+
+use std::fmt;
+use std::io::{self, Write};
+
+/// The default width of the [`ProgressBar`], in the characters.
+const WIDTH: usize = 30;
+
+/// The long computation reporting its progress.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// The best rate of all the pairs, by the source.
+    Pairs,
+    /// The arbitrage cycle scan, by the start vertex.
+    Arbitrage,
+    /// The rate matrix, by the row.
+    Matrix,
+    /// The [`HubIndex`](crate::hub::HubIndex) precomputation, by the
+    /// hub.
+    HubIndex,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Pairs => "pairs",
+            Self::Arbitrage => "arbitrage",
+            Self::Matrix => "matrix",
+            Self::HubIndex => "hub index",
+        };
+        f.pad(name)
+    }
+}
+
+/// The progress of the stage, the `done` steps out of the `total`.
+///
+/// The callback is called after each step, so the last call of the
+/// stage is with `done == total`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub stage: Stage,
+    pub done: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    /// Returns the ratio of the steps done, from 0.0 to 1.0.
+    pub fn ratio(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.done as f32 / self.total as f32
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done >= self.total
+    }
+}
+
+/// The text progress bar redrawn in place, e.g. on the standard error.
+///
+/// It's redrawn only as the bar or the stage changes, and moves on to
+/// the next line once the stage is done.
+#[derive(Debug)]
+pub struct ProgressBar<W> {
+    out: W,
+    width: Option<usize>,
+    // The stage and the filled width drawn last.
+    last: Option<(Stage, usize)>,
+}
+
+impl<W: Write> ProgressBar<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            width: None,
+            last: None,
+        }
+    }
+
+    /// Sets the width of the bar, without the stage and the counts.
+    pub fn with_width(mut self, width: usize) -> Self {
+        assert!(width > 0);
+        self.width = Some(width);
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width.unwrap_or(WIDTH)
+    }
+
+    /// Draws the progress.
+    pub fn update(&mut self, progress: Progress) -> io::Result<()> {
+        let width = self.width();
+        let filled = (progress.ratio() * width as f32) as usize;
+        let filled = filled.min(width);
+        if self.last == Some((progress.stage, filled)) && !progress.is_done() {
+            return Ok(());
+        }
+        self.last = Some((progress.stage, filled));
+        write!(
+            self.out,
+            "\r{:<10} [{}{}] {}/{}",
+            progress.stage,
+            "#".repeat(filled),
+            " ".repeat(width - filled),
+            progress.done,
+            progress.total,
+        )?;
+        if progress.is_done() {
+            self.last = None;
+            writeln!(self.out)?;
+        }
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,91 @@
+use super::{Progress, ProgressBar, Stage};
+use crate::Dex;
+
+fn dex() -> Dex {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 2.0);
+    dex.add_rate('C', 'A', 0.3);
+    dex
+}
+
+#[test]
+fn test_progress() {
+    let progress = Progress {
+        stage: Stage::Pairs,
+        done: 1,
+        total: 4,
+    };
+    assert_eq!(progress.ratio(), 0.25);
+    assert!(!progress.is_done());
+    let empty = Progress {
+        done: 0,
+        total: 0,
+        ..progress
+    };
+    assert_eq!(empty.ratio(), 1.0);
+    assert!(empty.is_done());
+    assert_eq!(Stage::HubIndex.to_string(), "hub index");
+}
+
+#[test]
+fn test_progress_bar() {
+    let mut bar = ProgressBar::new(Vec::new()).with_width(4);
+    for done in 1..=8 {
+        bar.update(Progress {
+            stage: Stage::Matrix,
+            done,
+            total: 8,
+        })
+        .unwrap();
+    }
+    let out = String::from_utf8(bar.into_inner()).unwrap();
+    assert_eq!(
+        out,
+        "\rmatrix     [    ] 1/8\
+         \rmatrix     [#   ] 2/8\
+         \rmatrix     [##  ] 4/8\
+         \rmatrix     [### ] 6/8\
+         \rmatrix     [####] 8/8\n"
+    );
+}
+
+#[test]
+fn test_report_progress() {
+    let dex = dex();
+    let mut calls = Vec::new();
+    let report = dex.report_with_progress(1e-4, |progress| calls.push(progress));
+    assert_eq!(report.arbitrage().len(), 1);
+    let stages: Vec<_> = calls.iter().map(|p| (p.stage, p.done, p.total)).collect();
+    assert_eq!(
+        stages,
+        [
+            (Stage::Pairs, 1, 3),
+            (Stage::Pairs, 2, 3),
+            (Stage::Pairs, 3, 3),
+            (Stage::Arbitrage, 1, 3),
+            (Stage::Arbitrage, 2, 3),
+            (Stage::Arbitrage, 3, 3),
+        ]
+    );
+}
+
+#[test]
+fn test_matrix_progress() {
+    let dex = dex();
+    let mut calls = Vec::new();
+    let matrix = dex.matrix_with_progress(None, |progress| calls.push(progress));
+    assert_eq!(matrix, dex.matrix(None));
+    assert_eq!(calls.len(), 3);
+    assert!(calls.iter().all(|p| p.stage == Stage::Matrix));
+    assert!(calls[2].is_done());
+
+    let mut calls = Vec::new();
+    let index = dex.hub_index_with_progress(vec!['A'.into(), 'B'.into()], |progress| {
+        calls.push(progress)
+    });
+    assert_eq!(index.hubs().len(), 2);
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[1].stage, Stage::HubIndex);
+    assert!(calls[1].is_done());
+}
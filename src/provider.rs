@@ -0,0 +1,181 @@
+//! Rate providers with priority
+
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use tracing::{debug, warn};
+
+use super::{Dex, Edge, Vertex};
+use crate::outlier::Outlier;
+
+/// The registered provider handle.
+#[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct ProviderId(usize);
+
+/// The rate provider, e.g. institutional feed or scraped data.
+///
+/// The higher priority, or trust, provider rate overrides the lower
+/// priority one.  The lower priority rate is only used when there is
+/// no rate from the higher priority providers.
+#[derive(Clone, Debug)]
+pub struct Provider {
+    name: String,
+    priority: u32,
+    pub(crate) updated_at: Option<SystemTime>,
+    pub(crate) down: bool,
+}
+
+impl Provider {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    /// Checks if the provider is marked down, see [`Dex::mark_down`].
+    pub fn is_down(&self) -> bool {
+        self.down
+    }
+
+    /// Returns the time of the last rate from the provider.
+    pub fn updated_at(&self) -> Option<SystemTime> {
+        self.updated_at
+    }
+}
+
+impl Dex {
+    pub fn register_provider(&mut self, name: &str, priority: u32) -> ProviderId {
+        self.providers.push(Provider {
+            name: name.to_string(),
+            priority,
+            updated_at: None,
+            down: false,
+        });
+        ProviderId(self.providers.len() - 1)
+    }
+
+    pub fn provider(&self, id: ProviderId) -> Option<&Provider> {
+        self.providers.get(id.0)
+    }
+
+    pub(crate) fn provider_mut(&mut self, id: ProviderId) -> Option<&mut Provider> {
+        self.providers.get_mut(id.0)
+    }
+
+    pub fn providers(&self) -> impl Iterator<Item = (ProviderId, &Provider)> {
+        self.providers
+            .iter()
+            .enumerate()
+            .map(|(i, provider)| (ProviderId(i), provider))
+    }
+
+    /// Adds the `src -> dst` rate quoted by the provider.
+    ///
+    /// The edge rate is updated only when the provider is the highest
+    /// priority one quoting the pair.  The rate rejected by the
+    /// [`OutlierGuard`](crate::outlier::OutlierGuard) is not kept as
+    /// the quote either, not to take over the pair later.
+    pub fn add_provider_rate<V: Into<Vertex>>(
+        &mut self,
+        id: ProviderId,
+        src: V,
+        dst: V,
+        rate: f32,
+    ) -> Option<Outlier> {
+        let src = src.into();
+        let dst = dst.into();
+        assert!(src != dst && rate != 0.0);
+        assert!(id.0 < self.providers.len());
+        let src = self.canonical(src);
+        let dst = self.canonical(dst);
+        if src == dst {
+            warn!(provider = ?id, %src, "ignored provider rate between the aliases");
+            return None;
+        }
+        self.providers[id.0].updated_at = Some(self.now());
+        if let Some(guard) = self.outlier_guard.filter(|guard| guard.is_reject()) {
+            if let Some(outlier) = self.check_rate(&src, &dst, rate, &guard) {
+                warn!(provider = ?id, %src, %dst, %outlier, "rejected provider rate");
+                return Some(outlier);
+            }
+        }
+        let (pair, rate) = pair(src, dst, rate);
+        self.quotes.entry(pair).or_default().insert(id, rate);
+        self.update_provider_rate(pair)
+    }
+
+    /// Removes the `src -> dst` rate quoted by the provider, and falls
+    /// back to the next highest priority provider rate, if any.
+    pub fn remove_provider_rate<V: Into<Vertex>>(&mut self, id: ProviderId, src: V, dst: V) {
+        let (pair, _) = pair(self.canonical(src.into()), self.canonical(dst.into()), 1.0);
+        let quotes = match self.quotes.get_mut(&pair) {
+            Some(quotes) => quotes,
+            None => return,
+        };
+        if quotes.remove(&id).is_none() {
+            return;
+        }
+        if quotes.is_empty() {
+            self.quotes.remove(&pair);
+            for (src, dst) in [pair, (pair.1, pair.0)] {
+                if let Some(edges) = self.edges.get_mut(&src) {
+                    edges.remove(&dst);
+                }
+                self.changes.push(src, dst);
+            }
+        } else {
+            self.update_provider_rate(pair);
+        }
+    }
+
+    /// Removes all the rates quoted by the provider, e.g. once it's
+    /// considered unhealthy.
+    pub fn remove_provider_rates(&mut self, id: ProviderId) {
+        let pairs: Vec<_> = self
+            .quotes
+            .iter()
+            .filter(|(_, quotes)| quotes.contains_key(&id))
+            .map(|(pair, _)| *pair)
+            .collect();
+        for (src, dst) in pairs {
+            self.remove_provider_rate(id, src, dst);
+        }
+    }
+
+    pub(crate) fn update_provider_rate(&mut self, pair: (Vertex, Vertex)) -> Option<Outlier> {
+        let (id, rate) = self
+            .quotes
+            .get(&pair)
+            .and_then(|quotes| self.best_quote(quotes))?;
+        debug!(provider = ?id, src = %pair.0, dst = %pair.1, %rate, "provider rate");
+        self.insert_edge(pair.0, pair.1, Edge::new(rate).with_source(id))
+    }
+
+    fn best_quote(&self, quotes: &BTreeMap<ProviderId, f32>) -> Option<(ProviderId, f32)> {
+        // The earlier registered provider wins in case of the same priority.
+        quotes
+            .iter()
+            .filter(|(id, _)| !self.providers[id.0].down)
+            .max_by(|(a, _), (b, _)| {
+                self.providers[a.0]
+                    .priority
+                    .cmp(&self.providers[b.0].priority)
+                    .then(b.cmp(a))
+            })
+            .map(|(id, rate)| (*id, *rate))
+    }
+}
+
+// Normalizes the pair so that both directions share the quotes.
+fn pair(src: Vertex, dst: Vertex, rate: f32) -> ((Vertex, Vertex), f32) {
+    if src < dst {
+        ((src, dst), rate)
+    } else {
+        ((dst, src), 1.0 / rate)
+    }
+}
+
+#[cfg(test)]
+mod test;
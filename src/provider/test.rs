@@ -0,0 +1,49 @@
+use crate::Dex;
+
+#[test]
+fn test_higher_priority_overrides() {
+    let mut dex = Dex::new();
+    let scraped = dex.register_provider("scraped", 1);
+    let institutional = dex.register_provider("institutional", 10);
+
+    dex.add_provider_rate(institutional, 'A', 'B', 1.4);
+    dex.add_provider_rate(scraped, 'A', 'B', 1.6);
+
+    let path = dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap();
+    assert_eq!(path.rate(), 1.4);
+
+    // Reverse quote shares the same pair.
+    dex.add_provider_rate(institutional, 'B', 'A', 0.5);
+    let path = dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap();
+    assert_eq!(path.rate(), 2.0);
+}
+
+#[test]
+fn test_lower_priority_fallback() {
+    let mut dex = Dex::new();
+    let scraped = dex.register_provider("scraped", 1);
+    let institutional = dex.register_provider("institutional", 10);
+
+    dex.add_provider_rate(scraped, 'A', 'C', 0.3);
+    let path = dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(path.rate(), 0.3);
+
+    dex.add_provider_rate(institutional, 'A', 'C', 0.29);
+    let path = dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(path.rate(), 0.29);
+
+    dex.remove_provider_rate(institutional, 'C', 'A');
+    let path = dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(path.rate(), 0.3);
+}
+
+#[test]
+fn test_remove_last_provider_rate() {
+    let mut dex = Dex::new();
+    let scraped = dex.register_provider("scraped", 1);
+
+    dex.add_provider_rate(scraped, 'A', 'B', 1.4);
+    dex.remove_provider_rate(scraped, 'A', 'B');
+    assert!(dex.get_best_rate(&'A'.into(), &'B'.into()).is_none());
+    assert!(dex.get_best_rate(&'B'.into(), &'A'.into()).is_none());
+}
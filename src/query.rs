@@ -0,0 +1,504 @@
+//! Query options
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use super::{Edge, Path, Vertex};
+use crate::cancel::CancelToken;
+use crate::cost::CostModel;
+use crate::forecast::RateForecast;
+use crate::provider::ProviderId;
+
+/// The search algorithm of the best rate query.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// The breadth first search over the paths, pruned by the dominated
+    /// labels.  It's exact with the resource constraints.
+    Bfs,
+    /// The queue-optimized Bellman-Ford, relaxing the single best path
+    /// per vertex.  It's faster on the dense graphs with the frequent
+    /// arbitrage cycles, but the resource constraints are checked on
+    /// the best rate path only.
+    Spfa,
+}
+
+/// The best rate query options.
+#[derive(Clone, Debug, Default)]
+pub struct QueryOptions {
+    algorithm: Option<Algorithm>,
+    amount: Option<f32>,
+    min_liquidity: Option<f32>,
+    deadline: Option<Duration>,
+    max_latency: Option<Duration>,
+    latency_penalty: Option<f32>,
+    volatility_penalty: Option<f32>,
+    max_risk: Option<f32>,
+    allowed_sources: Option<BTreeSet<ProviderId>>,
+    excluded_sources: BTreeSet<ProviderId>,
+    preferred_intermediaries: Option<(BTreeSet<Vertex>, f32)>,
+    timeout: Option<Duration>,
+    cancel_token: Option<CancelToken>,
+    max_queue_len: Option<usize>,
+    cost_model: Option<Arc<dyn CostModel>>,
+    forecast: Option<Arc<dyn RateForecast>>,
+    quote_ttl: Option<Duration>,
+}
+
+/// The trade amount which is negative, infinite, or NaN.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InvalidAmount(pub f32);
+
+impl fmt::Display for InvalidAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid amount {}", self.0)
+    }
+}
+
+impl Error for InvalidAmount {}
+
+/// The path whose accumulated rate became infinite, NaN, or zero by
+/// the underflow, and was dropped by the search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateOverflow {
+    pub path: Vec<Vertex>,
+    pub rate: f32,
+}
+
+impl fmt::Display for RateOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, v) in self.path.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" -> ")?;
+            }
+            write!(f, "{v}")?;
+        }
+        write!(f, ": {} rate overflow", self.rate)
+    }
+}
+
+impl Error for RateOverflow {}
+
+/// The query result, with the flag if the search was cut short by the
+/// limit, e.g. [`QueryOptions::with_max_queue_len`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bounded<T> {
+    value: T,
+    truncated: bool,
+    overflow: Option<RateOverflow>,
+}
+
+impl<T> Bounded<T> {
+    pub(crate) fn new(value: T, truncated: bool) -> Self {
+        Self {
+            value,
+            truncated,
+            overflow: None,
+        }
+    }
+
+    pub(crate) fn with_overflow(mut self, overflow: Option<RateOverflow>) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Checks if the limit dropped any path, and the better one might
+    /// have been missed.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Returns the first path dropped by the overflow, in which case
+    /// the result might have missed the path.
+    pub fn overflow(&self) -> Option<&RateOverflow> {
+        self.overflow.as_ref()
+    }
+
+    pub(crate) fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Bounded<U> {
+        Bounded::new(f(self.value), self.truncated).with_overflow(self.overflow)
+    }
+}
+
+/// The search label of the path, compared to drop the dominated paths.
+///
+/// The resources not constrained by the query are zeroed, so that
+/// they don't keep the worse rate paths alive.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Label {
+    rate: f32,
+    delay: Duration,
+    latency: Duration,
+    risk: f32,
+}
+
+impl Label {
+    /// Checks if the label is no worse than the `other` in all
+    /// aspects.
+    pub(crate) fn dominates(&self, other: &Self) -> bool {
+        self.rate >= other.rate
+            && self.delay <= other.delay
+            && self.latency <= other.latency
+            && self.risk <= other.risk
+    }
+}
+
+impl QueryOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the search algorithm, [`Algorithm::Bfs`] by default.
+    pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = Some(algorithm);
+        self
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm.unwrap_or(Algorithm::Bfs)
+    }
+
+    /// Sets the trade size in the source currency.
+    ///
+    /// Edges too shallow for the trade size converted so far are
+    /// skipped.
+    pub fn with_amount(mut self, amount: f32) -> Self {
+        assert!(amount > 0.0);
+        self.amount = Some(amount);
+        self
+    }
+
+    pub fn amount(&self) -> Option<f32> {
+        self.amount
+    }
+
+    /// Sets the minimum liquidity of each hop, in the source currency.
+    ///
+    /// The hop liquidity is converted back by the rate so far, and the
+    /// edges without the liquidity are taken as deep enough.
+    pub fn with_min_liquidity(mut self, liquidity: f32) -> Self {
+        assert!(liquidity >= 0.0);
+        self.min_liquidity = Some(liquidity);
+        self
+    }
+
+    pub fn min_liquidity(&self) -> Option<f32> {
+        self.min_liquidity
+    }
+
+    /// Sets the deadline for the path to settle.
+    ///
+    /// Paths whose accumulated settlement delay exceeds the deadline
+    /// are rejected.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    /// Caps the total execution latency of the path.
+    pub fn with_max_latency(mut self, max_latency: Duration) -> Self {
+        self.max_latency = Some(max_latency);
+        self
+    }
+
+    pub fn max_latency(&self) -> Option<Duration> {
+        self.max_latency
+    }
+
+    /// Penalizes the rate of the path by the execution latency when the
+    /// paths are compared, by the `penalty` per second, e.g. `0.0001`
+    /// for 1 bp a second.
+    ///
+    /// The penalty compounds, `(1 - penalty)^seconds`, so that the
+    /// faster route wins over the marginally better but slower one.
+    pub fn with_latency_penalty(mut self, penalty: f32) -> Self {
+        assert!((0.0..1.0).contains(&penalty));
+        self.latency_penalty = Some(penalty);
+        self
+    }
+
+    pub fn latency_penalty(&self) -> Option<f32> {
+        self.latency_penalty
+    }
+
+    /// Haircuts the rate of each hop by its volatility when the paths
+    /// are compared, by the `penalty` per the standard deviation, e.g.
+    /// `1.0` for the rate one standard deviation worse.
+    ///
+    /// The hops without the volatility are not penalized, see
+    /// [`Dex::set_rate_history`](crate::Dex::set_rate_history).
+    pub fn with_volatility_penalty(mut self, penalty: f32) -> Self {
+        assert!(penalty >= 0.0);
+        self.volatility_penalty = Some(penalty);
+        self
+    }
+
+    pub fn volatility_penalty(&self) -> Option<f32> {
+        self.volatility_penalty
+    }
+
+    /// Sets the maximum composite risk score of the path.
+    pub fn with_max_risk(mut self, max_risk: f32) -> Self {
+        self.max_risk = Some(max_risk);
+        self
+    }
+
+    pub fn max_risk(&self) -> Option<f32> {
+        self.max_risk
+    }
+
+    /// Restricts the routing to the whitelist of the edge sources,
+    /// e.g. the approved venues or counterparties.
+    ///
+    /// Edges without the source are not routable with the whitelist.
+    pub fn with_allowed_sources<I>(mut self, sources: I) -> Self
+    where
+        I: IntoIterator<Item = ProviderId>,
+    {
+        self.allowed_sources = Some(sources.into_iter().collect());
+        self
+    }
+
+    /// Excludes the blacklist of the edge sources from the routing.
+    pub fn with_excluded_sources<I>(mut self, sources: I) -> Self
+    where
+        I: IntoIterator<Item = ProviderId>,
+    {
+        self.excluded_sources = sources.into_iter().collect();
+        self
+    }
+
+    /// Checks if the edge source is allowed by the white and the
+    /// blacklist.
+    pub fn is_allowed(&self, source: Option<ProviderId>) -> bool {
+        if let Some(allowed) = &self.allowed_sources {
+            match source {
+                Some(source) if allowed.contains(&source) => {}
+                _ => return false,
+            }
+        }
+        match source {
+            Some(source) => !self.excluded_sources.contains(&source),
+            None => true,
+        }
+    }
+
+    /// Biases the routing toward the preferred intermediary vertices,
+    /// e.g. the stablecoins.
+    ///
+    /// The rate of the path is penalized by `penalty`, e.g. `0.001` for
+    /// 10 bps, for each intermediary vertex not in the preferred ones
+    /// when the paths are compared.
+    pub fn with_preferred_intermediaries<I>(mut self, vertices: I, penalty: f32) -> Self
+    where
+        I: IntoIterator<Item = Vertex>,
+    {
+        assert!((0.0..1.0).contains(&penalty));
+        self.preferred_intermediaries = Some((vertices.into_iter().collect(), penalty));
+        self
+    }
+
+    /// Sets the time to live of the quotes, to stamp the paths with the
+    /// expiry, the oldest quote used plus the TTL.
+    pub fn with_quote_ttl(mut self, ttl: Duration) -> Self {
+        self.quote_ttl = Some(ttl);
+        self
+    }
+
+    pub fn quote_ttl(&self) -> Option<Duration> {
+        self.quote_ttl
+    }
+
+    /// Sets the objective of the search, the net rate by default.
+    ///
+    /// The paths are compared by the value of the cost model, and the
+    /// rate of the path is still the net rate.
+    pub fn with_cost_model<M: CostModel + 'static>(mut self, model: M) -> Self {
+        self.cost_model = Some(Arc::new(model));
+        self
+    }
+
+    /// Sets the forecast adjusting the rate of each hop before the
+    /// paths are compared, e.g. by the drift expected until the hop is
+    /// executed.
+    ///
+    /// The adjusted rates are the rates of the path.
+    pub fn with_forecast<F: RateForecast + 'static>(mut self, forecast: F) -> Self {
+        self.forecast = Some(Arc::new(forecast));
+        self
+    }
+
+    /// Returns the rate of the `edge` extending the `path` to `dst`
+    /// adjusted by the forecast over the latency up to the hop, or the
+    /// `rate` in case of no forecast or the invalid one.
+    pub(crate) fn forecast(&self, path: &Path, dst: &Vertex, edge: &Edge, rate: f32) -> f32 {
+        let forecast = match &self.forecast {
+            Some(forecast) => forecast,
+            None => return rate,
+        };
+        let src = path.last();
+        let forecast = forecast.forecast(src, dst, edge, rate, path.latency() + edge.latency);
+        if forecast.is_finite() && forecast >= 0.0 {
+            forecast
+        } else {
+            warn!(%src, %dst, %rate, %forecast, "invalid forecast");
+            rate
+        }
+    }
+
+    /// Returns the value of the `edge` by the cost model, with the
+    /// volatility haircut.
+    pub(crate) fn value(&self, edge: &Edge, amount: Option<f32>) -> f32 {
+        let value = match &self.cost_model {
+            Some(model) => model.value(edge, amount),
+            None => edge.effective_rate(amount),
+        };
+        match self.volatility_penalty {
+            Some(penalty) => value * edge.haircut(penalty),
+            None => value,
+        }
+    }
+
+    /// Returns the score of the path to compare, the value of the cost
+    /// model with the penalties applied.
+    pub fn score(&self, path: &Path) -> f32 {
+        let mut score = path.value;
+        if let Some((preferred, penalty)) = &self.preferred_intermediaries {
+            let intermediaries = path.path.iter().skip(1).take(path.len().saturating_sub(2));
+            for vertex in intermediaries {
+                if !preferred.contains(vertex) {
+                    score *= 1.0 - penalty;
+                }
+            }
+        }
+        if let Some(penalty) = self.latency_penalty {
+            score *= (1.0 - penalty).powf(path.latency);
+        }
+        score
+    }
+
+    /// Sets the time limit of the search itself.
+    ///
+    /// The timed out query returns the best result found so far, or
+    /// the [`Timeout`] error with it for the `try_` queries.
+    ///
+    /// [`Timeout`]: crate::cancel::Timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Sets the token to cancel the search, the same as the timeout.
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel_token = Some(token);
+        self
+    }
+
+    pub fn cancel_token(&self) -> Option<&CancelToken> {
+        self.cancel_token.as_ref()
+    }
+
+    /// Caps the search queue length, to bound the search memory.
+    ///
+    /// The new paths are dropped once the queue is full, and the search
+    /// goes on with the queued ones.  The result is flagged truncated,
+    /// see [`Dex::get_best_rate_bounded`].
+    ///
+    /// [`Dex::get_best_rate_bounded`]: crate::Dex::get_best_rate_bounded
+    pub fn with_max_queue_len(mut self, len: usize) -> Self {
+        assert!(len > 0);
+        self.max_queue_len = Some(len);
+        self
+    }
+
+    pub fn max_queue_len(&self) -> Option<usize> {
+        self.max_queue_len
+    }
+
+    /// Checks if the search started at `start` should stop.
+    pub(crate) fn is_expired(&self, start: Instant) -> bool {
+        if let Some(token) = &self.cancel_token {
+            if token.is_cancelled() {
+                return true;
+            }
+        }
+        match self.timeout {
+            Some(timeout) => start.elapsed() >= timeout,
+            None => false,
+        }
+    }
+
+    /// Checks if the `path` can be extended with the `edge`.
+    pub(crate) fn is_routable(&self, path: &Path, edge: &Edge) -> bool {
+        if !self.is_allowed(edge.source) {
+            return false;
+        }
+        if let Some(deadline) = self.deadline {
+            if path.delay() + edge.delay > deadline {
+                return false;
+            }
+        }
+        if let Some(max_latency) = self.max_latency {
+            if path.latency() + edge.latency > max_latency {
+                return false;
+            }
+        }
+        if let Some(max_risk) = self.max_risk {
+            if path.risk + edge.risk > max_risk {
+                return false;
+            }
+        }
+        if let Some(min_liquidity) = self.min_liquidity {
+            if !edge.is_convertible(min_liquidity * path.rate) {
+                return false;
+            }
+        }
+        match self.amount {
+            Some(amount) => {
+                let amount = amount * path.rate;
+                edge.is_convertible(amount) && edge.effective_rate(Some(amount)) > 0.0
+            }
+            None => true,
+        }
+    }
+
+    pub(crate) fn label(&self, path: &Path) -> Label {
+        Label {
+            rate: self.score(path),
+            delay: match self.deadline {
+                Some(_) => path.delay(),
+                None => Duration::ZERO,
+            },
+            latency: match self.max_latency {
+                Some(_) => path.latency(),
+                None => Duration::ZERO,
+            },
+            risk: match self.max_risk {
+                Some(_) => path.risk,
+                None => 0.0,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
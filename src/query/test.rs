@@ -0,0 +1,243 @@
+use std::time::Duration;
+
+use super::{Algorithm, QueryOptions};
+use crate::cancel::CancelToken;
+use crate::rng::Rng;
+use crate::test::vertex;
+use crate::{Dex, Edge};
+
+#[test]
+fn test_min_liquidity() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(1.4).with_liquidity(500.0));
+    dex.add_edge('A', 'C', Edge::new(0.1).with_liquidity(1_000_000.0));
+    dex.add_edge('C', 'B', Edge::new(12.0).with_liquidity(1_000_000.0));
+
+    let src = 'A'.into();
+    let dst = 'B'.into();
+    let path = dex.get_best_rate(&src, &dst).unwrap();
+    assert_eq!(path.rate(), 1.4);
+
+    let options = QueryOptions::new().with_amount(400.0);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.rate(), 1.4);
+
+    let options = QueryOptions::new().with_amount(1_000.0);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> B: 1.2");
+
+    // The liquidity of C -> B is 10M A, converted back by the 0.1 rate.
+    let options = QueryOptions::new().with_min_liquidity(500.0);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.rate(), 1.4);
+    assert_eq!(path.liquidity(), Some(500.0));
+    let options = QueryOptions::new().with_min_liquidity(501.0);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.liquidity(), Some(1_000_000.0));
+    let options = QueryOptions::new().with_min_liquidity(1_000_001.0);
+    assert!(dex.get_best_rate_with(&src, &dst, &options).is_none());
+}
+
+#[test]
+fn test_reverse_liquidity() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_liquidity(500.0));
+
+    let src = 'B'.into();
+    let dst = 'A'.into();
+    let options = QueryOptions::new().with_amount(1_000.0);
+    assert!(dex.get_best_rate_with(&src, &dst, &options).is_some());
+    let options = QueryOptions::new().with_amount(1_001.0);
+    assert!(dex.get_best_rate_with(&src, &dst, &options).is_none());
+}
+
+#[test]
+fn test_deadline() {
+    let day = Duration::from_secs(24 * 60 * 60);
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(1.0).with_delay(2 * day));
+    dex.add_edge('B', 'D', Edge::new(1.2).with_delay(day));
+    dex.add_edge('A', 'C', Edge::new(1.0).with_delay(day));
+    dex.add_edge('C', 'B', Edge::new(0.9));
+
+    let src = 'A'.into();
+    let dst = 'D'.into();
+    let path = dex.get_best_rate(&src, &dst).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> D: 1.2");
+    assert_eq!(path.delay(), 3 * day);
+
+    // The slower but better rate path to B shouldn't shadow the faster
+    // one within the deadline.
+    let options = QueryOptions::new().with_deadline(2 * day);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> B -> D: 1.08");
+    assert_eq!(path.delay(), 2 * day);
+
+    let options = QueryOptions::new().with_deadline(day);
+    assert!(dex.get_best_rate_with(&src, &dst, &options).is_none());
+}
+
+#[test]
+fn test_latency() {
+    let second = Duration::from_secs(1);
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(1.01).with_latency(30 * second));
+    dex.add_edge('B', 'D', Edge::new(1.0).with_latency(second));
+    dex.add_edge('A', 'C', Edge::new(1.0).with_latency(second));
+    dex.add_edge('C', 'B', Edge::new(1.0));
+
+    let src = 'A'.into();
+    let dst = 'D'.into();
+    let path = dex.get_best_rate(&src, &dst).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> D: 1.01");
+    assert_eq!(path.latency(), 31 * second);
+
+    // The marginally better rate through the slow venue loses with the
+    // penalty of 10 bps a second.
+    let options = QueryOptions::new().with_latency_penalty(0.001);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> B -> D: 1");
+    assert_eq!(path.latency(), 2 * second);
+
+    let options = QueryOptions::new().with_max_latency(10 * second);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> B -> D: 1");
+    let options = QueryOptions::new().with_max_latency(second);
+    assert!(dex.get_best_rate_with(&src, &dst, &options).is_none());
+}
+
+#[test]
+fn test_max_risk() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(1.4).with_risk(0.5));
+    dex.add_edge('A', 'C', Edge::new(0.1).with_risk(0.1));
+    dex.add_edge('C', 'B', Edge::new(13.0).with_risk(0.1));
+
+    let src = 'A'.into();
+    let dst = 'B'.into();
+    let path = dex.get_best_rate(&src, &dst).unwrap();
+    assert_eq!(path.to_string(), "A -> B: 1.4");
+    assert_eq!(path.risk(), 0.5);
+
+    let options = QueryOptions::new().with_max_risk(0.3);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.len(), 3);
+    assert_eq!(path.risk(), 0.2);
+
+    let options = QueryOptions::new().with_max_risk(0.1);
+    assert!(dex.get_best_rate_with(&src, &dst, &options).is_none());
+}
+
+#[test]
+fn test_allowed_sources() {
+    let mut dex = Dex::new();
+    let approved = dex.register_provider("approved", 1);
+    let other = dex.register_provider("other", 1);
+    dex.add_edge('A', 'B', Edge::new(1.4).with_source(other));
+    dex.add_edge('A', 'C', Edge::new(0.5).with_source(approved));
+    dex.add_edge('C', 'B', Edge::new(2.5).with_source(approved));
+    dex.add_rate('A', 'D', 0.5);
+    dex.add_rate('D', 'B', 3.0);
+
+    let src = 'A'.into();
+    let dst = 'B'.into();
+    let path = dex.get_best_rate(&src, &dst).unwrap();
+    assert_eq!(path.to_string(), "A -> D -> B: 1.5");
+
+    let options = QueryOptions::new().with_allowed_sources([approved]);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> B: 1.25");
+
+    let options = QueryOptions::new().with_excluded_sources([approved]);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> D -> B: 1.5");
+
+    let options = QueryOptions::new().with_allowed_sources([other]);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> B: 1.4");
+}
+
+#[test]
+fn test_preferred_intermediaries() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'M', 1.0);
+    dex.add_rate('M', 'B', 1.002);
+    dex.add_rate('A', 'U', 1.0);
+    dex.add_rate('U', 'B', 1.0);
+
+    let src = 'A'.into();
+    let dst = 'B'.into();
+    let path = dex.get_best_rate(&src, &dst).unwrap();
+    assert_eq!(path.to_string(), "A -> M -> B: 1.002");
+
+    let options = QueryOptions::new().with_preferred_intermediaries(['U'.into()], 0.001);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> M -> B: 1.002");
+
+    let options = QueryOptions::new().with_preferred_intermediaries(['U'.into()], 0.005);
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> U -> B: 1");
+    assert_eq!(options.score(&path), 1.0);
+}
+
+#[test]
+fn test_timeout() {
+    let dex = Dex::generate(30, 120, 0.01, &mut Rng::new(1));
+    let (src, dst) = (vertex("V0"), vertex("V1"));
+
+    let token = CancelToken::new();
+    token.cancel();
+    let options = QueryOptions::new().with_cancel_token(token);
+    let timeout = dex.try_get_best_rates_from(&src, &options).unwrap_err();
+    assert!(timeout.partial().len() < 29);
+    assert!(dex.try_get_best_rate_with(&src, &dst, &options).is_err());
+
+    let options = QueryOptions::new().with_timeout(Duration::from_secs(60));
+    assert!(dex.try_get_best_rate_with(&src, &dst, &options).is_ok());
+}
+
+#[test]
+fn test_max_queue_len() {
+    let dex = Dex::generate(30, 120, 0.01, &mut Rng::new(1));
+    let (src, dst) = (vertex("V0"), vertex("V1"));
+
+    let options = QueryOptions::new();
+    let full = dex.get_best_rate_bounded(&src, &dst, &options);
+    assert!(!full.is_truncated());
+
+    let options = QueryOptions::new().with_max_queue_len(2);
+    let bounded = dex.get_best_rate_bounded(&src, &dst, &options);
+    assert!(bounded.is_truncated());
+    if let Some(path) = bounded.value() {
+        assert!(path.rate() <= full.value().as_ref().unwrap().rate());
+    }
+}
+
+#[test]
+fn test_rate_overflow() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1e30);
+    dex.add_rate('B', 'C', 1e30);
+    dex.add_rate('A', 'D', 2.0);
+    dex.add_rate('D', 'C', 3.0);
+
+    let (src, dst) = ('A'.into(), 'C'.into());
+    let options = QueryOptions::new();
+    let bounded = dex.get_best_rate_bounded(&src, &dst, &options);
+    assert_eq!(bounded.value().as_ref().unwrap().rate(), 6.0);
+    let overflow = bounded.overflow().unwrap();
+    assert_eq!(overflow.to_string(), "A -> B -> C: inf rate overflow");
+
+    let err = dex.checked_get_best_rate(&src, &dst, &options).unwrap_err();
+    assert_eq!(&err, overflow);
+    assert_eq!(dex.get_best_rates_from(&src, &options)[&dst].rate(), 6.0);
+    let err = dex.checked_get_best_rates_from(&src, &options).unwrap_err();
+    assert_eq!(&err, overflow);
+
+    // The underflow in the reverse direction.
+    let err = dex.checked_get_best_rate(&dst, &src, &options).unwrap_err();
+    assert_eq!(err.rate, 0.0);
+
+    let options = options.with_algorithm(Algorithm::Spfa);
+    assert!(dex.checked_get_best_rate(&src, &dst, &options).is_err());
+}
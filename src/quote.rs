@@ -0,0 +1,129 @@
+//! Quotes with the provenance
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{Dex, Path};
+use crate::edge::Edge;
+use crate::provider::ProviderId;
+
+/// The side of the `src/dst` book the rate is taken from.
+///
+/// The edge is quoted at the bid, selling the `src` for the `dst`, and
+/// its reverse edge converts at the ask.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+impl Side {
+    /// Returns the other side, of the reverse conversion.
+    pub fn reverse(self) -> Self {
+        match self {
+            Self::Bid => Self::Ask,
+            Self::Ask => Self::Bid,
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Self::Bid => "bid",
+            Self::Ask => "ask",
+        })
+    }
+}
+
+/// The quoted rate of the edge, with where and when it's from.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quote {
+    pub rate: f32,
+    pub side: Side,
+    pub timestamp: Option<SystemTime>,
+    pub source: Option<ProviderId>,
+}
+
+impl fmt::Display for Quote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.rate, self.side)
+    }
+}
+
+impl Quote {
+    /// Creates the bid quote without the provenance.
+    pub fn new(rate: f32) -> Self {
+        Self {
+            rate,
+            side: Side::Bid,
+            timestamp: None,
+            source: None,
+        }
+    }
+
+    pub fn with_side(mut self, side: Side) -> Self {
+        self.side = side;
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn with_source(mut self, source: ProviderId) -> Self {
+        self.source = Some(source);
+        self
+    }
+}
+
+impl Edge {
+    /// Returns the quote of the edge, the top of the book rate before
+    /// the fees.
+    pub fn quote(&self) -> Quote {
+        Quote {
+            rate: self.rate,
+            side: self.side,
+            timestamp: self.timestamp,
+            source: self.source,
+        }
+    }
+}
+
+impl From<Quote> for Edge {
+    fn from(quote: Quote) -> Self {
+        let mut edge = Edge::new(quote.rate).with_side(quote.side);
+        edge.timestamp = quote.timestamp;
+        edge.source = quote.source;
+        edge
+    }
+}
+
+impl Dex {
+    /// Returns the provenance of each hop of the `path`, one line per
+    /// hop, e.g. `A -> B: 1.1 bid from venue at 1700000000000 ms`.
+    ///
+    /// The quotes not from any provider or without the timestamp omit
+    /// the part.
+    pub fn provenance(&self, path: &Path) -> String {
+        let mut lines = Vec::with_capacity(path.quotes.len());
+        for (hop, quote) in path.path.windows(2).zip(&path.quotes) {
+            let mut line = format!("{} -> {}: {quote}", hop[0], hop[1]);
+            if let Some(provider) = quote.source.and_then(|id| self.provider(id)) {
+                line.push_str(&format!(" from {}", provider.name()));
+            }
+            if let Some(timestamp) = quote.timestamp {
+                let millis = timestamp
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_millis());
+                line.push_str(&format!(" at {millis} ms"));
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,40 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::{Quote, Side};
+use crate::{Dex, Edge};
+
+#[test]
+fn test_quote() {
+    let at = UNIX_EPOCH + Duration::from_secs(100);
+    let mut dex = Dex::new();
+    let venue = dex.register_provider("venue", 10);
+    let quote = Quote::new(2.0).with_timestamp(at).with_source(venue);
+    dex.add_edge('A', 'B', Edge::from(quote).with_fee(0.01));
+    dex.add_edge('C', 'B', Edge::new(0.5).with_side(Side::Ask));
+
+    let edge = &dex.edges[&'A'.into()][&'B'.into()];
+    assert_eq!(edge.quote(), quote);
+    assert_eq!(edge.reverse().quote().side, Side::Ask);
+    assert_eq!(Side::Ask.reverse(), Side::Bid);
+    assert_eq!(quote.to_string(), "2 bid");
+
+    let path = dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(path.rates(), &[1.98, 2.0]);
+    assert_eq!(path.quotes(), &[quote, Quote::new(2.0)]);
+    assert_eq!(
+        dex.provenance(&path),
+        "A -> B: 2 bid from venue at 100000 ms\nB -> C: 2 bid"
+    );
+}
+
+#[test]
+fn test_frozen_quotes() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    let frozen = dex.freeze();
+    let mut searcher = frozen.searcher();
+    let path = searcher
+        .get_best_rate(&frozen, &'A'.into(), &'B'.into())
+        .unwrap();
+    assert_eq!(path.quotes(), &[Quote::new(2.0)]);
+}
@@ -0,0 +1,119 @@
+//! Portfolio rebalancing
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex};
+use crate::flow::Flow;
+
+/// The value difference below which the holding is considered on
+/// target, relative to the portfolio value.
+const TOLERANCE: f32 = 1e-6;
+
+/// The conversion of the rebalancing plan, possibly split across the
+/// multiple paths.
+#[derive(Clone, Debug)]
+pub struct Conversion {
+    pub src: Vertex,
+    pub dst: Vertex,
+    pub flow: Flow,
+}
+
+/// The conversions to reach the target weights.
+#[derive(Clone, Debug, Default)]
+pub struct RebalancePlan {
+    pub conversions: Vec<Conversion>,
+    /// The value lost to the fees and the slippage, in the base
+    /// currency.
+    pub cost: f32,
+    /// The holdings after the conversions.
+    pub holdings: BTreeMap<Vertex, f32>,
+}
+
+impl Dex {
+    /// Plans the conversions of the `holdings` to reach the `targets`
+    /// weights of the value in the `base` currency.
+    ///
+    /// The surplus holdings are converted directly into the deficit
+    /// ones instead of through the base, netting the trades, and the
+    /// cheapest pair is converted first.  Each conversion is split
+    /// across the paths by [`Dex::route_amount`].  The assets without
+    /// the valuation in the base currency are left as is.
+    #[instrument(level = "debug", skip_all)]
+    pub fn rebalance<V>(
+        &self,
+        holdings: &[(V, f32)],
+        targets: &[(V, f32)],
+        base: V,
+    ) -> RebalancePlan
+    where
+        V: Into<Vertex> + Copy,
+    {
+        let valuations = self.valuations(&base.into());
+        let mut plan = RebalancePlan::default();
+        for (v, amount) in holdings {
+            *plan.holdings.entry((*v).into()).or_default() += amount;
+        }
+        let total: f32 = plan
+            .holdings
+            .iter()
+            .filter_map(|(v, amount)| valuations.get(v).map(|price| amount * price))
+            .sum();
+        let weights: f32 = targets.iter().map(|(_, weight)| weight).sum();
+        if total <= 0.0 || weights <= 0.0 {
+            return plan;
+        }
+
+        // The value over the target, negative for the deficit.
+        let mut deltas = BTreeMap::new();
+        for (v, price) in &valuations {
+            if let Some(amount) = plan.holdings.get(v) {
+                deltas.insert(*v, amount * price);
+            }
+        }
+        for (v, weight) in targets {
+            let v = (*v).into();
+            if valuations.contains_key(&v) {
+                *deltas.entry(v).or_default() -= total * weight / weights;
+            }
+        }
+
+        // The value retained by each surplus to deficit conversion.
+        let mut pairs = Vec::new();
+        for (src, _) in deltas.iter().filter(|(_, delta)| **delta > 0.0) {
+            for (dst, _) in deltas.iter().filter(|(_, delta)| **delta < 0.0) {
+                if let Some(path) = self.get_best_rate(src, dst) {
+                    let retained = path.rate() * valuations[dst] / valuations[src];
+                    pairs.push((*src, *dst, retained));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(Ordering::Equal));
+
+        for (src, dst, _) in pairs {
+            let value = deltas[&src].min(-deltas[&dst]);
+            if value <= total * TOLERANCE {
+                continue;
+            }
+            let flow = self.route_amount(&src, &dst, value / valuations[&src]);
+            if flow.routes().is_empty() {
+                continue;
+            }
+            let sent = flow.amount_in() * valuations[&src];
+            let received = flow.amount_out() * valuations[&dst];
+            debug!(%src, %dst, %sent, %received, "conversion");
+            *deltas.get_mut(&src).unwrap() -= sent;
+            *deltas.get_mut(&dst).unwrap() += received;
+            *plan.holdings.get_mut(&src).unwrap() -= flow.amount_in();
+            *plan.holdings.entry(dst).or_default() += flow.amount_out();
+            plan.cost += sent - received;
+            plan.conversions.push(Conversion { src, dst, flow });
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod test;
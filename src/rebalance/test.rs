@@ -0,0 +1,77 @@
+use crate::test::vertex;
+use crate::{Dex, Edge};
+
+#[test]
+fn test_rebalance() {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("EUR"), vertex("USD"), 2.0);
+    dex.add_rate(vertex("GBP"), vertex("USD"), 4.0);
+    dex.add_rate(vertex("CNY"), vertex("KRW"), 180.0);
+
+    let plan = dex.rebalance(
+        &[
+            (vertex("USD"), 100.0),
+            (vertex("GBP"), 50.0),
+            (vertex("CNY"), 10.0),
+        ],
+        &[
+            (vertex("USD"), 0.25),
+            (vertex("EUR"), 0.5),
+            (vertex("GBP"), 0.25),
+        ],
+        vertex("USD"),
+    );
+
+    // The 300 USD worth is split into 75 USD, 150 USD worth of EUR and
+    // 75 USD worth of GBP, with GBP converted directly into EUR.
+    assert_eq!(plan.conversions.len(), 2);
+    assert_eq!(plan.conversions[0].src, vertex("GBP"));
+    assert_eq!(plan.conversions[0].dst, vertex("EUR"));
+    assert_eq!(plan.conversions[0].flow.amount_in(), 31.25);
+    assert_eq!(plan.conversions[1].src, vertex("USD"));
+    assert_eq!(plan.conversions[1].dst, vertex("EUR"));
+    assert_eq!(plan.conversions[1].flow.amount_in(), 25.0);
+    assert_eq!(plan.holdings[&vertex("USD")], 75.0);
+    assert_eq!(plan.holdings[&vertex("GBP")], 18.75);
+    assert_eq!(plan.holdings[&vertex("EUR")], 75.0);
+    assert_eq!(plan.holdings[&vertex("CNY")], 10.0);
+    assert_eq!(plan.cost, 0.0);
+}
+
+#[test]
+fn test_rebalance_liquidity() {
+    let mut dex = Dex::new();
+    dex.add_edge(
+        vertex("USD"),
+        vertex("EUR"),
+        Edge::new(0.5).with_liquidity(10.0),
+    );
+    dex.add_edge(vertex("USD"), vertex("GBP"), Edge::new(0.25).with_fee(0.5));
+    dex.add_edge(vertex("GBP"), vertex("EUR"), Edge::new(2.0));
+
+    let plan = dex.rebalance(
+        &[(vertex("USD"), 100.0)],
+        &[(vertex("EUR"), 1.0)],
+        vertex("USD"),
+    );
+    assert_eq!(plan.conversions.len(), 1);
+    let flow = &plan.conversions[0].flow;
+    assert_eq!(flow.routes().len(), 2);
+    assert_eq!(flow.amount_in(), 100.0);
+    assert_eq!(flow.amount_out(), 5.0 + 22.5);
+    assert_eq!(plan.holdings[&vertex("EUR")], 27.5);
+}
+
+#[test]
+fn test_rebalance_on_target() {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("EUR"), vertex("USD"), 2.0);
+
+    let plan = dex.rebalance(
+        &[(vertex("USD"), 100.0), (vertex("EUR"), 50.0)],
+        &[(vertex("USD"), 1.0), (vertex("EUR"), 1.0)],
+        vertex("USD"),
+    );
+    assert!(plan.conversions.is_empty());
+    assert_eq!(plan.cost, 0.0);
+}
@@ -0,0 +1,186 @@
+//! Historical replay of the timestamped rate updates
+
+use std::io::{self, BufRead};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex};
+use crate::clock::MockClock;
+use crate::csv::parse_line;
+
+/// The rate update recorded at the `time` from the `provider`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayEvent {
+    pub time: SystemTime,
+    pub provider: String,
+    pub src: Vertex,
+    pub dst: Vertex,
+    pub rate: f32,
+}
+
+/// The replay driver of the recorded rate updates, e.g. of the market
+/// day, through the provider rates as the live feeds.
+///
+/// The updates of the same time are applied at once, and then the
+/// callback runs the queries, or the alert checks, as in the live mode.
+/// The recorded time is kept at the `speed`, e.g. `60.0` for a minute
+/// per second, or `f32::INFINITY` to replay as fast as possible.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Replay {
+    events: Vec<ReplayEvent>,
+    speed: Option<f32>,
+}
+
+impl Replay {
+    /// Creates the replay of the events, ordered by the time.
+    pub fn new(mut events: Vec<ReplayEvent>) -> Self {
+        events.sort_by_key(|event| event.time);
+        Self {
+            events,
+            speed: None,
+        }
+    }
+
+    /// Loads the `time,provider,src,dst,rate` lines, the time in the
+    /// seconds since the Unix epoch, e.g. `1700000000.25`.
+    ///
+    /// The blank lines and the lines starting with `#` are skipped.
+    #[instrument(level = "debug", skip_all, err)]
+    pub fn load_csv<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut events = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let event = parse_event(line).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {e}", i + 1))
+            })?;
+            events.push(event);
+        }
+        debug!(events = events.len(), "loaded");
+        Ok(Self::new(events))
+    }
+
+    /// Sets the replay speed relative to the recorded time.
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        assert!(speed > 0.0);
+        self.speed = Some(speed);
+        self
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed.unwrap_or(1.0)
+    }
+
+    pub fn events(&self) -> &[ReplayEvent] {
+        &self.events
+    }
+
+    /// Returns the recorded time span.
+    pub fn duration(&self) -> Duration {
+        match (self.events.first(), self.events.last()) {
+            (Some(first), Some(last)) => last.time.duration_since(first.time).unwrap_or_default(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Replays the events into the `dex`, and calls `f` after each
+    /// time's updates with the recorded time.  It returns the number of
+    /// the updates applied.
+    ///
+    /// The `dex` clock is set to the recorded time during the replay,
+    /// e.g. for the provider update times.
+    pub fn run<F>(&self, dex: &mut Dex, mut f: F) -> usize
+    where
+        F: FnMut(&Dex, SystemTime),
+    {
+        let clock = MockClock::new(UNIX_EPOCH);
+        let previous = dex.clock.replace(Arc::new(clock.clone()));
+        let count = self.play(|time, events| {
+            clock.set(time);
+            apply(dex, events);
+            f(dex, time);
+        });
+        dex.clock = previous;
+        count
+    }
+
+    /// Calls `f` with the events of each time at the replay speed, e.g.
+    /// to [`apply`] them to the [`Daemon`](crate::daemon::Daemon) graph.
+    #[instrument(level = "debug", skip_all)]
+    pub fn play<F>(&self, mut f: F) -> usize
+    where
+        F: FnMut(SystemTime, &[ReplayEvent]),
+    {
+        let first = match self.events.first() {
+            Some(first) => first.time,
+            None => return 0,
+        };
+        let speed = self.speed();
+        let start = Instant::now();
+        let mut events = &self.events[..];
+        while let Some(event) = events.first() {
+            let time = event.time;
+            let len = events.iter().take_while(|e| e.time == time).count();
+            if speed.is_finite() {
+                let at = time.duration_since(first).unwrap_or_default();
+                let wait = at.div_f32(speed).saturating_sub(start.elapsed());
+                if !wait.is_zero() {
+                    thread::sleep(wait);
+                }
+            }
+            f(time, &events[..len]);
+            events = &events[len..];
+        }
+        self.events.len()
+    }
+}
+
+/// Applies the events as the provider rates, registering the providers
+/// unknown to the `dex` with the lowest priority.
+pub fn apply(dex: &mut Dex, events: &[ReplayEvent]) {
+    for event in events {
+        let provider = dex
+            .providers()
+            .find(|(_, provider)| provider.name() == event.provider)
+            .map(|(id, _)| id);
+        let provider = match provider {
+            Some(id) => id,
+            None => dex.register_provider(&event.provider, 0),
+        };
+        dex.add_provider_rate(provider, event.src, event.dst, event.rate);
+    }
+}
+
+fn parse_event(line: &str) -> Result<ReplayEvent, String> {
+    let mut fields = line.splitn(3, ',');
+    let (time, provider, rate) = match (fields.next(), fields.next(), fields.next()) {
+        (Some(time), Some(provider), Some(rate)) => (time.trim(), provider.trim(), rate),
+        _ => return Err(format!("expected time,provider,src,dst,rate: {line:?}")),
+    };
+    let seconds: f64 = time
+        .parse()
+        .map_err(|e| format!("invalid time {time:?}: {e}"))?;
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(format!("invalid time {time:?}"));
+    }
+    if provider.is_empty() {
+        return Err(format!("missing provider: {line:?}"));
+    }
+    let (src, dst, rate) = parse_line(rate)?;
+    Ok(ReplayEvent {
+        time: UNIX_EPOCH + Duration::from_secs_f64(seconds),
+        provider: provider.to_string(),
+        src,
+        dst,
+        rate,
+    })
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,89 @@
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use super::{apply, Replay};
+use crate::alert::{Alert, Alerter, Rule};
+use crate::daemon::Daemon;
+use crate::test::vertex;
+use crate::Dex;
+
+const DAY: &str = "\
+# time,provider,src,dst,rate
+1700000000.5,kraken,B,C,3.0
+1700000000,kraken,A,B,2.0
+1700000000.5,binance,C,A,0.2
+
+1700000001,binance,C,A,0.16666667
+";
+
+#[test]
+fn test_replay() {
+    let replay = Replay::load_csv(DAY.as_bytes()).unwrap();
+    assert_eq!(replay.events().len(), 4);
+    assert_eq!(replay.events()[0].provider, "kraken");
+    assert_eq!(replay.duration(), Duration::from_secs(1));
+
+    let mut dex = Dex::new();
+    let mut alerter = Alerter::new().rule(Rule::Arbitrage { min_bps: 10.0 });
+    let mut ticks = Vec::new();
+    let start = Instant::now();
+    let count = replay.with_speed(f32::INFINITY).run(&mut dex, |dex, time| {
+        let seconds = time.duration_since(UNIX_EPOCH).unwrap().as_secs_f64();
+        ticks.push((seconds, alerter.check(dex, time).len()));
+    });
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert_eq!(count, 4);
+    assert_eq!(
+        ticks,
+        [(1700000000.0, 0), (1700000000.5, 1), (1700000001.0, 0)]
+    );
+    assert_eq!(dex.providers().count(), 2);
+    let (_, kraken) = dex.providers().next().unwrap();
+    let updated_at = UNIX_EPOCH + Duration::from_secs_f64(1700000000.5);
+    assert_eq!(kraken.updated_at(), Some(updated_at));
+    let path = dex.get_best_rate(&vertex("C"), &vertex("A")).unwrap();
+    assert!((path.rate() - 1.0 / 6.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_replay_speed() {
+    let replay = Replay::load_csv(DAY.as_bytes()).unwrap().with_speed(10.0);
+    assert_eq!(replay.speed(), 10.0);
+    let start = Instant::now();
+    let mut times = Vec::new();
+    replay.play(|_, events| times.push((start.elapsed(), events.len())));
+    assert_eq!(times.len(), 3);
+    assert_eq!(times[1].1, 2);
+    assert!(times[2].0 >= Duration::from_millis(100));
+}
+
+#[test]
+fn test_replay_daemon() {
+    let replay = Replay::load_csv(DAY.as_bytes())
+        .unwrap()
+        .with_speed(f32::INFINITY);
+    let daemon = Daemon::new(Dex::new());
+    let mut alerts = Vec::new();
+    let mut alerter = Alerter::new().rule(Rule::Arbitrage { min_bps: 10.0 });
+    replay.play(|time, events| {
+        daemon.update(|dex| apply(dex, events));
+        alerts.extend(daemon.read(|dex| alerter.check(dex, time)));
+    });
+    assert!(matches!(&alerts[..], [Alert::Arbitrage { .. }]));
+    assert!(daemon.get_best_rate(&vertex("A"), &vertex("C")).is_some());
+}
+
+#[test]
+fn test_replay_error() {
+    let err = Replay::load_csv("1,kraken,A,B,2.0\nsoon,kraken,A,B,2.0\n".as_bytes()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"line 2: invalid time "soon": invalid float literal"#
+    );
+    let err = Replay::load_csv("1,kraken,A,B\n".as_bytes()).unwrap_err();
+    assert_eq!(err.to_string(), r#"line 1: expected src,dst,rate: "A,B""#);
+    let err = Replay::load_csv("1,kraken\n".as_bytes()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"line 1: expected time,provider,src,dst,rate: "1,kraken""#
+    );
+}
@@ -0,0 +1,277 @@
+//! Structured report
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use tracing::instrument;
+
+use super::{Dex, Path, Vertex};
+use crate::cancel::{CancelToken, Timeout};
+use crate::cycle::Cycle;
+use crate::progress::{Progress, Stage};
+use crate::query::{Algorithm, QueryOptions};
+
+/// The one-shot report of the graph, written as JSON.
+#[derive(Clone, Debug)]
+pub struct Report {
+    vertices: usize,
+    edges: usize,
+    paths: Vec<Path>,
+    arbitrage: Vec<Cycle>,
+    // Boxed to keep the report, returned as the error of the timeout,
+    // small.
+    options: Box<QueryOptions>,
+    traces: Vec<Trace>,
+    partial: bool,
+}
+
+/// The trace of the best rate query of the pair.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trace {
+    pub src: Vertex,
+    pub dst: Vertex,
+    /// The best rate path, or `None` for the unreachable pair.
+    pub path: Option<Vec<Vertex>>,
+    pub elapsed: Duration,
+}
+
+impl Dex {
+    /// Generates the report with the graph stats, the best rate path
+    /// of all the pairs, and the arbitrage cycles above `epsilon`.
+    #[instrument(level = "debug", skip(self))]
+    pub fn report(&self, epsilon: f32) -> Report {
+        self.report_with_progress(epsilon, |_| {})
+    }
+
+    /// Generates the [`Dex::report`], and calls `progress` through the
+    /// pairs and then the arbitrage scan.
+    pub fn report_with_progress<F>(&self, epsilon: f32, progress: F) -> Report
+    where
+        F: FnMut(Progress),
+    {
+        self.try_report(epsilon, &CancelToken::new(), progress)
+            .unwrap_or_else(Timeout::into_partial)
+    }
+
+    /// Generates the [`Dex::report`], or the [`Timeout`] error with the
+    /// partial report of the pairs and the arbitrage found so far once
+    /// the `cancel` token is cancelled.
+    pub fn try_report<F>(
+        &self,
+        epsilon: f32,
+        cancel: &CancelToken,
+        mut progress: F,
+    ) -> Result<Report, Timeout<Report>>
+    where
+        F: FnMut(Progress),
+    {
+        let options = QueryOptions::new().with_cancel_token(cancel.clone());
+        let mut report = Report {
+            vertices: self.edges.len(),
+            edges: self.edges.values().map(|edges| edges.len()).sum(),
+            paths: Vec::new(),
+            arbitrage: Vec::new(),
+            options: Box::new(options.clone()),
+            traces: Vec::new(),
+            partial: true,
+        };
+        let total = self.edges.len();
+        for (i, src) in self.vertices().enumerate() {
+            if cancel.is_cancelled() {
+                return Err(Timeout::new(report));
+            }
+            for dst in self.vertices() {
+                if src == dst {
+                    continue;
+                }
+                let start = Instant::now();
+                let path = match self.try_get_best_rate_with(src, dst, &options) {
+                    Ok(path) => path,
+                    Err(_) => return Err(Timeout::new(report)),
+                };
+                report.traces.push(Trace {
+                    src: *src,
+                    dst: *dst,
+                    path: path.as_ref().map(|path| path.path.clone()),
+                    elapsed: start.elapsed(),
+                });
+                report.paths.extend(path);
+            }
+            progress(Progress {
+                stage: Stage::Pairs,
+                done: i + 1,
+                total,
+            });
+        }
+        match self.try_find_arbitrage(epsilon * 10_000.0, None, cancel, progress) {
+            Ok(arbitrage) => report.arbitrage = arbitrage,
+            Err(timeout) => {
+                report.arbitrage = timeout.into_partial();
+                return Err(Timeout::new(report));
+            }
+        }
+        report.partial = false;
+        Ok(report)
+    }
+}
+
+impl Report {
+    pub fn vertices(&self) -> usize {
+        self.vertices
+    }
+
+    /// Returns the number of the directed edges.
+    pub fn edges(&self) -> usize {
+        self.edges
+    }
+
+    /// Returns the best rate path of each pair.
+    pub fn paths(&self) -> &[Path] {
+        &self.paths
+    }
+
+    pub fn arbitrage(&self) -> &[Cycle] {
+        &self.arbitrage
+    }
+
+    /// Returns the query options of the pairs.
+    pub fn options(&self) -> &QueryOptions {
+        &self.options
+    }
+
+    /// Returns the trace of each pair query, reachable or not.
+    pub fn traces(&self) -> &[Trace] {
+        &self.traces
+    }
+
+    /// Checks if the report was cancelled before all the pairs and the
+    /// cycles were computed.
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Writes the report as JSON.
+    pub fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"vertices\": {},", self.vertices)?;
+        writeln!(out, "  \"edges\": {},", self.edges)?;
+        if self.partial {
+            writeln!(out, "  \"partial\": true,")?;
+        }
+        writeln!(out, "  \"pairs\": [")?;
+        for (i, path) in self.paths.iter().enumerate() {
+            write!(
+                out,
+                "    {{\"src\": {}, \"dst\": {}, \"rate\": {}, \"hops\": {}, \"path\": {}}}",
+                string(&path.path[0]),
+                string(path.last()),
+                number(path.rate),
+                path.len() - 1,
+                array(&path.path),
+            )?;
+            writeln!(out, "{}", if i + 1 < self.paths.len() { "," } else { "" })?;
+        }
+        writeln!(out, "  ],")?;
+        writeln!(out, "  \"arbitrage\": [")?;
+        for (i, cycle) in self.arbitrage.iter().enumerate() {
+            let vertices: Vec<_> = cycle.edges().iter().map(|(src, ..)| *src).collect();
+            write!(
+                out,
+                "    {{\"cycle\": {}, \"rate\": {}, \"net_rate\": {}}}",
+                array(&vertices),
+                number(cycle.rate()),
+                number(cycle.net_rate()),
+            )?;
+            writeln!(
+                out,
+                "{}",
+                if i + 1 < self.arbitrage.len() {
+                    ","
+                } else {
+                    ""
+                }
+            )?;
+        }
+        writeln!(out, "  ],")?;
+        writeln!(out, "  \"traces\": [")?;
+        let options = options(&self.options);
+        for (i, trace) in self.traces.iter().enumerate() {
+            write!(
+                out,
+                "    {{\"src\": {}, \"dst\": {}, \"options\": {options}, \"path\": {}, \
+                 \"elapsed_us\": {}}}",
+                string(&trace.src),
+                string(&trace.dst),
+                trace.path.as_deref().map_or("null".to_string(), array),
+                trace.elapsed.as_micros(),
+            )?;
+            writeln!(out, "{}", if i + 1 < self.traces.len() { "," } else { "" })?;
+        }
+        writeln!(out, "  ]")?;
+        writeln!(out, "}}")
+    }
+}
+
+// Returns the JSON object of the search options, the algorithm and the
+// constraints set.
+fn options(options: &QueryOptions) -> String {
+    let algorithm = match options.algorithm() {
+        Algorithm::Bfs => "bfs",
+        Algorithm::Spfa => "spfa",
+    };
+    let mut fields = vec![format!("\"algorithm\": \"{algorithm}\"")];
+    if let Some(amount) = options.amount() {
+        fields.push(format!("\"amount\": {}", number(amount)));
+    }
+    if let Some(min_liquidity) = options.min_liquidity() {
+        fields.push(format!("\"min_liquidity\": {}", number(min_liquidity)));
+    }
+    if let Some(deadline) = options.deadline() {
+        fields.push(format!("\"deadline_ms\": {}", deadline.as_millis()));
+    }
+    if let Some(max_latency) = options.max_latency() {
+        fields.push(format!("\"max_latency_ms\": {}", max_latency.as_millis()));
+    }
+    if let Some(max_risk) = options.max_risk() {
+        fields.push(format!("\"max_risk\": {}", number(max_risk)));
+    }
+    if let Some(timeout) = options.timeout() {
+        fields.push(format!("\"timeout_ms\": {}", timeout.as_millis()));
+    }
+    format!("{{{}}}", fields.join(", "))
+}
+
+pub(crate) fn string(v: &Vertex) -> String {
+    quote(&v.to_string())
+}
+
+pub(crate) fn quote(value: &str) -> String {
+    let mut s = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            c if c.is_control() => s.push_str(&format!("\\u{:04x}", c as u32)),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+pub(crate) fn array(vertices: &[Vertex]) -> String {
+    let vertices: Vec<_> = vertices.iter().map(string).collect();
+    format!("[{}]", vertices.join(", "))
+}
+
+// JSON has no infinity nor NaN.
+pub(crate) fn number(value: f32) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+#[cfg(test)]
+mod test;
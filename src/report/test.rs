@@ -0,0 +1,48 @@
+use crate::Dex;
+
+#[test]
+fn test_report() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 2.0);
+    dex.add_rate('A', 'C', 5.0);
+
+    let report = dex.report(1e-4);
+    assert_eq!(report.vertices(), 3);
+    assert_eq!(report.edges(), 6);
+    assert_eq!(report.paths().len(), 6);
+    assert_eq!(report.arbitrage().len(), 1);
+
+    let mut out = Vec::new();
+    report.write_json(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("{\n  \"vertices\": 3,\n  \"edges\": 6,\n"));
+    assert!(out.contains(
+        "    {\"src\": \"A\", \"dst\": \"C\", \"rate\": 5, \"hops\": 1, \"path\": [\"A\", \"C\"]},\n"
+    ));
+    assert!(out
+        .contains("\"arbitrage\": [\n    {\"cycle\": [\"A\", \"C\", \"B\"], \"rate\": 1.25, \"net_rate\": 1.25}\n  ],"));
+
+    // The trace of each pair, the unreachable ones included.
+    dex.add_rate('D', 'E', 1.0);
+    let report = dex.report(1e-4);
+    assert_eq!(report.paths().len(), 8);
+    assert_eq!(report.traces().len(), 20);
+    let trace = &report.traces()[1];
+    assert_eq!((trace.src, trace.dst), ('A'.into(), 'C'.into()));
+    assert_eq!(trace.path, Some(vec!['A'.into(), 'C'.into()]));
+    assert!(report.traces()[2].path.is_none());
+
+    let mut out = Vec::new();
+    report.write_json(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(
+        "    {\"src\": \"A\", \"dst\": \"C\", \"options\": {\"algorithm\": \"bfs\"}, \
+         \"path\": [\"A\", \"C\"], \"elapsed_us\": "
+    ));
+    assert!(out.contains(
+        "    {\"src\": \"A\", \"dst\": \"D\", \"options\": {\"algorithm\": \"bfs\"}, \
+         \"path\": null, \"elapsed_us\": "
+    ));
+    assert!(out.ends_with("}\n  ]\n}\n"));
+}
@@ -0,0 +1,91 @@
+//! Edge and vertex removal
+
+use std::collections::HashSet;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Edge, Vertex};
+
+impl Dex {
+    /// Removes all the vertices and the edges, as well as the provider
+    /// rates.
+    ///
+    /// The registered providers and the settings are kept.
+    pub fn clear(&mut self) {
+        self.edges.clear();
+        self.quotes.clear();
+        self.disabled.clear();
+        self.changes.reset();
+    }
+
+    /// Retains only the directed edges specified by the predicate, and
+    /// removes the vertices left without any edges.
+    ///
+    /// The provider rates of the removed pairs are dropped as well, as
+    /// are the edges disabled by [`Dex::mark_down`], so that neither the
+    /// next provider rate nor [`Dex::mark_up`] brings them back.
+    #[instrument(level = "debug", skip_all)]
+    pub fn retain_edges<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Vertex, &Vertex, &Edge) -> bool,
+    {
+        let mut removed = HashSet::new();
+        let changes = &mut self.changes;
+        for (src, edges) in self.edges.iter_mut() {
+            edges.retain(|dst, edge| {
+                let retain = f(src, dst, edge);
+                if !retain {
+                    changes.push(*src, *dst);
+                    removed.insert(pair(*src, *dst));
+                }
+                retain
+            });
+        }
+        for edges in self.disabled.values_mut() {
+            edges.retain(|(src, dst, edge)| {
+                let retain = f(src, dst, edge);
+                if !retain {
+                    removed.insert(pair(*src, *dst));
+                }
+                retain
+            });
+        }
+        if !removed.is_empty() {
+            self.quotes.retain(|pair, _| !removed.contains(pair));
+            for edges in self.disabled.values_mut() {
+                edges.retain(|(src, dst, _)| !removed.contains(&pair(*src, *dst)));
+            }
+            debug!(pairs = removed.len(), "removed provider rates");
+        }
+        self.remove_isolated_vertices();
+    }
+
+    /// Removes the vertices without any incoming or outgoing edges, and
+    /// returns the number of the vertices removed.
+    pub fn remove_isolated_vertices(&mut self) -> usize {
+        let connected: HashSet<_> = self
+            .edges
+            .values()
+            .flat_map(|edges| edges.keys())
+            .copied()
+            .collect();
+        let count = self.edges.len();
+        self.edges
+            .retain(|v, edges| !edges.is_empty() || connected.contains(v));
+        let count = count - self.edges.len();
+        debug!(%count, "removed isolated vertices");
+        count
+    }
+}
+
+// Normalizes the pair the way the provider rates are kept.
+fn pair(src: Vertex, dst: Vertex) -> (Vertex, Vertex) {
+    if src < dst {
+        (src, dst)
+    } else {
+        (dst, src)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,75 @@
+use crate::Dex;
+
+#[test]
+fn test_clear() {
+    let mut dex = Dex::new();
+    let provider = dex.register_provider("provider", 1);
+    dex.add_provider_rate(provider, 'A', 'B', 1.4);
+    dex.add_rate('B', 'C', 0.2);
+
+    dex.clear();
+    assert_eq!(dex.vertices().count(), 0);
+
+    // The provider is still registered.
+    dex.add_provider_rate(provider, 'A', 'C', 0.28);
+    assert_eq!(dex.vertices().count(), 2);
+}
+
+#[test]
+fn test_retain_edges() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1.4);
+    dex.add_rate('A', 'C', 0.1);
+    dex.add_rate('B', 'C', 0.2);
+    dex.add_rate('C', 'D', 0.2);
+
+    dex.retain_edges(|src, dst, _| src != &'D'.into() && dst != &'D'.into());
+    assert_eq!(dex.vertices().count(), 3);
+    assert!(dex.get_best_rate(&'A'.into(), &'D'.into()).is_none());
+
+    // Keeps the vertex with the incoming edge only.
+    dex.retain_edges(|src, _, edge| src == &'A'.into() && edge.rate() > 1.0);
+    let vertices: Vec<_> = dex.vertices().map(ToString::to_string).collect();
+    assert_eq!(vertices, ["A", "B"]);
+    assert_eq!(
+        dex.get_best_rate(&'A'.into(), &'B'.into()).unwrap().rate(),
+        1.4
+    );
+}
+
+#[test]
+fn test_remove_isolated_vertices() {
+    let mut dex = Dex::new();
+    let provider = dex.register_provider("provider", 1);
+    dex.add_provider_rate(provider, 'A', 'B', 1.4);
+    dex.add_rate('B', 'C', 0.2);
+    dex.remove_provider_rate(provider, 'A', 'B');
+
+    assert_eq!(dex.vertices().count(), 3);
+    assert_eq!(dex.remove_isolated_vertices(), 1);
+    assert_eq!(dex.vertices().count(), 2);
+}
+
+#[test]
+fn test_retain_provider_edges() {
+    let mut dex = Dex::new();
+    let primary = dex.register_provider("primary", 2);
+    let backup = dex.register_provider("backup", 1);
+    dex.add_provider_rate(primary, 'A', 'B', 1.4);
+    dex.add_provider_rate(backup, 'A', 'B', 1.3);
+    dex.add_provider_rate(backup, 'B', 'C', 0.2);
+    dex.add_provider_rate(primary, 'C', 'D', 0.5);
+    dex.mark_down(primary);
+
+    dex.retain_edges(|src, dst, _| src != &'A'.into() && dst != &'A'.into());
+    dex.retain_edges(|src, dst, _| src != &'D'.into() && dst != &'D'.into());
+    assert_eq!(dex.vertices().count(), 2);
+
+    // Neither the fallback nor the recovery brings them back.
+    dex.mark_up(primary);
+    dex.remove_provider_rate(backup, 'A', 'B');
+    dex.mark_down(backup);
+    dex.mark_up(backup);
+    let vertices: Vec<_> = dex.vertices().map(ToString::to_string).collect();
+    assert_eq!(vertices, ["B", "C"]);
+}
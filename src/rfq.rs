@@ -0,0 +1,303 @@
+//! Request-for-quote providers
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::time::SystemTime;
+
+use tracing::{debug, instrument, warn};
+
+use super::{Dex, Path, Vertex};
+use crate::query::QueryOptions;
+
+/// The default number of the candidate routes to request the quotes
+/// for.
+const CANDIDATES: usize = 3;
+
+// The best firm quote of each request, with the endpoint name.
+type Quotes = HashMap<(Vertex, Vertex, u32), Option<(String, FirmQuote)>>;
+
+/// The firm quote request of the `amount` of `src` into `dst`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RfqRequest {
+    pub src: Vertex,
+    pub dst: Vertex,
+    pub amount: f32,
+}
+
+/// The executable quote of the request, valid until `expires_at` if
+/// given.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FirmQuote {
+    pub amount_out: f32,
+    pub expires_at: Option<SystemTime>,
+}
+
+/// The RFQ endpoint of the market maker, e.g. the HTTP API.
+///
+/// It returns `None` in case the maker declines to quote the request.
+pub trait RfqEndpoint: Send {
+    fn request(&mut self, request: &RfqRequest) -> io::Result<Option<FirmQuote>>;
+}
+
+impl<F> RfqEndpoint for F
+where
+    F: FnMut(&RfqRequest) -> io::Result<Option<FirmQuote>> + Send,
+{
+    fn request(&mut self, request: &RfqRequest) -> io::Result<Option<FirmQuote>> {
+        self(request)
+    }
+}
+
+/// The hop of the [`RfqRoute`], with the endpoint of the firm quote, or
+/// at the indicative graph rate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RfqHop {
+    pub src: Vertex,
+    pub dst: Vertex,
+    pub amount_in: f32,
+    pub amount_out: f32,
+    pub endpoint: Option<String>,
+    pub expires_at: Option<SystemTime>,
+}
+
+/// The route priced with the firm quotes where available.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RfqRoute {
+    hops: Vec<RfqHop>,
+}
+
+impl fmt::Display for RfqRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, hop) in self.hops.iter().enumerate() {
+            if i == 0 {
+                write!(f, "{}", hop.src)?;
+            }
+            write!(f, " -> {}", hop.dst)?;
+            if let Some(endpoint) = &hop.endpoint {
+                write!(f, " ({endpoint})")?;
+            }
+        }
+        write!(f, ": {}", self.amount_out())
+    }
+}
+
+impl RfqRoute {
+    pub fn hops(&self) -> &[RfqHop] {
+        &self.hops
+    }
+
+    pub fn amount_in(&self) -> f32 {
+        self.hops.first().map_or(0.0, |hop| hop.amount_in)
+    }
+
+    pub fn amount_out(&self) -> f32 {
+        self.hops.last().map_or(0.0, |hop| hop.amount_out)
+    }
+
+    /// Checks if all the hops are firm quoted.
+    pub fn is_firm(&self) -> bool {
+        self.hops.iter().all(|hop| hop.endpoint.is_some())
+    }
+
+    /// Returns the time the first firm quote expires at.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.hops.iter().filter_map(|hop| hop.expires_at).min()
+    }
+}
+
+/// The provider requesting the firm quotes from the RFQ endpoints at
+/// the query time.
+///
+/// The top candidate routes are taken from the indicative graph rates,
+/// and each hop is requested from all the endpoints.  The best firm
+/// quote replaces the indicative rate of the hop, and the route with
+/// the most received wins.  The direct pair is requested as well, as
+/// the makers quote the pairs not in the graph.
+#[derive(Default)]
+pub struct RfqProvider {
+    endpoints: Vec<(String, Box<dyn RfqEndpoint>)>,
+    candidates: Option<usize>,
+}
+
+impl fmt::Debug for RfqProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let endpoints: Vec<_> = self.endpoints.iter().map(|(name, _)| name).collect();
+        f.debug_struct("RfqProvider")
+            .field("endpoints", &endpoints)
+            .field("candidates", &self.candidates())
+            .finish()
+    }
+}
+
+impl RfqProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the RFQ endpoint under the `name`.
+    pub fn endpoint<E>(mut self, name: &str, endpoint: E) -> Self
+    where
+        E: RfqEndpoint + 'static,
+    {
+        self.endpoints.push((name.to_string(), Box::new(endpoint)));
+        self
+    }
+
+    /// Sets the number of the candidate routes to request the quotes
+    /// for.
+    pub fn with_candidates(mut self, candidates: usize) -> Self {
+        assert!(candidates > 0);
+        self.candidates = Some(candidates);
+        self
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.candidates.unwrap_or(CANDIDATES)
+    }
+
+    /// Returns the best route for the `amount` of `src`, priced with
+    /// the firm quotes.
+    #[instrument(level = "debug", skip(self, dex, options))]
+    pub fn get_best_rate(
+        &mut self,
+        dex: &Dex,
+        src: &Vertex,
+        dst: &Vertex,
+        amount: f32,
+        options: &QueryOptions,
+    ) -> Option<RfqRoute> {
+        assert!(amount > 0.0);
+        let options = options.clone().with_amount(amount);
+        let mut routes: Vec<Vec<Vertex>> = dex
+            .candidate_routes(src, dst, &options, self.candidates())
+            .into_iter()
+            .map(|path| path.path)
+            .collect();
+        let direct = vec![*src, *dst];
+        if !routes.contains(&direct) {
+            routes.push(direct);
+        }
+        let mut quotes = HashMap::new();
+        let mut best: Option<RfqRoute> = None;
+        for route in routes {
+            let route = match self.price(dex, &route, amount, &mut quotes) {
+                Some(route) => route,
+                None => continue,
+            };
+            debug!(%route, "candidate");
+            if matches!(&best, Some(best) if best.amount_out() >= route.amount_out()) {
+                continue;
+            }
+            best = Some(route);
+        }
+        best
+    }
+
+    // Prices the route hop by hop with the best of the firm quotes, or
+    // the indicative rate if none.
+    fn price(
+        &mut self,
+        dex: &Dex,
+        route: &[Vertex],
+        amount: f32,
+        quotes: &mut Quotes,
+    ) -> Option<RfqRoute> {
+        let mut hops = Vec::new();
+        let mut amount_in = amount;
+        for hop in route.windows(2) {
+            let (src, dst) = (hop[0], hop[1]);
+            let firm = quotes
+                .entry((src, dst, amount_in.to_bits()))
+                .or_insert_with(|| self.request(src, dst, amount_in))
+                .clone();
+            let indicative = dex
+                .edges
+                .get(&src)
+                .and_then(|edges| edges.get(&dst))
+                .filter(|edge| edge.is_convertible(amount_in))
+                .map(|edge| amount_in * edge.effective_rate(Some(amount_in)));
+            let (amount_out, endpoint, expires_at) = match (firm, indicative) {
+                (Some((name, quote)), _) => (quote.amount_out, Some(name), quote.expires_at),
+                (None, Some(amount_out)) => (amount_out, None, None),
+                (None, None) => return None,
+            };
+            hops.push(RfqHop {
+                src,
+                dst,
+                amount_in,
+                amount_out,
+                endpoint,
+                expires_at,
+            });
+            amount_in = amount_out;
+        }
+        Some(RfqRoute { hops })
+    }
+
+    // Requests the quote from all the endpoints, and returns the best.
+    fn request(&mut self, src: Vertex, dst: Vertex, amount: f32) -> Option<(String, FirmQuote)> {
+        let request = RfqRequest { src, dst, amount };
+        let mut best: Option<(String, FirmQuote)> = None;
+        for (name, endpoint) in &mut self.endpoints {
+            let quote = match endpoint.request(&request) {
+                Ok(Some(quote)) if quote.amount_out.is_finite() && quote.amount_out > 0.0 => quote,
+                Ok(_) => continue,
+                Err(e) => {
+                    warn!(%name, %src, %dst, %e, "rfq failed");
+                    continue;
+                }
+            };
+            if matches!(&best, Some((_, best)) if best.amount_out >= quote.amount_out) {
+                continue;
+            }
+            best = Some((name.clone(), quote));
+        }
+        best
+    }
+}
+
+impl Dex {
+    // Returns up to `count` candidate routes, the best one first and
+    // then the best ones deviating from it by avoiding each of its
+    // hops.
+    pub(crate) fn candidate_routes(
+        &self,
+        src: &Vertex,
+        dst: &Vertex,
+        options: &QueryOptions,
+        count: usize,
+    ) -> Vec<Path> {
+        let best = match self.get_best_rate_with(src, dst, options) {
+            Some(best) => best,
+            None => return Vec::new(),
+        };
+        let mut deviations: Vec<Path> = Vec::new();
+        for hop in best.path.windows(2) {
+            let mut residual = Dex {
+                edges: self.edges.clone(),
+                ..Dex::default()
+            };
+            if let Some(edges) = residual.edges.get_mut(&hop[0]) {
+                edges.remove(&hop[1]);
+            }
+            if let Some(path) = residual.get_best_rate_with(src, dst, options) {
+                if !deviations.iter().any(|other| other.path == path.path) {
+                    deviations.push(path);
+                }
+            }
+        }
+        deviations.sort_by(|a, b| {
+            options
+                .score(b)
+                .partial_cmp(&options.score(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let mut routes = vec![best];
+        routes.extend(deviations.into_iter().take(count - 1));
+        routes
+    }
+}
+
+#[cfg(test)]
+mod test;
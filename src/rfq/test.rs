@@ -0,0 +1,87 @@
+use std::io;
+
+use super::*;
+use crate::test::vertex;
+
+fn quote(is_quoted: bool, amount_out: f32) -> io::Result<Option<FirmQuote>> {
+    if !is_quoted {
+        return Ok(None);
+    }
+    Ok(Some(FirmQuote {
+        amount_out,
+        expires_at: None,
+    }))
+}
+
+fn dex() -> Dex {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("ETH"), vertex("USDC"), 2000.0);
+    dex.add_rate(vertex("ETH"), vertex("BTC"), 0.05);
+    dex.add_rate(vertex("BTC"), vertex("USDC"), 39000.0);
+    dex
+}
+
+#[test]
+fn test_candidate_routes() {
+    let dex = dex();
+    let routes = dex.candidate_routes(&vertex("ETH"), &vertex("USDC"), &QueryOptions::new(), 3);
+    let routes: Vec<_> = routes.iter().map(|path| path.to_string()).collect();
+    assert_eq!(routes, ["ETH -> USDC: 2000", "ETH -> BTC -> USDC: 1950"]);
+    let routes = dex.candidate_routes(&vertex("ETH"), &vertex("USDC"), &QueryOptions::new(), 1);
+    assert_eq!(routes.len(), 1);
+}
+
+#[test]
+fn test_firm_quotes() {
+    let dex = dex();
+    let mut rfq = RfqProvider::new()
+        .endpoint("maker", |request: &RfqRequest| {
+            // Only the BTC -> USDC pair, better than the graph.
+            quote(request.src == vertex("BTC"), request.amount * 41000.0)
+        })
+        .endpoint("down", |_: &RfqRequest| {
+            Err(io::Error::new(io::ErrorKind::TimedOut, "timed out"))
+        });
+    let route = rfq
+        .get_best_rate(
+            &dex,
+            &vertex("ETH"),
+            &vertex("USDC"),
+            10.0,
+            &QueryOptions::new(),
+        )
+        .unwrap();
+    assert_eq!(route.to_string(), "ETH -> BTC -> USDC (maker): 20500");
+    assert_eq!(route.amount_in(), 10.0);
+    assert!(!route.is_firm());
+    assert_eq!(route.hops()[0].endpoint, None);
+}
+
+#[test]
+fn test_direct_quote() {
+    let dex = dex();
+    let mut rfq = RfqProvider::new().endpoint("maker", |request: &RfqRequest| {
+        let is_quoted = request.src == vertex("ETH") && request.dst == vertex("DAI");
+        quote(is_quoted, request.amount * 1990.0)
+    });
+    let route = rfq
+        .get_best_rate(
+            &dex,
+            &vertex("ETH"),
+            &vertex("DAI"),
+            1.0,
+            &QueryOptions::new(),
+        )
+        .unwrap();
+    assert_eq!(route.to_string(), "ETH -> DAI (maker): 1990");
+    assert!(route.is_firm());
+    assert!(rfq
+        .get_best_rate(
+            &dex,
+            &vertex("ETH"),
+            &vertex("EUR"),
+            1.0,
+            &QueryOptions::new()
+        )
+        .is_none());
+}
@@ -0,0 +1,25 @@
+use super::Rng;
+
+#[test]
+fn test_seeded() {
+    let mut a = Rng::new(42);
+    let mut b = Rng::new(42);
+    let mut c = Rng::new(43);
+    for _ in 0..100 {
+        let x = a.next_u64();
+        assert_eq!(x, b.next_u64());
+        assert_ne!(x, c.next_u64());
+    }
+    assert_eq!(Rng::new(0).next_u64(), 0xe220_a839_7b1d_cdaf);
+}
+
+#[test]
+fn test_range() {
+    let mut rng = Rng::new(7);
+    for _ in 0..1_000 {
+        let x = rng.next_f64();
+        assert!((0.0..1.0).contains(&x));
+        assert!(rng.below(10) < 10);
+        assert!(rng.normal().is_finite());
+    }
+}
@@ -0,0 +1,642 @@
+//! HTTP server of the daemon
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use tracing::{debug, instrument, warn};
+
+use super::{Path, Vertex};
+use crate::auth::{Auth, Denied};
+use crate::cancel::CancelToken;
+use crate::cycle::Cycle;
+use crate::daemon::Daemon;
+use crate::query::QueryOptions;
+use crate::report::{array, number, quote, string};
+use crate::shutdown::Shutdown;
+use crate::tls::{Acceptor, Stream};
+use crate::visualize::{graph_json, PAGE};
+
+/// The interval to check for the shutdown while idle.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The maximum request body size.
+const MAX_BODY: usize = 1 << 20;
+
+/// The maximum size of the request line and the headers together, and
+/// the maximum number of the headers.
+const MAX_HEADER: usize = 16 << 10;
+const MAX_HEADERS: usize = 100;
+
+/// The default read and write timeout of the connections.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The default and the maximum page size.
+const PAGE_LIMIT: usize = 1_000;
+const MAX_PAGE_LIMIT: usize = 10_000;
+
+/// The default minimum arbitrage reported, 1 bp.
+const MIN_BPS: f32 = 1.0;
+
+/// The name of the graph served without the `/graphs/<NAME>` prefix.
+pub const DEFAULT_GRAPH: &str = "default";
+
+/// The minimal HTTP/1.1 server of the [`Graphs`], one request per
+/// connection.
+///
+/// - `GET /graphs` returns the graph names.
+/// - `GET /graphs/<NAME>/health` returns the graph version.
+/// - `GET /graphs/<NAME>/best-rate?src=<SRC>&dst=<DST>` returns the
+///   best rate path.
+/// - `POST /graphs/<NAME>/rates` adds the `src,dst,rate` lines of the
+///   body.
+/// - `GET /graphs/<NAME>/pairs?limit=<N>&after=<SRC>,<DST>` returns the
+///   page of the precomputed best rate paths, with the `next` cursor.
+/// - `GET /graphs/<NAME>/arbitrage?min_bps=<BPS>&offset=<N>&limit=<N>`
+///   returns the page of the arbitrage cycles.
+/// - `GET /graphs/<NAME>/graph` returns the vertices and the edges with
+///   the age of the rates.
+/// - `GET /graphs/<NAME>/visualize` returns the page drawing the graph.
+///
+/// The pairs and the arbitrage are streamed as the server-sent events
+/// instead, as they're computed, with `?stream=sse` or the
+/// `Accept: text/event-stream` header.
+///
+/// The [`DEFAULT_GRAPH`] is also served without the prefix, e.g.
+/// `GET /best-rate`.  All the requests need the API key with the
+/// [`Auth`] set, and are over TLS with the [`Acceptor`] set.
+#[derive(Debug)]
+pub struct Server {
+    listener: TcpListener,
+    graphs: Arc<Graphs>,
+    timeout: Duration,
+    auth: Option<Arc<Auth>>,
+    acceptor: Option<Arc<dyn Acceptor>>,
+}
+
+/// The independent graphs by name, each with its own [`Daemon`] and so
+/// its own providers and settings.
+#[derive(Debug, Default)]
+pub struct Graphs {
+    daemons: BTreeMap<String, Daemon>,
+}
+
+impl Graphs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the graph served as [`DEFAULT_GRAPH`].
+    pub fn single(daemon: Daemon) -> Self {
+        Self::new().graph(DEFAULT_GRAPH, daemon)
+    }
+
+    /// Adds the graph, replacing the one with the same name.
+    pub fn graph(mut self, name: &str, daemon: Daemon) -> Self {
+        self.daemons.insert(name.to_string(), daemon);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Daemon> {
+        self.daemons.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.daemons.keys().map(String::as_str)
+    }
+}
+
+/// The parsed request.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Request {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) query: Vec<(String, String)>,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Request {
+    pub(crate) fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the header value, case-insensitively by the name.
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Reads the request, with the request line and the headers up to
+    /// [`MAX_HEADER`] bytes and [`MAX_HEADERS`] headers.
+    pub(crate) fn read<R: BufRead>(reader: &mut R) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut head = reader.by_ref().take(MAX_HEADER as u64);
+        let mut read_line = |line: &mut String| {
+            let n = head.read_line(line)?;
+            if head.limit() == 0 && !line.ends_with('\n') {
+                return Err(invalid("headers too large"));
+            }
+            Ok(n)
+        };
+        let mut line = String::new();
+        read_line(&mut line)?;
+        let mut parts = line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next()) {
+            (Some(method), Some(target)) => (method.to_string(), target),
+            _ => return Err(invalid("invalid request line")),
+        };
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, query),
+            None => (target, ""),
+        };
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => (decode(key), decode(value)),
+                None => (decode(pair), String::new()),
+            })
+            .collect();
+        let mut request = Self {
+            method,
+            path: decode(path),
+            query,
+            ..Self::default()
+        };
+        loop {
+            let mut line = String::new();
+            if read_line(&mut line)? == 0 {
+                return Err(invalid("unexpected end of headers"));
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| invalid("invalid header"))?;
+            if request.headers.len() == MAX_HEADERS {
+                return Err(invalid("too many headers"));
+            }
+            request
+                .headers
+                .push((name.trim().to_string(), value.trim().to_string()));
+        }
+        let len = match request.header("Content-Length") {
+            Some(len) => len.parse().map_err(|_| invalid("invalid content length"))?,
+            None => 0,
+        };
+        if len > MAX_BODY {
+            return Err(invalid("body too large"));
+        }
+        request.body = vec![0; len];
+        reader.read_exact(&mut request.body)?;
+        Ok(request)
+    }
+}
+
+/// The response with the JSON body, or the HTML page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Response {
+    pub(crate) status: u16,
+    pub(crate) headers: Vec<(&'static str, String)>,
+    pub(crate) content_type: &'static str,
+    pub(crate) body: String,
+}
+
+impl Response {
+    pub(crate) fn ok(body: String) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            content_type: "application/json",
+            body,
+        }
+    }
+
+    pub(crate) fn html(body: &str) -> Self {
+        Self {
+            content_type: "text/html; charset=utf-8",
+            ..Self::ok(body.to_string())
+        }
+    }
+
+    pub(crate) fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            ..Self::ok(format!("{{\"error\": {}}}", quote(message)))
+        }
+    }
+
+    pub(crate) fn denied(denied: Denied) -> Self {
+        match denied {
+            Denied::Missing | Denied::Invalid => Self::error(401, &denied.to_string()),
+            Denied::ReadOnly => Self::error(403, &denied.to_string()),
+            Denied::RateLimited(wait) => {
+                let mut response = Self::error(429, &denied.to_string());
+                let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+                response.headers.push(("Retry-After", secs.to_string()));
+                response
+            }
+        }
+    }
+
+    pub(crate) fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            429 => "Too Many Requests",
+            503 => "Service Unavailable",
+            _ => "",
+        };
+        write!(out, "HTTP/1.1 {} {reason}\r\n", self.status)?;
+        for (name, value) in &self.headers {
+            write!(out, "{name}: {value}\r\n")?;
+        }
+        write!(
+            out,
+            "Content-Type: {}\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.content_type,
+            self.body.len(),
+            self.body,
+        )?;
+        out.flush()
+    }
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A, graphs: Graphs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            graphs: Arc::new(graphs),
+            timeout: TIMEOUT,
+            auth: None,
+            acceptor: None,
+        })
+    }
+
+    /// Closes the connection idle for the `timeout` in either direction,
+    /// 10 seconds by default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Requires the API key.
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(Arc::new(auth));
+        self
+    }
+
+    /// Terminates the TLS, or any other protocol, of the connections.
+    pub fn with_acceptor(mut self, acceptor: Arc<dyn Acceptor>) -> Self {
+        self.acceptor = Some(acceptor);
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serves the requests until the shutdown is requested.
+    ///
+    /// Each connection is handled in its own thread as the in-flight
+    /// query of the `shutdown`, and is closed once idle for the timeout,
+    /// so that the slow client can't hold the shutdown.
+    #[instrument(level = "debug", skip_all)]
+    pub fn serve(&self, shutdown: &Shutdown) -> io::Result<()> {
+        while !shutdown.is_requested() {
+            let (stream, peer) = match self.listener.accept() {
+                Ok(conn) => conn,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(ACCEPT_INTERVAL);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let in_flight = match shutdown.begin() {
+                Some(in_flight) => in_flight,
+                None => break,
+            };
+            let graphs = self.graphs.clone();
+            let auth = self.auth.clone();
+            let acceptor = self.acceptor.clone();
+            let timeout = Some(self.timeout);
+            thread::spawn(move || {
+                let result = stream
+                    .set_nonblocking(false)
+                    .and_then(|_| stream.set_read_timeout(timeout))
+                    .and_then(|_| stream.set_write_timeout(timeout));
+                let result = result.and_then(|_| match acceptor {
+                    Some(acceptor) => acceptor.accept(stream),
+                    None => Ok(Box::new(stream) as Box<dyn Stream>),
+                });
+                let result = result.and_then(|stream| serve_conn(&graphs, auth.as_deref(), stream));
+                if let Err(e) = result {
+                    warn!(%peer, %e, "connection failed");
+                }
+                drop(in_flight);
+            });
+        }
+        debug!("stopped");
+        Ok(())
+    }
+}
+
+fn serve_conn(graphs: &Graphs, auth: Option<&Auth>, stream: Box<dyn Stream>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let response = match Request::read(&mut reader) {
+        Ok(request) => match auth.map(|auth| auth.check(&request, Instant::now())) {
+            Some(Err(denied)) => {
+                warn!(method = %request.method, path = %request.path, %denied, "denied");
+                Response::denied(denied)
+            }
+            _ if is_stream(&request) => return events(graphs, &request, reader.get_mut()),
+            _ => handle(graphs, &request),
+        },
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => Response::error(400, &e.to_string()),
+        Err(e) => return Err(e),
+    };
+    response.write(reader.get_mut())
+}
+
+/// Routes the request to the graph.
+pub(crate) fn handle(graphs: &Graphs, request: &Request) -> Response {
+    debug!(method = %request.method, path = %request.path, "request");
+    if request.path == "/graphs" {
+        if request.method != "GET" {
+            return Response::error(405, "method not allowed");
+        }
+        let names: Vec<_> = graphs.names().map(quote).collect();
+        return Response::ok(format!("{{\"graphs\": [{}]}}", names.join(", ")));
+    }
+    match resolve(graphs, request) {
+        Ok((daemon, path)) => route(daemon, request, path),
+        Err(response) => response,
+    }
+}
+
+// Returns the graph and the path within it.
+fn resolve<'a>(
+    graphs: &'a Graphs,
+    request: &'a Request,
+) -> Result<(&'a Daemon, &'a str), Response> {
+    let (name, path) = match request.path.strip_prefix("/graphs/") {
+        Some(rest) => match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        },
+        None => (DEFAULT_GRAPH, request.path.as_str()),
+    };
+    match graphs.get(name) {
+        Some(daemon) => Ok((daemon, path)),
+        None => Err(Response::error(404, &format!("no graph {name:?}"))),
+    }
+}
+
+fn route(daemon: &Daemon, request: &Request, path: &str) -> Response {
+    match (request.method.as_str(), path) {
+        ("GET", "/health") => Response::ok(format!("{{\"version\": {}}}", daemon.version())),
+        ("GET", "/best-rate") => best_rate(daemon, request),
+        ("GET", "/pairs") => pairs(daemon, request),
+        ("GET", "/arbitrage") => arbitrage(daemon, request),
+        ("GET", "/graph") => Response::ok(daemon.read(|dex| graph_json(dex, SystemTime::now()))),
+        ("GET", "/visualize") => Response::html(PAGE),
+        ("POST", "/rates") => match daemon.update(|dex| dex.load_csv(request.body.as_slice())) {
+            Ok(count) => Response::ok(format!("{{\"loaded\": {count}}}")),
+            Err(e) => Response::error(400, &e.to_string()),
+        },
+        (
+            _,
+            "/health" | "/best-rate" | "/rates" | "/pairs" | "/arbitrage" | "/graph" | "/visualize",
+        ) => Response::error(405, "method not allowed"),
+        _ => Response::error(404, "not found"),
+    }
+}
+
+fn best_rate(daemon: &Daemon, request: &Request) -> Response {
+    let (src, dst) = match (param(request, "src"), param(request, "dst")) {
+        (Ok(src), Ok(dst)) => (src, dst),
+        (Err(e), _) | (_, Err(e)) => return e,
+    };
+    match daemon.get_best_rate(&src, &dst) {
+        Some(served) => Response::ok(format!(
+            "{{\"src\": {}, \"dst\": {}, \"rate\": {}, \"path\": {}, \"staleness_ms\": {}}}",
+            string(&src),
+            string(&dst),
+            number(served.path.rate()),
+            array(&served.path.path),
+            served.staleness.as_millis(),
+        )),
+        None => Response::error(404, &format!("no {src} -> {dst} path")),
+    }
+}
+
+fn pairs(daemon: &Daemon, request: &Request) -> Response {
+    let limit = match limit(request) {
+        Ok(limit) => limit,
+        Err(e) => return e,
+    };
+    let after = match request.param("after").map(|after| after.split_once(',')) {
+        Some(Some((src, dst))) => match (src.trim().parse(), dst.trim().parse()) {
+            (Ok(src), Ok(dst)) => Some((src, dst)),
+            _ => return Response::error(400, "invalid after"),
+        },
+        Some(None) => return Response::error(400, "invalid after"),
+        None => None,
+    };
+    let mut table = daemon.table();
+    if table.version() == 0 {
+        daemon.refresh();
+        table = daemon.table();
+    }
+    let mut paths = table.paths(after);
+    let page: Vec<_> = paths.by_ref().take(limit).collect();
+    let next = match (paths.next(), page.last()) {
+        (Some(_), Some(last)) => quote(&format!("{},{}", last.path[0], last.last())),
+        _ => "null".to_string(),
+    };
+    let page: Vec<_> = page.into_iter().map(path_json).collect();
+    Response::ok(format!(
+        "{{\"pairs\": [{}], \"next\": {next}, \"version\": {}}}",
+        page.join(", "),
+        table.version(),
+    ))
+}
+
+fn arbitrage(daemon: &Daemon, request: &Request) -> Response {
+    let (limit, min_bps) = match (limit(request), number_param(request, "min_bps", MIN_BPS)) {
+        (Ok(limit), Ok(min_bps)) => (limit, min_bps),
+        (Err(e), _) | (_, Err(e)) => return e,
+    };
+    let offset = match number_param(request, "offset", 0) {
+        Ok(offset) => offset,
+        Err(e) => return e,
+    };
+    let cycles = cycles(daemon, min_bps);
+    let page: Vec<_> = cycles.iter().skip(offset).take(limit).cloned().collect();
+    let next = if offset + page.len() < cycles.len() {
+        (offset + page.len()).to_string()
+    } else {
+        "null".to_string()
+    };
+    Response::ok(format!(
+        "{{\"arbitrage\": [{}], \"next\": {next}}}",
+        page.join(", ")
+    ))
+}
+
+// Returns the arbitrage cycles above `min_bps` net of the fees as
+// JSON.
+fn cycles(daemon: &Daemon, min_bps: f32) -> Vec<String> {
+    let cycles = daemon.read(|dex| dex.find_arbitrage(min_bps, None));
+    cycles.iter().map(cycle_json).collect()
+}
+
+fn cycle_json(cycle: &Cycle) -> String {
+    let vertices: Vec<_> = cycle.edges().iter().map(|(src, ..)| *src).collect();
+    format!(
+        "{{\"cycle\": {}, \"rate\": {}, \"net_rate\": {}}}",
+        array(&vertices),
+        number(cycle.rate()),
+        number(cycle.net_rate()),
+    )
+}
+
+fn path_json(path: &Path) -> String {
+    format!(
+        "{{\"src\": {}, \"dst\": {}, \"rate\": {}, \"path\": {}}}",
+        string(&path.path[0]),
+        string(path.last()),
+        number(path.rate()),
+        array(&path.path),
+    )
+}
+
+fn is_stream(request: &Request) -> bool {
+    request.param("stream") == Some("sse")
+        || matches!(request.header("Accept"), Some(accept) if accept.contains("text/event-stream"))
+}
+
+/// Streams the pairs or the arbitrage as the server-sent events, one
+/// event per item as it's computed, and the `end` event with the
+/// count.
+pub(crate) fn events<W: Write>(graphs: &Graphs, request: &Request, out: &mut W) -> io::Result<()> {
+    let (daemon, path) = match resolve(graphs, request) {
+        Ok((daemon, path @ ("/pairs" | "/arbitrage"))) if request.method == "GET" => (daemon, path),
+        Ok(_) => return Response::error(404, "no event stream").write(out),
+        Err(response) => return response.write(out),
+    };
+    let min_bps = match number_param(request, "min_bps", MIN_BPS) {
+        Ok(min_bps) => min_bps,
+        Err(response) => return response.write(out),
+    };
+    write!(
+        out,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+         Cache-Control: no-cache\r\nConnection: close\r\n\r\n"
+    )?;
+    out.flush()?;
+    let mut count = 0;
+    if path == "/pairs" {
+        // Computed from the snapshot, flushed per source.
+        let dex = daemon.read(|dex| dex.clone());
+        let options = QueryOptions::default();
+        for src in dex.vertices() {
+            for path in dex.get_best_rates_from(src, &options).values() {
+                write!(out, "data: {}\n\n", path_json(path))?;
+                count += 1;
+            }
+            out.flush()?;
+        }
+    } else {
+        // Flushed per cycle as it's found, and the search stops on the
+        // first write error.
+        let dex = daemon.read(|dex| dex.clone());
+        let cancel = CancelToken::new();
+        let mut result = Ok(());
+        dex.for_each_arbitrage(min_bps, None, &cancel, |cycle| {
+            if result.is_ok() {
+                result = write!(out, "data: {}\n\n", cycle_json(&cycle)).and_then(|_| out.flush());
+                count += 1;
+            }
+            if result.is_err() {
+                cancel.cancel();
+            }
+        });
+        result?;
+    }
+    write!(out, "event: end\ndata: {{\"count\": {count}}}\n\n")?;
+    out.flush()
+}
+
+fn limit(request: &Request) -> Result<usize, Response> {
+    match number_param(request, "limit", PAGE_LIMIT)? {
+        limit if (1..=MAX_PAGE_LIMIT).contains(&limit) => Ok(limit),
+        _ => Err(Response::error(400, "invalid limit")),
+    }
+}
+
+fn number_param<T: FromStr>(request: &Request, name: &str, default: T) -> Result<T, Response> {
+    match request.param(name) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| Response::error(400, &format!("invalid {name}"))),
+        None => Ok(default),
+    }
+}
+
+fn param(request: &Request, name: &str) -> Result<Vertex, Response> {
+    let value = request
+        .param(name)
+        .ok_or_else(|| Response::error(400, &format!("missing {name}")))?;
+    value
+        .trim()
+        .parse()
+        .map_err(|e| Response::error(400, &format!("{e}")))
+}
+
+// Decodes the percent-encoded URL component.
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => {
+                        decoded.push(b);
+                        i += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,383 @@
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::{
+    decode, events, handle, is_stream, Graphs, Request, Response, Server, MAX_HEADER, MAX_HEADERS,
+};
+use crate::auth::{ApiKey, Auth, Denied};
+use crate::daemon::Daemon;
+use crate::shutdown::Shutdown;
+use crate::test::vertex;
+use crate::tls::{Acceptor, Stream};
+use crate::Dex;
+
+fn daemon() -> Daemon {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    Daemon::new(dex).with_max_staleness(Duration::from_secs(3600))
+}
+
+fn request(method: &str, target: &str, body: &str) -> Request {
+    let raw = format!(
+        "{method} {target} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+    Request::read(&mut raw.as_bytes()).unwrap()
+}
+
+#[test]
+fn test_request() {
+    let request = request("GET", "/best-rate?src=A&dst=B%20C", "");
+    assert_eq!(request.path, "/best-rate");
+    assert_eq!(request.param("dst"), Some("B C"));
+    assert_eq!(request.header("content-length"), Some("0"));
+    assert!(Request::read(&mut "GET\r\n\r\n".as_bytes()).is_err());
+    assert_eq!(decode("%2"), "%2");
+}
+
+#[test]
+fn test_request_limits() {
+    let long = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_HEADER));
+    let e = Request::read(&mut long.as_bytes()).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(e.to_string(), "headers too large");
+
+    let many = format!(
+        "GET /health HTTP/1.1\r\n{}\r\n",
+        "X-Pad: 1\r\n".repeat(MAX_HEADERS + 1)
+    );
+    let e = Request::read(&mut many.as_bytes()).unwrap_err();
+    assert_eq!(e.to_string(), "too many headers");
+    let max = format!(
+        "GET /health HTTP/1.1\r\n{}\r\n",
+        "X-Pad: 1\r\n".repeat(MAX_HEADERS)
+    );
+    assert_eq!(
+        Request::read(&mut max.as_bytes()).unwrap().headers.len(),
+        MAX_HEADERS
+    );
+}
+
+#[test]
+fn test_handle() {
+    let daemon = daemon();
+    daemon.refresh();
+    let daemon = Graphs::single(daemon);
+    let response = handle(&daemon, &request("GET", "/best-rate?src=A&dst=B", ""));
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.body,
+        "{\"src\": \"A\", \"dst\": \"B\", \"rate\": 2, \"path\": [\"A\", \"B\"], \"staleness_ms\": 0}"
+    );
+    let response = handle(&daemon, &request("GET", "/best-rate?src=A", ""));
+    assert_eq!(response.status, 400);
+    assert_eq!(response.body, "{\"error\": \"missing dst\"}");
+    let response = handle(&daemon, &request("GET", "/best-rate?src=A&dst=C", ""));
+    assert_eq!(response.status, 404);
+
+    let response = handle(&daemon, &request("POST", "/rates", "B,C,3\nA,D,4\n"));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, "{\"loaded\": 2}");
+    assert_eq!(
+        handle(&daemon, &request("POST", "/rates", "B,C\n")).status,
+        400
+    );
+    assert_eq!(handle(&daemon, &request("GET", "/rates", "")).status, 405);
+    assert_eq!(handle(&daemon, &request("GET", "/unknown", "")).status, 404);
+}
+
+#[test]
+fn test_visualize() {
+    let graphs = Graphs::single(daemon());
+    let response = handle(&graphs, &request("GET", "/graphs/default/visualize", ""));
+    assert_eq!(response.status, 200);
+    assert_eq!(response.content_type, "text/html; charset=utf-8");
+    assert!(response.body.starts_with("<!DOCTYPE html>"));
+    let response = handle(&graphs, &request("GET", "/graph", ""));
+    assert_eq!(response.content_type, "application/json");
+    assert!(response.body.starts_with("{\"nodes\": [\"A\", \"B\"]"));
+    assert_eq!(handle(&graphs, &request("POST", "/graph", "")).status, 405);
+
+    let mut out = Vec::new();
+    Response::html("<p>").write(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("Content-Type: text/html; charset=utf-8\r\n"));
+}
+
+#[test]
+fn test_serve() {
+    let server = Server::bind("127.0.0.1:0", Graphs::single(daemon().start())).unwrap();
+    let addr = server.local_addr().unwrap();
+    let shutdown = Shutdown::new();
+    let handle = {
+        let shutdown = shutdown.clone();
+        thread::spawn(move || server.serve(&shutdown))
+    };
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET /health HTTP/1.1\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("{\"version\": 1}"));
+
+    assert!(shutdown.finish(Duration::from_secs(10)));
+    handle.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_serve_timeout() {
+    let server = Server::bind("127.0.0.1:0", Graphs::single(daemon().start()))
+        .unwrap()
+        .with_timeout(Duration::from_millis(100));
+    let addr = server.local_addr().unwrap();
+    let shutdown = Shutdown::new();
+    let handle = {
+        let shutdown = shutdown.clone();
+        thread::spawn(move || server.serve(&shutdown))
+    };
+
+    // The slow client never finishing the headers.
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET /health HTTP/1.1\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert_eq!(response, "");
+
+    assert!(shutdown.finish(Duration::from_secs(10)));
+    handle.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_graphs() {
+    let mut fx = Dex::new();
+    fx.add_rate(vertex("USD"), vertex("EUR"), 0.9);
+    let graphs = Graphs::new()
+        .graph("dex", daemon())
+        .graph("fx", Daemon::new(fx));
+    let response = handle(&graphs, &request("GET", "/graphs", ""));
+    assert_eq!(response.body, "{\"graphs\": [\"dex\", \"fx\"]}");
+
+    let response = handle(
+        &graphs,
+        &request("GET", "/graphs/fx/best-rate?src=USD&dst=EUR", ""),
+    );
+    assert_eq!(response.status, 200);
+    assert!(response.body.contains("\"rate\": 0.9"));
+    let response = handle(&graphs, &request("POST", "/graphs/dex/rates", "B,C,3\n"));
+    assert_eq!(response.status, 200);
+    assert_eq!(graphs.get("dex").unwrap().version(), 2);
+    assert_eq!(graphs.get("fx").unwrap().version(), 1);
+
+    let response = handle(&graphs, &request("GET", "/graphs/nft/health", ""));
+    assert_eq!(response.status, 404);
+    assert_eq!(response.body, "{\"error\": \"no graph \\\"nft\\\"\"}");
+    assert_eq!(
+        handle(&graphs, &request("GET", "/graphs/fx", "")).status,
+        404
+    );
+    assert_eq!(handle(&graphs, &request("GET", "/health", "")).status, 404);
+}
+
+#[test]
+fn test_auth() {
+    let auth = Auth::new().key(ApiKey::new("ops", "s3cret"));
+    let server = Server::bind("127.0.0.1:0", Graphs::single(daemon()))
+        .unwrap()
+        .with_auth(auth);
+    let addr = server.local_addr().unwrap();
+    let shutdown = Shutdown::new();
+    let handle = {
+        let shutdown = shutdown.clone();
+        thread::spawn(move || server.serve(&shutdown))
+    };
+
+    let get = |request: &[u8]| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+    let response = get(b"GET /health HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+    let response = get(b"GET /health HTTP/1.1\r\nX-Api-Key: s3cret\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    assert_eq!(
+        Response::denied(Denied::RateLimited(Duration::from_millis(1500))).headers,
+        [("Retry-After", "2".to_string())]
+    );
+    assert!(shutdown.finish(Duration::from_secs(10)));
+    handle.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_acceptor() {
+    #[derive(Debug, Default)]
+    struct Counter(AtomicUsize);
+
+    impl Acceptor for Counter {
+        fn accept(&self, stream: TcpStream) -> io::Result<Box<dyn Stream>> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(Box::new(stream))
+        }
+    }
+
+    let acceptor = Arc::new(Counter::default());
+    let server = Server::bind("127.0.0.1:0", Graphs::single(daemon()))
+        .unwrap()
+        .with_acceptor(acceptor.clone());
+    let addr = server.local_addr().unwrap();
+    let shutdown = Shutdown::new();
+    let handle = {
+        let shutdown = shutdown.clone();
+        thread::spawn(move || server.serve(&shutdown))
+    };
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"GET /health HTTP/1.1\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(shutdown.finish(Duration::from_secs(10)));
+    handle.join().unwrap().unwrap();
+    assert_eq!(acceptor.0.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_pages() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('A', 'C', 7.0);
+    let graphs = Graphs::single(Daemon::new(dex).with_max_staleness(Duration::from_secs(3600)));
+
+    let response = handle(&graphs, &request("GET", "/pairs?limit=4", ""));
+    assert_eq!(response.status, 200);
+    assert!(
+        response.body.contains("\"next\": \"B,C\""),
+        "{}",
+        response.body
+    );
+    let response = handle(&graphs, &request("GET", "/pairs?limit=4&after=B,C", ""));
+    assert!(response
+        .body
+        .starts_with("{\"pairs\": [{\"src\": \"C\", \"dst\": \"A\""));
+    assert!(response.body.contains("\"next\": null"));
+    assert_eq!(
+        handle(&graphs, &request("GET", "/pairs?limit=0", "")).status,
+        400
+    );
+    assert_eq!(
+        handle(&graphs, &request("GET", "/pairs?after=B", "")).status,
+        400
+    );
+
+    let response = handle(&graphs, &request("GET", "/arbitrage?limit=1", ""));
+    assert_eq!(
+        response.body,
+        "{\"arbitrage\": [{\"cycle\": [\"A\", \"C\", \"B\"], \"rate\": 1.1666667, \"net_rate\": 1.1666667}], \"next\": null}"
+    );
+    let response = handle(&graphs, &request("GET", "/arbitrage?offset=1", ""));
+    assert_eq!(response.body, "{\"arbitrage\": [], \"next\": null}");
+    assert_eq!(
+        handle(&graphs, &request("GET", "/arbitrage?min_bps=x", "")).status,
+        400
+    );
+}
+
+#[test]
+fn test_events() {
+    let graphs = Graphs::single(daemon());
+    let mut out = Vec::new();
+    events(&graphs, &request("GET", "/pairs?stream=sse", ""), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n"));
+    assert!(out.contains(
+        "\r\n\r\ndata: {\"src\": \"A\", \"dst\": \"B\", \"rate\": 2, \"path\": [\"A\", \"B\"]}\n\n"
+    ));
+    assert!(out.ends_with("event: end\ndata: {\"count\": 2}\n\n"));
+
+    let mut request = request("GET", "/arbitrage", "");
+    request
+        .headers
+        .push(("Accept".into(), "text/event-stream".into()));
+    assert!(is_stream(&request));
+    let mut out = Vec::new();
+    events(&graphs, &request, &mut out).unwrap();
+    assert!(String::from_utf8(out)
+        .unwrap()
+        .ends_with("event: end\ndata: {\"count\": 0}\n\n"));
+
+    let mut out = Vec::new();
+    events(&graphs, &self::request("GET", "/health", ""), &mut out).unwrap();
+    assert!(out.starts_with(b"HTTP/1.1 404 Not Found\r\n"));
+}
+
+// The writer keeping the flushed bytes, and failing the writes after
+// `limit` flushes.
+#[derive(Default)]
+struct Flushes {
+    buf: Vec<u8>,
+    flushed: Vec<String>,
+    limit: Option<usize>,
+}
+
+impl Write for Flushes {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if matches!(self.limit, Some(limit) if self.flushed.len() >= limit) {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let buf = std::mem::take(&mut self.buf);
+        self.flushed.push(String::from_utf8(buf).unwrap());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_events_arbitrage() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 0.2);
+    dex.add_rate('C', 'D', 1.0);
+    dex.add_rate('D', 'A', 0.25);
+    let graphs = Graphs::single(Daemon::new(dex).with_max_staleness(Duration::from_secs(3600)));
+    let request = request("GET", "/arbitrage?stream=sse", "");
+
+    // Each cycle is flushed on its own as it's found.
+    let mut out = Flushes::default();
+    events(&graphs, &request, &mut out).unwrap();
+    let cycles: Vec<_> = out
+        .flushed
+        .iter()
+        .filter(|event| event.starts_with("data: {\"cycle\""))
+        .collect();
+    assert_eq!(cycles.len(), 3, "{:?}", out.flushed);
+    assert!(cycles
+        .iter()
+        .all(|event| event.matches("data: ").count() == 1));
+    assert_eq!(
+        out.flushed.last().unwrap(),
+        "event: end\ndata: {\"count\": 3}\n\n"
+    );
+
+    // The search stops on the write error.
+    let mut out = Flushes {
+        limit: Some(2),
+        ..Flushes::default()
+    };
+    let e = events(&graphs, &request, &mut out).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+    assert_eq!(out.flushed.len(), 2);
+    assert!(out.buf.is_empty());
+}
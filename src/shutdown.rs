@@ -0,0 +1,197 @@
+//! Graceful shutdown on the signals
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
+
+use crate::cancel::CancelToken;
+
+/// The interval to check for the signals.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Set by the signal handler, the only thing safe to do in it.
+static SIGNALLED: AtomicBool = AtomicBool::new(false);
+
+type Hook = Box<dyn FnOnce() + Send>;
+
+/// The shutdown coordinator of the long running process, e.g. the
+/// daemon.
+///
+/// Once the shutdown is requested, by [`Shutdown::request`] or by the
+/// SIGINT/SIGTERM with [`Shutdown::with_signals`], no new query begins,
+/// the in-flight ones finish, and then the hooks, e.g. to flush the
+/// persistence backend or to close the provider connections, run in
+/// the reverse order of the registration.
+///
+/// The clones share the same state.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    signals: AtomicBool,
+    requested: AtomicBool,
+    in_flight: Mutex<usize>,
+    idle: Condvar,
+    hooks: Mutex<Vec<Hook>>,
+}
+
+impl fmt::Debug for Shutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shutdown")
+            .field("requested", &self.is_requested())
+            .field("in_flight", &self.in_flight())
+            .finish()
+    }
+}
+
+/// The in-flight query, finished on drop.
+#[derive(Debug)]
+pub struct InFlight {
+    shutdown: Shutdown,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut in_flight = self.shutdown.inner.in_flight.lock().unwrap();
+        *in_flight -= 1;
+        if *in_flight == 0 {
+            self.shutdown.inner.idle.notify_all();
+        }
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests the shutdown on SIGINT and SIGTERM, instead of being
+    /// killed by them.
+    pub fn with_signals(self) -> Self {
+        signal::install();
+        self.inner.signals.store(true, Ordering::Relaxed);
+        self
+    }
+
+    pub fn request(&self) {
+        self.inner.requested.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.inner.requested.load(Ordering::Relaxed)
+            || (self.inner.signals.load(Ordering::Relaxed) && SIGNALLED.load(Ordering::Relaxed))
+    }
+
+    /// Returns the number of the in-flight queries.
+    pub fn in_flight(&self) -> usize {
+        *self.inner.in_flight.lock().unwrap()
+    }
+
+    /// Begins the query, or returns `None` once the shutdown is
+    /// requested.
+    pub fn begin(&self) -> Option<InFlight> {
+        let mut in_flight = self.inner.in_flight.lock().unwrap();
+        if self.is_requested() {
+            return None;
+        }
+        *in_flight += 1;
+        Some(InFlight {
+            shutdown: self.clone(),
+        })
+    }
+
+    /// Registers the hook to run on the shutdown.
+    pub fn on_shutdown<F>(&self, hook: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.inner.hooks.lock().unwrap().push(Box::new(hook));
+    }
+
+    /// Returns the token cancelled once the shutdown is requested, to
+    /// stop the long computation with the partial result.
+    pub fn cancel_token(&self) -> CancelToken {
+        CancelToken::with_shutdown(self.clone())
+    }
+
+    /// Blocks until the shutdown is requested.
+    pub fn wait(&self) {
+        while !self.is_requested() {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Shuts down, waiting up to the `timeout` for the in-flight queries
+    /// to finish, and runs the hooks.
+    ///
+    /// Returns false when the queries are still in flight after the
+    /// `timeout`, in which case the hooks run anyway.
+    pub fn finish(&self, timeout: Duration) -> bool {
+        self.request();
+        let deadline = Instant::now() + timeout;
+        let mut in_flight = self.inner.in_flight.lock().unwrap();
+        while *in_flight > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            in_flight = self
+                .inner
+                .idle
+                .wait_timeout(in_flight, deadline - now)
+                .unwrap()
+                .0;
+        }
+        let drained = *in_flight == 0;
+        drop(in_flight);
+        if !drained {
+            warn!(in_flight = self.in_flight(), "shutdown timed out");
+        }
+        let hooks: Vec<_> = self.inner.hooks.lock().unwrap().drain(..).collect();
+        debug!(hooks = hooks.len(), "shutdown");
+        for hook in hooks.into_iter().rev() {
+            hook();
+        }
+        drained
+    }
+}
+
+#[cfg(unix)]
+mod signal {
+    use std::os::raw::c_int;
+    use std::sync::atomic::Ordering;
+
+    const SIGINT: c_int = 2;
+    const SIGTERM: c_int = 15;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+    }
+
+    extern "C" fn handle(_: c_int) {
+        super::SIGNALLED.store(true, Ordering::Relaxed);
+    }
+
+    pub(super) fn install() {
+        // SAFETY: the handler only stores to the atomic.
+        unsafe {
+            signal(SIGINT, handle);
+            signal(SIGTERM, handle);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod signal {
+    pub(super) fn install() {}
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,41 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::Shutdown;
+
+#[test]
+fn test_finish() {
+    let shutdown = Shutdown::new();
+    let order = Arc::new(Mutex::new(Vec::new()));
+    for hook in ["close providers", "flush"] {
+        let order = order.clone();
+        shutdown.on_shutdown(move || order.lock().unwrap().push(hook));
+    }
+
+    let finished = Arc::new(AtomicUsize::new(0));
+    let query = shutdown.begin().unwrap();
+    let counter = finished.clone();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        counter.fetch_add(1, Ordering::Relaxed);
+        drop(query);
+    });
+    assert_eq!(shutdown.in_flight(), 1);
+
+    assert!(shutdown.finish(Duration::from_secs(10)));
+    assert_eq!(finished.load(Ordering::Relaxed), 1);
+    assert_eq!(*order.lock().unwrap(), ["flush", "close providers"]);
+    assert!(shutdown.is_requested());
+    assert!(shutdown.begin().is_none());
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_finish_timeout() {
+    let shutdown = Shutdown::new();
+    let _query = shutdown.begin().unwrap();
+    assert!(!shutdown.finish(Duration::from_millis(10)));
+    assert_eq!(shutdown.in_flight(), 1);
+}
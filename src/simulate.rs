@@ -0,0 +1,211 @@
+//! Execution simulator
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path, Vertex};
+
+/// The simulated conversion of a single hop.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Hop {
+    pub src: Vertex,
+    pub dst: Vertex,
+    pub amount_in: f32,
+    pub amount_out: f32,
+    /// The fees charged in the destination currency.
+    pub fee: f32,
+    /// The top of the book rate net of the percentage fee.
+    pub quoted_rate: f32,
+    /// The amount lost by the rounding to the destination currency
+    /// decimals, see [`Dex::set_rounding`].
+    pub dust: f32,
+}
+
+impl Hop {
+    pub fn realized_rate(&self) -> f32 {
+        self.amount_out / self.amount_in
+    }
+
+    /// Returns the slippage, the fraction of the quoted rate lost.
+    pub fn slippage(&self) -> f32 {
+        1.0 - self.realized_rate() / self.quoted_rate
+    }
+}
+
+/// The simulated execution of the path.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Execution {
+    hops: Vec<Hop>,
+    quoted_rate: f32,
+}
+
+impl fmt::Display for Execution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for hop in &self.hops {
+            writeln!(
+                f,
+                "{} -> {}: {} -> {} ({} quoted, {} realized, {:.4}% slippage)",
+                hop.src,
+                hop.dst,
+                hop.amount_in,
+                hop.amount_out,
+                hop.quoted_rate,
+                hop.realized_rate(),
+                hop.slippage() * 100.0,
+            )?;
+        }
+        write!(
+            f,
+            "{} quoted, {} realized, {:.4}% slippage",
+            self.quoted_rate,
+            self.realized_rate(),
+            self.slippage() * 100.0,
+        )
+    }
+}
+
+impl Execution {
+    pub fn hops(&self) -> &[Hop] {
+        &self.hops
+    }
+
+    pub fn amount_in(&self) -> f32 {
+        self.hops.first().map_or(0.0, |hop| hop.amount_in)
+    }
+
+    pub fn amount_out(&self) -> f32 {
+        self.hops.last().map_or(0.0, |hop| hop.amount_out)
+    }
+
+    /// Returns the rate of the path when it was routed.
+    pub fn quoted_rate(&self) -> f32 {
+        self.quoted_rate
+    }
+
+    pub fn realized_rate(&self) -> f32 {
+        self.amount_out() / self.amount_in()
+    }
+
+    /// Returns the slippage, the fraction of the quoted rate lost.
+    pub fn slippage(&self) -> f32 {
+        1.0 - self.realized_rate() / self.quoted_rate
+    }
+
+    /// Returns the dust accumulated by the rounding in each currency.
+    pub fn dust(&self) -> BTreeMap<Vertex, f32> {
+        let mut dust = BTreeMap::new();
+        for hop in self.hops.iter().filter(|hop| hop.dust != 0.0) {
+            *dust.entry(hop.dst).or_default() += hop.dust;
+        }
+        dust
+    }
+}
+
+/// The execution failure.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ExecutionError {
+    /// The edge of the hop is gone.
+    NoEdge { src: Vertex, dst: Vertex },
+    /// The edge of the hop is too shallow for the amount.
+    InsufficientLiquidity {
+        src: Vertex,
+        dst: Vertex,
+        amount: f32,
+        liquidity: f32,
+    },
+}
+
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoEdge { src, dst } => write!(f, "no {src} -> {dst} edge"),
+            Self::InsufficientLiquidity {
+                src,
+                dst,
+                amount,
+                liquidity,
+            } => write!(
+                f,
+                "insufficient {src} -> {dst} liquidity {liquidity} for {amount}"
+            ),
+        }
+    }
+}
+
+impl Error for ExecutionError {}
+
+impl Dex {
+    /// Simulates the execution of the `path` with the `amount` of the
+    /// source currency, without consuming the liquidity.
+    ///
+    /// The amount received at each hop is rounded to the currency
+    /// decimals in case the rounding is set, see [`Dex::set_rounding`].
+    #[instrument(level = "debug", skip(self), err)]
+    pub fn simulate(&self, path: &Path, amount: f32) -> Result<Execution, ExecutionError> {
+        assert!(amount > 0.0);
+        let mut hops = Vec::with_capacity(path.len());
+        let mut amount_in = amount;
+        for hop in path.path.windows(2) {
+            let (src, dst) = (hop[0], hop[1]);
+            let edge = self
+                .edges
+                .get(&src)
+                .and_then(|edges| edges.get(&dst))
+                .ok_or(ExecutionError::NoEdge { src, dst })?;
+            if !edge.is_convertible(amount_in) {
+                return Err(ExecutionError::InsufficientLiquidity {
+                    src,
+                    dst,
+                    amount: amount_in,
+                    liquidity: edge.liquidity.unwrap_or_default(),
+                });
+            }
+            let exact = edge.clone().consume(amount_in);
+            let amount_out = match self.rounding {
+                Some(rounding) => self.round_amount(&dst, exact, rounding),
+                None => exact,
+            };
+            let hop = Hop {
+                src,
+                dst,
+                amount_in,
+                amount_out,
+                fee: edge.fees(amount_in),
+                quoted_rate: edge.effective_rate(None),
+                dust: exact - amount_out,
+            };
+            debug!(?hop, "simulated");
+            hops.push(hop);
+            amount_in = amount_out;
+        }
+        Ok(Execution {
+            hops,
+            quoted_rate: path.rate,
+        })
+    }
+
+    /// Executes the `path` with the `amount` of the source currency,
+    /// consuming the liquidity of each hop.
+    ///
+    /// Nothing is consumed in case the execution fails.
+    pub fn execute(&mut self, path: &Path, amount: f32) -> Result<Execution, ExecutionError> {
+        let execution = self.simulate(path, amount)?;
+        for hop in &execution.hops {
+            if let Some(edge) = self
+                .edges
+                .get_mut(&hop.src)
+                .and_then(|edges| edges.get_mut(&hop.dst))
+            {
+                edge.consume(hop.amount_in);
+                self.changes.push(hop.src, hop.dst);
+            }
+        }
+        Ok(execution)
+    }
+}
+
+#[cfg(test)]
+mod test;
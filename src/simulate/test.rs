@@ -0,0 +1,49 @@
+use super::ExecutionError;
+use crate::{Dex, Edge};
+
+#[test]
+fn test_simulate() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_limit_order('B', 'C', 3.0, 100.0);
+    dex.add_limit_order('B', 'C', 2.0, 100.0);
+
+    let path = dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    assert_eq!(path.rate(), 6.0);
+
+    let execution = dex.simulate(&path, 100.0).unwrap();
+    assert_eq!(execution.hops().len(), 2);
+    assert_eq!(execution.hops()[0].slippage(), 0.0);
+    assert_eq!(execution.hops()[1].amount_in, 200.0);
+    assert_eq!(execution.hops()[1].realized_rate(), 2.5);
+    assert_eq!(execution.amount_out(), 500.0);
+    assert_eq!(execution.quoted_rate(), 6.0);
+    assert_eq!(execution.realized_rate(), 5.0);
+    assert!((execution.slippage() - 1.0 / 6.0).abs() < 1e-6);
+
+    // Simulation doesn't consume the liquidity.
+    assert_eq!(dex.simulate(&path, 100.0).unwrap(), execution);
+}
+
+#[test]
+fn test_execute() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_liquidity(150.0));
+    dex.add_rate('B', 'C', 3.0);
+
+    let path = dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    let execution = dex.execute(&path, 100.0).unwrap();
+    assert_eq!(execution.amount_out(), 600.0);
+
+    let err = dex.execute(&path, 100.0).unwrap_err();
+    assert_eq!(
+        err,
+        ExecutionError::InsufficientLiquidity {
+            src: 'A'.into(),
+            dst: 'B'.into(),
+            amount: 100.0,
+            liquidity: 50.0,
+        }
+    );
+    assert!(dex.execute(&path, 50.0).is_ok());
+}
@@ -0,0 +1,530 @@
+//! Versioned graph snapshots
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Edge, Vertex};
+use crate::concentrated::Pool;
+use crate::edge::{EdgeKind, Order};
+use crate::json::Json;
+use crate::provider::ProviderId;
+use crate::quote::Side;
+use crate::report::quote;
+
+/// The format name of the snapshot, to tell it from the other JSON.
+const FORMAT: &str = "best-rate-snapshot";
+
+/// The migration of the snapshot of the format version to the next
+/// one.
+type Migration = fn(Json) -> Result<Json, String>;
+
+/// The migrations of the older format versions, the first one of the
+/// version 1 to 2, and so on.
+///
+/// The format version is bumped by adding the migration of the current
+/// version here, so that the snapshots written by the older releases
+/// keep loading after the upgrade.
+const MIGRATIONS: &[Migration] = &[];
+
+/// The format version of the snapshots written.
+pub const SNAPSHOT_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+/// The edge kinds, in the order of the binary codes.
+const KINDS: [EdgeKind; 5] = [
+    EdgeKind::Exchange,
+    EdgeKind::Bridge,
+    EdgeKind::LimitOrder,
+    EdgeKind::Concentrated,
+    EdgeKind::Wrap,
+];
+
+impl Dex {
+    /// Writes the graph snapshot, the JSON document of the format
+    /// version [`SNAPSHOT_VERSION`].
+    ///
+    /// The snapshot has the vertices, the directed edges, the
+    /// providers with their quotes and the disabled edges, the
+    /// decimals, the labels, and the aliases.  The settings, e.g. the
+    /// outlier guard, the rounding, or the clock, are not kept.  The
+    /// timestamps are kept to the millisecond.
+    #[instrument(level = "debug", skip_all, err)]
+    pub fn save_snapshot<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let ids: HashMap<ProviderId, usize> = self
+            .providers()
+            .enumerate()
+            .map(|(i, (id, _))| (id, i))
+            .collect();
+        writeln!(writer, "{{")?;
+        writeln!(writer, "  \"format\": {},", quote(FORMAT))?;
+        writeln!(writer, "  \"version\": {SNAPSHOT_VERSION},")?;
+
+        let providers = self.providers().map(|(_, provider)| {
+            let mut members = vec![
+                format!("\"name\": {}", quote(provider.name())),
+                format!("\"priority\": {}", provider.priority()),
+                format!("\"down\": {}", provider.down),
+            ];
+            if let Some(updated_at) = provider.updated_at {
+                members.push(format!("\"updated_at_ms\": {}", millis(updated_at)));
+            }
+            object(&members)
+        });
+        list(&mut writer, "providers", providers)?;
+
+        let vertices = self.edges.keys().map(string);
+        list(&mut writer, "vertices", vertices)?;
+
+        let decimals: BTreeMap<_, _> = self.decimals.iter().collect();
+        let decimals = decimals.into_iter().map(|(v, decimals)| {
+            object(&[
+                format!("\"vertex\": {}", string(v)),
+                format!("\"decimals\": {decimals}"),
+            ])
+        });
+        list(&mut writer, "decimals", decimals)?;
+
+        let labels: BTreeMap<_, _> = self.labels.iter().collect();
+        let labels = labels.into_iter().map(|(v, label)| {
+            object(&[
+                format!("\"vertex\": {}", string(v)),
+                format!("\"label\": {}", quote(label)),
+            ])
+        });
+        list(&mut writer, "labels", labels)?;
+
+        let aliases: BTreeMap<_, _> = self.aliases.iter().collect();
+        let aliases = aliases.into_iter().map(|(alias, v)| {
+            object(&[
+                format!("\"alias\": {}", string(alias)),
+                format!("\"vertex\": {}", string(v)),
+            ])
+        });
+        list(&mut writer, "aliases", aliases)?;
+
+        let quotes: BTreeMap<_, _> = self.quotes.iter().collect();
+        let quotes = quotes.into_iter().flat_map(|((src, dst), rates)| {
+            rates.iter().filter_map(|(id, rate)| {
+                Some(object(&[
+                    format!("\"src\": {}", string(src)),
+                    format!("\"dst\": {}", string(dst)),
+                    format!("\"provider\": {}", ids.get(id)?),
+                    format!("\"rate\": {}", float(*rate)),
+                ]))
+            })
+        });
+        list(&mut writer, "quotes", quotes)?;
+
+        let disabled = self.disabled.iter().flat_map(|(id, edges)| {
+            let ids = &ids;
+            edges.iter().filter_map(move |(src, dst, edge)| {
+                let mut members = vec![format!("\"provider\": {}", ids.get(id)?)];
+                members.extend(edge_members(src, dst, edge, ids));
+                Some(object(&members))
+            })
+        });
+        list(&mut writer, "disabled", disabled)?;
+
+        let edges = self.edges.iter().flat_map(|(src, edges)| {
+            let ids = &ids;
+            edges
+                .iter()
+                .map(move |(dst, edge)| object(&edge_members(src, dst, edge, ids)))
+        });
+        writeln!(writer, "  \"edges\": [")?;
+        write_items(&mut writer, edges)?;
+        writeln!(writer, "  ]")?;
+        writeln!(writer, "}}")?;
+        debug!(vertices = self.edges.len(), "saved");
+        Ok(())
+    }
+
+    /// Loads the graph out of the snapshot written by
+    /// [`Dex::save_snapshot`], of the current or any older format
+    /// version.
+    ///
+    /// The older snapshot is migrated to the current format version on
+    /// the load.  The snapshot of the newer format version, written by
+    /// the newer release, is rejected.
+    #[instrument(level = "debug", skip_all, err)]
+    pub fn load_snapshot<R: Read>(mut reader: R) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut body = String::new();
+        reader.read_to_string(&mut body)?;
+        let json = Json::parse(&body).map_err(|e| invalid(format!("{e}")))?;
+        let json = upgrade(json).map_err(invalid)?;
+        let dex = decode(&json).map_err(invalid)?;
+        debug!(vertices = dex.edges.len(), "loaded");
+        Ok(dex)
+    }
+}
+
+// Checks the format and the version, and migrates the snapshot of the
+// older version to the current one.
+fn upgrade(json: Json) -> Result<Json, String> {
+    if json.get("format").and_then(Json::as_str) != Some(FORMAT) {
+        return Err("not a best-rate snapshot".to_string());
+    }
+    let version = match json.get("version") {
+        Some(Json::Number(version)) if *version >= 1.0 && version.fract() == 0.0 => *version as u32,
+        _ => return Err("snapshot without the valid format version".to_string()),
+    };
+    if version > SNAPSHOT_VERSION {
+        return Err(format!(
+            "snapshot format version {version} is newer than the supported \
+             {SNAPSHOT_VERSION}, upgrade best-rate to load it"
+        ));
+    }
+    migrate(json, version, MIGRATIONS)
+}
+
+// Migrates the snapshot of the `version` through the `migrations` from
+// the version on.
+fn migrate(mut json: Json, version: u32, migrations: &[Migration]) -> Result<Json, String> {
+    let from = version as usize - 1;
+    for (i, migration) in migrations.iter().enumerate().skip(from) {
+        json = migration(json).map_err(|e| {
+            format!(
+                "snapshot migration from version {} to {} failed: {e}",
+                i + 1,
+                i + 2
+            )
+        })?;
+        debug!(version = i + 2, "migrated");
+    }
+    Ok(json)
+}
+
+// Decodes the snapshot of the current format version.
+fn decode(json: &Json) -> Result<Dex, String> {
+    let mut dex = Dex::new();
+    let mut ids = Vec::new();
+    for (i, provider) in items(json, "providers")? {
+        let path = format!("providers[{i}]");
+        let name = text(provider, &path, "name")?;
+        let priority = integer(provider, &path, "priority")?;
+        let down = provider.get("down").and_then(Json::as_bool);
+        let updated_at = optional(provider, "updated_at_ms")
+            .map(|_| integer(provider, &path, "updated_at_ms"))
+            .transpose()?;
+        let id = dex.register_provider(name, priority);
+        if let Some(provider) = dex.provider_mut(id) {
+            provider.down = down.unwrap_or_default();
+            provider.updated_at = updated_at.map(|ms| UNIX_EPOCH + Duration::from_millis(ms));
+        }
+        ids.push(id);
+    }
+    let provider = |json: &Json, path: &str| -> Result<ProviderId, String> {
+        let i: usize = integer(json, path, "provider")?;
+        ids.get(i)
+            .copied()
+            .ok_or_else(|| format!("unknown {path}.provider {i}"))
+    };
+    for (i, v) in items(json, "vertices")? {
+        let v: Vertex = v
+            .as_str()
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| format!("invalid vertices[{i}]"))?;
+        dex.edges.entry(v).or_default();
+    }
+    for (i, item) in items(json, "decimals")? {
+        let path = format!("decimals[{i}]");
+        let decimals: u8 = integer(item, &path, "decimals")?;
+        dex.decimals
+            .insert(vertex(item, &path, "vertex")?, decimals);
+    }
+    for (i, item) in items(json, "labels")? {
+        let path = format!("labels[{i}]");
+        let label = text(item, &path, "label")?.to_string();
+        dex.labels.insert(vertex(item, &path, "vertex")?, label);
+    }
+    for (i, item) in items(json, "aliases")? {
+        let path = format!("aliases[{i}]");
+        let alias = vertex(item, &path, "alias")?;
+        dex.aliases.insert(alias, vertex(item, &path, "vertex")?);
+    }
+    for (i, item) in items(json, "quotes")? {
+        let path = format!("quotes[{i}]");
+        let pair = (vertex(item, &path, "src")?, vertex(item, &path, "dst")?);
+        let rate = number(item, &path, "rate")? as f32;
+        let id = provider(item, &path)?;
+        dex.quotes.entry(pair).or_default().insert(id, rate);
+    }
+    for (i, item) in items(json, "disabled")? {
+        let path = format!("disabled[{i}]");
+        let id = provider(item, &path)?;
+        let edge = decode_edge(item, &path, &ids)?;
+        dex.disabled.entry(id).or_default().push(edge);
+    }
+    for (i, item) in items(json, "edges")? {
+        let (src, dst, edge) = decode_edge(item, &format!("edges[{i}]"), &ids)?;
+        dex.edges.entry(dst).or_default();
+        dex.edges.entry(src).or_default().insert(dst, edge);
+    }
+    Ok(dex)
+}
+
+// Returns the members of the edge object, in the order written.
+fn edge_members(
+    src: &Vertex,
+    dst: &Vertex,
+    edge: &Edge,
+    ids: &HashMap<ProviderId, usize>,
+) -> Vec<String> {
+    let mut members = vec![
+        format!("\"src\": {}", string(src)),
+        format!("\"dst\": {}", string(dst)),
+        format!("\"rate\": {}", float(edge.rate)),
+    ];
+    if let Some(liquidity) = edge.liquidity {
+        members.push(format!("\"liquidity\": {}", float(liquidity)));
+    }
+    members.extend([
+        format!("\"fee\": {}", float(edge.fee)),
+        format!("\"fixed_fee\": {}", float(edge.fixed_fee)),
+        format!("\"delay_ns\": {}", edge.delay.as_nanos()),
+        format!("\"latency_ns\": {}", edge.latency.as_nanos()),
+        format!("\"risk\": {}", float(edge.risk)),
+        format!("\"side\": \"{}\"", edge.side),
+        format!("\"kind\": \"{}\"", kind_name(edge.kind)),
+        format!("\"zero_for_one\": {}", edge.zero_for_one),
+    ]);
+    if let Some(id) = edge.source.and_then(|id| ids.get(&id)) {
+        members.push(format!("\"source\": {id}"));
+    }
+    if let Some(timestamp) = edge.timestamp {
+        members.push(format!("\"timestamp_ms\": {}", millis(timestamp)));
+    }
+    if let Some(volatility) = edge.volatility {
+        members.push(format!("\"volatility\": {}", float(volatility)));
+    }
+    if !edge.orders.is_empty() {
+        let orders: Vec<_> = edge
+            .orders
+            .iter()
+            .map(|order| format!("[{}, {}]", float(order.price), float(order.size)))
+            .collect();
+        members.push(format!("\"orders\": [{}]", orders.join(", ")));
+    }
+    if !edge.pools.is_empty() {
+        let pools: Vec<_> = edge
+            .pools
+            .iter()
+            .map(|pool| {
+                let ticks: Vec<_> = pool
+                    .ticks
+                    .iter()
+                    .map(|(price, net)| format!("[{}, {}]", double(*price), double(*net)))
+                    .collect();
+                object(&[
+                    format!("\"fee\": {}", float(pool.fee)),
+                    format!("\"sqrt_price\": {}", double(pool.sqrt_price)),
+                    format!("\"ticks\": [{}]", ticks.join(", ")),
+                ])
+            })
+            .collect();
+        members.push(format!("\"pools\": [{}]", pools.join(", ")));
+    }
+    members
+}
+
+// Decodes the edge object at the `path`.
+fn decode_edge(
+    json: &Json,
+    path: &str,
+    ids: &[ProviderId],
+) -> Result<(Vertex, Vertex, Edge), String> {
+    let src = vertex(json, path, "src")?;
+    let dst = vertex(json, path, "dst")?;
+    let rate = number(json, path, "rate")? as f32;
+    if src == dst || !rate.is_finite() || rate <= 0.0 {
+        return Err(format!("invalid {path}"));
+    }
+    let mut edge = Edge::new(rate);
+    edge.liquidity = optional(json, "liquidity")
+        .map(|_| number(json, path, "liquidity"))
+        .transpose()?
+        .map(|liquidity| liquidity as f32);
+    edge.fee = number(json, path, "fee")? as f32;
+    edge.fixed_fee = number(json, path, "fixed_fee")? as f32;
+    edge.delay = Duration::from_nanos(integer(json, path, "delay_ns")?);
+    edge.latency = Duration::from_nanos(integer(json, path, "latency_ns")?);
+    edge.risk = number(json, path, "risk")? as f32;
+    edge.side = match text(json, path, "side")? {
+        "bid" => Side::Bid,
+        "ask" => Side::Ask,
+        side => return Err(format!("invalid {path}.side {side:?}")),
+    };
+    let kind = text(json, path, "kind")?;
+    edge.kind = kind_by_name(kind).ok_or_else(|| format!("invalid {path}.kind {kind:?}"))?;
+    edge.zero_for_one = member(json, path, "zero_for_one")?
+        .as_bool()
+        .ok_or_else(|| format!("invalid {path}.zero_for_one"))?;
+    if optional(json, "source").is_some() {
+        let i: usize = integer(json, path, "source")?;
+        let id = ids
+            .get(i)
+            .ok_or_else(|| format!("unknown {path}.source {i}"))?;
+        edge.source = Some(*id);
+    }
+    if optional(json, "timestamp_ms").is_some() {
+        let ms = integer(json, path, "timestamp_ms")?;
+        edge.timestamp = Some(UNIX_EPOCH + Duration::from_millis(ms));
+    }
+    if optional(json, "volatility").is_some() {
+        edge.volatility = Some(number(json, path, "volatility")? as f32);
+    }
+    let pairs = |json: &Json, path: &str| -> Result<Vec<(f64, f64)>, String> {
+        json.as_array()
+            .ok_or_else(|| format!("invalid {path}"))?
+            .iter()
+            .map(|pair| match pair.as_array() {
+                Some([a, b]) => Some((a.as_f64()?, b.as_f64()?)),
+                _ => None,
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(|| format!("invalid {path}"))
+    };
+    if let Some(orders) = optional(json, "orders") {
+        edge.orders = pairs(orders, &format!("{path}.orders"))?
+            .into_iter()
+            .map(|(price, size)| Order {
+                price: price as f32,
+                size: size as f32,
+            })
+            .collect();
+    }
+    if let Some(pools) = optional(json, "pools") {
+        let pools = pools
+            .as_array()
+            .ok_or_else(|| format!("invalid {path}.pools"))?;
+        for (i, pool) in pools.iter().enumerate() {
+            let path = format!("{path}.pools[{i}]");
+            edge.pools.push(Pool {
+                fee: number(pool, &path, "fee")? as f32,
+                sqrt_price: number(pool, &path, "sqrt_price")?,
+                ticks: pairs(member(pool, &path, "ticks")?, &format!("{path}.ticks"))?,
+            });
+        }
+    }
+    Ok((src, dst, edge))
+}
+
+fn kind_name(kind: EdgeKind) -> &'static str {
+    match kind {
+        EdgeKind::Exchange => "exchange",
+        EdgeKind::Bridge => "bridge",
+        EdgeKind::LimitOrder => "limit_order",
+        EdgeKind::Concentrated => "concentrated",
+        EdgeKind::Wrap => "wrap",
+    }
+}
+
+fn kind_by_name(name: &str) -> Option<EdgeKind> {
+    KINDS.into_iter().find(|kind| kind_name(*kind) == name)
+}
+
+// Writes the `name` member of the `items` array, one item per line.
+fn list<W, I>(writer: &mut W, name: &str, items: I) -> io::Result<()>
+where
+    W: Write,
+    I: Iterator<Item = String>,
+{
+    writeln!(writer, "  \"{name}\": [")?;
+    write_items(writer, items)?;
+    writeln!(writer, "  ],")
+}
+
+fn write_items<W, I>(writer: &mut W, items: I) -> io::Result<()>
+where
+    W: Write,
+    I: Iterator<Item = String>,
+{
+    let mut items = items.peekable();
+    while let Some(item) = items.next() {
+        let comma = if items.peek().is_some() { "," } else { "" };
+        writeln!(writer, "    {item}{comma}")?;
+    }
+    Ok(())
+}
+
+fn object(members: &[String]) -> String {
+    format!("{{{}}}", members.join(", "))
+}
+
+fn string(v: &Vertex) -> String {
+    quote(&v.to_string())
+}
+
+// The f32 written as the f64 of the same value, to load exactly.
+fn float(value: f32) -> String {
+    double(f64::from(value))
+}
+
+// JSON has no infinity nor NaN, which are taken as the strings.
+fn double(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value:?}")
+    } else {
+        quote(&value.to_string())
+    }
+}
+
+fn millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis())
+}
+
+// Returns the items of the `name` array member, with the index.
+fn items<'a>(
+    json: &'a Json,
+    name: &str,
+) -> Result<impl Iterator<Item = (usize, &'a Json)>, String> {
+    let items = member(json, "snapshot", name)?
+        .as_array()
+        .ok_or_else(|| format!("invalid {name}"))?;
+    Ok(items.iter().enumerate())
+}
+
+// Returns the `key` member of the object at the `path`.
+fn member<'a>(json: &'a Json, path: &str, key: &str) -> Result<&'a Json, String> {
+    json.get(key).ok_or_else(|| format!("missing {path}.{key}"))
+}
+
+fn optional<'a>(json: &'a Json, key: &str) -> Option<&'a Json> {
+    json.get(key).filter(|value| **value != Json::Null)
+}
+
+fn number(json: &Json, path: &str, key: &str) -> Result<f64, String> {
+    member(json, path, key)?
+        .as_f64()
+        .ok_or_else(|| format!("invalid {path}.{key}"))
+}
+
+fn integer<T: TryFrom<u64>>(json: &Json, path: &str, key: &str) -> Result<T, String> {
+    match number(json, path, key)? {
+        n if n >= 0.0 && n.fract() == 0.0 && n < u64::MAX as f64 => {
+            T::try_from(n as u64).map_err(|_| format!("invalid {path}.{key}"))
+        }
+        _ => Err(format!("invalid {path}.{key}")),
+    }
+}
+
+fn text<'a>(json: &'a Json, path: &str, key: &str) -> Result<&'a str, String> {
+    member(json, path, key)?
+        .as_str()
+        .ok_or_else(|| format!("invalid {path}.{key}"))
+}
+
+fn vertex(json: &Json, path: &str, key: &str) -> Result<Vertex, String> {
+    text(json, path, key)?
+        .parse()
+        .map_err(|e| format!("invalid {path}.{key}: {e}"))
+}
+
+mod binary;
+
+#[cfg(test)]
+mod test;
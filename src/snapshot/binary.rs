@@ -0,0 +1,455 @@
+//! Binary graph snapshots
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{self, Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tracing::{debug, instrument};
+
+use super::{KINDS, SNAPSHOT_VERSION};
+use crate::concentrated::Pool;
+use crate::edge::{Edge, Order};
+use crate::provider::ProviderId;
+use crate::quote::Side;
+use crate::{Dex, Vertex};
+
+/// The magic bytes of the binary snapshot.
+const MAGIC: &[u8; 4] = b"BRSB";
+
+// The edge flags of the optional fields.
+const LIQUIDITY: u8 = 1;
+const SOURCE: u8 = 1 << 1;
+const TIMESTAMP: u8 = 1 << 2;
+const VOLATILITY: u8 = 1 << 3;
+const ZERO_FOR_ONE: u8 = 1 << 4;
+
+impl Dex {
+    /// Writes the graph snapshot in the compact binary format, of the
+    /// same content as [`Dex::save_snapshot`], for the fast restart of
+    /// the large graph.
+    ///
+    /// The vertices are written once, and referred by the index.  The
+    /// numbers are in the little endian, and the timestamps are kept to
+    /// the nanosecond.
+    #[instrument(level = "debug", skip_all, err)]
+    pub fn save_binary_snapshot<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut encoder = Encoder { writer };
+        encoder.writer.write_all(MAGIC)?;
+        encoder.u32(SNAPSHOT_VERSION)?;
+
+        let ids: HashMap<ProviderId, u32> = self
+            .providers()
+            .enumerate()
+            .map(|(i, (id, _))| (id, i as u32))
+            .collect();
+        encoder.len(ids.len())?;
+        for (_, provider) in self.providers() {
+            encoder.bytes(provider.name().as_bytes())?;
+            encoder.u32(provider.priority())?;
+            encoder.u8(u8::from(provider.down))?;
+            encoder.time(provider.updated_at)?;
+        }
+
+        // The vertices of the graph first, and then the others only
+        // referred, e.g. by the aliases.
+        let mut others = BTreeSet::new();
+        others.extend(self.decimals.keys());
+        others.extend(self.labels.keys());
+        others.extend(self.aliases.iter().flat_map(|(alias, v)| [alias, v]));
+        others.extend(self.quotes.keys().flat_map(|(src, dst)| [src, dst]));
+        let disabled = self.disabled.values().flatten();
+        others.extend(disabled.flat_map(|(src, dst, _)| [src, dst]));
+        let vertices: Vec<&Vertex> = self
+            .edges
+            .keys()
+            .chain(others.iter().filter(|v| !self.edges.contains_key(v)))
+            .collect();
+        let index: HashMap<&Vertex, u32> = vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (*v, i as u32))
+            .collect();
+        encoder.len(self.edges.len())?;
+        encoder.len(vertices.len())?;
+        for v in &vertices {
+            encoder.bytes(v.to_string().as_bytes())?;
+        }
+
+        let decimals: BTreeMap<_, _> = self.decimals.iter().collect();
+        encoder.len(decimals.len())?;
+        for (v, decimals) in decimals {
+            encoder.u32(index[v])?;
+            encoder.u8(*decimals)?;
+        }
+        let labels: BTreeMap<_, _> = self.labels.iter().collect();
+        encoder.len(labels.len())?;
+        for (v, label) in labels {
+            encoder.u32(index[v])?;
+            encoder.bytes(label.as_bytes())?;
+        }
+        let aliases: BTreeMap<_, _> = self.aliases.iter().collect();
+        encoder.len(aliases.len())?;
+        for (alias, v) in aliases {
+            encoder.u32(index[alias])?;
+            encoder.u32(index[v])?;
+        }
+        let quotes: BTreeMap<_, _> = self.quotes.iter().collect();
+        encoder.len(quotes.values().map(|rates| rates.len()).sum())?;
+        for ((src, dst), rates) in quotes {
+            for (id, rate) in rates {
+                encoder.u32(index[src])?;
+                encoder.u32(index[dst])?;
+                encoder.u32(ids[id])?;
+                encoder.f32(*rate)?;
+            }
+        }
+        encoder.len(self.disabled.values().map(Vec::len).sum())?;
+        for (id, edges) in &self.disabled {
+            for (src, dst, edge) in edges {
+                encoder.u32(ids[id])?;
+                encoder.edge(index[src], index[dst], edge, &ids)?;
+            }
+        }
+        encoder.len(self.edges.values().map(BTreeMap::len).sum())?;
+        for (src, edges) in &self.edges {
+            for (dst, edge) in edges {
+                encoder.edge(index[src], index[dst], edge, &ids)?;
+            }
+        }
+        encoder.writer.flush()?;
+        debug!(vertices = self.edges.len(), "saved");
+        Ok(())
+    }
+
+    /// Loads the graph out of the binary snapshot written by
+    /// [`Dex::save_binary_snapshot`].
+    ///
+    /// The binary snapshot is the cache of the graph, and is only
+    /// loaded by the release of the same format version.  The older one
+    /// is to be loaded by the release which wrote it, and saved as the
+    /// JSON snapshot to migrate, see [`Dex::load_snapshot`].
+    #[instrument(level = "debug", skip_all, err)]
+    pub fn load_binary_snapshot<R: Read>(reader: R) -> io::Result<Self> {
+        let mut decoder = Decoder {
+            reader,
+            vertices: Vec::new(),
+            ids: Vec::new(),
+        };
+        let mut magic = [0; 4];
+        decoder.reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a best-rate binary snapshot".to_string()));
+        }
+        let version = decoder.u32()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(invalid(format!(
+                "binary snapshot format version {version} is not the supported \
+                 {SNAPSHOT_VERSION}, load it with the release which wrote it, and \
+                 migrate it through the JSON snapshot"
+            )));
+        }
+
+        let mut dex = Dex::new();
+        let mut ids = Vec::new();
+        for _ in 0..decoder.u32()? {
+            let name = decoder.string()?;
+            let id = dex.register_provider(&name, decoder.u32()?);
+            let down = decoder.u8()? != 0;
+            let updated_at = decoder.time()?;
+            if let Some(provider) = dex.provider_mut(id) {
+                provider.down = down;
+                provider.updated_at = updated_at;
+            }
+            ids.push(id);
+        }
+
+        let graph = decoder.u32()? as usize;
+        let count = decoder.u32()? as usize;
+        let mut vertices = Vec::with_capacity(count.min(1 << 20));
+        for i in 0..count {
+            let v: Vertex = decoder
+                .string()?
+                .parse()
+                .map_err(|e| invalid(format!("vertex {i}: {e}")))?;
+            if i < graph {
+                dex.edges.insert(v, BTreeMap::new());
+            }
+            vertices.push(v);
+        }
+        decoder.vertices = vertices;
+        decoder.ids = ids;
+
+        for _ in 0..decoder.u32()? {
+            let v = decoder.vertex()?;
+            dex.decimals.insert(v, decoder.u8()?);
+        }
+        for _ in 0..decoder.u32()? {
+            let v = decoder.vertex()?;
+            dex.labels.insert(v, decoder.string()?);
+        }
+        for _ in 0..decoder.u32()? {
+            let alias = decoder.vertex()?;
+            dex.aliases.insert(alias, decoder.vertex()?);
+        }
+        for _ in 0..decoder.u32()? {
+            let pair = (decoder.vertex()?, decoder.vertex()?);
+            let id = decoder.provider()?;
+            let rate = decoder.f32()?;
+            dex.quotes.entry(pair).or_default().insert(id, rate);
+        }
+        for _ in 0..decoder.u32()? {
+            let id = decoder.provider()?;
+            let edge = decoder.edge()?;
+            dex.disabled.entry(id).or_default().push(edge);
+        }
+        for _ in 0..decoder.u32()? {
+            let (src, dst, edge) = decoder.edge()?;
+            dex.edges.entry(dst).or_default();
+            dex.edges.entry(src).or_default().insert(dst, edge);
+        }
+        debug!(vertices = dex.edges.len(), "loaded");
+        Ok(dex)
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+struct Encoder<W> {
+    writer: W,
+}
+
+impl<W: Write> Encoder<W> {
+    fn u8(&mut self, n: u8) -> io::Result<()> {
+        self.writer.write_all(&[n])
+    }
+
+    fn u32(&mut self, n: u32) -> io::Result<()> {
+        self.writer.write_all(&n.to_le_bytes())
+    }
+
+    fn u64(&mut self, n: u64) -> io::Result<()> {
+        self.writer.write_all(&n.to_le_bytes())
+    }
+
+    fn f32(&mut self, n: f32) -> io::Result<()> {
+        self.writer.write_all(&n.to_le_bytes())
+    }
+
+    fn f64(&mut self, n: f64) -> io::Result<()> {
+        self.writer.write_all(&n.to_le_bytes())
+    }
+
+    fn len(&mut self, len: usize) -> io::Result<()> {
+        let len = u32::try_from(len).map_err(|_| invalid(format!("too many items {len}")))?;
+        self.u32(len)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.len(bytes.len())?;
+        self.writer.write_all(bytes)
+    }
+
+    fn nanos(&mut self, duration: Duration) -> io::Result<()> {
+        self.u64(u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX))
+    }
+
+    // The time since the epoch, or zero for none.
+    fn time(&mut self, time: Option<SystemTime>) -> io::Result<()> {
+        let since = time.and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+        self.nanos(since.unwrap_or_default())
+    }
+
+    fn edge(
+        &mut self,
+        src: u32,
+        dst: u32,
+        edge: &Edge,
+        ids: &HashMap<ProviderId, u32>,
+    ) -> io::Result<()> {
+        let source = edge.source.and_then(|id| ids.get(&id));
+        let mut flags = 0;
+        for (flag, set) in [
+            (LIQUIDITY, edge.liquidity.is_some()),
+            (SOURCE, source.is_some()),
+            (TIMESTAMP, edge.timestamp.is_some()),
+            (VOLATILITY, edge.volatility.is_some()),
+            (ZERO_FOR_ONE, edge.zero_for_one),
+        ] {
+            if set {
+                flags |= flag;
+            }
+        }
+        self.u32(src)?;
+        self.u32(dst)?;
+        self.u8(flags)?;
+        self.f32(edge.rate)?;
+        self.f32(edge.fee)?;
+        self.f32(edge.fixed_fee)?;
+        self.nanos(edge.delay)?;
+        self.nanos(edge.latency)?;
+        self.f32(edge.risk)?;
+        self.u8(match edge.side {
+            Side::Bid => 0,
+            Side::Ask => 1,
+        })?;
+        let kind = KINDS.iter().position(|kind| *kind == edge.kind);
+        self.u8(kind.unwrap_or_default() as u8)?;
+        if let Some(liquidity) = edge.liquidity {
+            self.f32(liquidity)?;
+        }
+        if let Some(source) = source {
+            self.u32(*source)?;
+        }
+        if edge.timestamp.is_some() {
+            self.time(edge.timestamp)?;
+        }
+        if let Some(volatility) = edge.volatility {
+            self.f32(volatility)?;
+        }
+        self.len(edge.orders.len())?;
+        for order in &edge.orders {
+            self.f32(order.price)?;
+            self.f32(order.size)?;
+        }
+        self.len(edge.pools.len())?;
+        for pool in &edge.pools {
+            self.f32(pool.fee)?;
+            self.f64(pool.sqrt_price)?;
+            self.len(pool.ticks.len())?;
+            for (price, net) in &pool.ticks {
+                self.f64(*price)?;
+                self.f64(*net)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Decoder<R> {
+    reader: R,
+    vertices: Vec<Vertex>,
+    ids: Vec<ProviderId>,
+}
+
+impl<R: Read> Decoder<R> {
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut buf = [0; N];
+        self.reader.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        self.array().map(f32::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> io::Result<f64> {
+        self.array().map(f64::from_le_bytes)
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.u32()? as usize;
+        let mut bytes = Vec::new();
+        // The length is taken as is only up to the bytes read.
+        (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        String::from_utf8(bytes).map_err(|e| invalid(format!("{e}")))
+    }
+
+    fn time(&mut self) -> io::Result<Option<SystemTime>> {
+        match self.u64()? {
+            0 => Ok(None),
+            nanos => Ok(Some(UNIX_EPOCH + Duration::from_nanos(nanos))),
+        }
+    }
+
+    fn vertex(&mut self) -> io::Result<Vertex> {
+        let i = self.u32()?;
+        self.vertices
+            .get(i as usize)
+            .copied()
+            .ok_or_else(|| invalid(format!("unknown vertex {i}")))
+    }
+
+    fn provider(&mut self) -> io::Result<ProviderId> {
+        let i = self.u32()?;
+        self.ids
+            .get(i as usize)
+            .copied()
+            .ok_or_else(|| invalid(format!("unknown provider {i}")))
+    }
+
+    fn edge(&mut self) -> io::Result<(Vertex, Vertex, Edge)> {
+        let src = self.vertex()?;
+        let dst = self.vertex()?;
+        let flags = self.u8()?;
+        let rate = self.f32()?;
+        if src == dst || !rate.is_finite() || rate <= 0.0 {
+            return Err(invalid(format!("invalid edge {src} -> {dst}")));
+        }
+        let mut edge = Edge::new(rate);
+        edge.fee = self.f32()?;
+        edge.fixed_fee = self.f32()?;
+        edge.delay = Duration::from_nanos(self.u64()?);
+        edge.latency = Duration::from_nanos(self.u64()?);
+        edge.risk = self.f32()?;
+        edge.side = match self.u8()? {
+            0 => Side::Bid,
+            1 => Side::Ask,
+            side => return Err(invalid(format!("invalid side {side}"))),
+        };
+        let kind = self.u8()?;
+        edge.kind = *KINDS
+            .get(usize::from(kind))
+            .ok_or_else(|| invalid(format!("invalid edge kind {kind}")))?;
+        edge.zero_for_one = flags & ZERO_FOR_ONE != 0;
+        if flags & LIQUIDITY != 0 {
+            edge.liquidity = Some(self.f32()?);
+        }
+        if flags & SOURCE != 0 {
+            edge.source = Some(self.provider()?);
+        }
+        if flags & TIMESTAMP != 0 {
+            edge.timestamp = Some(UNIX_EPOCH + Duration::from_nanos(self.u64()?));
+        }
+        if flags & VOLATILITY != 0 {
+            edge.volatility = Some(self.f32()?);
+        }
+        for _ in 0..self.u32()? {
+            let price = self.f32()?;
+            let size = self.f32()?;
+            edge.orders.push(Order { price, size });
+        }
+        for _ in 0..self.u32()? {
+            let fee = self.f32()?;
+            let sqrt_price = self.f64()?;
+            let mut ticks = Vec::new();
+            for _ in 0..self.u32()? {
+                ticks.push((self.f64()?, self.f64()?));
+            }
+            edge.pools.push(Pool {
+                fee,
+                sqrt_price,
+                ticks,
+            });
+        }
+        Ok((src, dst, edge))
+    }
+}
@@ -0,0 +1,234 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::{migrate, Migration, SNAPSHOT_VERSION};
+use crate::concentrated::Pool;
+use crate::edge::Edge;
+use crate::json::Json;
+use crate::test::vertex;
+use crate::Dex;
+
+fn dex() -> Dex {
+    let mut dex = Dex::new();
+    let venue = dex.register_provider("venue \"one\"", 10);
+    let feed = dex.register_provider("feed", 1);
+    let timestamp = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+    dex.add_provider_rate(venue, vertex("USD"), vertex("EUR"), 0.9);
+    dex.add_provider_rate(feed, vertex("USD"), vertex("EUR"), 0.91);
+    dex.add_provider_rate(feed, vertex("EUR"), vertex("GBP"), 0.85);
+    dex.add_edge(
+        vertex("USD"),
+        vertex("JPY"),
+        Edge::new(150.1)
+            .with_liquidity(1e6)
+            .with_fee(0.001)
+            .with_latency(Duration::from_millis(250))
+            .with_timestamp(timestamp),
+    );
+    dex.add_edge(
+        vertex("USDC"),
+        vertex("ETH"),
+        Edge::bridge(0.0005, 1.5, 0.002, Duration::from_secs(600)),
+    );
+    dex.add_limit_order(vertex("BTC"), vertex("USD"), 65_000.0, 0.5);
+    dex.add_limit_order(vertex("BTC"), vertex("USD"), 64_000.0, 1.0);
+    dex.add_pool(
+        vertex("WETH"),
+        vertex("USDC"),
+        Pool::new(3000.0, 0.003).with_position(-1000, 1000, 1e6),
+    );
+    dex.set_decimals(vertex("USDC"), 6);
+    dex.set_label(vertex("USD"), "US dollar");
+    dex.add_alias(vertex("XBT"), vertex("BTC"));
+    dex.mark_down(feed);
+    dex
+}
+
+fn round_trip(dex: &Dex) -> Dex {
+    let mut snapshot = Vec::new();
+    dex.save_snapshot(&mut snapshot).unwrap();
+    Dex::load_snapshot(snapshot.as_slice()).unwrap()
+}
+
+#[test]
+fn test_round_trip() {
+    let dex = dex();
+    let loaded = round_trip(&dex);
+    assert_eq!(loaded.edges, dex.edges);
+    assert_eq!(loaded.quotes, dex.quotes);
+    assert_eq!(loaded.disabled, dex.disabled);
+    assert_eq!(loaded.decimals, dex.decimals);
+    assert_eq!(loaded.labels, dex.labels);
+    assert_eq!(loaded.aliases, dex.aliases);
+    let providers: Vec<_> = loaded
+        .providers()
+        .map(|(_, p)| (p.name().to_string(), p.priority(), p.down))
+        .collect();
+    assert_eq!(
+        providers,
+        [
+            ("venue \"one\"".to_string(), 10, false),
+            ("feed".to_string(), 1, true)
+        ]
+    );
+
+    // The failover works on the loaded graph.
+    let mut loaded = loaded;
+    let feed = loaded.providers().nth(1).unwrap().0;
+    loaded.mark_up(feed);
+    let path = loaded
+        .get_best_rate(&vertex("EUR"), &vertex("GBP"))
+        .unwrap();
+    assert_eq!(path.rate(), 0.85);
+
+    // The same snapshot again.
+    let (mut first, mut second) = (Vec::new(), Vec::new());
+    dex.save_snapshot(&mut first).unwrap();
+    round_trip(&dex).save_snapshot(&mut second).unwrap();
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_round_trip_empty() {
+    let dex = Dex::new();
+    let mut snapshot = Vec::new();
+    dex.save_snapshot(&mut snapshot).unwrap();
+    let snapshot = String::from_utf8(snapshot).unwrap();
+    assert!(snapshot.starts_with(&format!(
+        "{{\n  \"format\": \"best-rate-snapshot\",\n  \"version\": {SNAPSHOT_VERSION},\n"
+    )));
+    assert_eq!(round_trip(&dex), dex);
+}
+
+#[test]
+fn test_load_error() {
+    let load = |snapshot: &str| {
+        Dex::load_snapshot(snapshot.as_bytes())
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(load("{\"data\": {}}"), "not a best-rate snapshot");
+    assert_eq!(
+        load("{\"format\": \"best-rate-snapshot\"}"),
+        "snapshot without the valid format version"
+    );
+    assert_eq!(
+        load("{\"format\": \"best-rate-snapshot\", \"version\": 99}"),
+        format!(
+            "snapshot format version 99 is newer than the supported {SNAPSHOT_VERSION}, \
+             upgrade best-rate to load it"
+        )
+    );
+
+    let mut snapshot = Vec::new();
+    dex().save_snapshot(&mut snapshot).unwrap();
+    let snapshot = String::from_utf8(snapshot).unwrap();
+    assert_eq!(
+        load(&snapshot.replacen("\"kind\": \"exchange\"", "\"kind\": \"swap\"", 1)),
+        "invalid disabled[0].kind \"swap\""
+    );
+    assert_eq!(
+        load(&snapshot.replacen("\"quotes\"", "\"rates\"", 1)),
+        "missing snapshot.quotes"
+    );
+}
+
+#[test]
+fn test_migrate() {
+    // The version 1 of the `rates`, renamed to the `edges` in 2, and
+    // the version 2 without the `vertices`, added in 3.
+    fn rename(json: Json) -> Result<Json, String> {
+        match json {
+            Json::Object(members) => Ok(Json::Object(
+                members
+                    .into_iter()
+                    .map(|(key, value)| match key.as_str() {
+                        "rates" => ("edges".to_string(), value),
+                        _ => (key, value),
+                    })
+                    .collect(),
+            )),
+            _ => Err("not an object".to_string()),
+        }
+    }
+    fn add_vertices(json: Json) -> Result<Json, String> {
+        match json {
+            Json::Object(mut members) => {
+                members.push(("vertices".to_string(), Json::Array(Vec::new())));
+                Ok(Json::Object(members))
+            }
+            _ => Err("not an object".to_string()),
+        }
+    }
+    let migrations: [Migration; 2] = [rename, add_vertices];
+
+    let v1 = Json::parse("{\"rates\": []}").unwrap();
+    let migrated = migrate(v1, 1, &migrations).unwrap();
+    assert_eq!(
+        migrated,
+        Json::parse("{\"edges\": [], \"vertices\": []}").unwrap()
+    );
+    let v2 = Json::parse("{\"edges\": []}").unwrap();
+    assert_eq!(migrate(v2, 2, &migrations).unwrap(), migrated);
+    assert_eq!(migrate(migrated.clone(), 3, &migrations).unwrap(), migrated);
+    assert_eq!(
+        migrate(Json::Null, 1, &migrations).unwrap_err(),
+        "snapshot migration from version 1 to 2 failed: not an object"
+    );
+}
+
+#[test]
+fn test_binary_round_trip() {
+    let dex = dex();
+    let mut snapshot = Vec::new();
+    dex.save_binary_snapshot(&mut snapshot).unwrap();
+    let loaded = Dex::load_binary_snapshot(snapshot.as_slice()).unwrap();
+    assert_eq!(loaded.edges, dex.edges);
+    assert_eq!(loaded.quotes, dex.quotes);
+    assert_eq!(loaded.disabled, dex.disabled);
+    assert_eq!(loaded.decimals, dex.decimals);
+    assert_eq!(loaded.labels, dex.labels);
+    assert_eq!(loaded.aliases, dex.aliases);
+    let providers: Vec<_> = loaded
+        .providers()
+        .map(|(_, p)| (p.name().to_string(), p.priority(), p.down))
+        .collect();
+    assert_eq!(
+        providers,
+        [
+            ("venue \"one\"".to_string(), 10, false),
+            ("feed".to_string(), 1, true)
+        ]
+    );
+
+    // The same snapshot again, and the same graph as the JSON one.
+    let mut again = Vec::new();
+    loaded.save_binary_snapshot(&mut again).unwrap();
+    assert_eq!(again, snapshot);
+    assert_eq!(loaded, round_trip(&dex));
+
+    let empty = Dex::new();
+    let mut snapshot = Vec::new();
+    empty.save_binary_snapshot(&mut snapshot).unwrap();
+    assert_eq!(
+        Dex::load_binary_snapshot(snapshot.as_slice()).unwrap(),
+        empty
+    );
+}
+
+#[test]
+fn test_binary_load_error() {
+    let load = |snapshot: &[u8]| Dex::load_binary_snapshot(snapshot).unwrap_err().to_string();
+    assert_eq!(load(b"{\"format\": 1}"), "not a best-rate binary snapshot");
+    assert_eq!(
+        load(b"BRSB\x63\0\0\0"),
+        format!(
+            "binary snapshot format version 99 is not the supported {SNAPSHOT_VERSION}, \
+             load it with the release which wrote it, and migrate it through the JSON snapshot"
+        )
+    );
+
+    let mut snapshot = Vec::new();
+    dex().save_binary_snapshot(&mut snapshot).unwrap();
+    let truncated = load(&snapshot[..snapshot.len() - 1]);
+    assert_eq!(truncated, "failed to fill whole buffer");
+}
@@ -0,0 +1,116 @@
+//! Queue-optimized Bellman-Ford search
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::time::Instant;
+
+use tracing::{trace, warn};
+
+use super::{Dex, Path, Paths, Vertex};
+use crate::cancel::Timeout;
+use crate::query::{Bounded, QueryOptions};
+
+impl Dex {
+    // The shortest path faster algorithm over the negative log rates.
+    //
+    // Only the vertices whose best path improved are queued again, and
+    // the paths are kept simple so that the arbitrage cycles are not
+    // routed through.  The vertex relaxed more than the number of the
+    // vertices is on such a cycle, and is not relaxed any further.
+    pub(crate) fn spfa(
+        &self,
+        src: &Vertex,
+        dst: Option<&Vertex>,
+        options: &QueryOptions,
+    ) -> Result<Bounded<Paths>, Timeout<Paths>> {
+        let mut best_paths = BTreeMap::new();
+        best_paths.insert(*src, Path::new(*src));
+        let remove = |mut paths: Paths| {
+            if dst.is_some() {
+                paths.remove(src);
+            }
+            paths
+        };
+        self.relax(src, dst, options, best_paths, [*src])
+            .map(|paths| paths.map(remove))
+            .map_err(|timeout| timeout.map(remove))
+    }
+
+    // Relaxes the `best_paths` from `src` starting with the `queue`d
+    // vertices, e.g. the warm start from the previous result.
+    pub(crate) fn relax<I>(
+        &self,
+        src: &Vertex,
+        dst: Option<&Vertex>,
+        options: &QueryOptions,
+        mut best_paths: Paths,
+        queue: I,
+    ) -> Result<Bounded<Paths>, Timeout<Paths>>
+    where
+        I: IntoIterator<Item = Vertex>,
+    {
+        const CHECK_INTERVAL: usize = 64;
+        let start = Instant::now();
+        let max_relax = self.edges.len();
+        let mut relaxed = HashMap::new();
+        let mut queue: VecDeque<_> = queue
+            .into_iter()
+            .filter(|v| best_paths.contains_key(v))
+            .collect();
+        let mut queued: HashSet<_> = queue.iter().copied().collect();
+        let mut truncated = false;
+        let mut overflow = None;
+
+        let mut count = 0;
+        while let Some(v) = queue.pop_front() {
+            queued.remove(&v);
+            count += 1;
+            if count % CHECK_INTERVAL == 0 && options.is_expired(start) {
+                warn!(%src, %count, "search timed out");
+                return Err(Timeout::new(best_paths));
+            }
+            if dst == Some(&v) {
+                continue;
+            }
+            let path = best_paths[&v].clone();
+            let edges = match self.edges.get(&v) {
+                Some(edges) => edges,
+                None => continue,
+            };
+            for (w, edge) in edges {
+                if path.contains(w) || !options.is_routable(&path, edge) {
+                    continue;
+                }
+                let mut next = path.clone();
+                next.push(*w, edge, options);
+                if let Some(e) = next.overflow() {
+                    warn!(%e, "path dropped");
+                    overflow.get_or_insert(e);
+                    continue;
+                }
+                match best_paths.get(w) {
+                    Some(current) if options.score(&next) <= options.score(current) => continue,
+                    _ => {}
+                }
+                let n = relaxed.entry(*w).or_insert(0);
+                if *n >= max_relax {
+                    truncated = true;
+                    continue;
+                }
+                *n += 1;
+                trace!(path = %next, "relaxed");
+                best_paths.insert(*w, next);
+                if queued.insert(*w) {
+                    queue.push_back(*w);
+                }
+            }
+        }
+
+        if truncated {
+            warn!(%src, "search stopped on the arbitrage cycle");
+        }
+        Ok(Bounded::new(best_paths, truncated).with_overflow(overflow))
+    }
+}
+
+#[cfg(test)]
+mod test;
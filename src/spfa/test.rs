@@ -0,0 +1,56 @@
+use crate::query::{Algorithm, QueryOptions};
+use crate::rng::Rng;
+use crate::{Dex, Edge};
+
+#[test]
+fn test_spfa() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('A', 'C', 5.0);
+    dex.add_rate('C', 'D', 1.5);
+
+    let options = QueryOptions::new().with_algorithm(Algorithm::Spfa);
+    let path = dex
+        .get_best_rate_with(&'A'.into(), &'D'.into(), &options)
+        .unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D: 9");
+
+    let paths = dex.get_best_rates_from(&'D'.into(), &options);
+    assert_eq!(paths.len(), 3);
+    assert!(!dex
+        .get_best_rate_bounded(&'A'.into(), &'D'.into(), &options)
+        .is_truncated());
+}
+
+#[test]
+fn test_spfa_matches_bfs() {
+    // The consistent prices with the spread, without the arbitrage.
+    let mut rng = Rng::new(7);
+    let vertices = ['A', 'B', 'C', 'D', 'E', 'F'];
+    let prices: Vec<_> = vertices.iter().map(|_| 1.0 + rng.next_f64()).collect();
+    let mut dex = Dex::new();
+    for i in 0..vertices.len() {
+        for j in i + 1..vertices.len() {
+            if rng.next_f64() < 0.6 {
+                let rate = (prices[i] / prices[j]) as f32;
+                let fee = 0.01 * rng.next_f64() as f32;
+                dex.add_edge(vertices[i], vertices[j], Edge::new(rate).with_fee(fee));
+            }
+        }
+    }
+
+    let bfs = QueryOptions::new();
+    let spfa = QueryOptions::new().with_algorithm(Algorithm::Spfa);
+    for src in &vertices {
+        let expected = dex.get_best_rates_from(&(*src).into(), &bfs);
+        let paths = dex.get_best_rates_from(&(*src).into(), &spfa);
+        assert_eq!(
+            paths.keys().collect::<Vec<_>>(),
+            expected.keys().collect::<Vec<_>>()
+        );
+        for (dst, path) in paths {
+            assert_eq!(path.rate(), expected[&dst].rate());
+        }
+    }
+}
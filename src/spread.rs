@@ -0,0 +1,203 @@
+//! Bid-ask spread statistics
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+use tracing::{debug, instrument, warn};
+
+use super::{Dex, Vertex};
+
+/// The default number of the recent spreads kept per pair.
+const WINDOW: usize = 100;
+
+/// The distribution of the recent spreads of the pair, in bps.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpreadStats {
+    pub samples: usize,
+    pub min: f32,
+    pub mean: f32,
+    pub median: f32,
+    pub p95: f32,
+    pub max: f32,
+}
+
+impl SpreadStats {
+    // Returns the stats of the non-empty `samples`.
+    fn new(samples: &VecDeque<f32>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<_> = samples.iter().copied().collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        // The nearest rank percentile.
+        let percentile = |p: f32| {
+            let rank = (p * sorted.len() as f32).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        Some(Self {
+            samples: sorted.len(),
+            min: sorted[0],
+            mean: sorted.iter().sum::<f32>() / sorted.len() as f32,
+            median: percentile(0.5),
+            p95: percentile(0.95),
+            max: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+/// The spread of the pair, implied by its edges in both directions.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PairSpread {
+    pub src: Vertex,
+    pub dst: Vertex,
+    /// The latest spread in bps, the round trip loss before the fees.
+    pub bps: f32,
+    pub stats: SpreadStats,
+    /// The latest spread exceeds the threshold, see
+    /// [`SpreadMonitor::new`].
+    pub wide: bool,
+}
+
+impl PairSpread {
+    /// Checks if the round trip gains, i.e. the negative spread of the
+    /// crossed quotes.
+    pub fn is_crossed(&self) -> bool {
+        self.bps < 0.0
+    }
+}
+
+impl fmt::Display for PairSpread {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stats = &self.stats;
+        write!(
+            f,
+            "{}/{}: {:.1} bps (min {:.1}, median {:.1}, p95 {:.1}, max {:.1} of {})",
+            self.src,
+            self.dst,
+            self.bps,
+            stats.min,
+            stats.median,
+            stats.p95,
+            stats.max,
+            stats.samples,
+        )?;
+        if self.wide {
+            write!(f, " wide")?;
+        }
+        if self.is_crossed() {
+            write!(f, " crossed")?;
+        }
+        Ok(())
+    }
+}
+
+/// The monitor of the spreads of all the pairs over time.
+///
+/// The spreads are sampled on each [`SpreadMonitor::record`], e.g.
+/// after every batch of the rate updates, and the recent ones are kept
+/// per pair for the distribution.  The wide spread is the usual sign of
+/// the bad or the stale rate on either side of the pair.
+#[derive(Clone, Debug)]
+pub struct SpreadMonitor {
+    threshold_bps: f32,
+    window: Option<usize>,
+    history: BTreeMap<(Vertex, Vertex), VecDeque<f32>>,
+}
+
+impl SpreadMonitor {
+    /// Creates the monitor flagging the spreads above `threshold_bps`.
+    pub fn new(threshold_bps: f32) -> Self {
+        assert!(threshold_bps >= 0.0);
+        Self {
+            threshold_bps,
+            window: None,
+            history: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the number of the recent spreads kept per pair.
+    pub fn with_window(mut self, window: usize) -> Self {
+        assert!(window > 0);
+        self.window = Some(window);
+        self
+    }
+
+    pub fn threshold_bps(&self) -> f32 {
+        self.threshold_bps
+    }
+
+    pub fn window(&self) -> usize {
+        self.window.unwrap_or(WINDOW)
+    }
+
+    /// Samples the current spreads of the `dex`, and forgets the pairs
+    /// no longer quoted in both directions.
+    #[instrument(level = "debug", skip_all)]
+    pub fn record(&mut self, dex: &Dex) {
+        let spreads = dex.spreads();
+        self.history.retain(|pair, _| spreads.contains_key(pair));
+        let window = self.window();
+        for (pair, bps) in spreads {
+            let samples = self.history.entry(pair).or_default();
+            if samples.len() == window {
+                samples.pop_front();
+            }
+            samples.push_back(bps);
+            if bps > self.threshold_bps {
+                warn!(src = %pair.0, dst = %pair.1, %bps, "wide spread");
+            }
+        }
+        debug!(pairs = self.history.len(), "recorded");
+    }
+
+    /// Returns the spread of each pair recorded, by the pair.
+    pub fn report(&self) -> Vec<PairSpread> {
+        self.history
+            .iter()
+            .filter_map(|((src, dst), samples)| {
+                Some(PairSpread {
+                    src: *src,
+                    dst: *dst,
+                    bps: *samples.back()?,
+                    stats: SpreadStats::new(samples)?,
+                    wide: *samples.back()? > self.threshold_bps,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the pairs with the latest spread above the threshold,
+    /// the widest first.
+    pub fn wide(&self) -> Vec<PairSpread> {
+        let mut wide: Vec<_> = self.report().into_iter().filter(|s| s.wide).collect();
+        wide.sort_by(|a, b| b.bps.partial_cmp(&a.bps).unwrap_or(Ordering::Equal));
+        wide
+    }
+}
+
+impl Dex {
+    /// Returns the current spread in bps of each pair quoted in both
+    /// directions, by the pair in the vertex order.
+    ///
+    /// The spread is the round trip loss at the quoted rates before the
+    /// fees, `1 - rate(src -> dst) * rate(dst -> src)`, and negative
+    /// in case the quotes are crossed.
+    pub fn spreads(&self) -> BTreeMap<(Vertex, Vertex), f32> {
+        let mut spreads = BTreeMap::new();
+        for (src, edges) in &self.edges {
+            for (dst, edge) in edges.range(src..).filter(|(dst, _)| *dst != src) {
+                let reverse = match self.edges.get(dst).and_then(|edges| edges.get(src)) {
+                    Some(reverse) => reverse,
+                    None => continue,
+                };
+                let round_trip = f64::from(edge.rate()) * f64::from(reverse.rate());
+                spreads.insert((*src, *dst), ((1.0 - round_trip) * 10_000.0) as f32);
+            }
+        }
+        spreads
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,64 @@
+use super::SpreadMonitor;
+use crate::{Dex, Edge};
+
+// Sets the directed `src -> dst` rate only.
+fn quote(dex: &mut Dex, src: char, dst: char, rate: f32) {
+    dex.edges.entry(dst.into()).or_default();
+    let edges = dex.edges.entry(src.into()).or_default();
+    edges.insert(dst.into(), Edge::new(rate));
+}
+
+#[test]
+fn test_spreads() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_limit_order('B', 'C', 4.0, 10.0);
+    dex.add_limit_order('C', 'B', 0.24, 10.0);
+    dex.add_limit_order('C', 'D', 1.0, 10.0);
+
+    let spreads = dex.spreads();
+    assert_eq!(spreads.len(), 2);
+    assert_eq!(spreads[&('A'.into(), 'B'.into())], 0.0);
+    assert!((spreads[&('B'.into(), 'C'.into())] - 400.0).abs() < 1e-2);
+}
+
+#[test]
+fn test_spread_monitor() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    quote(&mut dex, 'B', 'C', 1.0);
+    let mut monitor = SpreadMonitor::new(50.0).with_window(3);
+    assert_eq!(monitor.window(), 3);
+    for bid in [1.0, 0.999, 0.998, 0.99] {
+        quote(&mut dex, 'C', 'B', bid);
+        monitor.record(&dex);
+    }
+
+    let report = monitor.report();
+    assert_eq!(report.len(), 2);
+    assert_eq!(report[0].bps, 0.0);
+    assert!(!report[0].wide);
+    let spread = &report[1];
+    assert!((spread.bps - 100.0).abs() < 1e-2, "{spread}");
+    assert!(spread.wide);
+    assert!(!spread.is_crossed());
+    assert_eq!(spread.stats.samples, 3);
+    assert!((spread.stats.min - 10.0).abs() < 1e-2);
+    assert!((spread.stats.median - 20.0).abs() < 1e-2);
+    assert_eq!(spread.stats.p95, spread.stats.max);
+    assert!((spread.stats.mean - 130.0 / 3.0).abs() < 1e-2);
+    assert_eq!(
+        spread.to_string(),
+        "B/C: 100.0 bps (min 10.0, median 20.0, p95 100.0, max 100.0 of 3) wide"
+    );
+    assert_eq!(monitor.wide(), vec![*spread]);
+
+    // The crossed quotes, and the pair gone.
+    quote(&mut dex, 'C', 'B', 1.01);
+    dex.retain_edges(|src, _, _| *src != 'A'.into());
+    monitor.record(&dex);
+    let report = monitor.report();
+    assert_eq!(report.len(), 1);
+    assert!(report[0].is_crossed());
+    assert!(monitor.wide().is_empty());
+}
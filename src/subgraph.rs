@@ -0,0 +1,90 @@
+//! Uniswap subgraph importer
+
+use std::io::{self, Read};
+
+use tracing::{debug, instrument, warn};
+
+use super::{Dex, Edge, Vertex};
+use crate::json::Json;
+
+/// Returns the GraphQL request body of the top `first` pools by the
+/// total value locked, for the Uniswap V3 compatible subgraph.
+///
+/// The response is loaded by [`Dex::load_subgraph`], e.g.
+///
+/// ```text
+/// curl -d "$(best-rate subgraph-query)" <SUBGRAPH_URL> > pools.json
+/// best-rate --input pools.json
+/// ```
+pub fn query(first: usize) -> String {
+    format!(
+        "{{\"query\": \"{{ pools(first: {first}, orderBy: totalValueLockedUSD, \
+         orderDirection: desc) {{ id feeTier token0 {{ symbol }} token1 {{ symbol }} \
+         token1Price totalValueLockedToken0 }} }}\"}}"
+    )
+}
+
+impl Dex {
+    /// Loads the pools out of the subgraph response to the [`query`].
+    ///
+    /// Each pool is the pair of the edges with the fee tier, e.g. 3000
+    /// for 30 bps, and the total value locked as the liquidity.  Only
+    /// the first, the deepest, pool of the pair is loaded, and the pools
+    /// with the unsupported symbols or without the price are skipped.
+    /// It returns the number of the pools loaded.
+    #[instrument(level = "debug", skip_all, err)]
+    pub fn load_subgraph<R: Read>(&mut self, mut reader: R) -> io::Result<usize> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut body = String::new();
+        reader.read_to_string(&mut body)?;
+        let json = Json::parse(&body).map_err(|e| invalid(format!("{e}")))?;
+        if let Some(errors) = json.get("errors") {
+            return Err(invalid(format!("subgraph errors: {errors:?}")));
+        }
+        let pools = json
+            .get("data")
+            .and_then(|data| data.get("pools"))
+            .and_then(Json::as_array)
+            .ok_or_else(|| invalid("no data.pools".to_string()))?;
+        let mut count = 0;
+        for pool in pools {
+            let token = |key: &str| -> Option<Vertex> {
+                pool.get(key)?.get("symbol")?.as_str()?.parse().ok()
+            };
+            let number = |key: &str| pool.get(key).and_then(Json::as_f64);
+            let (src, dst, price, fee) = match (
+                token("token0"),
+                token("token1"),
+                number("token1Price"),
+                number("feeTier"),
+            ) {
+                (Some(src), Some(dst), Some(price), Some(fee))
+                    if src != dst
+                        && price > 0.0
+                        && price.is_finite()
+                        && (0.0..1e6).contains(&fee) =>
+                {
+                    (src, dst, price, fee)
+                }
+                _ => {
+                    warn!(id = ?pool.get("id").and_then(Json::as_str), "pool skipped");
+                    continue;
+                }
+            };
+            if matches!(self.edges.get(&src), Some(edges) if edges.contains_key(&dst)) {
+                continue;
+            }
+            let mut edge = Edge::new(price as f32).with_fee((fee / 1e6) as f32);
+            if let Some(tvl) = number("totalValueLockedToken0") {
+                edge = edge.with_liquidity(tvl.max(0.0) as f32);
+            }
+            self.insert_edge(src, dst, edge);
+            count += 1;
+        }
+        debug!(%count, "loaded");
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,41 @@
+use super::query;
+use crate::json::Json;
+use crate::test::vertex;
+use crate::Dex;
+
+const POOLS: &str = r#"{"data": {"pools": [
+    {"id": "0x88e6", "feeTier": "500", "token0": {"symbol": "USDC"}, "token1": {"symbol": "WETH"},
+     "token1Price": "0.0004", "totalValueLockedToken0": "100000000"},
+    {"id": "0xcbcd", "feeTier": "3000", "token0": {"symbol": "WBTC"}, "token1": {"symbol": "WETH"},
+     "token1Price": "16.5", "totalValueLockedToken0": "5000"},
+    {"id": "0x8ad5", "feeTier": "3000", "token0": {"symbol": "USDC"}, "token1": {"symbol": "WETH"},
+     "token1Price": "0.0005", "totalValueLockedToken0": "1000"},
+    {"id": "0xdead", "feeTier": "10000", "token0": {"symbol": "AVERYLONGTOKENSYMBOL"},
+     "token1": {"symbol": "WETH"}, "token1Price": "1", "totalValueLockedToken0": "1"}
+]}}"#;
+
+#[test]
+fn test_load_subgraph() {
+    let mut dex = Dex::new();
+    assert_eq!(dex.load_subgraph(POOLS.as_bytes()).unwrap(), 2);
+
+    let path = dex.get_best_rate(&vertex("USDC"), &vertex("WETH")).unwrap();
+    assert!((path.rate() - 0.0004 * 0.9995).abs() < 1e-9);
+    let edge = &dex.edges[&vertex("USDC")][&vertex("WETH")];
+    assert_eq!(edge.fee(), 0.0005);
+    assert_eq!(edge.liquidity(), Some(1e8));
+
+    let mut dex = Dex::new();
+    let err = dex
+        .load_subgraph(r#"{"errors": [{"message": "bad"}]}"#.as_bytes())
+        .unwrap_err();
+    assert!(err.to_string().starts_with("subgraph errors"));
+    assert!(dex.load_subgraph("{}".as_bytes()).is_err());
+}
+
+#[test]
+fn test_query() {
+    let query = Json::parse(&query(100)).unwrap();
+    let query = query.get("query").unwrap().as_str().unwrap();
+    assert!(query.starts_with("{ pools(first: 100, orderBy: totalValueLockedUSD"));
+}
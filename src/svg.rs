@@ -0,0 +1,140 @@
+//! SVG rendering of the routes
+
+use std::fmt::Write;
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path};
+use crate::simulate::ExecutionError;
+
+/// The width of the currency box.
+const NODE_WIDTH: usize = 80;
+
+/// The width of the hop arrow between the currency boxes.
+const HOP_WIDTH: usize = 220;
+
+/// The margin around the drawing.
+const MARGIN: usize = 20;
+
+/// The height of the drawing.
+const HEIGHT: usize = 160;
+
+// The vertical center of the currency boxes and the hop arrows.
+const MIDDLE: usize = 90;
+
+impl Dex {
+    /// Returns the standalone SVG drawing of the `path` converting the
+    /// `amount` of the source currency, e.g. for the trade tickets.
+    ///
+    /// Each hop is labeled with the pair and the realized rate above
+    /// the arrow, and the venue, the provider of the edge if any, and
+    /// the amounts in and out below it.
+    #[instrument(level = "debug", skip(self), err)]
+    pub fn route_svg(&self, path: &Path, amount: f32) -> Result<String, ExecutionError> {
+        let execution = self.simulate(path, amount)?;
+        let count = path.path.len();
+        let width = 2 * MARGIN + count * NODE_WIDTH + (count - 1) * HOP_WIDTH;
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{HEIGHT}\" \
+             viewBox=\"0 0 {width} {HEIGHT}\" font-family=\"sans-serif\" font-size=\"12\">"
+        );
+        svg.push_str(
+            "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" \
+             markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\">\
+             <path d=\"M0,0 L10,5 L0,10 z\" fill=\"#555\"/></marker></defs>\n",
+        );
+        let _ = writeln!(
+            svg,
+            "<rect width=\"{width}\" height=\"{HEIGHT}\" fill=\"white\"/>"
+        );
+        let _ = writeln!(
+            svg,
+            "<text x=\"{MARGIN}\" y=\"{}\" font-size=\"14\" font-weight=\"bold\">{}</text>",
+            MARGIN + 8,
+            escape(&format!(
+                "{} -> {} ({:.6} rate)",
+                self.format_amount(&path.path[0], execution.amount_in()),
+                self.format_amount(path.last(), execution.amount_out()),
+                execution.realized_rate(),
+            )),
+        );
+        for (i, v) in path.path.iter().enumerate() {
+            let x = MARGIN + i * (NODE_WIDTH + HOP_WIDTH);
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{x}\" y=\"{}\" width=\"{NODE_WIDTH}\" height=\"40\" rx=\"6\" \
+                 fill=\"#e8f0fe\" stroke=\"#1a73e8\"/>",
+                MIDDLE - 20,
+            );
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-weight=\"bold\">{}</text>",
+                x + NODE_WIDTH / 2,
+                MIDDLE + 5,
+                escape(&v.to_string()),
+            );
+        }
+        for (i, hop) in execution.hops().iter().enumerate() {
+            let x1 = MARGIN + i * (NODE_WIDTH + HOP_WIDTH) + NODE_WIDTH;
+            let x2 = x1 + HOP_WIDTH;
+            let center = x1 + HOP_WIDTH / 2;
+            let venue = self
+                .edges
+                .get(&hop.src)
+                .and_then(|edges| edges.get(&hop.dst))
+                .and_then(|edge| edge.source())
+                .and_then(|id| self.provider(id))
+                .map(|provider| provider.name().to_string());
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{}\" y1=\"{MIDDLE}\" x2=\"{}\" y2=\"{MIDDLE}\" stroke=\"#555\" \
+                 stroke-width=\"2\" marker-end=\"url(#arrow)\"/>",
+                x1 + 4,
+                x2 - 4,
+            );
+            let labels = [
+                (MIDDLE - 22, format!("{}/{}", hop.src, hop.dst)),
+                (MIDDLE - 8, format!("rate {}", hop.realized_rate())),
+                (MIDDLE + 20, venue.unwrap_or_default()),
+                (
+                    MIDDLE + 34,
+                    format!(
+                        "{} -> {}",
+                        self.format_amount(&hop.src, hop.amount_in),
+                        self.format_amount(&hop.dst, hop.amount_out),
+                    ),
+                ),
+            ];
+            for (y, label) in labels.iter().filter(|(_, label)| !label.is_empty()) {
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{center}\" y=\"{y}\" text-anchor=\"middle\">{}</text>",
+                    escape(label),
+                );
+            }
+        }
+        svg.push_str("</svg>\n");
+        debug!(hops = execution.hops().len(), bytes = svg.len(), "svg");
+        Ok(svg)
+    }
+}
+
+// Escapes the text for the XML content and the attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test;
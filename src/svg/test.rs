@@ -0,0 +1,33 @@
+use super::escape;
+use crate::simulate::ExecutionError;
+use crate::Dex;
+
+#[test]
+fn test_route_svg() {
+    let mut dex = Dex::new();
+    let venue = dex.register_provider("R&D FX", 10);
+    dex.add_provider_rate(venue, 'A', 'B', 2.0);
+    dex.add_limit_order('B', 'C', 3.0, 1000.0);
+
+    let path = dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap();
+    let svg = dex.route_svg(&path, 100.0).unwrap();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"720\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert!(svg.contains("100.00 A -&gt; 600.00 C (6.000000 rate)"));
+    assert!(svg.contains(">A/B</text>"));
+    assert!(svg.contains(">rate 2</text>"));
+    assert!(svg.contains(">R&amp;D FX</text>"));
+    assert!(svg.contains(">200.00 B -&gt; 600.00 C</text>"));
+    assert_eq!(svg.matches("<line ").count(), 2);
+
+    // The amount beyond the liquidity isn't drawn.
+    assert!(matches!(
+        dex.route_svg(&path, 1000.0),
+        Err(ExecutionError::InsufficientLiquidity { .. })
+    ));
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(escape("a<b>&\"c\""), "a&lt;b&gt;&amp;&quot;c&quot;");
+}
@@ -0,0 +1,71 @@
+//! Table output helpers
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use super::Vertex;
+use crate::csv::parse_line;
+use crate::term::Color;
+
+/// The best rates of the pairs, e.g. of the last run.
+pub type Rates = BTreeMap<(Vertex, Vertex), f32>;
+
+/// The order of the table rows.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SortBy {
+    /// By the source and then the destination.
+    Pair,
+    /// By the rate, the best first.
+    Rate,
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pair" => Ok(Self::Pair),
+            "rate" => Ok(Self::Rate),
+            _ => Err(format!("invalid sort {s:?}, expected pair or rate")),
+        }
+    }
+}
+
+/// Returns the color of the `rate` improved, in green, or worsened, in
+/// red, since the `previous` one.
+pub fn change_color(rate: f32, previous: Option<f32>) -> Option<Color> {
+    match previous {
+        Some(previous) if rate > previous * (1.0 + f32::EPSILON) => Some(Color::Green),
+        Some(previous) if rate < previous * (1.0 - f32::EPSILON) => Some(Color::Red),
+        _ => None,
+    }
+}
+
+/// Reads the rates of the `src,dst,rate` lines.
+pub fn read_rates<R: BufRead>(reader: R) -> io::Result<Rates> {
+    let mut rates = Rates::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (src, dst, rate) = parse_line(line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {e}", i + 1))
+        })?;
+        rates.insert((src, dst), rate);
+    }
+    Ok(rates)
+}
+
+/// Writes the rates as the `src,dst,rate` lines.
+pub fn write_rates<W: Write>(rates: &Rates, out: &mut W) -> io::Result<()> {
+    for ((src, dst), rate) in rates {
+        writeln!(out, "{src},{dst},{rate}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,48 @@
+use super::{change_color, read_rates, write_rates, Rates, SortBy};
+use crate::term::Color;
+use crate::Dex;
+
+#[test]
+fn test_sort_by() {
+    assert_eq!("pair".parse(), Ok(SortBy::Pair));
+    assert_eq!("rate".parse(), Ok(SortBy::Rate));
+    assert!("hops".parse::<SortBy>().is_err());
+}
+
+#[test]
+fn test_change_color() {
+    assert_eq!(change_color(1.5, None), None);
+    assert_eq!(change_color(1.5, Some(1.5)), None);
+    assert_eq!(change_color(1.6, Some(1.5)), Some(Color::Green));
+    assert_eq!(change_color(1.4, Some(1.5)), Some(Color::Red));
+}
+
+#[test]
+fn test_rates() {
+    let mut rates = Rates::new();
+    rates.insert(('A'.into(), 'B'.into()), 1.5);
+    rates.insert(('B'.into(), 'A'.into()), 0.5);
+    let mut out = Vec::new();
+    write_rates(&rates, &mut out).unwrap();
+    assert_eq!(out, b"A,B,1.5\nB,A,0.5\n");
+    assert_eq!(read_rates(&out[..]).unwrap(), rates);
+    assert!(read_rates("# comment\n\nA,B\n".as_bytes()).is_err());
+}
+
+#[test]
+fn test_matrix_render() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    let matrix = dex.matrix(None);
+    let rates = matrix.rates();
+    assert_eq!(rates.len(), 2);
+    assert_eq!(rates[&('A'.into(), 'B'.into())], 2.0);
+
+    let mut previous = rates.clone();
+    previous.insert(('A'.into(), 'B'.into()), 1.5);
+    previous.insert(('B'.into(), 'A'.into()), 0.6);
+    let out = matrix.render(Some(&previous), true);
+    assert!(out.contains("\x1b[32m    2.0000\x1b[0m"), "{out}");
+    assert!(out.contains("\x1b[31m    0.5000\x1b[0m"), "{out}");
+    assert_eq!(matrix.render(Some(&previous), false), matrix.to_string());
+}
@@ -0,0 +1,44 @@
+//! ANSI terminal styling
+
+use std::fmt::Display;
+
+/// The foreground color.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Dim,
+}
+
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Self::Red => "31",
+            Self::Green => "32",
+            Self::Yellow => "33",
+            Self::Dim => "2",
+        }
+    }
+}
+
+/// The escape sequence to clear the screen, and to move the cursor to
+/// the top left.
+pub const CLEAR: &str = "\x1b[2J\x1b[H";
+
+/// Returns the `text` in the `color`, or as is unless `enabled`.
+pub fn paint<T: Display>(text: T, color: Option<Color>, enabled: bool) -> String {
+    match color {
+        Some(color) if enabled => format!("\x1b[{}m{text}\x1b[0m", color.code()),
+        _ => text.to_string(),
+    }
+}
+
+/// Returns the `text` in the reverse video, to mark the selection.
+pub fn reverse<T: Display>(text: T, enabled: bool) -> String {
+    if enabled {
+        format!("\x1b[7m{text}\x1b[0m")
+    } else {
+        text.to_string()
+    }
+}
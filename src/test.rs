@@ -1,4 +1,11 @@
-use super::Dex;
+use std::time::{Duration, SystemTime};
+
+use super::{Dex, Edge, QueryOptions, Vertex};
+
+// Returns the vertex of the valid `symbol`, for the tests.
+pub(crate) fn vertex(symbol: &str) -> Vertex {
+    symbol.parse().unwrap()
+}
 
 #[test]
 fn test_direct() {
@@ -56,3 +63,110 @@ fn test_loop() {
     let path = dex.get_best_rate(&src, &dst).unwrap();
     assert_eq!(path.rate(), 2.5);
 }
+
+#[test]
+fn test_vertex_symbol() {
+    let usd: Vertex = "USD".parse().unwrap();
+    assert_eq!(usd.to_string(), "USD");
+    assert_eq!(format!("{usd:?}"), r#"Vertex("USD")"#);
+    assert!(Vertex::from('A') < vertex("AB"));
+    assert!(vertex("AB") < Vertex::from('B'));
+    assert!("".parse::<Vertex>().is_err());
+    assert!("TOO_LONG_VERTEX_SYMBOL".parse::<Vertex>().is_err());
+    assert_eq!(Vertex::try_from("USD"), Ok(usd));
+    assert!(Vertex::try_from("").is_err());
+    assert_eq!(Vertex::from('€').to_string(), "€");
+}
+
+#[test]
+fn test_clone_eq() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1.4);
+    dex.add_rate('A', 'C', 0.1);
+    dex.add_rate('B', 'C', 0.2);
+
+    let mut other = dex.clone();
+    assert_eq!(dex, other);
+
+    // What-if analysis on the copy.
+    other.add_rate('A', 'C', 0.3);
+    assert_ne!(dex, other);
+    assert_eq!(
+        dex.get_best_rate(&'A'.into(), &'C'.into()).unwrap().rate(),
+        0.28
+    );
+    assert_eq!(
+        other
+            .get_best_rate(&'A'.into(), &'C'.into())
+            .unwrap()
+            .rate(),
+        0.3
+    );
+
+    // Rates within the epsilon.
+    other.add_rate('A', 'C', 0.1 * (1.0 + 1e-7));
+    assert_eq!(dex, other);
+    other.add_rate('A', 'C', 0.1001);
+    assert_ne!(dex, other);
+    assert!(dex.approx_eq(&other, 1e-2));
+
+    // Directed edges.
+    let mut other = dex.clone();
+    other.retain_edges(|src, _, _| src != &'C'.into());
+    assert_ne!(dex, other);
+    assert_eq!(dex, dex.clone());
+}
+
+#[test]
+fn test_deterministic_tie_breaking() {
+    for _ in 0..10 {
+        let mut dex = Dex::new();
+        dex.add_rate('A', 'C', 2.0);
+        dex.add_rate('C', 'D', 1.0);
+        dex.add_rate('A', 'B', 1.0);
+        dex.add_rate('B', 'D', 2.0);
+
+        let path = dex.get_best_rate(&'A'.into(), &'D'.into()).unwrap();
+        assert_eq!(path.to_string(), "A -> B -> D: 2");
+    }
+}
+
+#[test]
+fn test_min_amounts_out() {
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_fee(0.5));
+    dex.add_edge('B', 'C', Edge::bridge(4.0, 1.0, 0.0, Duration::ZERO));
+
+    let options = QueryOptions::new().with_amount(100.0);
+    let path = dex
+        .get_best_rate_with(&'A'.into(), &'C'.into(), &options)
+        .unwrap();
+    assert_eq!(path.rates(), [1.0, 3.99]);
+    assert_eq!(path.amounts_out(100.0), [100.0, 399.0]);
+    assert_eq!(path.min_amounts_out(100.0, 0.5), [50.0, 199.5]);
+    assert_eq!(path.min_amount_out(100.0, 0.5), Some(199.5));
+}
+
+#[test]
+fn test_quote_expiry() {
+    let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_timestamp(t0));
+    dex.add_edge(
+        'B',
+        'C',
+        Edge::new(2.0).with_timestamp(t0 + Duration::from_secs(5)),
+    );
+    dex.add_rate('C', 'D', 2.0);
+
+    let (src, dst) = ('A'.into(), 'D'.into());
+    let path = dex.get_best_rate(&src, &dst).unwrap();
+    assert_eq!(path.quoted_at(), Some(t0));
+    assert_eq!(path.expires_at(), None);
+
+    let options = QueryOptions::new().with_quote_ttl(Duration::from_secs(30));
+    let path = dex.get_best_rate_with(&src, &dst, &options).unwrap();
+    assert_eq!(path.expires_at(), Some(t0 + Duration::from_secs(30)));
+    assert!(!path.is_expired(t0 + Duration::from_secs(29)));
+    assert!(path.is_expired(t0 + Duration::from_secs(30)));
+}
@@ -0,0 +1,315 @@
+//! TLS certificates with the hot reload
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+#[cfg(feature = "tls")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "tls")]
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+#[cfg(feature = "tls")]
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use tracing::{debug, warn};
+
+/// The interval of checking the certificate files for the renewal.
+#[cfg(feature = "tls")]
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The accepted connection stream, e.g. the TLS session over the
+/// [`TcpStream`].
+pub trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+/// The TLS termination of the accepted connections.
+///
+/// The [`RustlsAcceptor`] is built in with the `tls` feature, and the
+/// embedding application may wrap any other implementation configured
+/// from the [`CertFiles::certs`].
+pub trait Acceptor: fmt::Debug + Send + Sync {
+    fn accept(&self, stream: TcpStream) -> io::Result<Box<dyn Stream>>;
+}
+
+/// The DER encoded certificate chain and the private key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Certs {
+    chain: Vec<Vec<u8>>,
+    key: Vec<u8>,
+}
+
+/// Never prints the key itself.
+impl fmt::Debug for Certs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Certs")
+            .field("chain", &self.chain.len())
+            .finish()
+    }
+}
+
+impl Certs {
+    /// Parses the PEM encoded certificate chain and the private key.
+    pub fn from_pem(chain: &str, key: &str) -> io::Result<Self> {
+        let chain: Vec<_> = pem(chain)?
+            .into_iter()
+            .filter(|(label, _)| label == "CERTIFICATE")
+            .map(|(_, der)| der)
+            .collect();
+        if chain.is_empty() {
+            return Err(invalid("no certificate"));
+        }
+        let key = pem(key)?
+            .into_iter()
+            .find(|(label, _)| label.ends_with("PRIVATE KEY"))
+            .map(|(_, der)| der)
+            .ok_or_else(|| invalid("no private key"))?;
+        Ok(Self { chain, key })
+    }
+
+    pub fn chain(&self) -> &[Vec<u8>] {
+        &self.chain
+    }
+
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+}
+
+/// The certificate and the key files, reloaded once modified.
+#[derive(Debug)]
+pub struct CertFiles {
+    cert: PathBuf,
+    key: PathBuf,
+    loaded: Mutex<Loaded>,
+}
+
+#[derive(Debug)]
+struct Loaded {
+    modified: (SystemTime, SystemTime),
+    certs: Arc<Certs>,
+}
+
+impl CertFiles {
+    pub fn load(cert: &Path, key: &Path) -> io::Result<Self> {
+        let loaded = Self::read(cert, key)?;
+        Ok(Self {
+            cert: cert.to_path_buf(),
+            key: key.to_path_buf(),
+            loaded: Mutex::new(loaded),
+        })
+    }
+
+    pub fn certs(&self) -> Arc<Certs> {
+        self.loaded.lock().unwrap().certs.clone()
+    }
+
+    /// Reloads the files in case either is modified, and returns true
+    /// when reloaded.
+    ///
+    /// The current certificates are kept in case of the error, e.g. the
+    /// files caught in the middle of the renewal.
+    pub fn reload(&self) -> io::Result<bool> {
+        let modified = (modified(&self.cert)?, modified(&self.key)?);
+        let mut loaded = self.loaded.lock().unwrap();
+        if modified == loaded.modified {
+            return Ok(false);
+        }
+        match Self::read(&self.cert, &self.key) {
+            Ok(reloaded) => {
+                debug!(cert = %self.cert.display(), "reloaded");
+                *loaded = reloaded;
+                Ok(true)
+            }
+            Err(e) => {
+                warn!(cert = %self.cert.display(), %e, "reload failed");
+                Err(e)
+            }
+        }
+    }
+
+    fn read(cert: &Path, key: &Path) -> io::Result<Loaded> {
+        let with_path = |path: &Path, e: io::Error| {
+            io::Error::new(e.kind(), format!("{}: {e}", path.display()))
+        };
+        let modified = (
+            modified(cert).map_err(|e| with_path(cert, e))?,
+            modified(key).map_err(|e| with_path(key, e))?,
+        );
+        let chain = fs::read_to_string(cert).map_err(|e| with_path(cert, e))?;
+        let key_pem = fs::read_to_string(key).map_err(|e| with_path(key, e))?;
+        let certs = Certs::from_pem(&chain, &key_pem).map_err(|e| with_path(cert, e))?;
+        Ok(Loaded {
+            modified,
+            certs: Arc::new(certs),
+        })
+    }
+}
+
+/// The rustls termination with the certificate files, reloaded once
+/// renewed without the restart.
+#[cfg(feature = "tls")]
+#[derive(Debug)]
+pub struct RustlsAcceptor {
+    files: CertFiles,
+    // The config of the current certificates, and when the files were
+    // last checked.
+    config: Mutex<(Arc<ServerConfig>, Instant)>,
+}
+
+#[cfg(feature = "tls")]
+impl RustlsAcceptor {
+    pub fn new(files: CertFiles) -> io::Result<Self> {
+        let config = server_config(&files.certs())?;
+        Ok(Self {
+            files,
+            config: Mutex::new((config, Instant::now())),
+        })
+    }
+
+    // Returns the config, reloaded in case the files are renewed.  The
+    // current one is kept in case of the error.
+    fn config(&self) -> Arc<ServerConfig> {
+        let mut config = self.config.lock().unwrap();
+        if config.1.elapsed() >= RELOAD_INTERVAL {
+            config.1 = Instant::now();
+            if let Ok(true) = self.files.reload() {
+                match server_config(&self.files.certs()) {
+                    Ok(reloaded) => config.0 = reloaded,
+                    Err(e) => warn!(%e, "invalid certificates"),
+                }
+            }
+        }
+        config.0.clone()
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Acceptor for RustlsAcceptor {
+    fn accept(&self, stream: TcpStream) -> io::Result<Box<dyn Stream>> {
+        let conn = ServerConnection::new(self.config()).map_err(invalid_tls)?;
+        Ok(Box::new(TlsStream(StreamOwned::new(conn, stream))))
+    }
+}
+
+// The TLS session closed with the close_notify alert, for the client
+// to tell the complete response from the truncated one.
+#[cfg(feature = "tls")]
+struct TlsStream(StreamOwned<ServerConnection, TcpStream>);
+
+#[cfg(feature = "tls")]
+impl Read for TlsStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Write for TlsStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Drop for TlsStream {
+    fn drop(&mut self) {
+        let StreamOwned { conn, sock } = &mut self.0;
+        conn.send_close_notify();
+        while conn.wants_write() {
+            if conn.write_tls(sock).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+fn server_config(certs: &Certs) -> io::Result<Arc<ServerConfig>> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let chain = certs
+        .chain()
+        .iter()
+        .map(|der| CertificateDer::from(der.clone()))
+        .collect();
+    let key = PrivateKeyDer::try_from(certs.key().to_vec()).map_err(invalid)?;
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(invalid_tls)?
+        .with_no_client_auth()
+        .with_single_cert(chain, key)
+        .map_err(invalid_tls)?;
+    Ok(Arc::new(config))
+}
+
+#[cfg(feature = "tls")]
+fn invalid_tls(e: rustls::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn modified(path: &Path) -> io::Result<SystemTime> {
+    fs::metadata(path)?.modified()
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+// Decodes the PEM blocks into the labels and the DER bytes.
+fn pem(s: &str) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut blocks = Vec::new();
+    let mut lines = s.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        let label = match line
+            .strip_prefix("-----BEGIN ")
+            .and_then(|rest| rest.strip_suffix("-----"))
+        {
+            Some(label) => label,
+            None => continue,
+        };
+        let end = format!("-----END {label}-----");
+        let mut base64 = String::new();
+        loop {
+            match lines.next() {
+                Some(line) if line == end => break,
+                Some(line) => base64.push_str(line),
+                None => return Err(invalid("unterminated PEM block")),
+            }
+        }
+        blocks.push((label.to_string(), decode_base64(&base64)?));
+    }
+    Ok(blocks)
+}
+
+fn decode_base64(s: &str) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len() / 4 * 3);
+    let mut buf = 0u32;
+    let mut bits = 0;
+    for c in s.bytes().take_while(|c| *c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(invalid("invalid base64")),
+        };
+        buf = buf << 6 | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buf >> bits) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test;
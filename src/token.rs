@@ -0,0 +1,224 @@
+//! EVM token address vertices
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{ParseVertexError, Vertex};
+
+/// The length of the EVM address in bytes.
+pub const ADDRESS_LEN: usize = 20;
+
+/// The EVM token address tagged with the chain ID, e.g.
+/// `0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48@1` for USDC on the
+/// Ethereum mainnet.
+///
+/// It converts into the [`Vertex`] distinct from the same address on
+/// the other chains, and is displayed with the EIP-55 checksum.
+#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
+pub struct Token {
+    chain_id: u64,
+    address: [u8; ADDRESS_LEN],
+}
+
+impl Token {
+    pub fn new(chain_id: u64, address: [u8; ADDRESS_LEN]) -> Self {
+        Self { chain_id, address }
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn address(&self) -> &[u8; ADDRESS_LEN] {
+        &self.address
+    }
+
+    /// Returns the EIP-55 checksummed address, e.g.
+    /// `0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48`.
+    pub fn checksummed(&self) -> String {
+        checksum(&hex(&self.address))
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.checksummed(), self.chain_id)
+    }
+}
+
+/// Parses `<address>@<chain ID>`.  The mixed-case address is validated
+/// against the EIP-55 checksum.
+impl FromStr for Token {
+    type Err = ParseVertexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseVertexError(s.to_string());
+        let (address, chain_id) = s.split_once('@').ok_or_else(error)?;
+        let chain_id = chain_id.parse().map_err(|_| error())?;
+        let digits = address.strip_prefix("0x").ok_or_else(error)?;
+        if digits.len() != ADDRESS_LEN * 2 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+        let lower = digits.to_ascii_lowercase();
+        let is_mixed = digits != lower && digits != digits.to_ascii_uppercase();
+        if is_mixed && checksum(&lower)[2..] != *digits {
+            return Err(error());
+        }
+        let mut bytes = [0; ADDRESS_LEN];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&lower[i * 2..i * 2 + 2], 16).map_err(|_| error())?;
+        }
+        Ok(Self::new(chain_id, bytes))
+    }
+}
+
+// The token vertex layout, tagged with the leading zero byte which is
+// never in the symbol.
+const CHAIN_ID: std::ops::Range<usize> = 1..9;
+const ADDRESS: std::ops::Range<usize> = 9..9 + ADDRESS_LEN;
+
+impl From<Token> for Vertex {
+    fn from(token: Token) -> Self {
+        let mut bytes = [0; super::VERTEX_LEN];
+        bytes[CHAIN_ID].copy_from_slice(&token.chain_id.to_be_bytes());
+        bytes[ADDRESS].copy_from_slice(&token.address);
+        Self(bytes)
+    }
+}
+
+impl Vertex {
+    /// Returns the token in case of the token address vertex.
+    pub fn token(&self) -> Option<Token> {
+        if self.0[0] != 0 {
+            return None;
+        }
+        let mut chain_id = [0; 8];
+        chain_id.copy_from_slice(&self.0[CHAIN_ID]);
+        let mut address = [0; ADDRESS_LEN];
+        address.copy_from_slice(&self.0[ADDRESS]);
+        Some(Token::new(u64::from_be_bytes(chain_id), address))
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// Upper-cases each hex letter of the lower-case address whose nibble of
+// the address hash is 8 or greater.
+fn checksum(lower: &str) -> String {
+    let hash = keccak256(lower.as_bytes());
+    let mut s = String::from("0x");
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0xf;
+        s.push(if nibble >= 8 {
+            c.to_ascii_uppercase()
+        } else {
+            c
+        });
+    }
+    s
+}
+
+const KECCAK_RATE: usize = 136;
+
+const KECCAK_RC: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+const KECCAK_ROTC: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+const KECCAK_PILN: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+// The original Keccak-256 used by Ethereum, with the `0x01` padding
+// instead of the SHA3 one.
+pub(crate) fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut state = [0u64; 25];
+    let mut block = [0u8; KECCAK_RATE];
+    let mut chunks = data.chunks_exact(KECCAK_RATE);
+    for chunk in &mut chunks {
+        absorb(&mut state, chunk);
+    }
+    let rest = chunks.remainder();
+    block[..rest.len()].copy_from_slice(rest);
+    block[rest.len()] ^= 0x01;
+    block[KECCAK_RATE - 1] ^= 0x80;
+    absorb(&mut state, &block);
+    let mut hash = [0; 32];
+    for (bytes, lane) in hash.chunks_exact_mut(8).zip(&state) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    hash
+}
+
+fn absorb(state: &mut [u64; 25], block: &[u8]) {
+    for (lane, bytes) in state.iter_mut().zip(block.chunks_exact(8)) {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(bytes);
+        *lane ^= u64::from_le_bytes(buf);
+    }
+    keccak_f(state);
+}
+
+fn keccak_f(state: &mut [u64; 25]) {
+    for rc in KECCAK_RC {
+        // Theta.
+        let mut c = [0u64; 5];
+        for (x, c) in c.iter_mut().enumerate() {
+            *c = (0..5).fold(0, |c, y| c ^ state[x + 5 * y]);
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+        // Rho and pi.
+        let mut last = state[1];
+        for (rotc, piln) in KECCAK_ROTC.iter().zip(KECCAK_PILN) {
+            let next = state[piln];
+            state[piln] = last.rotate_left(*rotc);
+            last = next;
+        }
+        // Chi.
+        for y in 0..5 {
+            let mut row = [0; 5];
+            row.copy_from_slice(&state[5 * y..5 * y + 5]);
+            for x in 0..5 {
+                state[x + 5 * y] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+        // Iota.
+        state[0] ^= rc;
+    }
+}
+
+#[cfg(test)]
+mod test;
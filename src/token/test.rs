@@ -0,0 +1,77 @@
+use super::*;
+use crate::test::vertex;
+use crate::Dex;
+
+const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+#[test]
+fn test_keccak256() {
+    assert_eq!(
+        hex(&keccak256(b"")),
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+    );
+    assert_eq!(
+        hex(&keccak256(&[b'a'; 200])),
+        hex(&keccak256("a".repeat(200).as_bytes()))
+    );
+}
+
+#[test]
+fn test_checksummed() {
+    for address in [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ] {
+        let token: Token = format!("{}@1", address.to_lowercase()).parse().unwrap();
+        assert_eq!(token.checksummed(), address);
+    }
+}
+
+#[test]
+fn test_parse() {
+    let token: Token = format!("{USDC}@1").parse().unwrap();
+    assert_eq!(token.chain_id(), 1);
+    assert_eq!(token.address()[0], 0xa0);
+    assert_eq!(token.to_string(), format!("{USDC}@1"));
+    assert_eq!(
+        format!("{}@1", USDC.to_uppercase().replace("0X", "0x")).parse(),
+        Ok(token)
+    );
+    // The invalid checksum.
+    assert!(format!("{}@1", USDC.replace('A', "a").replace("b8", "B8"))
+        .parse::<Token>()
+        .is_err());
+    assert!(USDC.parse::<Token>().is_err());
+    assert!("0xA0b8@1".parse::<Token>().is_err());
+    assert!(format!("{USDC}@mainnet").parse::<Token>().is_err());
+}
+
+#[test]
+fn test_vertex() {
+    let mainnet: Vertex = format!("{}@1", USDC.to_lowercase()).parse().unwrap();
+    let arbitrum: Vertex = format!("{USDC}@42161").parse().unwrap();
+    assert_ne!(mainnet, arbitrum);
+    assert_eq!(mainnet.token().map(|token| token.chain_id()), Some(1));
+    assert_eq!(mainnet.to_string(), format!("{USDC}@1"));
+    assert_eq!(mainnet.as_str(), "");
+    assert_eq!(vertex("USDC").token(), None);
+    assert_eq!(mainnet.to_string().parse(), Ok(mainnet));
+}
+
+#[test]
+fn test_multi_chain_graph() {
+    let mainnet: Vertex = format!("{USDC}@1").parse().unwrap();
+    let arbitrum: Vertex = format!("{USDC}@42161").parse().unwrap();
+    let mut dex = Dex::new();
+    dex.add_rate(mainnet, vertex("ETH"), 0.0005);
+    dex.add_rate(mainnet, arbitrum, 0.999);
+    dex.set_label(arbitrum, "USDC.arb");
+    assert_eq!(dex.vertices().count(), 3);
+    let path = dex.get_best_rate(&arbitrum, &vertex("ETH")).unwrap();
+    assert_eq!(
+        dex.display_path(&path),
+        format!("USDC.arb -> {USDC}@1 -> ETH: {}", path.rate())
+    );
+}
@@ -0,0 +1,370 @@
+//! 256-bit unsigned amounts in the base units
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use tracing::debug;
+
+use super::{Dex, Path, Vertex};
+
+/// The 256-bit unsigned integer amount in the base units, e.g. the wei,
+/// as the on-chain token amounts.
+///
+/// The amounts are converted from and to the decimal strings of the
+/// currency units with the currency decimals at the API boundary, see
+/// [`Dex::parse_units`] and [`Dex::format_units`], and never through
+/// the floating point.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct U256([u64; 4]);
+
+/// The invalid decimal amount.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseU256Error(String);
+
+impl fmt::Display for ParseU256Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid amount {:?}", self.0)
+    }
+}
+
+impl Error for ParseU256Error {}
+
+impl U256 {
+    pub const ZERO: Self = Self([0; 4]);
+    pub const ONE: Self = Self([1, 0, 0, 0]);
+    pub const MAX: Self = Self([u64::MAX; 4]);
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
+    /// Returns `10^exp`, or `None` in case of the overflow.
+    pub fn pow10(exp: u32) -> Option<Self> {
+        (0..exp).try_fold(Self::ONE, |n, _| n.checked_mul_u64(10))
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let mut sum = [0; 4];
+        let mut carry = false;
+        for (i, limb) in sum.iter_mut().enumerate() {
+            let (n, c1) = self.0[i].overflowing_add(other.0[i]);
+            let (n, c2) = n.overflowing_add(u64::from(carry));
+            *limb = n;
+            carry = c1 || c2;
+        }
+        if carry {
+            None
+        } else {
+            Some(Self(sum))
+        }
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let mut diff = [0; 4];
+        let mut borrow = false;
+        for (i, limb) in diff.iter_mut().enumerate() {
+            let (n, b1) = self.0[i].overflowing_sub(other.0[i]);
+            let (n, b2) = n.overflowing_sub(u64::from(borrow));
+            *limb = n;
+            borrow = b1 || b2;
+        }
+        if borrow {
+            None
+        } else {
+            Some(Self(diff))
+        }
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let mut product = [0u64; 8];
+        for (i, a) in self.0.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, b) in other.0.iter().enumerate() {
+                let n = u128::from(*a) * u128::from(*b) + u128::from(product[i + j]) + carry;
+                product[i + j] = n as u64;
+                carry = n >> 64;
+            }
+            product[i + 4] = carry as u64;
+        }
+        if product[4..].iter().any(|limb| *limb != 0) {
+            return None;
+        }
+        Some(Self([product[0], product[1], product[2], product[3]]))
+    }
+
+    pub fn checked_mul_u64(self, other: u64) -> Option<Self> {
+        self.checked_mul(Self::from(other))
+    }
+
+    /// Returns the quotient and the remainder of the division by the
+    /// non-zero `divisor`.
+    pub fn div_rem_u64(self, divisor: u64) -> (Self, u64) {
+        assert!(divisor != 0);
+        let mut quotient = [0; 4];
+        let mut rem = 0u128;
+        for i in (0..4).rev() {
+            let n = rem << 64 | u128::from(self.0[i]);
+            quotient[i] = (n / u128::from(divisor)) as u64;
+            rem = n % u128::from(divisor);
+        }
+        (Self(quotient), rem as u64)
+    }
+
+    /// Returns the amount times the `rate`, rounded down.
+    ///
+    /// The rate is taken exactly as the binary fraction it is, so that
+    /// the amount is never rounded through the floating point.  It's
+    /// `None` for the negative, the non-finite, or the overflowing
+    /// result.
+    pub fn mul_rate(self, rate: f32) -> Option<Self> {
+        if !rate.is_finite() || rate < 0.0 {
+            return None;
+        }
+        let (mantissa, exp) = decompose(rate);
+        let n = self.checked_mul_u64(mantissa)?;
+        if exp >= 0 {
+            n.checked_shl(exp as u32)
+        } else {
+            Some(n.shr(exp.unsigned_abs()))
+        }
+    }
+
+    /// Returns the lossy approximation, e.g. for the display.
+    pub fn to_f64(&self) -> f64 {
+        self.0.iter().rev().fold(0.0, |n, limb| {
+            n * 18_446_744_073_709_551_616.0 + *limb as f64
+        })
+    }
+
+    /// Parses the decimal amount of the currency units, e.g. `"1.5"`,
+    /// into the base units with the `decimals`.
+    ///
+    /// The amount with more fractional digits than the decimals is
+    /// rejected rather than rounded.
+    pub fn parse_units(s: &str, decimals: u8) -> Result<Self, ParseU256Error> {
+        let error = || ParseU256Error(s.to_string());
+        let (int, frac) = match s.split_once('.') {
+            Some((int, frac)) => (int, frac),
+            None => (s, ""),
+        };
+        if (int.is_empty() && frac.is_empty()) || frac.len() > usize::from(decimals) {
+            return Err(error());
+        }
+        let padding = "0".repeat(usize::from(decimals) - frac.len());
+        format!("{int}{frac}{padding}").parse().map_err(|_| error())
+    }
+
+    /// Formats the amount in the currency units with the `decimals`,
+    /// e.g. `"1.500000"`.
+    pub fn format_units(&self, decimals: u8) -> String {
+        let digits = self.to_string();
+        let decimals = usize::from(decimals);
+        if decimals == 0 {
+            return digits;
+        }
+        let digits = format!("{digits:0>width$}", width = decimals + 1);
+        let (int, frac) = digits.split_at(digits.len() - decimals);
+        format!("{int}.{frac}")
+    }
+
+    fn checked_shl(self, bits: u32) -> Option<Self> {
+        if self.is_zero() {
+            return Some(self);
+        }
+        if bits >= 256 || self.leading_zeros() < bits {
+            return None;
+        }
+        Some(self.shl(bits))
+    }
+
+    fn shl(self, bits: u32) -> Self {
+        let (limbs, bits) = ((bits / 64) as usize, bits % 64);
+        let mut n = [0; 4];
+        for i in (limbs..4).rev() {
+            n[i] = self.0[i - limbs] << bits;
+            if bits > 0 && i > limbs {
+                n[i] |= self.0[i - limbs - 1] >> (64 - bits);
+            }
+        }
+        Self(n)
+    }
+
+    fn shr(self, bits: u32) -> Self {
+        if bits >= 256 {
+            return Self::ZERO;
+        }
+        let (limbs, bits) = ((bits / 64) as usize, bits % 64);
+        let mut n = [0; 4];
+        for (i, limb) in n.iter_mut().enumerate().take(4 - limbs) {
+            *limb = self.0[i + limbs] >> bits;
+            if bits > 0 && i + limbs + 1 < 4 {
+                *limb |= self.0[i + limbs + 1] << (64 - bits);
+            }
+        }
+        Self(n)
+    }
+
+    fn leading_zeros(&self) -> u32 {
+        let mut zeros = 0;
+        for limb in self.0.iter().rev() {
+            zeros += limb.leading_zeros();
+            if *limb != 0 {
+                break;
+            }
+        }
+        zeros
+    }
+}
+
+// Decomposes the finite non-negative `rate` into the integer mantissa
+// and the binary exponent, `rate == mantissa * 2^exp`.
+fn decompose(rate: f32) -> (u64, i32) {
+    let bits = rate.to_bits();
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let fraction = u64::from(bits & 0x7f_ffff);
+    if exp == 0 {
+        (fraction, -149)
+    } else {
+        (fraction | 0x80_0000, exp - 150)
+    }
+}
+
+impl From<u64> for U256 {
+    fn from(n: u64) -> Self {
+        Self([n, 0, 0, 0])
+    }
+}
+
+impl From<u128> for U256 {
+    fn from(n: u128) -> Self {
+        Self([n as u64, (n >> 64) as u64, 0, 0])
+    }
+}
+
+impl TryFrom<U256> for u128 {
+    type Error = U256;
+
+    fn try_from(n: U256) -> Result<Self, Self::Error> {
+        if n.0[2] != 0 || n.0[3] != 0 {
+            return Err(n);
+        }
+        Ok(u128::from(n.0[0]) | u128::from(n.0[1]) << 64)
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for U256 {
+    type Err = ParseU256Error;
+
+    /// Parses the decimal integer.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseU256Error(s.to_string());
+        if s.is_empty() {
+            return Err(error());
+        }
+        s.chars().try_fold(Self::ZERO, |n, c| {
+            let digit = c.to_digit(10).ok_or_else(error)?;
+            n.checked_mul_u64(10)
+                .and_then(|n| n.checked_add(Self::from(u64::from(digit))))
+                .ok_or_else(error)
+        })
+    }
+}
+
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The 19 digits chunks, the largest power of ten in u64.
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+        let mut chunks = Vec::new();
+        let mut n = *self;
+        loop {
+            let (quotient, rem) = n.div_rem_u64(CHUNK);
+            chunks.push(rem);
+            n = quotient;
+            if n.is_zero() {
+                break;
+            }
+        }
+        let mut digits = chunks.pop().unwrap_or_default().to_string();
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{chunk:019}"));
+        }
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+impl Dex {
+    /// Parses the decimal amount of the currency into the base units,
+    /// with the currency decimals, see [`Dex::decimals`].
+    pub fn parse_units(&self, v: &Vertex, s: &str) -> Result<U256, ParseU256Error> {
+        U256::parse_units(s, self.decimals(v))
+    }
+
+    /// Formats the base units `amount` of the currency with its
+    /// decimals.
+    pub fn format_units(&self, v: &Vertex, amount: U256) -> String {
+        format!("{} {v}", amount.format_units(self.decimals(v)))
+    }
+
+    /// Returns the base units amount received at each hop of the `path`
+    /// for the `amount` of the source currency in the base units.
+    ///
+    /// Each hop applies its rate exactly and rounds down to the base
+    /// unit of the destination currency, never to quote more than
+    /// executable.  It's `None` in case any amount overflows.
+    pub fn amounts_out_units(&self, path: &Path, amount: U256) -> Option<Vec<U256>> {
+        let mut amounts = Vec::with_capacity(path.rates.len());
+        let mut amount = amount;
+        for (hop, rate) in path.path.windows(2).zip(&path.rates) {
+            let src = i32::from(self.decimals(&hop[0]));
+            let dst = i32::from(self.decimals(&hop[1]));
+            // Scales up before the rate, and down after it, to round
+            // down only once.
+            let scale = U256::pow10((dst - src).unsigned_abs())?;
+            amount = if dst >= src {
+                amount.checked_mul(scale)?.mul_rate(*rate)?
+            } else {
+                div(amount.mul_rate(*rate)?, scale)
+            };
+            amounts.push(amount);
+        }
+        debug!(hops = amounts.len(), "amounts out");
+        Some(amounts)
+    }
+
+    /// Returns the base units amount received for the `amount` of the
+    /// source currency, see [`Dex::amounts_out_units`].
+    pub fn amount_out_units(&self, path: &Path, amount: U256) -> Option<U256> {
+        self.amounts_out_units(path, amount)?
+            .last()
+            .copied()
+            .or(Some(amount))
+    }
+}
+
+// Divides by the power of ten, rounded down.
+fn div(mut n: U256, mut divisor: U256) -> U256 {
+    // The power of ten divides in the u64 steps.
+    const STEP: u64 = 10_000_000_000_000_000_000;
+    while divisor > U256::from(STEP) {
+        n = n.div_rem_u64(STEP).0;
+        divisor = divisor.div_rem_u64(STEP).0;
+    }
+    n.div_rem_u64(divisor.0[0]).0
+}
+
+#[cfg(test)]
+mod test;
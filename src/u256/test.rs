@@ -0,0 +1,128 @@
+use super::{ParseU256Error, U256};
+use crate::test::vertex;
+use crate::Dex;
+
+#[test]
+fn test_u256() {
+    let max: U256 =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+            .parse()
+            .unwrap();
+    assert_eq!(max, U256::MAX);
+    assert_eq!(
+        max.to_string(),
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+    );
+    assert_eq!(U256::ZERO.to_string(), "0");
+    assert_eq!(format!("{:>5}", U256::from(42u64)), "   42");
+    assert!(
+        "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+            .parse::<U256>()
+            .is_err()
+    );
+    assert!("".parse::<U256>().is_err());
+    assert!("-1".parse::<U256>().is_err());
+
+    let wei = U256::pow10(18).unwrap();
+    assert_eq!(wei, U256::from(1_000_000_000_000_000_000u64));
+    assert_eq!(
+        U256::pow10(77).unwrap().to_string(),
+        format!("1{}", "0".repeat(77))
+    );
+    assert_eq!(U256::pow10(78), None);
+    assert_eq!(max.checked_add(U256::ONE), None);
+    assert_eq!(U256::ZERO.checked_sub(U256::ONE), None);
+    assert_eq!(max.checked_sub(max), Some(U256::ZERO));
+    assert_eq!(max.checked_mul(U256::from(2u64)), None);
+    assert_eq!(wei.checked_mul(wei), U256::pow10(36));
+    assert!(U256::pow10(39).unwrap() > U256::from(u128::MAX));
+    assert_eq!(u128::try_from(U256::from(u128::MAX)), Ok(u128::MAX));
+    assert!(u128::try_from(max).is_err());
+    assert_eq!(max.div_rem_u64(10).1, 5);
+    assert!(
+        U256::from(1u64 << 63)
+            .checked_mul(U256::from(2u64))
+            .unwrap()
+            > U256::from(u64::MAX)
+    );
+}
+
+#[test]
+fn test_mul_rate() {
+    let amount: U256 = "123456789012345678901234567890".parse().unwrap();
+    assert_eq!(amount.mul_rate(1.0), Some(amount));
+    assert_eq!(
+        amount.mul_rate(0.5).unwrap().to_string(),
+        "61728394506172839450617283945"
+    );
+    assert_eq!(
+        amount.mul_rate(4.0).unwrap().to_string(),
+        "493827156049382715604938271560"
+    );
+    // The f32 0.1 is slightly above the decimal 0.1.
+    assert_eq!(
+        U256::from(10u64.pow(18)).mul_rate(0.1).unwrap().to_string(),
+        "100000001490116119"
+    );
+    assert_eq!(
+        U256::from(3u64)
+            .mul_rate(2f32.powi(100))
+            .unwrap()
+            .to_string(),
+        "3802951800684688204490109616128"
+    );
+    assert_eq!(U256::ONE.mul_rate(f32::MIN_POSITIVE), Some(U256::ZERO));
+    assert_eq!(amount.mul_rate(-1.0), None);
+    assert_eq!(amount.mul_rate(f32::NAN), None);
+    assert_eq!(U256::MAX.mul_rate(2.0), None);
+    assert_eq!(U256::ZERO.mul_rate(f32::MAX), Some(U256::ZERO));
+}
+
+#[test]
+fn test_units() {
+    let amount = U256::parse_units("1.5", 18).unwrap();
+    assert_eq!(amount.to_string(), "1500000000000000000");
+    assert_eq!(amount.format_units(18), "1.500000000000000000");
+    assert_eq!(U256::parse_units("12", 0).unwrap().format_units(0), "12");
+    assert_eq!(
+        U256::parse_units(".05", 6).unwrap().format_units(6),
+        "0.050000"
+    );
+    assert_eq!(
+        U256::parse_units("0.0000001", 6),
+        Err(ParseU256Error("0.0000001".to_string()))
+    );
+    assert!(U256::parse_units(".", 6).is_err());
+    assert!(U256::parse_units("1e3", 6).is_err());
+}
+
+#[test]
+fn test_amounts_out_units() {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("ETH"), vertex("USDC"), 2048.0);
+    dex.add_rate(vertex("USDC"), vertex("JPY"), 150.0);
+    let (eth, jpy) = (vertex("ETH"), vertex("JPY"));
+    let path = dex.get_best_rate(&eth, &jpy).unwrap();
+
+    let amount = dex.parse_units(&eth, "1.000000000000000001").unwrap();
+    let amounts = dex.amounts_out_units(&path, amount).unwrap();
+    assert_eq!(
+        dex.format_units(&vertex("USDC"), amounts[0]),
+        "2048.000000 USDC"
+    );
+    assert_eq!(dex.format_units(&jpy, amounts[1]), "307200 JPY");
+
+    // The wei beyond the f32 precision are kept.
+    let amount = dex.parse_units(&eth, "3.000000000000000005").unwrap();
+    let path = dex.get_best_rate(&eth, &vertex("USDC")).unwrap();
+    assert_eq!(
+        dex.amount_out_units(&path, amount).unwrap().to_string(),
+        "6144000000"
+    );
+    let path = dex.get_best_rate(&jpy, &eth).unwrap();
+    let amount = dex.parse_units(&jpy, "307200").unwrap();
+    let out = dex.amount_out_units(&path, amount).unwrap();
+    // The f32 reverse rates are rounded.
+    assert_eq!(out.to_string(), "1000000023925781250");
+    assert!((out.to_f64() / 1e18 - 1.0).abs() < 1e-7);
+}
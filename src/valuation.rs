@@ -0,0 +1,123 @@
+//! Base-currency valuations
+
+use std::collections::BTreeMap;
+
+use tracing::instrument;
+
+use super::{Dex, Path, Vertex};
+use crate::query::{InvalidAmount, QueryOptions};
+
+/// The holding valued in the base currency.
+#[derive(Clone, Debug)]
+pub struct Holding {
+    pub asset: Vertex,
+    pub amount: f32,
+    /// The amount of the base currency received through the path, or
+    /// `None` in case there is no route into the base.  The zero amount
+    /// is valued as 0, routed or not.
+    pub value: Option<f32>,
+    pub path: Option<Path>,
+}
+
+/// The portfolio valued in the base currency.
+#[derive(Clone, Debug)]
+pub struct Portfolio {
+    pub base: Vertex,
+    pub holdings: Vec<Holding>,
+}
+
+impl Portfolio {
+    /// Returns the total value of the routable holdings.
+    pub fn total(&self) -> f32 {
+        self.holdings
+            .iter()
+            .filter_map(|holding| holding.value)
+            .sum()
+    }
+
+    /// Returns the holdings without the route into the base currency.
+    pub fn unpriced(&self) -> impl Iterator<Item = &Holding> {
+        self.holdings
+            .iter()
+            .filter(|holding| holding.value.is_none())
+    }
+}
+
+impl Dex {
+    /// Returns the best rate of each vertex into the `base` currency,
+    /// e.g. the price list in the base currency.
+    ///
+    /// It runs the single search from `base` over the reversed edges,
+    /// instead of the point query per vertex.
+    #[instrument(level = "debug", skip(self))]
+    pub fn valuations(&self, base: &Vertex) -> BTreeMap<Vertex, f32> {
+        let mut reversed = Dex::new();
+        for (src, edges) in &self.edges {
+            reversed.edges.entry(*src).or_default();
+            for (dst, edge) in edges {
+                let edges = reversed.edges.entry(*dst).or_default();
+                edges.insert(*src, edge.clone());
+            }
+        }
+        let mut valuations: BTreeMap<_, _> = reversed
+            .get_best_rates_from(base, &QueryOptions::new())
+            .into_iter()
+            .map(|(v, path)| (v, path.rate))
+            .collect();
+        if self.edges.contains_key(base) {
+            valuations.insert(*base, 1.0);
+        }
+        valuations
+    }
+
+    /// Values the `holdings` in the `base` currency, routing each
+    /// holding amount through its best path into the base.
+    ///
+    /// Unlike [`Dex::valuations`], the paths account for the amount,
+    /// e.g. the liquidity and the fixed fees.  It returns the
+    /// [`InvalidAmount`] error in case of the negative, infinite, or NaN
+    /// holding amount.
+    #[instrument(level = "debug", skip(self, holdings))]
+    pub fn value_portfolio<V>(
+        &self,
+        holdings: &[(V, f32)],
+        base: V,
+    ) -> Result<Portfolio, InvalidAmount>
+    where
+        V: Into<Vertex> + Copy + std::fmt::Debug,
+    {
+        let base = base.into();
+        let holdings = holdings
+            .iter()
+            .map(|(asset, amount)| {
+                if !amount.is_finite() || *amount < 0.0 {
+                    return Err(InvalidAmount(*amount));
+                }
+                let asset = (*asset).into();
+                let path = if asset == base {
+                    Some(Path::new(base))
+                } else if *amount == 0.0 {
+                    self.get_best_rate(&asset, &base)
+                } else {
+                    let options = QueryOptions::new().with_amount(*amount);
+                    self.get_best_rate_with(&asset, &base, &options)
+                };
+                let value = match &path {
+                    _ if *amount == 0.0 => Some(0.0),
+                    Some(path) => Some(amount * path.rate()),
+                    None => None,
+                };
+                Ok(Holding {
+                    asset,
+                    amount: *amount,
+                    value,
+                    path,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Portfolio { base, holdings })
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use crate::test::vertex;
+use crate::{Dex, Edge};
+
+#[test]
+fn test_valuations() {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("EUR"), vertex("USD"), 1.1);
+    dex.add_rate(vertex("GBP"), vertex("EUR"), 1.2);
+    dex.add_rate(vertex("JPY"), vertex("USD"), 0.01);
+    dex.add_rate(vertex("CNY"), vertex("KRW"), 180.0);
+    dex.add_edge(
+        vertex("BTC"),
+        vertex("USD"),
+        Edge::new(20_000.0).with_liquidity(1.0),
+    );
+
+    let usd = vertex("USD");
+    let valuations = dex.valuations(&usd);
+    assert_eq!(valuations.len(), 5);
+    assert_eq!(valuations[&usd], 1.0);
+    assert_eq!(valuations[&vertex("EUR")], 1.1);
+    assert_eq!(valuations[&vertex("GBP")], 1.2 * 1.1);
+    assert_eq!(valuations[&vertex("JPY")], 0.01);
+    assert_eq!(valuations[&vertex("BTC")], 20_000.0);
+    assert!(!valuations.contains_key(&vertex("CNY")));
+
+    for (v, rate) in valuations {
+        if v != usd {
+            assert_eq!(dex.get_best_rate(&v, &usd).unwrap().rate(), rate);
+        }
+    }
+}
+
+#[test]
+fn test_value_portfolio() {
+    let mut dex = Dex::new();
+    dex.add_rate(vertex("EUR"), vertex("USD"), 1.1);
+    dex.add_rate(vertex("GBP"), vertex("EUR"), 1.2);
+    dex.add_rate(vertex("CNY"), vertex("KRW"), 180.0);
+    dex.add_edge(
+        vertex("BTC"),
+        vertex("USD"),
+        Edge::bridge(20_000.0, 100.0, 0.0, Duration::ZERO),
+    );
+
+    let portfolio = dex
+        .value_portfolio(
+            &[
+                (vertex("USD"), 10.0),
+                (vertex("GBP"), 100.0),
+                (vertex("BTC"), 1.0),
+                (vertex("CNY"), 5.0),
+            ],
+            vertex("USD"),
+        )
+        .unwrap();
+    assert_eq!(portfolio.holdings.len(), 4);
+    assert_eq!(portfolio.holdings[0].value, Some(10.0));
+    assert_eq!(portfolio.holdings[1].value, Some(132.0));
+    assert_eq!(portfolio.holdings[1].path.as_ref().unwrap().len(), 3);
+    assert_eq!(portfolio.holdings[2].value, Some(19_900.0));
+    assert_eq!(portfolio.holdings[3].value, None);
+    assert_eq!(portfolio.total(), 10.0 + 132.0 + 19_900.0);
+    assert_eq!(portfolio.unpriced().count(), 1);
+
+    // The zero holding is of no value, even short of the fixed fee.
+    let portfolio = dex
+        .value_portfolio(&[(vertex("BTC"), 0.0), (vertex("CNY"), 0.0)], vertex("USD"))
+        .unwrap();
+    assert_eq!(portfolio.holdings[0].value, Some(0.0));
+    assert!(portfolio.holdings[0].path.is_some());
+    assert_eq!(portfolio.holdings[1].value, Some(0.0));
+    assert_eq!(portfolio.total(), 0.0);
+
+    for amount in [-1.0, f32::NAN, f32::INFINITY] {
+        let holdings = [(vertex("GBP"), 1.0), (vertex("BTC"), amount)];
+        let err = dex.value_portfolio(&holdings, vertex("USD")).unwrap_err();
+        assert_eq!(err.to_string(), format!("invalid amount {amount}"));
+    }
+}
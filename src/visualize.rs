@@ -0,0 +1,47 @@
+//! Graph visualization page
+
+use std::time::SystemTime;
+
+use super::Dex;
+use crate::report::{number, string};
+
+/// The self-contained page drawing the force-directed graph, with the
+/// edges colored by the rate freshness and the best path of the two
+/// clicked currencies highlighted.
+///
+/// It fetches the `graph` and the `best-rate` relative to itself, so
+/// it's served next to them, e.g. `/graphs/<NAME>/visualize`.
+pub(crate) const PAGE: &str = include_str!("visualize/index.html");
+
+/// Returns the vertices and the edges of the graph as JSON, with the
+/// age of each rate as of `now`, or `null` without the timestamp.
+pub(crate) fn graph_json(dex: &Dex, now: SystemTime) -> String {
+    let nodes: Vec<_> = dex.vertices().map(string).collect();
+    let mut edges = Vec::new();
+    for (src, dst_edges) in &dex.edges {
+        for (dst, edge) in dst_edges {
+            let age = match edge.timestamp() {
+                Some(timestamp) => now
+                    .duration_since(timestamp)
+                    .unwrap_or_default()
+                    .as_millis()
+                    .to_string(),
+                None => "null".to_string(),
+            };
+            edges.push(format!(
+                "{{\"src\": {}, \"dst\": {}, \"rate\": {}, \"age_ms\": {age}}}",
+                string(src),
+                string(dst),
+                number(edge.rate()),
+            ));
+        }
+    }
+    format!(
+        "{{\"nodes\": [{}], \"edges\": [{}]}}",
+        nodes.join(", "),
+        edges.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod test;
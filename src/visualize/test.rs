@@ -0,0 +1,22 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::{graph_json, PAGE};
+use crate::{Dex, Edge};
+
+#[test]
+fn test_graph_json() {
+    let at = UNIX_EPOCH + Duration::from_secs(100);
+    let mut dex = Dex::new();
+    dex.add_edge('A', 'B', Edge::new(2.0).with_timestamp(at));
+    dex.add_limit_order('B', 'C', 3.0, 10.0);
+    let json = graph_json(&dex, at + Duration::from_millis(1500));
+    assert_eq!(
+        json,
+        "{\"nodes\": [\"A\", \"B\", \"C\"], \"edges\": [\
+         {\"src\": \"A\", \"dst\": \"B\", \"rate\": 2, \"age_ms\": 1500}, \
+         {\"src\": \"B\", \"dst\": \"A\", \"rate\": 0.5, \"age_ms\": 1500}, \
+         {\"src\": \"B\", \"dst\": \"C\", \"rate\": 3, \"age_ms\": null}]}"
+    );
+    assert!(PAGE.contains("fetch(\"graph\")"));
+    assert!(PAGE.contains("best-rate?src="));
+}
@@ -0,0 +1,120 @@
+//! Rolling rate volatility
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use tracing::debug;
+
+use super::{Dex, Edge, Vertex};
+
+/// The recent rates of each pair, in the vertex order of the pair.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RateHistory {
+    window: usize,
+    rates: HashMap<(Vertex, Vertex), VecDeque<f32>>,
+}
+
+impl RateHistory {
+    // Records the `src -> dst` rate, and returns the volatility of the
+    // pair over the window.
+    fn record(&mut self, src: Vertex, dst: Vertex, rate: f32) -> Option<f32> {
+        let (pair, rate) = if src < dst {
+            ((src, dst), rate)
+        } else {
+            ((dst, src), 1.0 / rate)
+        };
+        let rates = self.rates.entry(pair).or_default();
+        if rates.len() == self.window {
+            rates.pop_front();
+        }
+        rates.push_back(rate);
+        volatility(rates)
+    }
+}
+
+// Returns the sample standard deviation of the log returns, in case of
+// two or more returns.
+fn volatility(rates: &VecDeque<f32>) -> Option<f32> {
+    let returns: Vec<f64> = rates
+        .iter()
+        .zip(rates.iter().skip(1))
+        .map(|(prev, rate)| (f64::from(*rate) / f64::from(*prev)).ln())
+        .filter(|r| r.is_finite())
+        .collect();
+    if returns.len() < 2 {
+        return None;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    Some(variance.sqrt() as f32)
+}
+
+impl Edge {
+    /// Returns the rolling volatility of the pair, the standard
+    /// deviation of the log returns per rate update, see
+    /// [`Dex::set_rate_history`].
+    pub fn volatility(&self) -> Option<f32> {
+        self.volatility
+    }
+
+    // Returns the factor of the value for the volatility `penalty`, see
+    // [`QueryOptions::with_volatility_penalty`].
+    //
+    // [`QueryOptions::with_volatility_penalty`]: crate::query::QueryOptions::with_volatility_penalty
+    pub(crate) fn haircut(&self, penalty: f32) -> f32 {
+        match self.volatility {
+            Some(volatility) => (1.0 - penalty * volatility).max(0.0),
+            None => 1.0,
+        }
+    }
+}
+
+impl Dex {
+    /// Keeps the last `window` rates of each pair added, for the
+    /// rolling volatility of the edges, or stops keeping them with
+    /// `None`.
+    ///
+    /// The volatility is the standard deviation of the log returns of
+    /// the rate updates within the window, and is set on the edge as
+    /// the rate is updated, see [`Edge::volatility`].
+    pub fn set_rate_history(&mut self, window: Option<usize>) {
+        self.history = window.map(|window| {
+            assert!(window > 2);
+            RateHistory {
+                window,
+                ..RateHistory::default()
+            }
+        });
+    }
+
+    /// Returns the rate history window, if kept.
+    pub fn rate_history(&self) -> Option<usize> {
+        self.history.as_ref().map(|history| history.window)
+    }
+
+    /// Records the `src -> dst` rate in the history, if kept, and
+    /// returns the volatility of the pair.
+    pub(crate) fn record_rate(&mut self, src: Vertex, dst: Vertex, rate: f32) -> Option<f32> {
+        let volatility = self.history.as_mut()?.record(src, dst, rate);
+        if let Some(volatility) = volatility {
+            debug!(%src, %dst, %volatility, "volatility");
+        }
+        volatility
+    }
+
+    /// Returns the volatility of each directed edge known, by the
+    /// pair.
+    pub fn volatilities(&self) -> BTreeMap<(Vertex, Vertex), f32> {
+        self.edges
+            .iter()
+            .flat_map(|(src, edges)| {
+                edges
+                    .iter()
+                    .filter_map(move |(dst, edge)| Some(((*src, *dst), edge.volatility?)))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,54 @@
+use crate::query::QueryOptions;
+use crate::Dex;
+
+#[test]
+fn test_volatility() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 1.0);
+    assert_eq!(dex.rate_history(), None);
+    dex.set_rate_history(Some(3));
+    assert_eq!(dex.rate_history(), Some(3));
+
+    let (a, b) = ('A'.into(), 'B'.into());
+    dex.add_rate('A', 'B', 1.0);
+    dex.add_rate('B', 'A', 1.0 / 1.1);
+    assert_eq!(dex.edges[&a][&b].volatility(), None);
+    dex.add_rate('A', 'B', 1.0);
+    let volatility = dex.edges[&a][&b].volatility().unwrap();
+    // The log returns of +/- ln(1.1).
+    assert!((volatility - 2f32.sqrt() * 1.1f32.ln()).abs() < 1e-5);
+    assert_eq!(dex.edges[&b][&a].volatility(), Some(volatility));
+    assert_eq!(dex.volatilities().len(), 2);
+
+    // The window drops the oldest rate.
+    dex.add_rate('A', 'B', 1.0);
+    let volatility = dex.edges[&a][&b].volatility().unwrap();
+    assert!((volatility - 0.5f32.sqrt() * 1.1f32.ln()).abs() < 1e-5);
+
+    dex.set_rate_history(None);
+    dex.add_rate('A', 'B', 1.0);
+    assert!(dex.volatilities().is_empty());
+}
+
+#[test]
+fn test_volatility_penalty() {
+    let mut dex = Dex::new();
+    dex.set_rate_history(Some(10));
+    for rate in [1.0, 1.2, 0.8, 1.05] {
+        dex.add_rate('A', 'B', rate);
+    }
+    dex.add_rate('B', 'D', 1.0);
+    dex.add_rate('A', 'C', 1.0);
+    dex.add_rate('C', 'D', 1.0);
+
+    let (a, d) = ('A'.into(), 'D'.into());
+    let path = dex.get_best_rate(&a, &d).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> D: 1.05");
+
+    let options = QueryOptions::new().with_volatility_penalty(1.0);
+    assert_eq!(options.volatility_penalty(), Some(1.0));
+    let path = dex.get_best_rate_with(&a, &d, &options).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> D: 1");
+    let score = options.score(&path);
+    assert_eq!(score, 1.0);
+}
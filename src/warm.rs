@@ -0,0 +1,173 @@
+//! Warm-start queries
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Path, Vertex};
+use crate::cancel::Timeout;
+use crate::change::Version;
+use crate::edge::Edge;
+use crate::query::QueryOptions;
+
+/// The best rate queries warm-started from the previous results.
+///
+/// The best rate paths from each queried source are kept along with
+/// the edge rates they were computed with.  After the rate only update,
+/// the kept paths are re-evaluated with the new rates and relaxed from
+/// the changed vertices by the queue-optimized Bellman-Ford, instead of
+/// searching from scratch.  The structural change, the edge added or
+/// removed or changed in anything but the rate and the percentage fee,
+/// e.g. the liquidity or the fixed fee, falls back to the full search,
+/// as does the kept path no longer routable with the new rates.  The
+/// changes are read from the change log of the graph, see
+/// [`Dex::changed_since`].
+#[derive(Debug, Default)]
+pub struct WarmStart {
+    options: QueryOptions,
+    // The edges as of the `version`.
+    version: Version,
+    edges: BTreeMap<(Vertex, Vertex), Edge>,
+    paths: HashMap<Vertex, BTreeMap<Vertex, Path>>,
+}
+
+impl WarmStart {
+    pub fn new(options: QueryOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the number of the sources kept.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn get_best_rate(&mut self, dex: &Dex, src: &Vertex, dst: &Vertex) -> Option<Path> {
+        self.get_best_rates_from(dex, src).get(dst).cloned()
+    }
+
+    /// Returns the best rate paths from `src` on the current `dex`.
+    pub fn get_best_rates_from(&mut self, dex: &Dex, src: &Vertex) -> &BTreeMap<Vertex, Path> {
+        self.refresh(dex);
+        if !self.paths.contains_key(src) {
+            debug!(%src, "full search");
+            let mut paths = dex.get_best_rates_from(src, &self.options);
+            paths.insert(*src, Path::new(*src));
+            self.paths.insert(*src, paths);
+        }
+        &self.paths[src]
+    }
+
+    // Brings the kept paths up to date with the `dex` rates.
+    #[instrument(level = "debug", skip_all)]
+    fn refresh(&mut self, dex: &Dex) {
+        let pairs: BTreeSet<_> = match dex.changed_since(self.version) {
+            Some(changes) => changes.copied().collect(),
+            None => dex
+                .edges
+                .iter()
+                .flat_map(|(src, edges)| edges.keys().map(move |dst| (*src, *dst)))
+                .chain(self.edges.keys().copied())
+                .collect(),
+        };
+        self.version = dex.version();
+        let mut structural = false;
+        let mut changed = BTreeSet::new();
+        for pair in pairs {
+            let edge = dex.edges.get(&pair.0).and_then(|edges| edges.get(&pair.1));
+            match (self.edges.get(&pair), edge) {
+                (Some(old), Some(new)) if old == new => continue,
+                (Some(old), Some(new)) if is_rate_only(old, new) => {
+                    changed.insert(pair.0);
+                }
+                (None, None) => continue,
+                _ => structural = true,
+            }
+            match edge {
+                Some(edge) => self.edges.insert(pair, edge.clone()),
+                None => self.edges.remove(&pair),
+            };
+        }
+        if structural {
+            if !self.paths.is_empty() {
+                debug!("structural change");
+            }
+            self.paths.clear();
+            return;
+        }
+        if changed.is_empty() {
+            return;
+        }
+        debug!(changed = changed.len(), "warm start");
+        let options = &self.options;
+        self.paths.retain(|src, paths| {
+            let (warm, worse) = match reevaluate(dex, paths, options) {
+                Some(reevaluated) => reevaluated,
+                None => {
+                    debug!(%src, "unroutable");
+                    return false;
+                }
+            };
+            // The worse estimate may be beaten through any vertex.
+            let queue: Vec<_> = if worse {
+                warm.keys().copied().collect()
+            } else {
+                changed.iter().copied().collect()
+            };
+            *paths = dex
+                .relax(src, None, options, warm, queue)
+                .map(|paths| paths.into_value())
+                .unwrap_or_else(Timeout::into_partial);
+            true
+        });
+    }
+}
+
+// Checks if the edge changed in the rate only, which the kept paths are
+// re-evaluated with.  The volatility and the timestamp are of the rate.
+fn is_rate_only(old: &Edge, new: &Edge) -> bool {
+    let rated = Edge {
+        rate: new.rate,
+        fee: new.fee,
+        volatility: new.volatility,
+        timestamp: new.timestamp,
+        ..old.clone()
+    };
+    rated == *new
+}
+
+// Re-evaluates the `paths` with the current rates, and returns them
+// with the flag if any path got worse, or `None` in case any path is
+// no longer routable, e.g. by the fixed fee for the amount.
+fn reevaluate(
+    dex: &Dex,
+    paths: &BTreeMap<Vertex, Path>,
+    options: &QueryOptions,
+) -> Option<(BTreeMap<Vertex, Path>, bool)> {
+    let mut worse = false;
+    let mut warm = BTreeMap::new();
+    for (dst, path) in paths {
+        let mut next = Path::new(path.path[0]);
+        for hop in path.path.windows(2) {
+            let edge = &dex.edges[&hop[0]][&hop[1]];
+            if !options.is_routable(&next, edge) {
+                return None;
+            }
+            next.push(hop[1], edge, options);
+        }
+        if options.score(&next) < options.score(path) {
+            worse = true;
+        }
+        warm.insert(*dst, next);
+    }
+    Some((warm, worse))
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use super::WarmStart;
+use crate::query::QueryOptions;
+use crate::{Dex, Edge};
+
+#[test]
+fn test_warm_start() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('A', 'C', 5.0);
+    dex.add_rate('C', 'D', 1.0);
+
+    let (a, c, d) = ('A'.into(), 'C'.into(), 'D'.into());
+    let mut warm = WarmStart::new(QueryOptions::new());
+    let path = warm.get_best_rate(&dex, &a, &d).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D: 6");
+    assert_eq!(warm.len(), 1);
+
+    // The rate only update is relaxed from the kept paths.
+    dex.add_rate('A', 'C', 7.0);
+    let path = warm.get_best_rate(&dex, &a, &d).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> D: 7");
+    assert_eq!(warm.len(), 1);
+
+    // The worse rate of the best path.
+    dex.add_rate('A', 'C', 4.0);
+    let path = warm.get_best_rate(&dex, &a, &c).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C: 6");
+    assert_eq!(warm.get_best_rates_from(&dex, &a).len(), 4);
+
+    // The structural change falls back to the full search.
+    dex.add_rate('D', 'E', 2.0);
+    assert_eq!(warm.len(), 1);
+    let path = warm.get_best_rate(&dex, &a, &'E'.into()).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C -> D -> E: 12");
+    assert_eq!(
+        path.rate(),
+        dex.get_best_rate(&a, &'E'.into()).unwrap().rate()
+    );
+
+    // The edge removed.
+    let b = 'B'.into();
+    dex.retain_edges(|src, dst, _| (*src, *dst) != (b, c));
+    let path = warm.get_best_rate(&dex, &a, &d).unwrap();
+    assert_eq!(path.to_string(), "A -> C -> D: 4");
+}
+
+#[test]
+fn test_warm_start_amount() {
+    let mut dex = Dex::new();
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_edge('A', 'C', Edge::new(7.0).with_liquidity(100.0));
+    dex.add_edge('A', 'D', Edge::bridge(8.0, 20.0, 0.0, Duration::ZERO));
+
+    let (a, c, d) = ('A'.into(), 'C'.into(), 'D'.into());
+    let mut warm = WarmStart::new(QueryOptions::new().with_amount(10.0));
+    let path = warm.get_best_rate(&dex, &a, &c).unwrap();
+    assert_eq!(path.to_string(), "A -> C: 7");
+    assert_eq!(warm.get_best_rate(&dex, &a, &d).unwrap().rate(), 6.0);
+
+    // The liquidity below the amount, at the same rate.
+    dex.add_edge('A', 'C', Edge::new(7.0).with_liquidity(5.0));
+    let path = warm.get_best_rate(&dex, &a, &c).unwrap();
+    assert_eq!(path.to_string(), "A -> B -> C: 6");
+
+    // The rate short of the fixed fee for the amount.
+    dex.add_edge('A', 'D', Edge::bridge(1.5, 20.0, 0.0, Duration::ZERO));
+    assert!(warm.get_best_rate(&dex, &a, &d).is_none());
+    assert!(dex
+        .get_best_rate_with(&a, &d, &QueryOptions::new().with_amount(10.0))
+        .is_none());
+}
@@ -0,0 +1,123 @@
+//! Continuous arbitrage watch
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tracing::{debug, instrument};
+
+use super::{Dex, Vertex};
+
+/// The arbitrage opportunity opened or closed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArbEvent {
+    Opened {
+        at: SystemTime,
+        cycle: Vec<Vertex>,
+        /// The cycle rate net of the fees.
+        rate: f32,
+    },
+    Closed {
+        at: SystemTime,
+        cycle: Vec<Vertex>,
+        /// The time the opportunity was open for.
+        open_for: Duration,
+    },
+}
+
+impl fmt::Display for ArbEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (at, cycle) = match self {
+            Self::Opened { at, cycle, .. } | Self::Closed { at, cycle, .. } => (at, cycle),
+        };
+        let at = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+        write!(f, "{}.{:03} ", at.as_secs(), at.subsec_millis())?;
+        match self {
+            Self::Opened { .. } => f.write_str("open  ")?,
+            Self::Closed { .. } => f.write_str("close ")?,
+        }
+        for v in cycle {
+            write!(f, "{v} -> ")?;
+        }
+        if let Some(v) = cycle.first() {
+            write!(f, "{v}")?;
+        }
+        match self {
+            Self::Opened { rate, .. } => {
+                write!(f, ": {:.1} bps (rate {rate})", (rate - 1.0) * 10_000.0)
+            }
+            Self::Closed { open_for, .. } => write!(f, " after {open_for:?}"),
+        }
+    }
+}
+
+/// The watch of the arbitrage cycles above `min_bps` net of the fees,
+/// reporting each one once as it appears and once as it goes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ArbWatch {
+    min_bps: f32,
+    // The open cycles with the time they opened at.
+    open: BTreeMap<Vec<Vertex>, SystemTime>,
+}
+
+impl ArbWatch {
+    pub fn new(min_bps: f32) -> Self {
+        Self {
+            min_bps,
+            open: BTreeMap::new(),
+        }
+    }
+
+    pub fn min_bps(&self) -> f32 {
+        self.min_bps
+    }
+
+    /// Returns the number of the open opportunities.
+    pub fn len(&self) -> usize {
+        self.open.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.open.is_empty()
+    }
+
+    /// Checks the `dex` at its [`Dex::now`], and returns the cycles
+    /// closed and then the ones opened since the last check.
+    #[instrument(level = "debug", skip_all)]
+    pub fn check(&mut self, dex: &Dex) -> Vec<ArbEvent> {
+        let at = dex.now();
+        let mut current = BTreeMap::new();
+        let mut opened = Vec::new();
+        for cycle in dex.find_arbitrage(self.min_bps, None) {
+            let vertices: Vec<_> = cycle.edges().iter().map(|(src, ..)| *src).collect();
+            let opened_at = match self.open.remove(&vertices) {
+                Some(opened_at) => opened_at,
+                None => {
+                    opened.push(ArbEvent::Opened {
+                        at,
+                        cycle: vertices.clone(),
+                        rate: cycle.net_rate(),
+                    });
+                    at
+                }
+            };
+            current.insert(vertices, opened_at);
+        }
+        let mut events = Vec::new();
+        for (cycle, opened_at) in std::mem::replace(&mut self.open, current) {
+            events.push(ArbEvent::Closed {
+                at,
+                cycle,
+                open_for: at.duration_since(opened_at).unwrap_or_default(),
+            });
+        }
+        events.extend(opened);
+        if !events.is_empty() {
+            debug!(events = events.len(), open = self.open.len(), "changed");
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod test;
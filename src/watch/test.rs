@@ -0,0 +1,47 @@
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::{ArbEvent, ArbWatch};
+use crate::clock::MockClock;
+use crate::Dex;
+
+#[test]
+fn test_arb_watch() {
+    let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    let mut dex = Dex::new();
+    dex.set_clock(Some(Arc::new(clock.clone())));
+    dex.add_rate('A', 'B', 2.0);
+    dex.add_rate('B', 'C', 3.0);
+    dex.add_rate('C', 'A', 1.0 / 6.0);
+
+    let mut watch = ArbWatch::new(10.0);
+    assert!(watch.check(&dex).is_empty());
+
+    clock.advance(Duration::from_millis(250));
+    dex.add_rate('C', 'A', 0.2);
+    let events = watch.check(&dex);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].to_string(),
+        "1700000000.250 open  A -> B -> C -> A: 2000.0 bps (rate 1.2)"
+    );
+    assert_eq!(watch.len(), 1);
+
+    // Reported once while open.
+    clock.advance(Duration::from_secs(1));
+    dex.add_rate('C', 'A', 0.19);
+    assert!(watch.check(&dex).is_empty());
+
+    clock.advance(Duration::from_secs(1));
+    dex.add_rate('C', 'A', 1.0 / 6.0);
+    let events = watch.check(&dex);
+    assert!(matches!(
+        &events[..],
+        [ArbEvent::Closed { open_for, .. }] if *open_for == Duration::from_secs(2)
+    ));
+    assert_eq!(
+        events[0].to_string(),
+        "1700000002.250 close A -> B -> C -> A after 2s"
+    );
+    assert!(watch.is_empty());
+}
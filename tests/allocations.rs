@@ -0,0 +1,70 @@
+//! Zero allocation check of the frozen graph searcher
+//!
+//! It's a test binary of its own, as the counting allocator is global.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use best_rate_rs::rng::Rng;
+use best_rate_rs::Dex;
+
+// Counts the allocations of each thread, for the tests running in
+// parallel.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn test_searcher_allocations() {
+    let dex = Dex::generate(2_000, 8_000, 0.01, &mut Rng::new(5));
+    let frozen = dex.freeze();
+    let vertices: Vec<_> = frozen.vertices().copied().collect();
+    let mut rng = Rng::new(5);
+    let queries: Vec<_> = (0..1_000)
+        .map(|_| {
+            let src = vertices[rng.below(vertices.len())];
+            let dst = vertices[rng.below(vertices.len())];
+            (src, dst)
+        })
+        .collect();
+    let mut searcher = frozen.searcher();
+    // Warms up the path buffer, the only one allocated lazily.
+    searcher.get_best_rate(&frozen, &vertices[0], &vertices[1]);
+
+    let before = allocations();
+    let mut found = 0;
+    for (src, dst) in &queries {
+        if searcher.get_best_rate(&frozen, src, dst).is_some() {
+            found += 1;
+        }
+    }
+    assert_eq!(allocations() - before, 0, "steady state allocations");
+    assert!(found > 0);
+
+    // The one-off query allocates the search state each time.
+    let before = allocations();
+    for (src, dst) in &queries[..10] {
+        frozen.get_best_rate(src, dst);
+    }
+    assert!(allocations() - before >= 10);
+}